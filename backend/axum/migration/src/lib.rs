@@ -6,6 +6,27 @@ mod m20250725_030642_create_logs_table;
 mod m20250725_030719_create_tags_table;
 mod m20250725_044431_create_log_tag_table;
 mod m20250725_044442_create_tag_task_table;
+mod m20251020_010000_create_personal_access_tokens_table;
+mod m20251021_010000_create_oidc_tables;
+mod m20251022_010000_add_pending_email_to_users;
+mod m20251023_010000_add_role_to_users;
+mod m20251024_010000_create_mail_outbox_table;
+mod m20251025_010000_add_body_html_to_mail_outbox;
+mod m20251025_020000_add_event_to_logs;
+mod m20251026_010000_add_version_to_tasks_and_sessions;
+mod m20251027_010000_create_idempotency_keys_table;
+mod m20251028_010000_create_webhooks_tables;
+mod m20251029_010000_add_uuid_to_rows;
+mod m20251030_010000_add_uuid_to_users;
+mod m20251030_020000_create_sync_changes_table;
+mod m20251031_010000_add_due_at_to_tasks;
+mod m20251031_020000_add_calendar_token_to_users;
+mod m20251031_030000_normalize_log_timestamps;
+mod m20251101_010000_add_daily_journal_to_users;
+mod m20251102_010000_add_version_to_logs_and_tags;
+mod m20251102_020000_reserve_idempotency_keys;
+mod m20251103_010000_store_idempotency_bodies_as_bytes;
+mod m20251104_010000_add_daily_goal_to_users;
 
 pub struct Migrator;
 
@@ -20,6 +41,27 @@ impl MigratorTrait for Migrator {
             Box::new(m20250725_030719_create_tags_table::Migration),
             Box::new(m20250725_044431_create_log_tag_table::Migration),
             Box::new(m20250725_044442_create_tag_task_table::Migration),
+            Box::new(m20251020_010000_create_personal_access_tokens_table::Migration),
+            Box::new(m20251021_010000_create_oidc_tables::Migration),
+            Box::new(m20251022_010000_add_pending_email_to_users::Migration),
+            Box::new(m20251023_010000_add_role_to_users::Migration),
+            Box::new(m20251024_010000_create_mail_outbox_table::Migration),
+            Box::new(m20251025_010000_add_body_html_to_mail_outbox::Migration),
+            Box::new(m20251025_020000_add_event_to_logs::Migration),
+            Box::new(m20251026_010000_add_version_to_tasks_and_sessions::Migration),
+            Box::new(m20251027_010000_create_idempotency_keys_table::Migration),
+            Box::new(m20251028_010000_create_webhooks_tables::Migration),
+            Box::new(m20251029_010000_add_uuid_to_rows::Migration),
+            Box::new(m20251030_010000_add_uuid_to_users::Migration),
+            Box::new(m20251030_020000_create_sync_changes_table::Migration),
+            Box::new(m20251031_010000_add_due_at_to_tasks::Migration),
+            Box::new(m20251031_020000_add_calendar_token_to_users::Migration),
+            Box::new(m20251031_030000_normalize_log_timestamps::Migration),
+            Box::new(m20251101_010000_add_daily_journal_to_users::Migration),
+            Box::new(m20251102_010000_add_version_to_logs_and_tags::Migration),
+            Box::new(m20251102_020000_reserve_idempotency_keys::Migration),
+            Box::new(m20251103_010000_store_idempotency_bodies_as_bytes::Migration),
+            Box::new(m20251104_010000_add_daily_goal_to_users::Migration),
         ]
     }
 }
@@ -45,7 +45,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum DecoponSessions {
+pub enum DecoponSessions {
     Table,
     Id,
     UserId,
@@ -0,0 +1,79 @@
+use sea_orm::{EnumIter, Iterable};
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250725_022035_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PersonalAccessTokens::Table)
+                    .if_not_exists()
+                    .col(pk_auto(PersonalAccessTokens::Id))
+                    .col(integer(PersonalAccessTokens::UserId))
+                    .col(string(PersonalAccessTokens::Name))
+                    .col(
+                        string(PersonalAccessTokens::TokenHash)
+                            .unique_key()
+                            .not_null(),
+                    )
+                    .col(enumeration(
+                        PersonalAccessTokens::Scope,
+                        Alias::new("scope"),
+                        TokenScope::iter(),
+                    ))
+                    .col(timestamp_null(PersonalAccessTokens::ExpiresAt))
+                    .col(timestamp_null(PersonalAccessTokens::LastUsedAt))
+                    .col(
+                        timestamp(PersonalAccessTokens::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        timestamp(PersonalAccessTokens::UpdatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_personal_access_tokens_user_id")
+                            .from(PersonalAccessTokens::Table, PersonalAccessTokens::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PersonalAccessTokens::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PersonalAccessTokens {
+    Table,
+    Id,
+    UserId,
+    Name,
+    TokenHash,
+    Scope,
+    ExpiresAt,
+    LastUsedAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden, EnumIter)]
+pub enum TokenScope {
+    #[iden = "Read"]
+    Read,
+    #[iden = "ReadWrite"]
+    ReadWrite,
+}
@@ -0,0 +1,91 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250725_022035_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserIdentities::Table)
+                    .if_not_exists()
+                    .col(pk_auto(UserIdentities::Id))
+                    .col(integer(UserIdentities::UserId))
+                    .col(string(UserIdentities::Issuer))
+                    .col(string(UserIdentities::Subject))
+                    .col(string_null(UserIdentities::Email))
+                    .col(timestamp(UserIdentities::CreatedAt).default(Expr::current_timestamp()))
+                    .col(timestamp(UserIdentities::UpdatedAt).default(Expr::current_timestamp()))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_identities_user_id")
+                            .from(UserIdentities::Table, UserIdentities::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_identities_issuer_subject")
+                    .table(UserIdentities::Table)
+                    .col(UserIdentities::Issuer)
+                    .col(UserIdentities::Subject)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(OidcAuthRequests::Table)
+                    .if_not_exists()
+                    .col(pk_auto(OidcAuthRequests::Id))
+                    .col(string(OidcAuthRequests::State).unique_key().not_null())
+                    .col(string(OidcAuthRequests::Nonce))
+                    .col(string(OidcAuthRequests::CodeVerifier))
+                    .col(timestamp(OidcAuthRequests::CreatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OidcAuthRequests::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(UserIdentities::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserIdentities {
+    Table,
+    Id,
+    UserId,
+    Issuer,
+    Subject,
+    Email,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum OidcAuthRequests {
+    Table,
+    Id,
+    State,
+    Nonce,
+    CodeVerifier,
+    CreatedAt,
+}
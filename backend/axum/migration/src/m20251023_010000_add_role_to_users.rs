@@ -0,0 +1,96 @@
+use sea_orm::{EnumIter, Iterable};
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250725_022035_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(
+                        enumeration(UserAdmin::Role, Alias::new("role"), UserRole::iter())
+                            .default("User"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(timestamp_null(UserAdmin::DisabledAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdminAuditLogs::Table)
+                    .if_not_exists()
+                    .col(pk_auto(AdminAuditLogs::Id))
+                    .col(integer_null(AdminAuditLogs::ActorId))
+                    .col(integer_null(AdminAuditLogs::TargetUserId))
+                    .col(string(AdminAuditLogs::Action))
+                    .col(text_null(AdminAuditLogs::Details))
+                    .col(timestamp(AdminAuditLogs::CreatedAt).default(Expr::current_timestamp()))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_admin_audit_logs_actor_id")
+                            .from(AdminAuditLogs::Table, AdminAuditLogs::ActorId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AdminAuditLogs::Table).to_owned())
+            .await?;
+        for column in [UserAdmin::DisabledAt, UserAdmin::Role] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Users::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserAdmin {
+    Role,
+    DisabledAt,
+}
+
+#[derive(DeriveIden)]
+enum AdminAuditLogs {
+    Table,
+    Id,
+    ActorId,
+    TargetUserId,
+    Action,
+    Details,
+    CreatedAt,
+}
+
+#[derive(Iden, EnumIter)]
+pub enum UserRole {
+    #[iden = "User"]
+    User,
+    #[iden = "Admin"]
+    Admin,
+}
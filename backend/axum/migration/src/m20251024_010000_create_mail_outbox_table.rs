@@ -0,0 +1,77 @@
+use sea_orm::{EnumIter, Iterable};
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MailOutbox::Table)
+                    .if_not_exists()
+                    .col(pk_auto(MailOutbox::Id))
+                    .col(string(MailOutbox::Recipient))
+                    .col(string(MailOutbox::Subject))
+                    .col(text(MailOutbox::Body))
+                    .col(
+                        enumeration(MailOutbox::Status, Alias::new("status"), MailStatus::iter())
+                            .default("Pending"),
+                    )
+                    .col(integer(MailOutbox::Attempts).default(0))
+                    .col(timestamp(MailOutbox::NextAttemptAt).default(Expr::current_timestamp()))
+                    .col(text_null(MailOutbox::LastError))
+                    .col(timestamp_null(MailOutbox::SentAt))
+                    .col(timestamp(MailOutbox::CreatedAt).default(Expr::current_timestamp()))
+                    .col(timestamp(MailOutbox::UpdatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        // ワーカーは送信待ちのメールを次回送信時刻順に取り出す
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_mail_outbox_status_next_attempt_at")
+                    .table(MailOutbox::Table)
+                    .col(MailOutbox::Status)
+                    .col(MailOutbox::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MailOutbox::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum MailOutbox {
+    Table,
+    Id,
+    Recipient,
+    Subject,
+    Body,
+    Status,
+    Attempts,
+    NextAttemptAt,
+    LastError,
+    SentAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden, EnumIter)]
+pub enum MailStatus {
+    #[iden = "Pending"]
+    Pending,
+    #[iden = "Sent"]
+    Sent,
+    #[iden = "Failed"]
+    Failed,
+}
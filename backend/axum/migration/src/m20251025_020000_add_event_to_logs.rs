@@ -0,0 +1,109 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+use sea_orm_migration::sea_orm::ConnectionTrait;
+
+use crate::m20250725_030642_create_logs_table::Logs;
+
+/// 導入前に英語の文面で保存されていたタスク完了ログ
+const LEGACY_COMPLETED_PREFIX: &str = "Task \"";
+const LEGACY_COMPLETED_SUFFIX: &str = "\" completed.";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Logs::Table)
+                    .add_column(string_null(LogEvent::EventCode))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Logs::Table)
+                    .add_column(text_null(LogEvent::EventParams))
+                    .to_owned(),
+            )
+            .await?;
+
+        // 既存のシステムログもイベントとして読み替えられるようにする
+        let db = manager.get_connection();
+        let backend = db.get_database_backend();
+        let select = Query::select()
+            .columns([Logs::Id, Logs::TaskId, Logs::Content])
+            .from(Logs::Table)
+            .and_where(Expr::col(Logs::Source).eq("System"))
+            .and_where(Expr::col(Logs::TaskId).is_not_null())
+            .to_owned();
+        for row in db.query_all(backend.build(&select)).await? {
+            let id: i32 = row.try_get("", "id")?;
+            let task_id: i32 = row.try_get("", "task_id")?;
+            let content: String = row.try_get("", "content")?;
+            let Some(title) = content
+                .strip_prefix(LEGACY_COMPLETED_PREFIX)
+                .and_then(|rest| rest.strip_suffix(LEGACY_COMPLETED_SUFFIX))
+            else {
+                continue;
+            };
+            let params = format!(
+                "{{\"task_id\":{},\"title\":{}}}",
+                task_id,
+                json_string(title)
+            );
+            let update = Query::update()
+                .table(Logs::Table)
+                .values([
+                    (LogEvent::EventCode, "task.completed".into()),
+                    (LogEvent::EventParams, params.into()),
+                ])
+                .and_where(Expr::col(Logs::Id).eq(id))
+                .to_owned();
+            db.execute(backend.build(&update)).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [LogEvent::EventParams, LogEvent::EventCode] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Logs::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// JSON の文字列リテラルとしてエスケープする
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[derive(DeriveIden)]
+enum LogEvent {
+    EventCode,
+    EventParams,
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250725_022035_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(IdempotencyKeys::Table)
+                    .if_not_exists()
+                    .col(pk_auto(IdempotencyKeys::Id))
+                    .col(integer(IdempotencyKeys::UserId))
+                    .col(string(IdempotencyKeys::Key))
+                    .col(string(IdempotencyKeys::RequestHash))
+                    .col(integer(IdempotencyKeys::ResponseStatus))
+                    .col(text(IdempotencyKeys::ResponseBody))
+                    .col(timestamp(IdempotencyKeys::CreatedAt).default(Expr::current_timestamp()))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_idempotency_keys_user_id")
+                            .from(IdempotencyKeys::Table, IdempotencyKeys::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // キーはユーザーごとに一意
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_idempotency_keys_user_id_key")
+                    .table(IdempotencyKeys::Table)
+                    .col(IdempotencyKeys::UserId)
+                    .col(IdempotencyKeys::Key)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IdempotencyKeys::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum IdempotencyKeys {
+    Table,
+    Id,
+    UserId,
+    Key,
+    RequestHash,
+    ResponseStatus,
+    ResponseBody,
+    CreatedAt,
+}
@@ -0,0 +1,145 @@
+use sea_orm::{EnumIter, Iterable};
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250725_022035_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Webhooks::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Webhooks::Id))
+                    .col(integer(Webhooks::UserId))
+                    .col(string(Webhooks::Url))
+                    .col(string(Webhooks::Secret))
+                    .col(text(Webhooks::EventTypes))
+                    .col(boolean(Webhooks::Active).default(true))
+                    .col(timestamp(Webhooks::CreatedAt).default(Expr::current_timestamp()))
+                    .col(timestamp(Webhooks::UpdatedAt).default(Expr::current_timestamp()))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_webhooks_user_id")
+                            .from(Webhooks::Table, Webhooks::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookDeliveries::Table)
+                    .if_not_exists()
+                    .col(pk_auto(WebhookDeliveries::Id))
+                    .col(integer(WebhookDeliveries::WebhookId))
+                    .col(string(WebhookDeliveries::EventType))
+                    .col(text(WebhookDeliveries::Payload))
+                    .col(
+                        enumeration(
+                            WebhookDeliveries::Status,
+                            Alias::new("status"),
+                            DeliveryStatus::iter(),
+                        )
+                        .default("Pending"),
+                    )
+                    .col(integer(WebhookDeliveries::Attempts).default(0))
+                    .col(
+                        timestamp(WebhookDeliveries::NextAttemptAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(integer_null(WebhookDeliveries::ResponseStatus))
+                    .col(text_null(WebhookDeliveries::LastError))
+                    .col(timestamp_null(WebhookDeliveries::DeliveredAt))
+                    .col(timestamp(WebhookDeliveries::CreatedAt).default(Expr::current_timestamp()))
+                    .col(timestamp(WebhookDeliveries::UpdatedAt).default(Expr::current_timestamp()))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_webhook_deliveries_webhook_id")
+                            .from(WebhookDeliveries::Table, WebhookDeliveries::WebhookId)
+                            .to(Webhooks::Table, Webhooks::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // ワーカーは送信待ちの配信を次回送信時刻順に取り出す
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_deliveries_status_next_attempt_at")
+                    .table(WebhookDeliveries::Table)
+                    .col(WebhookDeliveries::Status)
+                    .col(WebhookDeliveries::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_deliveries_webhook_id")
+                    .table(WebhookDeliveries::Table)
+                    .col(WebhookDeliveries::WebhookId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WebhookDeliveries::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Webhooks::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Webhooks {
+    Table,
+    Id,
+    UserId,
+    Url,
+    Secret,
+    EventTypes,
+    Active,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WebhookDeliveries {
+    Table,
+    Id,
+    WebhookId,
+    EventType,
+    Payload,
+    Status,
+    Attempts,
+    NextAttemptAt,
+    ResponseStatus,
+    LastError,
+    DeliveredAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden, EnumIter)]
+enum DeliveryStatus {
+    #[iden = "Pending"]
+    Pending,
+    #[iden = "Succeeded"]
+    Succeeded,
+    #[iden = "Failed"]
+    Failed,
+}
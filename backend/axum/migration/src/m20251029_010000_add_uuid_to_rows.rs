@@ -0,0 +1,96 @@
+use sea_orm_migration::{prelude::*, schema::*, sea_orm::ConnectionTrait};
+use uuid::Uuid;
+
+use crate::m20250725_022428_create_tasks_table::Tasks;
+use crate::m20250725_030614_create_decopon_sessions_table::DecoponSessions;
+use crate::m20250725_030642_create_logs_table::Logs;
+use crate::m20250725_030719_create_tags_table::Tags;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 端末をまたいで行を識別する UUID を持たせる、ユーザーごとのテーブル
+fn owned_tables() -> [DynIden; 4] {
+    [
+        Tags::Table.into_iden(),
+        Tasks::Table.into_iden(),
+        Logs::Table.into_iden(),
+        DecoponSessions::Table.into_iden(),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        for table in owned_tables() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table.clone())
+                        .add_column(string(RowUuid::Uuid).default(""))
+                        .to_owned(),
+                )
+                .await?;
+
+            // 既存の行にも UUID を振ってから一意制約を付ける
+            let select = Query::select()
+                .column(RowUuid::Id)
+                .from(table.clone())
+                .to_owned();
+            for row in db.query_all(backend.build(&select)).await? {
+                let id: i32 = row.try_get("", &RowUuid::Id.to_string())?;
+                let update = Query::update()
+                    .table(table.clone())
+                    .value(RowUuid::Uuid, Uuid::new_v4().to_string())
+                    .and_where(Expr::col(RowUuid::Id).eq(id))
+                    .to_owned();
+                manager.exec_stmt(update).await?;
+            }
+
+            manager
+                .create_index(
+                    Index::create()
+                        .name(format!("idx_{}_user_id_uuid", table.to_string()))
+                        .table(table.clone())
+                        .col(RowUuid::UserId)
+                        .col(RowUuid::Uuid)
+                        .unique()
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in owned_tables() {
+            manager
+                .drop_index(
+                    Index::drop()
+                        .name(format!("idx_{}_user_id_uuid", table.to_string()))
+                        .table(table.clone())
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .drop_column(RowUuid::Uuid)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum RowUuid {
+    Id,
+    UserId,
+    Uuid,
+}
@@ -0,0 +1,74 @@
+use sea_orm_migration::{prelude::*, schema::*, sea_orm::ConnectionTrait};
+use uuid::Uuid;
+
+use crate::m20250725_022035_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(string(UserUuid::Uuid).default(""))
+                    .to_owned(),
+            )
+            .await?;
+
+        // 既存のユーザーにも UUID を振ってから一意制約を付ける
+        let select = Query::select()
+            .column(Users::Id)
+            .from(Users::Table)
+            .to_owned();
+        for row in db.query_all(backend.build(&select)).await? {
+            let id: i32 = row.try_get("", &Users::Id.to_string())?;
+            let update = Query::update()
+                .table(Users::Table)
+                .value(UserUuid::Uuid, Uuid::new_v4().to_string())
+                .and_where(Expr::col(Users::Id).eq(id))
+                .to_owned();
+            manager.exec_stmt(update).await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_users_uuid")
+                    .table(Users::Table)
+                    .col(UserUuid::Uuid)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_users_uuid")
+                    .table(Users::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(UserUuid::Uuid)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserUuid {
+    Uuid,
+}
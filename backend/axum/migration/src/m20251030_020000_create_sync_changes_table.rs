@@ -0,0 +1,131 @@
+use sea_orm::{EnumIter, Iterable};
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250725_022035_create_users_table::Users;
+use crate::m20250725_022428_create_tasks_table::Tasks;
+use crate::m20250725_030614_create_decopon_sessions_table::DecoponSessions;
+use crate::m20250725_030642_create_logs_table::Logs;
+use crate::m20250725_030719_create_tags_table::Tags;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SyncChanges::Table)
+                    .if_not_exists()
+                    .col(pk_auto(SyncChanges::Id))
+                    .col(integer(SyncChanges::UserId))
+                    .col(string(SyncChanges::Entity))
+                    .col(string(SyncChanges::EntityUuid))
+                    .col(enumeration(
+                        SyncChanges::Operation,
+                        Alias::new("operation"),
+                        SyncOperation::iter(),
+                    ))
+                    .col(big_integer(SyncChanges::Clock))
+                    .col(string_null(SyncChanges::Origin))
+                    .col(timestamp(SyncChanges::CreatedAt).default(Expr::current_timestamp()))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_sync_changes_user_id")
+                            .from(SyncChanges::Table, SyncChanges::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // 変更の取得はユーザーごとに ID (カーソル) 順で行う
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_sync_changes_user_id_id")
+                    .table(SyncChanges::Table)
+                    .col(SyncChanges::UserId)
+                    .col(SyncChanges::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        // 既存の行も次の同期で送られるよう、最も古い時刻の変更として記録しておく。
+        // タスクは親を先に作れるよう階層の浅い順に並べる
+        let existing = [
+            ("tag", Tags::Table.into_iden(), None),
+            (
+                "task",
+                Tasks::Table.into_iden(),
+                Some(Tasks::Depth.into_iden()),
+            ),
+            ("log", Logs::Table.into_iden(), None),
+            ("decopon_session", DecoponSessions::Table.into_iden(), None),
+        ];
+        for (entity, table, order) in existing {
+            let mut select = Query::select();
+            select
+                .column(Synced::UserId)
+                .expr(Expr::val(entity))
+                .column(Synced::Uuid)
+                .expr(Expr::val("Upsert"))
+                .expr(Expr::val(0i64))
+                .from(table);
+            if let Some(order) = order {
+                select.order_by(order, Order::Asc);
+            }
+            select.order_by(Synced::Id, Order::Asc);
+            let insert = Query::insert()
+                .into_table(SyncChanges::Table)
+                .columns([
+                    SyncChanges::UserId,
+                    SyncChanges::Entity,
+                    SyncChanges::EntityUuid,
+                    SyncChanges::Operation,
+                    SyncChanges::Clock,
+                ])
+                .select_from(select)
+                .map_err(|e| DbErr::Custom(e.to_string()))?
+                .to_owned();
+            manager.exec_stmt(insert).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SyncChanges::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SyncChanges {
+    Table,
+    Id,
+    UserId,
+    Entity,
+    EntityUuid,
+    Operation,
+    Clock,
+    Origin,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Synced {
+    Id,
+    UserId,
+    Uuid,
+}
+
+#[derive(Iden, EnumIter)]
+enum SyncOperation {
+    #[iden = "Upsert"]
+    Upsert,
+    #[iden = "Delete"]
+    Delete,
+}
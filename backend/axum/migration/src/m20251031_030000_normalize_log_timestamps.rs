@@ -0,0 +1,27 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite の CURRENT_TIMESTAMP は `YYYY-MM-DD HH:MM:SS` の文字列で保存され、
+        // アプリが書く RFC 3339 の値と文字列として比べると日付で正しく絞り込めない
+        let db = manager.get_connection();
+        if db.get_database_backend() != DatabaseBackend::Sqlite {
+            return Ok(());
+        }
+        db.execute_unprepared(
+            "UPDATE logs SET created_at = strftime('%Y-%m-%dT%H:%M:%S+00:00', created_at) \
+             WHERE created_at NOT LIKE '%T%'",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250725_022035_create_users_table::Users;
+
+/// 処理中のキーを予約しておけるよう、レスポンスの列を空にできる形で作り直す。
+/// 保存しているのは 24 時間で捨てる再送用のレスポンスだけなので、既存の行は引き継がない。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        recreate(
+            manager,
+            [
+                integer_null(IdempotencyKeys::ResponseStatus),
+                text_null(IdempotencyKeys::ResponseHeaders),
+                text_null(IdempotencyKeys::ResponseBody),
+            ],
+        )
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        recreate(
+            manager,
+            [
+                integer(IdempotencyKeys::ResponseStatus),
+                text(IdempotencyKeys::ResponseBody),
+            ],
+        )
+        .await
+    }
+}
+
+pub(crate) async fn recreate(
+    manager: &SchemaManager<'_>,
+    response_columns: impl IntoIterator<Item = ColumnDef>,
+) -> Result<(), DbErr> {
+    manager
+        .drop_table(Table::drop().table(IdempotencyKeys::Table).to_owned())
+        .await?;
+
+    let mut table = Table::create();
+    table
+        .table(IdempotencyKeys::Table)
+        .col(pk_auto(IdempotencyKeys::Id))
+        .col(integer(IdempotencyKeys::UserId))
+        .col(string(IdempotencyKeys::Key))
+        .col(string(IdempotencyKeys::RequestHash));
+    for column in response_columns {
+        table.col(column);
+    }
+    table
+        .col(timestamp(IdempotencyKeys::CreatedAt).default(Expr::current_timestamp()))
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_idempotency_keys_user_id")
+                .from(IdempotencyKeys::Table, IdempotencyKeys::UserId)
+                .to(Users::Table, Users::Id)
+                .on_delete(ForeignKeyAction::Cascade),
+        );
+    manager.create_table(table).await?;
+
+    // キーはユーザーごとに一意
+    manager
+        .create_index(
+            Index::create()
+                .name("idx_idempotency_keys_user_id_key")
+                .table(IdempotencyKeys::Table)
+                .col(IdempotencyKeys::UserId)
+                .col(IdempotencyKeys::Key)
+                .unique()
+                .to_owned(),
+        )
+        .await
+}
+
+#[derive(DeriveIden)]
+pub enum IdempotencyKeys {
+    Table,
+    Id,
+    UserId,
+    Key,
+    RequestHash,
+    ResponseStatus,
+    ResponseHeaders,
+    ResponseBody,
+    CreatedAt,
+}
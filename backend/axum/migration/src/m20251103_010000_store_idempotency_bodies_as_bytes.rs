@@ -0,0 +1,35 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20251102_020000_reserve_idempotency_keys::{IdempotencyKeys, recreate};
+
+/// 再送用のレスポンス本文を、文字列に直さずバイト列のまま保存する。
+/// 予約時と同じく 24 時間で捨てる行だけなので、既存の行は引き継がない。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        recreate(
+            manager,
+            [
+                integer_null(IdempotencyKeys::ResponseStatus),
+                text_null(IdempotencyKeys::ResponseHeaders),
+                blob_null(IdempotencyKeys::ResponseBody),
+            ],
+        )
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        recreate(
+            manager,
+            [
+                integer_null(IdempotencyKeys::ResponseStatus),
+                text_null(IdempotencyKeys::ResponseHeaders),
+                text_null(IdempotencyKeys::ResponseBody),
+            ],
+        )
+        .await
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::usecases::{
+    admin::{AdminUser, AuditLog, Page},
+    users::UserRole,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListUsersQuery {
+    /// メールアドレスまたは名前の部分一致
+    pub q: Option<String>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PageResponse<T> {
+    pub items: Vec<T>,
+    pub page: u64,
+    pub per_page: u64,
+    pub total: u64,
+}
+
+impl<S, T: From<S>> From<Page<S>> for PageResponse<T> {
+    fn from(page: Page<S>) -> Self {
+        Self {
+            items: page.items.into_iter().map(T::from).collect(),
+            page: page.page,
+            per_page: page.per_page,
+            total: page.total,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminUserResponse {
+    pub id: i32,
+    pub uuid: String,
+    pub name: String,
+    pub email: String,
+    pub role: UserRole,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AdminUser> for AdminUserResponse {
+    fn from(user: AdminUser) -> Self {
+        Self {
+            id: user.id,
+            uuid: user.uuid,
+            name: user.name,
+            email: user.email,
+            role: user.role,
+            email_verified_at: user.email_verified_at,
+            disabled_at: user.disabled_at,
+            created_at: user.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AuditLogResponse {
+    pub id: i32,
+    pub actor_id: Option<i32>,
+    pub target_user_id: Option<i32>,
+    pub action: String,
+    pub details: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditLog> for AuditLogResponse {
+    fn from(log: AuditLog) -> Self {
+        Self {
+            id: log.id,
+            actor_id: log.actor_id,
+            target_user_id: log.target_user_id,
+            action: log.action,
+            details: log.details,
+            created_at: log.created_at,
+        }
+    }
+}
+
+/// 代理ログイン用の読み取り専用トークン
+#[derive(Serialize, ToSchema)]
+pub struct ImpersonationResponse {
+    pub token: String,
+}
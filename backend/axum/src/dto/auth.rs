@@ -1,22 +1,29 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
 
-use crate::usecases::users::{User, UserFull};
+use crate::dto::validation::{MAX_NAME_LEN, MAX_PASSWORD_LEN, MIN_PASSWORD_LEN};
+use crate::usecases::users::{User, UserFull, UserRole};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
 pub struct RegisterUserRequest {
+    #[validate(length(min = 1, max = MAX_NAME_LEN))]
     pub name: String,
+    #[validate(email, length(max = MAX_NAME_LEN))]
     pub email: String,
+    #[validate(length(min = MIN_PASSWORD_LEN, max = MAX_PASSWORD_LEN))]
     pub password: String,
+    #[validate(must_match(other = "password", message = "must match password"))]
     pub password_confirmation: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct RegisterUserResponse {
     pub user: UserResponse,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct UserFullResponse {
     pub id: i32,
     pub name: String,
@@ -29,25 +36,43 @@ pub struct UserFullResponse {
     pub locale: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct UserResponse {
     pub id: i32,
+    #[serde(default)]
+    pub uuid: String,
     pub name: String,
     pub email: String,
     pub work_time: i32,
     pub break_time: i32,
     pub locale: String,
+    /// 確認待ちの新しいメールアドレス
+    #[serde(default)]
+    pub pending_email: Option<String>,
+    #[serde(default)]
+    pub role: UserRole,
+    /// 前日の日報をメールで受け取る
+    #[serde(default)]
+    pub daily_journal_email: bool,
+    /// 1 日に完了したいセッション数。0 なら目標なし
+    #[serde(default)]
+    pub daily_goal: i32,
 }
 
 impl From<User> for UserResponse {
     fn from(user: User) -> Self {
         Self {
             id: user.id,
+            uuid: user.uuid,
             name: user.name,
             email: user.email,
             work_time: user.work_time,
             break_time: user.break_time,
             locale: user.locale,
+            pending_email: user.pending_email,
+            role: user.role,
+            daily_journal_email: user.daily_journal_email,
+            daily_goal: user.daily_goal,
         }
     }
 }
@@ -72,55 +97,82 @@ impl From<UserFull> for UserResponse {
     fn from(user: UserFull) -> Self {
         Self {
             id: user.id,
+            uuid: user.uuid,
             name: user.name,
             email: user.email,
             work_time: user.work_time,
             break_time: user.break_time,
             locale: user.locale,
+            pending_email: user.pending_email,
+            role: user.role,
+            daily_journal_email: user.daily_journal_email,
+            daily_goal: user.daily_goal,
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct GetAuthUserResponse {
     pub user: UserResponse,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
 pub struct LoginRequest {
+    #[validate(length(min = 1))]
     pub email: String,
+    #[validate(length(min = 1))]
     pub password: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct AuthResponse {
     pub token: String,
     pub user: UserResponse,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
 pub struct ForgotPasswordRequest {
+    #[validate(email, length(max = MAX_NAME_LEN))]
     pub email: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
 pub struct ResetPasswordRequest {
+    #[validate(length(min = 1))]
     pub token: String,
+    #[validate(email, length(max = MAX_NAME_LEN))]
     pub email: String,
+    #[validate(length(min = MIN_PASSWORD_LEN, max = MAX_PASSWORD_LEN))]
     pub password: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct ConfirmPasswordRequest {
+    #[validate(length(min = 1))]
     pub password: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct ResendVerificationRequest {
+    #[validate(email, length(max = MAX_NAME_LEN))]
     pub email: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcAuthorizationResponse {
+    pub authorization_url: String,
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct OidcCallbackRequest {
+    #[validate(length(min = 1))]
+    pub code: String,
+    #[validate(length(min = 1))]
+    pub state: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct StatusResponse {
     pub status: String,
 }
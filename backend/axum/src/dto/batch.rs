@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationErrors};
+
+use crate::dto::{
+    decopon_sessions::{
+        DecoponSessionResponse, StoreDecoponSessionRequest, UpdateDecoponSessionRequest,
+    },
+    logs::{LogResponse, StoreLogRequest},
+    tasks::{StoreTaskRequest, TaskResponse, UpdateTaskRequest},
+    validation,
+};
+use crate::usecases::ids::RowRef;
+
+/// 一度のバッチで実行できる操作の上限
+pub const MAX_BATCH_OPERATIONS: u64 = 100;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct BatchRequest {
+    /// 先頭から順に実行する。一つでも失敗すればすべて取り消す
+    #[validate(length(min = 1, max = MAX_BATCH_OPERATIONS), nested)]
+    pub operations: Vec<BatchOperation>,
+}
+
+/// バッチ内の一操作。`op` で種類を選び、`data` には対応するエンドポイントと同じ本文を渡す。
+/// `id` には ID か UUID を渡す。`version` を指定すると `If-Match` と同じく版が一致する場合だけ実行する。
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOperation {
+    CreateTask {
+        data: StoreTaskRequest,
+    },
+    UpdateTask {
+        id: RowRef,
+        version: Option<i32>,
+        data: UpdateTaskRequest,
+    },
+    DeleteTask {
+        id: RowRef,
+        version: Option<i32>,
+    },
+    CreateLog {
+        data: StoreLogRequest,
+    },
+    /// ログのタグを置き換える
+    SetLogTags {
+        id: RowRef,
+        version: Option<i32>,
+        #[serde(default)]
+        tag_ids: Vec<RowRef>,
+        #[serde(default)]
+        tag_names: Vec<String>,
+    },
+    CreateDecoponSession {
+        data: StoreDecoponSessionRequest,
+    },
+    UpdateDecoponSession {
+        id: RowRef,
+        version: Option<i32>,
+        data: UpdateDecoponSessionRequest,
+    },
+    DeleteDecoponSession {
+        id: RowRef,
+        version: Option<i32>,
+    },
+}
+
+// 列挙型には derive が使えないため、各操作の本文の規則をそのまま適用する
+impl Validate for BatchOperation {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let data = match self {
+            BatchOperation::CreateTask { data } => data.validate(),
+            BatchOperation::UpdateTask { data, .. } => data.validate(),
+            BatchOperation::CreateLog { data } => data.validate(),
+            BatchOperation::CreateDecoponSession { data } => data.validate(),
+            BatchOperation::UpdateDecoponSession { data, .. } => data.validate(),
+            BatchOperation::SetLogTags { tag_names, .. } => {
+                let mut errors = ValidationErrors::new();
+                if let Err(error) = validation::tag_names(tag_names) {
+                    errors.add("tag_names", error);
+                }
+                return if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors)
+                };
+            }
+            BatchOperation::DeleteTask { .. } | BatchOperation::DeleteDecoponSession { .. } => {
+                Ok(())
+            }
+        };
+        ValidationErrors::merge(Ok(()), "data", data)
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BatchResponse {
+    /// `operations` と同じ順序の結果
+    pub results: Vec<BatchResult>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum BatchResult {
+    Task(TaskResponse),
+    Log(LogResponse),
+    DecoponSession(DecoponSessionResponse),
+    Deleted { id: i32 },
+}
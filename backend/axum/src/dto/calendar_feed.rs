@@ -0,0 +1,26 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::usecases::calendar_feed::CalendarFeedToken;
+
+#[derive(Serialize, ToSchema)]
+pub struct CalendarFeedResponse {
+    /// 購読用の URL が発行されているか
+    pub enabled: bool,
+}
+
+/// 発行時のみ平文トークンと購読用の URL を返す
+#[derive(Serialize, ToSchema)]
+pub struct CalendarFeedTokenResponse {
+    pub token: String,
+    pub url: String,
+}
+
+impl From<CalendarFeedToken> for CalendarFeedTokenResponse {
+    fn from(issued: CalendarFeedToken) -> Self {
+        Self {
+            token: issued.token,
+            url: issued.url,
+        }
+    }
+}
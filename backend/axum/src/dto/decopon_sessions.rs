@@ -1,47 +1,68 @@
-use sea_orm::prelude::DateTimeUtc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
 
+use crate::dto::validation;
 use crate::usecases::decopon_sessions::DecoponSession;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DecoponSessionResponse {
     pub id: i32,
+    pub uuid: String,
     pub status: String,
-    pub started_at: DateTimeUtc,
-    pub ended_at: Option<DateTimeUtc>,
-    pub created_at: DateTimeUtc,
-    pub updated_at: DateTimeUtc,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
     pub user_id: i32,
+    /// 更新のたびに増える版。`ETag` と同じ値
+    pub version: i32,
 }
 
 impl From<DecoponSession> for DecoponSessionResponse {
     fn from(s: DecoponSession) -> Self {
         Self {
             id: s.id,
+            uuid: s.uuid,
             status: s.status,
             started_at: s.started_at,
             ended_at: s.ended_at,
             created_at: s.created_at,
             updated_at: s.updated_at,
             user_id: s.user_id,
+            version: s.version,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "ended_after_start"))]
 pub struct StoreDecoponSessionRequest {
+    #[validate(custom(function = "validation::session_status"))]
     pub status: String,
-    pub started_at: DateTimeUtc,
-    pub ended_at: Option<DateTimeUtc>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+fn ended_after_start(request: &StoreDecoponSessionRequest) -> Result<(), ValidationError> {
+    match request.ended_at {
+        Some(ended_at) if ended_at < request.started_at => {
+            Err(ValidationError::new("ended_before_start")
+                .with_message("ended_at must not be before started_at".into()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct UpdateDecoponSessionRequest {
+    #[validate(custom(function = "validation::session_status"))]
     pub status: Option<String>,
-    pub ended_at: Option<DateTimeUtc>,
+    pub ended_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct CycleCountResponse {
     pub date: String,
     pub count: u64,
@@ -0,0 +1,114 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::dto::{decopon_sessions::DecoponSessionResponse, logs::LogResponse};
+use crate::usecases::journal::{CompletedTask, Journal, JournalLogGroup, JournalTotals};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalFormat {
+    #[default]
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct JournalQuery {
+    /// `markdown` を指定すると `text/markdown` で返す
+    #[serde(default)]
+    pub format: JournalFormat,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JournalResponse {
+    /// 集計した日 (UTC)
+    pub date: NaiveDate,
+    /// ユーザーが書いたログをタスクごとにまとめたもの。タスクのないログは最後
+    pub log_groups: Vec<JournalLogGroupResponse>,
+    /// その日に終えたセッション
+    pub sessions: Vec<DecoponSessionResponse>,
+    pub completed_tasks: Vec<CompletedTaskResponse>,
+    pub totals: JournalTotalsResponse,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JournalLogGroupResponse {
+    pub task_id: Option<i32>,
+    /// タスクが削除されていれば null
+    pub task_title: Option<String>,
+    pub logs: Vec<LogResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CompletedTaskResponse {
+    pub task_id: i32,
+    /// 完了したときのタイトル
+    pub title: String,
+    pub completed_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JournalTotalsResponse {
+    pub logs: usize,
+    pub sessions: usize,
+    /// 終えたセッションの合計時間 (分)
+    pub focus_minutes: i64,
+    pub completed_tasks: usize,
+}
+
+impl From<Journal> for JournalResponse {
+    fn from(journal: Journal) -> Self {
+        Self {
+            date: journal.date,
+            log_groups: journal
+                .log_groups
+                .into_iter()
+                .map(JournalLogGroupResponse::from)
+                .collect(),
+            sessions: journal
+                .sessions
+                .into_iter()
+                .map(DecoponSessionResponse::from)
+                .collect(),
+            completed_tasks: journal
+                .completed_tasks
+                .into_iter()
+                .map(CompletedTaskResponse::from)
+                .collect(),
+            totals: journal.totals.into(),
+        }
+    }
+}
+
+impl From<JournalLogGroup> for JournalLogGroupResponse {
+    fn from(group: JournalLogGroup) -> Self {
+        Self {
+            task_id: group.task_id,
+            task_title: group.task_title,
+            logs: group.logs.into_iter().map(LogResponse::from).collect(),
+        }
+    }
+}
+
+impl From<CompletedTask> for CompletedTaskResponse {
+    fn from(task: CompletedTask) -> Self {
+        Self {
+            task_id: task.task_id,
+            title: task.title,
+            completed_at: task.completed_at,
+        }
+    }
+}
+
+impl From<JournalTotals> for JournalTotalsResponse {
+    fn from(totals: JournalTotals) -> Self {
+        Self {
+            logs: totals.logs,
+            sessions: totals.sessions,
+            focus_minutes: totals.focus_minutes,
+            completed_tasks: totals.completed_tasks,
+        }
+    }
+}
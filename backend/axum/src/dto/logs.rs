@@ -1,14 +1,21 @@
-use sea_orm::prelude::DateTimeUtc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
 
-use crate::usecases::logs::{Log, LogSource, LogTagInfo};
+use crate::dto::validation::{self, MAX_TEXT_LEN};
+use crate::usecases::{
+    ids::RowRef,
+    logs::{Log, LogEvent, LogSource, LogTagInfo},
+};
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct LogTagResponse {
     pub id: i32,
+    pub uuid: String,
     pub name: String,
-    pub created_at: DateTimeUtc,
-    pub updated_at: DateTimeUtc,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
     pub user_id: i32,
 }
 
@@ -16,6 +23,7 @@ impl From<LogTagInfo> for LogTagResponse {
     fn from(tag: LogTagInfo) -> Self {
         Self {
             id: tag.id,
+            uuid: tag.uuid,
             name: tag.name,
             created_at: tag.created_at,
             updated_at: tag.updated_at,
@@ -24,15 +32,21 @@ impl From<LogTagInfo> for LogTagResponse {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct LogResponse {
     pub id: i32,
+    pub uuid: String,
     pub content: String,
     pub source: LogSource,
-    pub created_at: DateTimeUtc,
-    pub updated_at: DateTimeUtc,
+    /// システムイベントのコードとパラメータ (`{"code": ..., "params": {...}}`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<LogEvent>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
     pub user_id: i32,
     pub task_id: Option<i32>,
+    /// タグを付け替えるたびに増える版。`ETag` と同じ値
+    pub version: i32,
     pub tags: Vec<LogTagResponse>,
 }
 
@@ -40,24 +54,31 @@ impl From<Log> for LogResponse {
     fn from(log: Log) -> Self {
         Self {
             id: log.id,
+            uuid: log.uuid,
             content: log.content,
             source: log.source,
+            event: log.event,
             created_at: log.created_at,
             updated_at: log.updated_at,
             user_id: log.user_id,
             task_id: log.task_id,
+            version: log.version,
             tags: log.tags.into_iter().map(LogTagResponse::from).collect(),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct StoreLogRequest {
+    #[validate(length(min = 1, max = MAX_TEXT_LEN))]
     pub content: String,
     pub source: LogSource,
-    pub task_id: Option<i32>,
+    /// タスクの ID か UUID
+    pub task_id: Option<RowRef>,
+    /// タグの ID か UUID
     #[serde(default)]
-    pub tag_ids: Vec<i32>,
+    pub tag_ids: Vec<RowRef>,
     #[serde(default)]
+    #[validate(custom(function = "validation::tag_names"))]
     pub tag_names: Vec<String>,
 }
@@ -3,11 +3,19 @@
 //! 命名を採用しています。一方、サービス層で扱う `NewTask` や `Task`、`User` などのドメイン
 //! モデルはプレーンな名前を維持し、ビジネスロジックや永続化処理に限定して使用します。
 
+pub mod admin;
 pub mod auth;
+pub mod batch;
+pub mod calendar_feed;
 pub mod common;
 pub mod decopon_sessions;
+pub mod journal;
 pub mod logs;
+pub mod personal_access_tokens;
 pub mod preferences;
 pub mod profiles;
+pub mod sync;
 pub mod tags;
 pub mod tasks;
+pub mod validation;
+pub mod webhooks;
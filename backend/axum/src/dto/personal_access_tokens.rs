@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::dto::validation::MAX_NAME_LEN;
+use crate::usecases::personal_access_tokens::{
+    IssuedPersonalAccessToken, PersonalAccessToken, TokenScope,
+};
+
+#[derive(Serialize, ToSchema)]
+pub struct PersonalAccessTokenResponse {
+    pub id: i32,
+    pub name: String,
+    pub scope: TokenScope,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<PersonalAccessToken> for PersonalAccessTokenResponse {
+    fn from(token: PersonalAccessToken) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            scope: token.scope,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+            created_at: token.created_at,
+            updated_at: token.updated_at,
+        }
+    }
+}
+
+/// 発行時のみ平文トークンを含めて返すレスポンス
+#[derive(Serialize, ToSchema)]
+pub struct IssuedPersonalAccessTokenResponse {
+    pub token: String,
+    pub personal_access_token: PersonalAccessTokenResponse,
+}
+
+impl From<IssuedPersonalAccessToken> for IssuedPersonalAccessTokenResponse {
+    fn from(issued: IssuedPersonalAccessToken) -> Self {
+        Self {
+            token: issued.plain_text,
+            personal_access_token: PersonalAccessTokenResponse::from(issued.token),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct StorePersonalAccessTokenRequest {
+    #[validate(length(min = 1, max = MAX_NAME_LEN))]
+    pub name: String,
+    pub scope: TokenScope,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdatePersonalAccessTokenRequest {
+    #[validate(length(min = 1, max = MAX_NAME_LEN))]
+    pub name: Option<String>,
+}
@@ -1,12 +1,17 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
 
+use crate::dto::validation;
 use crate::usecases::users::User;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct PreferenceResponse {
     pub work_time: i32,
     pub break_time: i32,
     pub locale: String,
+    pub daily_journal_email: bool,
+    pub daily_goal: i32,
 }
 
 impl From<User> for PreferenceResponse {
@@ -15,13 +20,27 @@ impl From<User> for PreferenceResponse {
             work_time: user.work_time,
             break_time: user.break_time,
             locale: user.locale,
+            daily_journal_email: user.daily_journal_email,
+            daily_goal: user.daily_goal,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct UpdatePreferenceRequest {
+    /// 作業時間 (分)
+    #[validate(range(min = 1, max = 180))]
     pub work_time: i32,
+    /// 休憩時間 (分)
+    #[validate(range(min = 1, max = 60))]
     pub break_time: i32,
+    #[validate(custom(function = "validation::locale"))]
     pub locale: String,
+    /// 前日の日報をメールで受け取るか。省略すると変更しない
+    #[serde(default)]
+    pub daily_journal_email: Option<bool>,
+    /// 1 日に完了したいセッション数。0 で目標なし、省略すると変更しない
+    #[serde(default)]
+    #[validate(range(min = 0, max = 50))]
+    pub daily_goal: Option<i32>,
 }
@@ -1,18 +1,57 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::dto::validation::{MAX_NAME_LEN, MAX_PASSWORD_LEN, MIN_PASSWORD_LEN};
+use crate::usecases::archive::{Archive, ImportSummary};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct UpdateProfileRequest {
+    #[validate(length(min = 1, max = MAX_NAME_LEN))]
     pub name: Option<String>,
+    #[validate(email, length(max = MAX_NAME_LEN))]
     pub email: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct UpdatePasswordRequest {
+    #[validate(length(min = 1))]
     pub current_password: String,
+    #[validate(length(min = MIN_PASSWORD_LEN, max = MAX_PASSWORD_LEN))]
     pub password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct DeleteProfileRequest {
+    #[validate(length(min = 1))]
     pub password: String,
 }
+
+/// `GET /profiles/export` で書き出したアーカイブをそのまま送る
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct ImportProfileRequest {
+    #[serde(flatten)]
+    pub archive: Archive,
+}
+
+/// 取り込んだ行の数。取り込み先に同じ UUID の行があって取り込まなかった行は `skipped` に数える
+#[derive(Serialize, ToSchema)]
+pub struct ImportProfileResponse {
+    pub tags: usize,
+    pub tasks: usize,
+    pub logs: usize,
+    pub decopon_sessions: usize,
+    pub skipped: usize,
+}
+
+impl From<ImportSummary> for ImportProfileResponse {
+    fn from(summary: ImportSummary) -> Self {
+        Self {
+            tags: summary.tags,
+            tasks: summary.tasks,
+            logs: summary.logs,
+            decopon_sessions: summary.decopon_sessions,
+            skipped: summary.skipped,
+        }
+    }
+}
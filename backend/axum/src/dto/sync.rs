@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::dto::validation::MAX_NAME_LEN;
+use crate::usecases::sync::{ApplyResult, ChangePage, SkippedChange, SyncChange};
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SyncChangesQuery {
+    /// 前回のレスポンスの `cursor`。省略すると最初から
+    #[serde(default)]
+    pub since: i32,
+    /// 取得する端末の ID。その端末から送られた変更は返さない
+    pub device_id: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SyncChangesResponse {
+    pub changes: Vec<SyncChange>,
+    pub cursor: i32,
+    /// true なら `cursor` を `since` に渡して続きを取得する
+    pub has_more: bool,
+}
+
+impl From<ChangePage> for SyncChangesResponse {
+    fn from(page: ChangePage) -> Self {
+        Self {
+            changes: page.changes,
+            cursor: page.cursor,
+            has_more: page.has_more,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct SyncPushRequest {
+    #[validate(length(min = 1, max = MAX_NAME_LEN))]
+    pub device_id: String,
+    pub changes: Vec<SyncChange>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SyncPushResponse {
+    pub applied: usize,
+    pub skipped: Vec<SkippedChange>,
+}
+
+impl From<ApplyResult> for SyncPushResponse {
+    fn from(result: ApplyResult) -> Self {
+        Self {
+            applied: result.applied,
+            skipped: result.skipped,
+        }
+    }
+}
@@ -1,14 +1,20 @@
-use sea_orm::prelude::DateTimeUtc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
 
-use crate::usecases::tags::Tag;
+use crate::dto::validation::MAX_NAME_LEN;
+use crate::usecases::{ids::RowRef, tags::Tag};
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TagResponse {
     pub id: i32,
+    pub uuid: String,
     pub name: String,
-    pub created_at: DateTimeUtc,
-    pub updated_at: DateTimeUtc,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// 更新のたびに増える版。`ETag` と同じ値
+    pub version: i32,
     pub task_count: u64,
 }
 
@@ -16,26 +22,35 @@ impl From<Tag> for TagResponse {
     fn from(tag: Tag) -> Self {
         Self {
             id: tag.id,
+            uuid: tag.uuid,
             name: tag.name,
             created_at: tag.created_at,
             updated_at: tag.updated_at,
+            version: tag.version,
             task_count: tag.task_count,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct StoreTagRequest {
+    #[validate(length(min = 1, max = MAX_NAME_LEN))]
     pub name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct TagRelationRequest {
-    pub task_id: i32,
+    /// タスクの ID か UUID
+    pub task_id: RowRef,
+    #[validate(length(min = 1, max = MAX_NAME_LEN))]
     pub name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct DeleteTagsRequest {
-    pub tag_ids: Vec<i32>,
+    /// タグの ID か UUID
+    pub tag_ids: Vec<RowRef>,
+    /// `tag_ids` と同じ順序の版。指定すれば、一つでも食い違うと何も削除しない
+    #[serde(default)]
+    pub versions: Option<Vec<i32>>,
 }
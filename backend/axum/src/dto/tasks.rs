@@ -1,11 +1,18 @@
-use sea_orm::prelude::DateTimeUtc;
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 
+use crate::dto::validation::{MAX_NAME_LEN, MAX_TEXT_LEN};
+use crate::usecases::ids::RowRef;
+use crate::usecases::task_import::{ImportedTask, TaskImport, TaskImportFormat};
+use crate::usecases::task_outline::OutlineFormat;
 use crate::usecases::tasks::{Task, TaskSubtreeNode, TaskTag};
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TaskResponse {
     pub id: i32,
+    pub uuid: String,
     pub title: String,
     pub description: String,
     pub completed: bool,
@@ -13,8 +20,11 @@ pub struct TaskResponse {
     pub root_task_id: Option<i32>,
     pub depth: i32,
     pub position: i32,
-    pub created_at: DateTimeUtc,
-    pub updated_at: DateTimeUtc,
+    pub due_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// 更新のたびに増える版。`ETag` と同じ値
+    pub version: i32,
     pub tags: Vec<TaskTagResponse>,
 }
 
@@ -22,6 +32,7 @@ impl From<Task> for TaskResponse {
     fn from(task: Task) -> Self {
         Self {
             id: task.id,
+            uuid: task.uuid,
             title: task.title,
             description: task.description,
             completed: task.completed,
@@ -29,14 +40,16 @@ impl From<Task> for TaskResponse {
             root_task_id: task.root_task_id,
             depth: task.depth,
             position: task.position,
+            due_at: task.due_at,
             created_at: task.created_at,
             updated_at: task.updated_at,
+            version: task.version,
             tags: task.tags.into_iter().map(TaskTagResponse::from).collect(),
         }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TaskSubtreeResponse {
     pub task: TaskResponse,
     pub relative_depth: i32,
@@ -51,18 +64,20 @@ impl From<TaskSubtreeNode> for TaskSubtreeResponse {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TaskTagResponse {
     pub id: i32,
+    pub uuid: String,
     pub name: String,
-    pub created_at: DateTimeUtc,
-    pub updated_at: DateTimeUtc,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 impl From<TaskTag> for TaskTagResponse {
     fn from(tag: TaskTag) -> Self {
         Self {
             id: tag.id,
+            uuid: tag.uuid,
             name: tag.name,
             created_at: tag.created_at,
             updated_at: tag.updated_at,
@@ -70,19 +85,104 @@ impl From<TaskTag> for TaskTagResponse {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct StoreTaskRequest {
+    #[validate(length(min = 1, max = MAX_NAME_LEN))]
     pub title: String,
+    #[validate(length(max = MAX_TEXT_LEN))]
     pub description: String,
-    pub parent_task_id: Option<i32>,
-    pub tag_ids: Option<Vec<i32>>,
+    /// 親タスクの ID か UUID
+    pub parent_task_id: Option<RowRef>,
+    /// タグの ID か UUID
+    pub tag_ids: Option<Vec<RowRef>>,
+    pub due_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 pub struct UpdateTaskRequest {
+    #[validate(length(min = 1, max = MAX_NAME_LEN))]
     pub title: Option<String>,
+    #[validate(length(max = MAX_TEXT_LEN))]
     pub description: Option<String>,
     pub completed: Option<bool>,
-    pub parent_task_id: Option<i32>,
-    pub tag_ids: Option<Vec<i32>>,
+    /// 親タスクの ID か UUID
+    pub parent_task_id: Option<RowRef>,
+    /// タグの ID か UUID
+    pub tag_ids: Option<Vec<RowRef>>,
+    /// `null` を送ると期限を外す
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<DateTime<Utc>>)]
+    pub due_at: Option<Option<DateTime<Utc>>>,
+}
+
+/// 省略と `null` を区別する。`null` は `Some(None)` になる
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskExportQuery {
+    /// `markdown` (チェックボックスのリスト) か `opml`。省略すると `markdown`
+    #[serde(default)]
+    pub format: OutlineFormat,
+}
+
+/// 他のツールから書き出した内容を、そのまま `content` に入れて送る
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct ImportTasksRequest {
+    pub format: TaskImportFormat,
+    #[validate(length(min = 1))]
+    pub content: String,
+    /// `true` なら何も作らず、作られる内容だけを返す
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportedTaskResponse {
+    /// 作成したタスクの ID。試行のときは `null`
+    pub id: Option<i32>,
+    pub title: String,
+    pub description: String,
+    pub completed: bool,
+    /// 親タスクの `tasks` の中での位置
+    pub parent_index: Option<usize>,
+    pub tags: Vec<String>,
+}
+
+impl From<ImportedTask> for ImportedTaskResponse {
+    fn from(task: ImportedTask) -> Self {
+        Self {
+            id: task.id,
+            title: task.title,
+            description: task.description,
+            completed: task.completed,
+            parent_index: task.parent,
+            tags: task.tags,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportTasksResponse {
+    pub dry_run: bool,
+    /// 親が子より先に並ぶ
+    pub tasks: Vec<ImportedTaskResponse>,
+    /// 新しく作られたタグ
+    pub new_tags: Vec<String>,
+}
+
+impl From<TaskImport> for ImportTasksResponse {
+    fn from(import: TaskImport) -> Self {
+        Self {
+            dry_run: import.dry_run,
+            tasks: import.tasks.into_iter().map(Into::into).collect(),
+            new_tags: import.new_tags,
+        }
+    }
 }
@@ -0,0 +1,56 @@
+//! 複数の DTO で共有する入力値の規則です。`#[validate(...)]` から参照します。
+
+use decopon_services::locale::Locale;
+use validator::ValidationError;
+
+/// 名前・タイトルなど一行の文字列の上限
+pub const MAX_NAME_LEN: u64 = 255;
+/// 説明やログ本文など複数行の文字列の上限
+pub const MAX_TEXT_LEN: u64 = 10_000;
+pub const MIN_PASSWORD_LEN: u64 = 8;
+pub const MAX_PASSWORD_LEN: u64 = 128;
+
+/// セッションの状態として保存できる値
+const SESSION_STATUSES: [&str; 5] = [
+    "In_Progress",
+    "Completed",
+    "Interrupted",
+    "Abandoned",
+    "Extended",
+];
+
+fn invalid(code: &'static str, message: &'static str) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+pub fn locale(value: &str) -> Result<(), ValidationError> {
+    Locale::parse(value)
+        .map(|_| ())
+        .ok_or_else(|| invalid("unsupported_locale", "must be one of: en, ja"))
+}
+
+pub fn session_status(value: &str) -> Result<(), ValidationError> {
+    if SESSION_STATUSES.contains(&value) {
+        Ok(())
+    } else {
+        Err(invalid(
+            "unknown_status",
+            "must be one of: In_Progress, Completed, Interrupted, Abandoned, Extended",
+        ))
+    }
+}
+
+pub fn tag_names(names: &[String]) -> Result<(), ValidationError> {
+    let valid = names.iter().all(|name| {
+        let len = name.trim().chars().count() as u64;
+        (1..=MAX_NAME_LEN).contains(&len)
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid(
+            "length",
+            "each tag name must be 1 to 255 characters long",
+        ))
+    }
+}
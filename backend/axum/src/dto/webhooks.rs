@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::dto::validation::MAX_NAME_LEN;
+use crate::usecases::webhooks::{CreatedWebhook, DeliveryStatus, Webhook, WebhookDelivery};
+
+#[derive(Serialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: i32,
+    pub url: String,
+    /// 購読しているイベントの種類 (`task_created` など)
+    pub event_types: Vec<String>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            id: webhook.id,
+            url: webhook.url,
+            event_types: webhook.event_types,
+            active: webhook.active,
+            created_at: webhook.created_at,
+            updated_at: webhook.updated_at,
+        }
+    }
+}
+
+/// 登録時のみ署名用のシークレットを含めて返すレスポンス
+#[derive(Serialize, ToSchema)]
+pub struct CreatedWebhookResponse {
+    pub secret: String,
+    pub webhook: WebhookResponse,
+}
+
+impl From<CreatedWebhook> for CreatedWebhookResponse {
+    fn from(created: CreatedWebhook) -> Self {
+        Self {
+            secret: created.secret,
+            webhook: WebhookResponse::from(created.webhook),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WebhookDeliveryResponse {
+    pub id: i32,
+    pub event_type: String,
+    /// 送信した (または送信する) 本文
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<WebhookDelivery> for WebhookDeliveryResponse {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            event_type: delivery.event_type,
+            payload: serde_json::from_str(&delivery.payload)
+                .unwrap_or(serde_json::Value::String(delivery.payload)),
+            status: delivery.status,
+            attempts: delivery.attempts,
+            response_status: delivery.response_status,
+            last_error: delivery.last_error,
+            next_attempt_at: delivery.next_attempt_at,
+            delivered_at: delivery.delivered_at,
+            created_at: delivery.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct StoreWebhookRequest {
+    #[validate(url, length(max = MAX_NAME_LEN))]
+    pub url: String,
+    #[validate(length(min = 1))]
+    pub event_types: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdateWebhookRequest {
+    #[validate(url, length(max = MAX_NAME_LEN))]
+    pub url: Option<String>,
+    #[validate(length(min = 1))]
+    pub event_types: Option<Vec<String>>,
+    /// false にすると配信を止める
+    pub active: Option<bool>,
+}
@@ -1,10 +1,16 @@
-use axum::{Json, http::StatusCode, response::IntoResponse};
-use axum_password_worker::{Bcrypt, PasswordWorkerError};
+use axum::{
+    Json,
+    http::{StatusCode, header::CONTENT_TYPE},
+    response::IntoResponse,
+};
+use axum_password_worker::PasswordWorkerError;
 use jsonwebtoken::errors::Error as JwtError;
 use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
 
-use crate::ServiceError;
+use crate::{ServiceError, middleware::request_id::current_request_id};
+use decopon_services::{errors::FieldError, password::PasswordHasher};
 
 #[derive(Debug, Error)]
 pub enum ApiError {
@@ -15,18 +21,35 @@ pub enum ApiError {
     #[error("conflict: {0}")]
     Conflict(&'static str),
 
+    #[error("precondition failed: {0}")]
+    PreconditionFailed(&'static str),
+
     #[error("bad request: {0}")]
     BadRequest(String),
 
+    #[error("validation failed")]
+    Validation(Vec<FieldError>),
+
     #[error("unauthorized")]
     Unauthorized,
 
+    #[error("forbidden")]
+    Forbidden,
+
+    /// バッチの `index` 番目の操作の失敗
+    #[error("batch operation {index} failed")]
+    Batch {
+        index: usize,
+        #[source]
+        source: Box<ApiError>,
+    },
+
     // 外部ライブラリのラップ（原因は source に残す）
     #[error("database error")]
     Db(#[source] sea_orm::DbErr),
 
     #[error("password error")]
-    Password(#[source] PasswordWorkerError<Bcrypt>),
+    Password(#[source] PasswordWorkerError<PasswordHasher>),
 
     #[cfg(feature = "web")]
     #[error("mail error")]
@@ -37,14 +60,153 @@ pub enum ApiError {
     Internal(#[source] Box<dyn std::error::Error + Send + Sync>),
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorBody<'a> {
-    code: u16,
+/// クライアントが分岐に使う安定したエラーコード。問題の種類 (`type`) にも使う
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    Conflict,
+    PreconditionFailed,
+    BadRequest,
+    ValidationFailed,
+    Unauthorized,
+    Forbidden,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::PreconditionFailed => "precondition_failed",
+            ErrorCode::BadRequest => "bad_request",
+            ErrorCode::ValidationFailed => "validation_failed",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::InternalError => "internal_error",
+        }
+    }
+
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Conflict => StatusCode::CONFLICT,
+            ErrorCode::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            ErrorCode::BadRequest => StatusCode::BAD_REQUEST,
+            ErrorCode::ValidationFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "Not found",
+            ErrorCode::Conflict => "Conflict",
+            ErrorCode::PreconditionFailed => "Precondition failed",
+            ErrorCode::BadRequest => "Bad request",
+            ErrorCode::ValidationFailed => "Validation failed",
+            ErrorCode::Unauthorized => "Unauthorized",
+            ErrorCode::Forbidden => "Forbidden",
+            ErrorCode::InternalError => "Internal error",
+        }
+    }
+}
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// エラー時のレスポンスボディ (RFC 7807 problem+json)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    /// `urn:decopon:problem:<code>`
+    #[serde(rename = "type")]
+    problem_type: String,
+    title: &'static str,
+    status: u16,
+    code: ErrorCode,
     // クライアント向けの安全なメッセージ
-    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    /// 見つからない・競合した・更新済みだったリソースの種類
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resource: Option<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FieldError>,
+    /// バッチで失敗した操作の位置 (0 始まり)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
-// ApiError -> HTTP レスポンス（JSON）へ
+impl ApiError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ApiError::NotFound(_) => ErrorCode::NotFound,
+            ApiError::Conflict(_) => ErrorCode::Conflict,
+            ApiError::PreconditionFailed(_) => ErrorCode::PreconditionFailed,
+            ApiError::BadRequest(_) => ErrorCode::BadRequest,
+            ApiError::Validation(_) => ErrorCode::ValidationFailed,
+            ApiError::Unauthorized => ErrorCode::Unauthorized,
+            ApiError::Forbidden => ErrorCode::Forbidden,
+            ApiError::Batch { source, .. } => source.code(),
+            ApiError::Db(_) | ApiError::Password(_) | ApiError::Internal(_) => {
+                ErrorCode::InternalError
+            }
+            #[cfg(feature = "web")]
+            ApiError::Mail(_) => ErrorCode::InternalError,
+        }
+    }
+
+    fn into_body(self) -> ErrorBody {
+        if let ApiError::Batch { index, source } = self {
+            let mut body = source.into_body();
+            body.operation = Some(index);
+            return body;
+        }
+        let code = self.code();
+        let (detail, resource, errors) = match self {
+            ApiError::NotFound(resource) => (
+                Some(format!("{resource} not found")),
+                Some(resource),
+                vec![],
+            ),
+            ApiError::Conflict(resource) => (
+                Some(format!("{resource} conflicts with an existing one")),
+                Some(resource),
+                vec![],
+            ),
+            ApiError::PreconditionFailed(resource) => (
+                Some(format!("{resource} has been modified since it was read")),
+                Some(resource),
+                vec![],
+            ),
+            ApiError::BadRequest(message) => (Some(message), None, vec![]),
+            ApiError::Validation(errors) => (
+                Some("One or more fields are invalid".to_string()),
+                None,
+                errors,
+            ),
+            // 認証・内部エラーの詳細は返さない
+            _ => (None, None, vec![]),
+        };
+        ErrorBody {
+            problem_type: format!("urn:decopon:problem:{}", code.as_str()),
+            title: code.title(),
+            status: code.status().as_u16(),
+            code,
+            detail,
+            resource,
+            errors,
+            operation: None,
+            request_id: current_request_id(),
+        }
+    }
+}
+
+// ApiError -> HTTP レスポンス（problem+json）へ
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         // 内部原因はログにだけ出す
@@ -53,27 +215,9 @@ impl IntoResponse for ApiError {
             _ => tracing::error!(?self, "request failed"),
         }
 
-        let (status, msg): (StatusCode, &str) = match &self {
-            // 業務系
-            ApiError::NotFound(_) => (StatusCode::NOT_FOUND, "Not found"),
-            ApiError::Conflict(_) => (StatusCode::CONFLICT, "Conflict"),
-            ApiError::BadRequest(_) => (StatusCode::BAD_REQUEST, "Bad request"),
-            ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized"),
-
-            // ライブラリ系は “安全な” メッセージに正規化
-            ApiError::Db(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error"),
-            ApiError::Password(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Password error"),
-            #[cfg(feature = "web")]
-            ApiError::Mail(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Mail error"),
-
-            ApiError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Internal error"),
-        };
-
-        let body = ErrorBody {
-            code: status.as_u16(),
-            message: msg,
-        };
-        (status, Json(body)).into_response()
+        let body = self.into_body();
+        let status = body.code.status();
+        (status, [(CONTENT_TYPE, PROBLEM_JSON)], Json(body)).into_response()
     }
 }
 
@@ -84,8 +228,8 @@ impl From<sea_orm::DbErr> for ApiError {
     }
 }
 
-impl From<PasswordWorkerError<Bcrypt>> for ApiError {
-    fn from(err: PasswordWorkerError<Bcrypt>) -> Self {
+impl From<PasswordWorkerError<PasswordHasher>> for ApiError {
+    fn from(err: PasswordWorkerError<PasswordHasher>) -> Self {
         ApiError::Password(err)
     }
 }
@@ -114,8 +258,11 @@ impl From<ServiceError> for ApiError {
         match err {
             ServiceError::NotFound(target) => ApiError::NotFound(target),
             ServiceError::Conflict(target) => ApiError::Conflict(target),
+            ServiceError::PreconditionFailed(target) => ApiError::PreconditionFailed(target),
             ServiceError::BadRequest(message) => ApiError::BadRequest(message),
+            ServiceError::Validation(errors) => ApiError::Validation(errors),
             ServiceError::Unauthorized => ApiError::Unauthorized,
+            ServiceError::Forbidden => ApiError::Forbidden,
             ServiceError::Db(source) => ApiError::Db(source),
             ServiceError::Password(source) => ApiError::Password(source),
             #[cfg(feature = "web")]
@@ -1,17 +1,16 @@
-use axum::{
-    extract::FromRequestParts,
-    http::{StatusCode, request::Parts},
-};
+use axum::{extract::FromRequestParts, http::request::Parts};
 use std::future::ready;
 
 // ミドルウェアで付与された認証済みユーザ情報をそのまま再利用する
 pub use crate::middleware::auth::AuthenticatedUser;
 
+use crate::errors::ApiError;
+
 impl<S> FromRequestParts<S> for AuthenticatedUser
 where
     S: Send + Sync,
 {
-    type Rejection = StatusCode;
+    type Rejection = ApiError;
 
     fn from_request_parts(
         parts: &mut Parts,
@@ -19,6 +18,6 @@ where
     ) -> impl std::future::Future<Output = Result<Self, <Self as FromRequestParts<S>>::Rejection>> + Send
     {
         let user = parts.extensions.get::<AuthenticatedUser>().cloned();
-        ready(user.ok_or(StatusCode::UNAUTHORIZED))
+        ready(user.ok_or(ApiError::Unauthorized))
     }
 }
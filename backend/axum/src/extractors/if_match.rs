@@ -0,0 +1,65 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{header::IF_MATCH, request::Parts},
+};
+use std::future::ready;
+
+use crate::errors::ApiError;
+
+/// `If-Match` で指定された版。ヘッダがないか `*` のときは `None` で、版を問わず更新する。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IfMatch(pub Option<i32>);
+
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
+        let result = match parts.headers.get(IF_MATCH) {
+            None => Ok(IfMatch(None)),
+            Some(value) => value
+                .to_str()
+                .ok()
+                .and_then(parse_if_match)
+                .map(IfMatch)
+                .ok_or_else(|| {
+                    ApiError::BadRequest(
+                        "If-Match must be \"*\" or a version ETag such as \"3\"".to_string(),
+                    )
+                }),
+        };
+        ready(result)
+    }
+}
+
+/// 版の ETag は強い比較しか許されないため、`W/` 付きの値は受け付けない
+fn parse_if_match(value: &str) -> Option<Option<i32>> {
+    let value = value.trim();
+    if value == "*" {
+        return Some(None);
+    }
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .and_then(|version| version.parse().ok())
+        .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions_and_wildcard() {
+        assert_eq!(parse_if_match("\"3\""), Some(Some(3)));
+        assert_eq!(parse_if_match(" * "), Some(None));
+        assert_eq!(parse_if_match("W/\"3\""), None);
+        assert_eq!(parse_if_match("3"), None);
+        assert_eq!(parse_if_match("\"3\", \"4\""), None);
+    }
+}
@@ -1 +1,3 @@
 pub mod authenticated_user;
+pub mod if_match;
+pub mod validated_json;
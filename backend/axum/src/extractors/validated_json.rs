@@ -0,0 +1,148 @@
+use axum::{
+    Json,
+    extract::{FromRequest, Request, rejection::JsonRejection},
+};
+use decopon_services::errors::FieldError;
+use serde::de::DeserializeOwned;
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::errors::ApiError;
+
+/// `Json` と同様にボディを読み込み、DTO に宣言した `Validate` の規則も検査する抽出子。
+/// 規則に反する項目は 422 で項目ごとに返す。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(rejection_to_error)?;
+        value.validate().map_err(|errors| {
+            let mut fields = Vec::new();
+            collect_field_errors("", &errors, &mut fields);
+            fields.sort_by(|a, b| a.field.cmp(&b.field));
+            ApiError::Validation(fields)
+        })?;
+        Ok(Self(value))
+    }
+}
+
+fn rejection_to_error(rejection: JsonRejection) -> ApiError {
+    match rejection {
+        // 型の不一致や必須項目の欠落は入力値の誤りとして扱う
+        JsonRejection::JsonDataError(err) => {
+            ApiError::Validation(vec![FieldError::new("body", "invalid", err.body_text())])
+        }
+        other => ApiError::BadRequest(other.body_text()),
+    }
+}
+
+/// ネストした構造体や配列の要素は `parent.child` / `items[0]` の形で項目名を組み立てる
+fn collect_field_errors(prefix: &str, errors: &ValidationErrors, out: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{prefix}.{field}")
+        };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                out.extend(errors.iter().map(|error| {
+                    FieldError::new(path.clone(), error.code.clone(), describe(error))
+                }));
+            }
+            ValidationErrorsKind::Struct(inner) => collect_field_errors(&path, inner, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, inner) in items {
+                    collect_field_errors(&format!("{path}[{index}]"), inner, out);
+                }
+            }
+        }
+    }
+}
+
+/// 規則に `message` がなければ、組み込みの規則名と引数から文面を作る
+fn describe(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+    let param = |name: &str| error.params.get(name).map(|value| value.to_string());
+    match (error.code.as_ref(), param("min"), param("max")) {
+        ("length", Some(min), Some(max)) => format!("must be {min} to {max} characters long"),
+        ("length", Some(min), None) if min == "1" => "must not be empty".to_string(),
+        ("length", Some(min), None) => format!("must be at least {min} characters long"),
+        ("length", None, Some(max)) => format!("must be at most {max} characters long"),
+        ("range", Some(min), Some(max)) => format!("must be between {min} and {max}"),
+        ("range", Some(min), None) => format!("must be at least {min}"),
+        ("range", None, Some(max)) => format!("must be at most {max}"),
+        ("email", ..) => "must be a valid email address".to_string(),
+        _ => "is invalid".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Validate)]
+    struct Item {
+        #[validate(length(min = 1))]
+        name: String,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Payload {
+        #[validate(length(min = 1, max = 3))]
+        title: String,
+        #[validate(range(min = 1))]
+        count: i32,
+        #[validate(nested)]
+        items: Vec<Item>,
+    }
+
+    #[test]
+    fn collects_nested_field_errors_with_messages() {
+        let payload = Payload {
+            title: "long".to_string(),
+            count: 0,
+            items: vec![
+                Item {
+                    name: "ok".to_string(),
+                },
+                Item {
+                    name: String::new(),
+                },
+            ],
+        };
+        let mut fields = Vec::new();
+        collect_field_errors("", &payload.validate().unwrap_err(), &mut fields);
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+
+        let summary: Vec<_> = fields
+            .iter()
+            .map(|error| {
+                (
+                    error.field.as_str(),
+                    error.code.as_ref(),
+                    error.message.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("count", "range", "must be at least 1"),
+                ("items[1].name", "length", "must not be empty"),
+                ("title", "length", "must be 1 to 3 characters long"),
+            ]
+        );
+    }
+}
@@ -2,21 +2,22 @@ pub mod dto;
 pub mod errors;
 pub mod extractors;
 pub mod middleware;
+pub mod openapi;
 pub mod routes;
 
 use decopon_runtime::{bootstrap_runtime_from_env, RuntimeBootstrapOptions};
 pub use decopon_services::{
-    ServiceContext, ServiceContextBuilder, ServiceError, entities, usecases,
+    ServiceContext, ServiceContextBuilder, ServiceError, entities, events, mail_transport,
+    password, usecases,
 };
 
 use axum::{
     extract::FromRef,
     http::{
         HeaderValue, Method,
-        header::{AUTHORIZATION, CONTENT_TYPE, HeaderName},
+        header::{AUTHORIZATION, CONTENT_TYPE, ETAG, HeaderName, IF_MATCH, IF_NONE_MATCH},
     },
 };
-use axum_password_worker::{Bcrypt, PasswordWorker};
 use dotenvy::{dotenv, from_path};
 use sea_orm::DatabaseConnection;
 use std::{
@@ -29,7 +30,15 @@ use std::{
 use tower_http::cors::{Any, CorsLayer};
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
-use usecases::{mails::Mailer, single_user::SingleUserSession};
+#[cfg(feature = "web")]
+use usecases::oidc::OidcClient;
+use middleware::{
+    idempotency::{IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER},
+    request_id::REQUEST_ID_HEADER,
+};
+use events::EventBus;
+use password::PasswordWorker;
+use usecases::{mails::Mailer, single_user::SingleUserSession, webhooks::TargetPolicy};
 
 fn resolve_env_candidates(primary: &str) -> Vec<PathBuf> {
     let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
@@ -101,11 +110,11 @@ impl AppState {
         self.services().db_arc()
     }
 
-    pub fn password_worker(&self) -> &PasswordWorker<Bcrypt> {
+    pub fn password_worker(&self) -> &PasswordWorker {
         self.services().password_worker()
     }
 
-    pub fn password_worker_arc(&self) -> Arc<PasswordWorker<Bcrypt>> {
+    pub fn password_worker_arc(&self) -> Arc<PasswordWorker> {
         self.services().password_worker_arc()
     }
 
@@ -124,6 +133,15 @@ impl AppState {
     pub fn single_user_session(&self) -> Option<SingleUserSession> {
         self.services().single_user_session_owned()
     }
+
+    #[cfg(feature = "web")]
+    pub fn oidc(&self) -> Option<&OidcClient> {
+        self.services().oidc()
+    }
+
+    pub fn events(&self) -> Arc<EventBus> {
+        self.services().events_arc()
+    }
 }
 
 impl From<ServiceContext> for AppState {
@@ -138,7 +156,7 @@ impl FromRef<AppState> for Arc<DatabaseConnection> {
     }
 }
 
-impl FromRef<AppState> for Arc<PasswordWorker<Bcrypt>> {
+impl FromRef<AppState> for Arc<PasswordWorker> {
     fn from_ref(state: &AppState) -> Self {
         state.password_worker_arc()
     }
@@ -150,6 +168,18 @@ impl FromRef<AppState> for Option<Mailer> {
     }
 }
 
+impl FromRef<AppState> for Arc<EventBus> {
+    fn from_ref(state: &AppState) -> Self {
+        state.events()
+    }
+}
+
+impl FromRef<AppState> for TargetPolicy {
+    fn from_ref(state: &AppState) -> Self {
+        state.services().webhook_targets()
+    }
+}
+
 impl FromRef<AppState> for Option<SingleUserSession> {
     fn from_ref(state: &AppState) -> Self {
         state.single_user_session()
@@ -192,8 +222,13 @@ pub fn setup_cors() -> CorsLayer {
     .allow_headers([
         AUTHORIZATION,
         CONTENT_TYPE,
+        IF_MATCH,
+        IF_NONE_MATCH,
+        IDEMPOTENCY_KEY_HEADER,
+        HeaderName::from_static("last-event-id"),
         HeaderName::from_static("x-requested-with"),
     ])
+    .expose_headers([REQUEST_ID_HEADER, ETAG, IDEMPOTENT_REPLAYED_HEADER])
     .allow_credentials(true)
 }
 
@@ -0,0 +1,30 @@
+use axum::{body::Body, extract::State, http::Request, middleware::Next, response::Response};
+
+use crate::{
+    errors::ApiError,
+    middleware::auth::{AuthenticatedUser, Impersonation, PersonalAccessTokenAuth},
+    usecases::admin,
+    AppState,
+};
+
+/// 管理 API へのアクセスを有効な管理者のログインセッションに限定するミドルウェア。
+/// `auth_middleware` の内側に配置し、PAT や代理ログインのトークンは拒否する。
+pub async fn admin_middleware(
+    State(app_state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let extensions = req.extensions();
+    if extensions.get::<PersonalAccessTokenAuth>().is_some()
+        || extensions.get::<Impersonation>().is_some()
+    {
+        return Err(ApiError::Forbidden);
+    }
+    let user = extensions
+        .get::<AuthenticatedUser>()
+        .ok_or(ApiError::Unauthorized)?;
+
+    admin::require_admin(app_state.db(), user.id).await?;
+
+    Ok(next.run(req).await)
+}
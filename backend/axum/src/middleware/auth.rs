@@ -1,15 +1,21 @@
 use axum::{
     body::Body,
     extract::State,
-    http::{header::AUTHORIZATION, Request, StatusCode},
+    http::{header::AUTHORIZATION, Method, Request},
     middleware::Next,
     response::Response,
 };
 
 use crate::{
-    usecases::auth::{decode_jwt, verify_jwt},
+    entities::prelude::Users,
+    errors::ApiError,
+    usecases::{
+        auth::{decode_jwt, verify_jwt},
+        personal_access_tokens::{self, TokenScope},
+    },
     AppState,
 };
+use sea_orm::EntityTrait;
 
 #[derive(Clone, Debug)]
 pub struct AuthenticatedUser {
@@ -17,13 +23,40 @@ pub struct AuthenticatedUser {
     pub exp: usize,
 }
 
+/// パーソナルアクセストークンで認証されたリクエストにだけ付与される情報
+#[derive(Clone, Debug)]
+pub struct PersonalAccessTokenAuth {
+    pub token_id: i32,
+    pub scope: TokenScope,
+}
+
+/// 管理者による代理ログインのトークンで認証されたリクエストにだけ付与される情報
+#[derive(Clone, Debug)]
+pub struct Impersonation {
+    pub admin_id: i32,
+}
+
+/// 削除済み・無効化済みのユーザーに発行済みのトークンを拒否する
+async fn ensure_active_user(app_state: &AppState, user_id: i32) -> Result<(), ApiError> {
+    let user = Users::find_by_id(user_id)
+        .one(app_state.db())
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+    if user.disabled_at.is_some() {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(())
+}
+
 /// ローカル（シングルユーザー）モードで JWT を要求せず固定ユーザーを注入するミドルウェア
 pub async fn local_single_user_middleware(
     State(app_state): State<AppState>,
     mut req: Request<Body>,
     next: Next,
-) -> Result<Response, StatusCode> {
-    let session = app_state.single_user_session().ok_or(StatusCode::UNAUTHORIZED)?;
+) -> Result<Response, ApiError> {
+    let session = app_state
+        .single_user_session()
+        .ok_or(ApiError::Unauthorized)?;
     let user = AuthenticatedUser {
         id: session.user.id,
         exp: usize::MAX,
@@ -36,7 +69,7 @@ pub async fn auth_middleware(
     State(app_state): State<AppState>,
     mut req: Request<Body>,
     next: Next,
-) -> Result<Response, StatusCode> {
+) -> Result<Response, ApiError> {
     // AuthorizationヘッダからBearerトークンを取得
     let token = req
         .headers()
@@ -44,15 +77,48 @@ pub async fn auth_middleware(
         .and_then(|h| h.to_str().ok())
         .and_then(|s| s.strip_prefix("Bearer "))
         .map(|s| s.to_string())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+        .ok_or(ApiError::Unauthorized)?;
+
+    if personal_access_tokens::is_personal_access_token(&token) {
+        let token = personal_access_tokens::authenticate_token(app_state.db(), &token).await?;
+
+        // 読み取り専用トークンでは参照系メソッドのみ許可する
+        if !token.scope.allows_write() && !is_read_only_method(req.method()) {
+            return Err(ApiError::Forbidden);
+        }
+        ensure_active_user(&app_state, token.user_id).await?;
+
+        let user = AuthenticatedUser {
+            id: token.user_id,
+            exp: token
+                .expires_at
+                .map(|expires_at| expires_at.timestamp() as usize)
+                .unwrap_or(usize::MAX),
+        };
+        req.extensions_mut().insert(user);
+        req.extensions_mut().insert(PersonalAccessTokenAuth {
+            token_id: token.id,
+            scope: token.scope,
+        });
+        return Ok(next.run(req).await);
+    }
 
     // AppStateからシークレットを取得
     let secret = app_state.jwt_secret().to_owned();
 
     // JWTをデコード
-    let claims = decode_jwt(token, &secret).map_err(|_| StatusCode::UNAUTHORIZED)?;
-    if !verify_jwt(&claims).map_err(|_| StatusCode::UNAUTHORIZED)? {
-        return Err(StatusCode::UNAUTHORIZED);
+    let claims = decode_jwt(token, &secret).map_err(|_| ApiError::Unauthorized)?;
+    if !verify_jwt(&claims).map_err(|_| ApiError::Unauthorized)? {
+        return Err(ApiError::Unauthorized);
+    }
+    ensure_active_user(&app_state, claims.sub).await?;
+
+    // 代理ログインは閲覧のみを許可する
+    if let Some(admin_id) = claims.impersonated_by {
+        if !is_read_only_method(req.method()) {
+            return Err(ApiError::Forbidden);
+        }
+        req.extensions_mut().insert(Impersonation { admin_id });
     }
 
     let user = AuthenticatedUser {
@@ -66,3 +132,7 @@ pub async fn auth_middleware(
     // 次のハンドラへ
     Ok(next.run(req).await)
 }
+
+fn is_read_only_method(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
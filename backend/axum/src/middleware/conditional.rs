@@ -0,0 +1,86 @@
+use axum::{
+    body::{Body, HttpBody, to_bytes},
+    http::{
+        HeaderValue, Method, Request, StatusCode,
+        header::{ETAG, IF_NONE_MATCH},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+/// 版を持つリソースの ETag。`If-Match` にそのまま渡せる強い ETag になる
+pub fn version_etag(version: i32) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{version}\"")).expect("version etag is a valid header")
+}
+
+/// レスポンスに版の ETag を付ける
+pub struct Versioned<T>(pub i32, pub T);
+
+impl<T: IntoResponse> IntoResponse for Versioned<T> {
+    fn into_response(self) -> Response {
+        ([(ETAG, version_etag(self.0))], self.1).into_response()
+    }
+}
+
+/// GET の成功レスポンスに ETag を付け、`If-None-Match` と一致すれば 304 を返すミドルウェア。
+/// ハンドラが ETag を付けていなければ本文のハッシュから弱い ETag を作る。
+/// 少しずつ送る本文 (SSE や CSV のダウンロード) は、読み切らないようそのまま返す。
+pub async fn conditional_get_middleware(req: Request<Body>, next: Next) -> Response {
+    if req.method() != Method::GET {
+        return next.run(req).await;
+    }
+    let if_none_match = req.headers().get(IF_NONE_MATCH).cloned();
+
+    let response = next.run(req).await;
+    if response.status() != StatusCode::OK || is_streamed(&response) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let body = match parts.headers.get(ETAG) {
+        Some(_) => body,
+        None => {
+            let bytes = match to_bytes(body, usize::MAX).await {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    tracing::error!(?err, "failed to buffer response body");
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            };
+            parts.headers.insert(ETAG, content_etag(&bytes));
+            Body::from(bytes)
+        }
+    };
+
+    let etag = parts.headers.get(ETAG).cloned();
+    if let (Some(etag), Some(if_none_match)) = (etag, if_none_match)
+        && matches_any(&if_none_match, &etag)
+    {
+        return (StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response();
+    }
+    Response::from_parts(parts, body)
+}
+
+fn content_etag(bytes: &[u8]) -> HeaderValue {
+    let digest = Sha256::digest(bytes);
+    let hash: String = digest[..16].iter().map(|b| format!("{b:02x}")).collect();
+    HeaderValue::from_str(&format!("W/\"{hash}\"")).expect("content etag is a valid header")
+}
+
+/// `If-None-Match` は弱い比較なので `W/` の有無は無視する
+fn matches_any(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    let (Ok(candidates), Ok(etag)) = (if_none_match.to_str(), etag.to_str()) else {
+        return false;
+    };
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    candidates
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == etag)
+}
+
+/// 長さが決まっていない本文は、ストリームとして送られている
+fn is_streamed(response: &Response) -> bool {
+    response.body().size_hint().exact().is_none()
+}
@@ -0,0 +1,116 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::State,
+    http::{
+        HeaderName, HeaderValue, Method, Request, StatusCode,
+        header::{CONTENT_TYPE, ETAG, LOCATION},
+    },
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    AppState,
+    errors::ApiError,
+    extractors::authenticated_user::AuthenticatedUser,
+    routes,
+    usecases::idempotency::{self, Reservation, StoredResponse},
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+/// 保存済みのレスポンスを再送したときに付けるヘッダ
+pub const IDEMPOTENT_REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// 再送時に最初のレスポンスと同じ値を返すヘッダ。リクエスト ID などは再送ごとに付け直す
+const REPLAYED_HEADERS: [HeaderName; 3] = [CONTENT_TYPE, ETAG, LOCATION];
+
+/// ハッシュを取るために読み込むリクエスト本文の上限。最も大きな本文を受け付けるアーカイブの取り込みに合わせ、
+/// ルートごとの上限は読み直した本文に対してハンドラ側で確かめる
+const MAX_BODY_BYTES: usize = routes::profiles::IMPORT_BODY_LIMIT;
+
+/// `Idempotency-Key` 付きの POST の成功レスポンスを保存し、同じキーの再送には保存済みのレスポンスを返すミドルウェア。
+/// 最初のリクエストの処理中に届いた再送は 409 にする。
+/// 認証済みユーザーごとにキーを区別するため、認証ミドルウェアの内側に置く。
+pub async fn idempotency_middleware(
+    State(app_state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if req.method() != Method::POST {
+        return Ok(next.run(req).await);
+    }
+    let Some(key) = req.headers().get(&IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(next.run(req).await);
+    };
+    let key = key
+        .to_str()
+        .ok()
+        .filter(|key| idempotency::is_valid_key(key))
+        .map(str::to_string)
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "Idempotency-Key must be 1 to {} visible ASCII characters",
+                idempotency::MAX_KEY_LEN
+            ))
+        })?;
+    let user_id = req
+        .extensions()
+        .get::<AuthenticatedUser>()
+        .map(|user| user.id)
+        .ok_or(ApiError::Unauthorized)?;
+
+    let (parts, body) = req.into_parts();
+    let body = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+    let path = parts
+        .uri
+        .path_and_query()
+        .map_or_else(|| parts.uri.path(), |path| path.as_str());
+    let hash = idempotency::request_hash(parts.method.as_str(), path, &body);
+
+    let db = app_state.db();
+    if let Reservation::Completed(stored) = idempotency::reserve(db, user_id, &key, &hash).await? {
+        return Ok(replay(stored));
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    // 失敗したリクエストはやり直せるように保存しない
+    if !response.status().is_success() {
+        idempotency::release(db, user_id, &key).await?;
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|err| ApiError::Internal(Box::new(err)))?;
+    let headers = REPLAYED_HEADERS
+        .iter()
+        .filter_map(|name| {
+            let value = parts.headers.get(name)?.to_str().ok()?;
+            Some((name.as_str().to_string(), value.to_string()))
+        })
+        .collect();
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        headers,
+        body: body.to_vec(),
+    };
+    idempotency::complete(db, user_id, &key, stored).await?;
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    for (name, value) in stored.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            headers.insert(name, value);
+        }
+    }
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
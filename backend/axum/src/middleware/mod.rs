@@ -1 +1,5 @@
+pub mod admin;
 pub mod auth;
+pub mod conditional;
+pub mod idempotency;
+pub mod request_id;
@@ -0,0 +1,159 @@
+//! ルートと DTO から生成する OpenAPI 3 仕様です。
+//! 仕様は `openapi.json` としてコミットし、`tests/openapi.rs` で差分を検出します。
+
+use utoipa::{
+    Modify, OpenApi, PartialSchema,
+    openapi::{
+        RefOr, Schema,
+        path::{ParameterBuilder, ParameterIn},
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    },
+};
+
+use crate::errors::PROBLEM_JSON;
+use crate::routes::{
+    admin, auth, batch, calendar_feed, decopon_sessions, events, journal, logs,
+    personal_access_tokens, preferences, profiles, sync, tags, tasks, webhooks,
+};
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT or personal access token")
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// `ErrorBody` を返すレスポンスのメディアタイプを problem+json にする
+struct ProblemJsonAddon;
+
+impl Modify for ProblemJsonAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let responses = openapi
+            .paths
+            .paths
+            .values_mut()
+            .flat_map(|item| {
+                [
+                    &mut item.get,
+                    &mut item.put,
+                    &mut item.post,
+                    &mut item.delete,
+                    &mut item.patch,
+                ]
+            })
+            .flatten()
+            .flat_map(|operation| operation.responses.responses.values_mut());
+        for response in responses {
+            let RefOr::T(response) = response else {
+                continue;
+            };
+            let is_problem = response
+                .content
+                .get("application/json")
+                .is_some_and(|content| is_error_body(&content.schema));
+            if is_problem && let Some(content) = response.content.shift_remove("application/json") {
+                response.content.insert(PROBLEM_JSON.to_string(), content);
+            }
+        }
+    }
+}
+
+/// 認証が必要な POST に `Idempotency-Key` ヘッダを載せる（`middleware::idempotency`）
+struct IdempotencyKeyAddon;
+
+impl Modify for IdempotencyKeyAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let operations = openapi
+            .paths
+            .paths
+            .iter_mut()
+            .filter(|(path, _)| !path.starts_with("/auth"))
+            .filter_map(|(_, item)| item.post.as_mut());
+        for operation in operations {
+            let parameter = ParameterBuilder::new()
+                .name("Idempotency-Key")
+                .parameter_in(ParameterIn::Header)
+                .description(Some(
+                    "Replays the stored response when the same request is retried within 24 hours; 409 while the first request is still being processed",
+                ))
+                .schema(Some(String::schema()))
+                .build();
+            operation
+                .parameters
+                .get_or_insert_with(Vec::new)
+                .push(parameter);
+        }
+    }
+}
+
+fn is_error_body(schema: &Option<RefOr<Schema>>) -> bool {
+    matches!(schema, Some(RefOr::Ref(r)) if r.ref_location.ends_with("/ErrorBody"))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Decopon API"),
+    security(("bearer_auth" = [])),
+    modifiers(&SecurityAddon)
+)]
+struct ApiDoc;
+
+/// 各ルートモジュールの仕様を `routes::create_routes` と同じパスに束ねる。
+pub fn api_doc() -> utoipa::openapi::OpenApi {
+    let mut doc = ApiDoc::openapi()
+        .nest_with_path_composer("/auth", auth::AuthApi::openapi(), compose)
+        .nest_with_path_composer("/batch", batch::BatchApi::openapi(), compose)
+        .nest_with_path_composer("/calendar", calendar_feed::CalendarApi::openapi(), compose)
+        .nest_with_path_composer(
+            "/decopon_sessions",
+            decopon_sessions::DecoponSessionsApi::openapi(),
+            compose,
+        )
+        .nest_with_path_composer("/events", events::EventsApi::openapi(), compose)
+        .nest_with_path_composer("/journal", journal::JournalApi::openapi(), compose)
+        .nest_with_path_composer("/logs", logs::LogsApi::openapi(), compose)
+        .nest_with_path_composer("/profiles", profiles::ProfilesApi::openapi(), compose)
+        .nest_with_path_composer(
+            "/profiles/calendar_feed",
+            calendar_feed::CalendarFeedApi::openapi(),
+            compose,
+        )
+        .nest_with_path_composer(
+            "/profiles/tokens",
+            personal_access_tokens::PersonalAccessTokensApi::openapi(),
+            compose,
+        )
+        .nest_with_path_composer(
+            "/preferences",
+            preferences::PreferencesApi::openapi(),
+            compose,
+        )
+        .nest_with_path_composer("/sync", sync::SyncApi::openapi(), compose)
+        .nest_with_path_composer("/tags", tags::TagsApi::openapi(), compose)
+        .nest_with_path_composer("/tasks", tasks::TasksApi::openapi(), compose)
+        .nest_with_path_composer("/webhooks", webhooks::WebhooksApi::openapi(), compose)
+        // 管理 API は Web モードでのみ有効
+        .nest_with_path_composer("/admin", admin::AdminApi::openapi(), compose);
+    ProblemJsonAddon.modify(&mut doc);
+    IdempotencyKeyAddon.modify(&mut doc);
+    doc
+}
+
+/// axum の `nest` と同様に、ルート直下の `/` はベースパスそのものにする。
+fn compose(base: &str, path: &str) -> String {
+    if path == "/" {
+        base.to_string()
+    } else {
+        format!("{base}{path}")
+    }
+}
@@ -0,0 +1,223 @@
+use axum::{
+    Extension, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    middleware,
+    response::Json,
+    routing::{delete, get, post},
+};
+use utoipa::OpenApi;
+
+use crate::dto::admin::*;
+use crate::{
+    AppState,
+    errors::{ApiError, ErrorBody},
+    extractors::authenticated_user::AuthenticatedUser,
+    middleware::admin::admin_middleware,
+    usecases::{
+        admin,
+        ids::{self, RowRef},
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "admin",
+    params(ListUsersQuery),
+    responses((status = 200, body = PageResponse<AdminUserResponse>))
+)]
+#[tracing::instrument(skip(app_state))]
+async fn index(
+    State(app_state): State<AppState>,
+    Query(query): Query<ListUsersQuery>,
+) -> Result<Json<PageResponse<AdminUserResponse>>, ApiError> {
+    let params = admin::UserSearch {
+        query: query.q,
+        pagination: admin::Pagination::new(query.page, query.per_page),
+    };
+    let page = admin::list_users(app_state.db(), params).await?;
+    Ok(Json(page.into()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Target user id or UUID")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 400, description = "Cannot target yourself", body = ErrorBody),
+        (status = 404, description = "User not found", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(app_state, user))]
+async fn destroy(
+    Path(id): Path<RowRef>,
+    State(app_state): State<AppState>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<StatusCode, ApiError> {
+    let id = ids::user_id(app_state.db(), &id).await?;
+    admin::delete_user(app_state.db(), user.id, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/disable",
+    tag = "admin",
+    params(("id" = String, Path, description = "Target user id or UUID")),
+    responses(
+        (status = 200, body = AdminUserResponse),
+        (status = 400, description = "Cannot target yourself", body = ErrorBody),
+        (status = 404, description = "User not found", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(app_state, user))]
+async fn disable(
+    Path(id): Path<RowRef>,
+    State(app_state): State<AppState>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<Json<AdminUserResponse>, ApiError> {
+    let id = ids::user_id(app_state.db(), &id).await?;
+    let target = admin::disable_user(app_state.db(), user.id, id).await?;
+    Ok(Json(target.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/enable",
+    tag = "admin",
+    params(("id" = String, Path, description = "Target user id or UUID")),
+    responses(
+        (status = 200, body = AdminUserResponse),
+        (status = 404, description = "User not found", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(app_state, user))]
+async fn enable(
+    Path(id): Path<RowRef>,
+    State(app_state): State<AppState>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<Json<AdminUserResponse>, ApiError> {
+    let id = ids::user_id(app_state.db(), &id).await?;
+    let target = admin::enable_user(app_state.db(), user.id, id).await?;
+    Ok(Json(target.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/impersonate",
+    tag = "admin",
+    params(("id" = String, Path, description = "Target user id or UUID")),
+    responses(
+        (status = 200, body = ImpersonationResponse),
+        (status = 400, description = "Cannot impersonate yourself or a disabled user", body = ErrorBody),
+        (status = 404, description = "User not found", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(app_state, user))]
+async fn impersonate(
+    Path(id): Path<RowRef>,
+    State(app_state): State<AppState>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<Json<ImpersonationResponse>, ApiError> {
+    let id = ids::user_id(app_state.db(), &id).await?;
+    let token =
+        admin::impersonate_user(app_state.db(), app_state.jwt_secret(), user.id, id).await?;
+    Ok(Json(ImpersonationResponse { token }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/verification",
+    tag = "admin",
+    params(("id" = String, Path, description = "Target user id or UUID")),
+    responses(
+        (status = 202, description = "Verification mail queued"),
+        (status = 404, description = "User not found", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(app_state, user))]
+async fn resend_verification(
+    Path(id): Path<RowRef>,
+    State(app_state): State<AppState>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<StatusCode, ApiError> {
+    let id = ids::user_id(app_state.db(), &id).await?;
+    admin::resend_verification(app_state.db(), app_state.mailer(), user.id, id).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/password_reset",
+    tag = "admin",
+    params(("id" = String, Path, description = "Target user id or UUID")),
+    responses(
+        (status = 202, description = "Password reset mail queued"),
+        (status = 400, description = "Mail delivery is disabled", body = ErrorBody),
+        (status = 404, description = "User not found", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(app_state, user))]
+async fn force_password_reset(
+    Path(id): Path<RowRef>,
+    State(app_state): State<AppState>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<StatusCode, ApiError> {
+    let id = ids::user_id(app_state.db(), &id).await?;
+    admin::force_password_reset(
+        app_state.db(),
+        app_state.password_worker(),
+        app_state.mailer(),
+        user.id,
+        id,
+    )
+    .await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit_logs",
+    tag = "admin",
+    params(PageQuery),
+    responses((status = 200, body = PageResponse<AuditLogResponse>))
+)]
+#[tracing::instrument(skip(app_state))]
+async fn audit_logs(
+    State(app_state): State<AppState>,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<PageResponse<AuditLogResponse>>, ApiError> {
+    let pagination = admin::Pagination::new(query.page, query.per_page);
+    let page = admin::list_audit_logs(app_state.db(), pagination).await?;
+    Ok(Json(page.into()))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    index,
+    destroy,
+    disable,
+    enable,
+    impersonate,
+    resend_verification,
+    force_password_reset,
+    audit_logs
+))]
+pub(crate) struct AdminApi;
+
+/// 管理者専用のルート。`auth_middleware` の内側にネストすること。
+pub fn routes(app_state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/users", get(index))
+        .route("/users/{id}", delete(destroy))
+        .route("/users/{id}/disable", post(disable))
+        .route("/users/{id}/enable", post(enable))
+        .route("/users/{id}/impersonate", post(impersonate))
+        .route("/users/{id}/verification", post(resend_verification))
+        .route("/users/{id}/password_reset", post(force_password_reset))
+        .route("/audit_logs", get(audit_logs))
+        .route_layer(middleware::from_fn_with_state(app_state, admin_middleware))
+}
@@ -0,0 +1,121 @@
+//! アカウントのアーカイブを、サービス層が返すページごとに少しずつ送る。
+//! 配列以外の部分だけは応答を返す前に読み、DB のエラーを通常のエラー応答にする。
+
+use std::{
+    future::{Future, ready},
+    sync::Arc,
+};
+
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use futures_util::{Stream, StreamExt, TryStreamExt, stream};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+
+use crate::{
+    ServiceError,
+    errors::ApiError,
+    usecases::archive::{self, ArchivePage},
+};
+
+pub(crate) async fn archive_download(
+    db: Arc<DatabaseConnection>,
+    user_id: i32,
+) -> Result<Response, ApiError> {
+    let header = archive::export_header(&db, user_id).await?;
+    // 配列を続けて書けるよう、閉じ括弧を外しておく
+    let mut head = serde_json::to_vec(&header).map_err(internal)?;
+    head.pop();
+
+    let tags = section("tags", {
+        let db = db.clone();
+        move |after| {
+            let db = db.clone();
+            async move { archive::tags_page(&db, user_id, after).await }
+        }
+    });
+    let tasks = section("tasks", {
+        let db = db.clone();
+        move |after| {
+            let db = db.clone();
+            async move { archive::tasks_page(&db, user_id, after).await }
+        }
+    });
+    let logs = section("logs", {
+        let db = db.clone();
+        move |after| {
+            let db = db.clone();
+            async move { archive::logs_page(&db, user_id, after).await }
+        }
+    });
+    let sessions = section("decopon_sessions", move |after| {
+        let db = db.clone();
+        async move { archive::sessions_page(&db, user_id, after).await }
+    });
+    let rest = tags
+        .chain(tasks)
+        .chain(logs)
+        .chain(sessions)
+        .chain(stream::once(ready(Ok(b"}".to_vec()))))
+        .inspect_err(
+            |error: &ServiceError| tracing::error!(error = %error, "Archive export failed"),
+        );
+    let body = Body::from_stream(stream::once(ready(Ok(head))).chain(rest));
+
+    let disposition = format!(
+        "attachment; filename=\"decopon-{}.json\"",
+        Utc::now().format("%Y%m%d")
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// `,"name":[...]` を、ページを読むたびに送る
+fn section<T, C, F, Fut>(
+    name: &'static str,
+    next_page: F,
+) -> impl Stream<Item = Result<Vec<u8>, ServiceError>> + Send + 'static
+where
+    T: Serialize,
+    C: Send + 'static,
+    F: FnMut(Option<C>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<ArchivePage<T, C>, ServiceError>> + Send + 'static,
+{
+    let open = format!(",\"{name}\":[").into_bytes();
+    // 状態が `None` なら読み終えている。最初のページは `Some(None)` から読む
+    let pages = stream::try_unfold(
+        (next_page, Some(None)),
+        |(mut next_page, after): (F, Option<Option<C>>)| async move {
+            let Some(after) = after else {
+                return Ok(None);
+            };
+            let first = after.is_none();
+            let page = next_page(after).await?;
+            let mut chunk = Vec::new();
+            for item in &page.items {
+                if !first || !chunk.is_empty() {
+                    chunk.push(b',');
+                }
+                serde_json::to_writer(&mut chunk, item).map_err(internal)?;
+            }
+            Ok(Some((chunk, (next_page, page.next.map(Some)))))
+        },
+    );
+    stream::once(ready(Ok(open)))
+        .chain(pages)
+        .chain(stream::once(ready(Ok(b"]".to_vec()))))
+}
+
+fn internal(error: serde_json::Error) -> ServiceError {
+    ServiceError::Internal(Box::new(error))
+}
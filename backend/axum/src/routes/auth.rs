@@ -9,7 +9,8 @@ use axum_macros::debug_handler;
 
 use crate::AppState;
 use crate::dto::auth::*;
-use crate::errors::ApiError;
+use crate::errors::{ApiError, ErrorBody};
+use utoipa::OpenApi;
 
 #[cfg(feature = "web")]
 use axum::{
@@ -19,9 +20,19 @@ use axum::{
 };
 
 #[cfg(feature = "web")]
-use crate::usecases;
+use crate::{extractors::validated_json::ValidatedJson, usecases};
 
 #[cfg(feature = "app")]
+#[utoipa::path(
+    get,
+    path = "/local/session",
+    tag = "auth",
+    security(()),
+    responses(
+        (status = 200, body = AuthResponse),
+        (status = 404, description = "Session not found", body = ErrorBody)
+    )
+)]
 #[debug_handler]
 #[tracing::instrument(skip(app_state))]
 async fn get_single_user_session(
@@ -40,11 +51,23 @@ async fn get_single_user_session(
 }
 
 #[cfg(feature = "web")]
+#[utoipa::path(
+    post,
+    path = "/users",
+    tag = "auth",
+    request_body = RegisterUserRequest,
+    security(()),
+    responses(
+        (status = 201, body = RegisterUserResponse),
+        (status = 409, description = "Email already in use", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
 #[debug_handler]
 #[tracing::instrument(skip(app_state, payload))]
 async fn register_user(
     State(app_state): State<AppState>,
-    Json(payload): Json<RegisterUserRequest>,
+    ValidatedJson(payload): ValidatedJson<RegisterUserRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     let result = usecases::auth::register_user(
         app_state.db(),
@@ -64,6 +87,15 @@ async fn register_user(
 }
 
 #[cfg(feature = "web")]
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "auth",
+    responses(
+        (status = 200, body = GetAuthUserResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody)
+    )
+)]
 #[debug_handler]
 #[tracing::instrument(skip(app_state, headers))]
 async fn get_auth_user(
@@ -82,11 +114,23 @@ async fn get_auth_user(
 }
 
 #[cfg(feature = "web")]
+#[utoipa::path(
+    post,
+    path = "/sessions",
+    tag = "auth",
+    request_body = LoginRequest,
+    security(()),
+    responses(
+        (status = 200, body = AuthResponse),
+        (status = 401, description = "Invalid credentials", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
 #[debug_handler]
 #[tracing::instrument(skip(app_state, payload))]
 async fn login(
     State(app_state): State<AppState>,
-    Json(payload): Json<LoginRequest>,
+    ValidatedJson(payload): ValidatedJson<LoginRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     let result = usecases::auth::login_user(
         app_state.db(),
@@ -107,6 +151,12 @@ async fn login(
 }
 
 #[cfg(feature = "web")]
+#[utoipa::path(
+    delete,
+    path = "/sessions",
+    tag = "auth",
+    responses((status = 204, description = "Logged out"))
+)]
 #[tracing::instrument]
 async fn logout() -> StatusCode {
     // No-op for logout, as JWTs are stateless
@@ -115,22 +165,45 @@ async fn logout() -> StatusCode {
 }
 
 #[cfg(feature = "web")]
+#[utoipa::path(
+    post,
+    path = "/password/forgot",
+    tag = "auth",
+    request_body = ForgotPasswordRequest,
+    security(()),
+    responses(
+        (status = 200, description = "Reset mail queued if the address is registered"),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
 #[debug_handler]
 #[tracing::instrument(skip(app_state, payload))]
 async fn forgot_password(
     State(app_state): State<AppState>,
-    Json(payload): Json<ForgotPasswordRequest>,
+    ValidatedJson(payload): ValidatedJson<ForgotPasswordRequest>,
 ) -> Result<StatusCode, ApiError> {
     usecases::auth::forgot_password(app_state.db(), app_state.mailer(), &payload.email).await?;
     Ok(StatusCode::OK)
 }
 
 #[cfg(feature = "web")]
+#[utoipa::path(
+    post,
+    path = "/password/reset",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    security(()),
+    responses(
+        (status = 200, description = "Password updated"),
+        (status = 400, description = "Invalid or expired token", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
 #[debug_handler]
 #[tracing::instrument(skip(app_state, payload))]
 async fn reset_password(
     State(app_state): State<AppState>,
-    Json(payload): Json<ResetPasswordRequest>,
+    ValidatedJson(payload): ValidatedJson<ResetPasswordRequest>,
 ) -> Result<StatusCode, ApiError> {
     usecases::auth::reset_password(
         app_state.db(),
@@ -144,6 +217,17 @@ async fn reset_password(
 }
 
 #[cfg(feature = "web")]
+#[utoipa::path(
+    get,
+    path = "/email/verify/{token}",
+    tag = "auth",
+    params(("token" = String, Path)),
+    security(()),
+    responses(
+        (status = 200, body = AuthResponse),
+        (status = 400, description = "Invalid or expired token", body = ErrorBody)
+    )
+)]
 #[debug_handler]
 #[tracing::instrument(skip(app_state, token))]
 async fn verify_email(
@@ -162,12 +246,49 @@ async fn verify_email(
 }
 
 #[cfg(feature = "web")]
+#[utoipa::path(
+    get,
+    path = "/email/change/{token}",
+    tag = "auth",
+    params(("token" = String, Path)),
+    security(()),
+    responses(
+        (status = 200, body = GetAuthUserResponse),
+        (status = 400, description = "Invalid or expired token", body = ErrorBody),
+        (status = 409, description = "Email already in use", body = ErrorBody)
+    )
+)]
+#[debug_handler]
+#[tracing::instrument(skip(app_state, token))]
+async fn confirm_email_change(
+    State(app_state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = usecases::profiles::confirm_email_change(app_state.db(), &token).await?;
+    Ok((
+        StatusCode::OK,
+        Json(GetAuthUserResponse { user: user.into() }),
+    ))
+}
+
+#[cfg(feature = "web")]
+#[utoipa::path(
+    post,
+    path = "/password/confirm",
+    tag = "auth",
+    request_body = ConfirmPasswordRequest,
+    responses(
+        (status = 200, body = StatusResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
 #[debug_handler]
 #[tracing::instrument(skip(app_state, headers, payload))]
 async fn confirm_password(
     State(app_state): State<AppState>,
     headers: HeaderMap,
-    Json(payload): Json<ConfirmPasswordRequest>,
+    ValidatedJson(payload): ValidatedJson<ConfirmPasswordRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     let token = extract_bearer_token(&headers)?;
     usecases::auth::confirm_password(
@@ -188,11 +309,22 @@ async fn confirm_password(
 }
 
 #[cfg(feature = "web")]
+#[utoipa::path(
+    post,
+    path = "/email/resend",
+    tag = "auth",
+    request_body = ResendVerificationRequest,
+    security(()),
+    responses(
+        (status = 200, body = StatusResponse),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
 #[debug_handler]
 #[tracing::instrument(skip(app_state, payload))]
 async fn resend_verification(
     State(app_state): State<AppState>,
-    Json(payload): Json<ResendVerificationRequest>,
+    ValidatedJson(payload): ValidatedJson<ResendVerificationRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     usecases::auth::resend_verification(app_state.db(), app_state.mailer(), &payload.email).await?;
 
@@ -204,6 +336,70 @@ async fn resend_verification(
     ))
 }
 
+#[cfg(feature = "web")]
+#[utoipa::path(
+    get,
+    path = "/oidc/authorize",
+    tag = "auth",
+    security(()),
+    responses(
+        (status = 200, body = OidcAuthorizationResponse),
+        (status = 404, description = "OIDC is not configured", body = ErrorBody)
+    )
+)]
+#[debug_handler]
+#[tracing::instrument(skip(app_state))]
+async fn oidc_authorize(State(app_state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    let client = app_state.oidc().ok_or(ApiError::NotFound("oidc"))?;
+    let request = usecases::oidc::begin_login(app_state.db(), client).await?;
+    Ok((
+        StatusCode::OK,
+        Json(OidcAuthorizationResponse {
+            authorization_url: request.authorization_url,
+            state: request.state,
+        }),
+    ))
+}
+
+#[cfg(feature = "web")]
+#[utoipa::path(
+    post,
+    path = "/oidc/callback",
+    tag = "auth",
+    request_body = OidcCallbackRequest,
+    security(()),
+    responses(
+        (status = 200, body = AuthResponse),
+        (status = 400, description = "Invalid state or code", body = ErrorBody),
+        (status = 404, description = "OIDC is not configured", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[debug_handler]
+#[tracing::instrument(skip(app_state, payload))]
+async fn oidc_callback(
+    State(app_state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<OidcCallbackRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let client = app_state.oidc().ok_or(ApiError::NotFound("oidc"))?;
+    let result = usecases::oidc::complete_login(
+        app_state.db(),
+        client,
+        app_state.password_worker(),
+        app_state.jwt_secret(),
+        &payload.code,
+        &payload.state,
+    )
+    .await?;
+    Ok((
+        StatusCode::OK,
+        Json(AuthResponse {
+            token: result.token,
+            user: result.user.into(),
+        }),
+    ))
+}
+
 #[cfg(feature = "web")]
 fn extract_bearer_token(headers: &HeaderMap) -> Result<String, ApiError> {
     headers
@@ -214,6 +410,29 @@ fn extract_bearer_token(headers: &HeaderMap) -> Result<String, ApiError> {
         .ok_or(ApiError::Unauthorized)
 }
 
+#[cfg(feature = "app")]
+#[derive(OpenApi)]
+#[openapi(paths(get_single_user_session))]
+pub(crate) struct AuthApi;
+
+#[cfg(feature = "web")]
+#[derive(OpenApi)]
+#[openapi(paths(
+    register_user,
+    get_auth_user,
+    login,
+    logout,
+    forgot_password,
+    reset_password,
+    confirm_password,
+    verify_email,
+    resend_verification,
+    confirm_email_change,
+    oidc_authorize,
+    oidc_callback
+))]
+pub(crate) struct AuthApi;
+
 #[cfg(feature = "app")]
 pub fn app_routes() -> Router<AppState> {
     Router::<AppState>::new().route("/local/session", get(get_single_user_session))
@@ -229,6 +448,9 @@ pub fn web_routes() -> Router<AppState> {
         .route("/password/confirm", post(confirm_password))
         .route("/email/verify/{token}", get(verify_email))
         .route("/email/resend", post(resend_verification))
+        .route("/email/change/{token}", get(confirm_email_change))
+        .route("/oidc/authorize", get(oidc_authorize))
+        .route("/oidc/callback", post(oidc_callback))
 }
 
 #[cfg(feature = "web")]
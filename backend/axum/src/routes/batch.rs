@@ -0,0 +1,207 @@
+use axum::{Extension, Json, Router, extract::State, routing::post};
+use sea_orm::{DatabaseConnection, DatabaseTransaction, TransactionTrait};
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+use crate::dto::{
+    batch::*, decopon_sessions::DecoponSessionResponse, logs::LogResponse, tasks::TaskResponse,
+};
+use crate::{
+    AppState, ServiceError,
+    errors::{ApiError, ErrorBody},
+    events::{DomainEvent, EventBus},
+    extractors::{authenticated_user::AuthenticatedUser, validated_json::ValidatedJson},
+    usecases::{decopon_sessions, ids, logs, tasks, webhooks},
+};
+
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "batch",
+    request_body = BatchRequest,
+    responses(
+        (status = 200, body = BatchResponse),
+        (status = 404, description = "A target of an operation was not found", body = ErrorBody),
+        (status = 412, description = "A target was modified by another request", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, events, user, payload))]
+async fn run(
+    State(db): State<Arc<DatabaseConnection>>,
+    State(events): State<Arc<EventBus>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    ValidatedJson(payload): ValidatedJson<BatchRequest>,
+) -> Result<Json<BatchResponse>, ApiError> {
+    // 途中で失敗したらコミットせずに破棄し、すべての操作を取り消す
+    let txn = db.begin().await?;
+    let mut results = Vec::with_capacity(payload.operations.len());
+    let mut published = Vec::with_capacity(payload.operations.len());
+    for (index, operation) in payload.operations.into_iter().enumerate() {
+        let (result, operation_events) =
+            execute(&txn, user.id, operation)
+                .await
+                .map_err(|source| ApiError::Batch {
+                    index,
+                    source: Box::new(prefix_fields(source, &format!("operations[{index}]."))),
+                })?;
+        results.push(result);
+        published.extend(operation_events);
+    }
+    txn.commit().await?;
+    // 取り消される可能性があるうちは知らせない
+    for event in published {
+        webhooks::publish(db.as_ref(), &events, user.id, event).await;
+    }
+    Ok(Json(BatchResponse { results }))
+}
+
+async fn execute(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    operation: BatchOperation,
+) -> Result<(BatchResult, Vec<DomainEvent>), ApiError> {
+    let result = match operation {
+        BatchOperation::CreateTask { data } => {
+            let params = tasks::NewTask {
+                title: data.title,
+                description: data.description,
+                parent_task_id: ids::optional_task_id(txn, user_id, data.parent_task_id.as_ref())
+                    .await?,
+                tag_ids: ids::optional_tag_ids(txn, user_id, data.tag_ids.as_deref()).await?,
+                due_at: data.due_at,
+                user_id,
+            };
+            let task = tasks::insert_task(txn, params).await.map_err(in_data)?;
+            let event = DomainEvent::TaskCreated { task_id: task.id };
+            (BatchResult::Task(TaskResponse::from(task)), vec![event])
+        }
+        BatchOperation::UpdateTask { id, version, data } => {
+            let params = tasks::TaskUpdate {
+                id: ids::task_id(txn, user_id, &id).await?,
+                title: data.title,
+                description: data.description,
+                completed: data.completed,
+                parent_task_id: ids::optional_task_id(txn, user_id, data.parent_task_id.as_ref())
+                    .await?,
+                tag_ids: ids::optional_tag_ids(txn, user_id, data.tag_ids.as_deref()).await?,
+                due_at: data.due_at,
+                user_id,
+                expected_version: version,
+            };
+            let completed = params.completed == Some(true);
+            let task = tasks::update_task(txn, params).await.map_err(in_data)?;
+            let mut events = vec![DomainEvent::TaskUpdated { task_id: task.id }];
+            if completed {
+                events.push(task.completed_event());
+            }
+            (BatchResult::Task(TaskResponse::from(task)), events)
+        }
+        BatchOperation::DeleteTask { id, version } => {
+            let id = ids::task_id(txn, user_id, &id).await?;
+            tasks::delete_task(txn, id, user_id, version).await?;
+            (
+                BatchResult::Deleted { id },
+                vec![DomainEvent::TaskDeleted { task_id: id }],
+            )
+        }
+        BatchOperation::CreateLog { data } => {
+            let params = logs::NewLog {
+                content: data.content,
+                source: data.source,
+                task_id: ids::optional_task_id(txn, user_id, data.task_id.as_ref()).await?,
+                user_id,
+                tag_ids: ids::tag_ids(txn, user_id, &data.tag_ids).await?,
+                tag_names: data.tag_names,
+            };
+            let log = logs::insert_log(txn, params).await.map_err(in_data)?;
+            let event = DomainEvent::LogAdded { log_id: log.id };
+            (BatchResult::Log(LogResponse::from(log)), vec![event])
+        }
+        BatchOperation::SetLogTags {
+            id,
+            version,
+            tag_ids,
+            tag_names,
+        } => {
+            let id = ids::log_id(txn, user_id, &id).await?;
+            let tag_ids = ids::tag_ids(txn, user_id, &tag_ids).await?;
+            let log = logs::replace_log_tags(txn, user_id, id, tag_ids, tag_names, version).await?;
+            let event = DomainEvent::LogUpdated { log_id: log.id };
+            (BatchResult::Log(LogResponse::from(log)), vec![event])
+        }
+        BatchOperation::CreateDecoponSession { data } => {
+            let params = decopon_sessions::NewDecoponSession {
+                status: data.status,
+                started_at: data.started_at,
+                ended_at: data.ended_at,
+                user_id,
+            };
+            let session = decopon_sessions::insert_session(txn, params)
+                .await
+                .map_err(in_data)?;
+            let mut events = vec![DomainEvent::for_session(session.id, &session.status, true)];
+            events.extend(decopon_sessions::daily_goal_event(txn, &session).await?);
+            (
+                BatchResult::DecoponSession(DecoponSessionResponse::from(session)),
+                events,
+            )
+        }
+        BatchOperation::UpdateDecoponSession { id, version, data } => {
+            let params = decopon_sessions::DecoponSessionUpdate {
+                id: ids::decopon_session_id(txn, user_id, &id).await?,
+                status: data.status,
+                ended_at: data.ended_at,
+                user_id,
+                expected_version: version,
+            };
+            let session = decopon_sessions::update_session(txn, params)
+                .await
+                .map_err(in_data)?;
+            let mut events = vec![DomainEvent::for_session(session.id, &session.status, false)];
+            events.extend(decopon_sessions::daily_goal_event(txn, &session).await?);
+            (
+                BatchResult::DecoponSession(DecoponSessionResponse::from(session)),
+                events,
+            )
+        }
+        BatchOperation::DeleteDecoponSession { id, version } => {
+            let id = ids::decopon_session_id(txn, user_id, &id).await?;
+            decopon_sessions::delete_session(txn, id, user_id, version).await?;
+            (
+                BatchResult::Deleted { id },
+                vec![DomainEvent::SessionDeleted { session_id: id }],
+            )
+        }
+    };
+    Ok(result)
+}
+
+/// ユースケースが返す項目名は `data` の中の項目を指す
+fn in_data(err: ServiceError) -> ApiError {
+    prefix_fields(err.into(), "data.")
+}
+
+/// 項目ごとのエラーの名前を、リクエスト全体の中の位置で表す
+fn prefix_fields(err: ApiError, prefix: &str) -> ApiError {
+    match err {
+        ApiError::Validation(errors) => ApiError::Validation(
+            errors
+                .into_iter()
+                .map(|mut error| {
+                    error.field = format!("{prefix}{}", error.field);
+                    error
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(run))]
+pub(crate) struct BatchApi;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/", post(run))
+}
@@ -0,0 +1,107 @@
+use axum::{
+    Extension, Router,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Json},
+    routing::get,
+};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+use crate::dto::calendar_feed::*;
+use crate::{
+    AppState,
+    errors::{ApiError, ErrorBody},
+    extractors::authenticated_user::AuthenticatedUser,
+    usecases::calendar_feed,
+};
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "calendar_feed",
+    responses((status = 200, body = CalendarFeedResponse))
+)]
+#[tracing::instrument(skip(db, user))]
+async fn show(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<Json<CalendarFeedResponse>, ApiError> {
+    let enabled = calendar_feed::is_enabled(&db, user.id).await?;
+    Ok(Json(CalendarFeedResponse { enabled }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "calendar_feed",
+    responses((status = 201, description = "New feed URL; any previous URL stops working", body = CalendarFeedTokenResponse))
+)]
+#[tracing::instrument(skip(db, user))]
+async fn regenerate(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<(StatusCode, Json<CalendarFeedTokenResponse>), ApiError> {
+    let issued = calendar_feed::regenerate_token(&db, user.id).await?;
+    Ok((StatusCode::CREATED, Json(issued.into())))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/",
+    tag = "calendar_feed",
+    responses((status = 204, description = "Feed disabled"))
+)]
+#[tracing::instrument(skip(db, user))]
+async fn destroy(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<StatusCode, ApiError> {
+    calendar_feed::revoke_token(&db, user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{token}",
+    tag = "calendar_feed",
+    security(()),
+    params(("token" = String, Path, description = "Secret feed token, optionally followed by `.ics`")),
+    responses(
+        (status = 200, description = "iCalendar feed", content_type = "text/calendar", body = String),
+        (status = 404, description = "Unknown or revoked token", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip_all)]
+async fn feed(
+    Path(token): Path<String>,
+    State(db): State<Arc<DatabaseConnection>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let calendar = calendar_feed::render_feed(&db, &token).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CACHE_CONTROL, "private, max-age=300"),
+        ],
+        calendar,
+    ))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(show, regenerate, destroy))]
+pub(crate) struct CalendarFeedApi;
+
+#[derive(OpenApi)]
+#[openapi(paths(feed))]
+pub(crate) struct CalendarApi;
+
+/// `/profiles/calendar_feed` で購読用の URL を管理する
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/", get(show).post(regenerate).delete(destroy))
+}
+
+/// 認証なしで URL のトークンだけでフィードを返す
+pub fn feed_routes() -> Router<AppState> {
+    Router::new().route("/{token}", get(feed))
+}
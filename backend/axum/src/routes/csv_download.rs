@@ -0,0 +1,53 @@
+//! CSV のダウンロードを、サービス層が返すページごとに少しずつ送る。
+//! 最初のページだけは応答を返す前に読み、DB のエラーを通常のエラー応答にする。
+
+use std::future::{Future, ready};
+
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use futures_util::{StreamExt, TryStreamExt, stream};
+
+use crate::{ServiceError, errors::ApiError, usecases::csv_export::CsvPage};
+
+/// Excel が UTF-8 として読むよう、先頭に BOM を付ける
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+pub(crate) async fn csv_download<F, Fut>(name: &str, mut next_page: F) -> Result<Response, ApiError>
+where
+    F: FnMut(Option<i32>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<CsvPage, ServiceError>> + Send + 'static,
+{
+    let first = next_page(None).await?;
+    let mut head = UTF8_BOM.to_vec();
+    head.extend(first.csv);
+    // 続きがなくなったら状態を `None` にして終える
+    let rest = stream::try_unfold(
+        (next_page, first.next),
+        |(mut next_page, after)| async move {
+            let Some(after) = after else {
+                return Ok(None);
+            };
+            let page = next_page(Some(after)).await?;
+            Ok(Some((page.csv, (next_page, page.next))))
+        },
+    )
+    .inspect_err(|error: &ServiceError| tracing::error!(error = %error, "CSV export failed"));
+    let body = Body::from_stream(stream::once(ready(Ok(head))).chain(rest));
+
+    let disposition = format!(
+        "attachment; filename=\"decopon-{name}-{}.csv\"",
+        Utc::now().format("%Y%m%d")
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
@@ -2,18 +2,39 @@ use axum::{
     Extension, Json, Router,
     extract::{Path, Query, State},
     http::StatusCode,
+    response::Response,
     routing::get,
 };
 use axum_macros::debug_handler;
 use chrono::NaiveDate;
 use sea_orm::DatabaseConnection;
 use std::sync::Arc;
+use utoipa::{IntoParams, OpenApi};
 
 use crate::{
-    AppState, dto::decopon_sessions::*, errors::ApiError,
-    extractors::authenticated_user::AuthenticatedUser, usecases::decopon_sessions,
+    AppState,
+    dto::decopon_sessions::*,
+    errors::{ApiError, ErrorBody},
+    events::{DomainEvent, EventBus},
+    extractors::{
+        authenticated_user::AuthenticatedUser, if_match::IfMatch, validated_json::ValidatedJson,
+    },
+    middleware::conditional::Versioned,
+    routes::csv_download::csv_download,
+    usecases::{
+        csv_export::{self, ExportRange},
+        decopon_sessions,
+        ids::{self, RowRef},
+        webhooks,
+    },
 };
 
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "decopon_sessions",
+    responses((status = 200, body = Vec<DecoponSessionResponse>))
+)]
 #[debug_handler]
 #[tracing::instrument(skip(db, user))]
 async fn index(
@@ -28,68 +49,169 @@ async fn index(
     Ok(Json(sessions))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "decopon_sessions",
+    params(("id" = String, Path, description = "Session id or UUID")),
+    responses(
+        (status = 200, body = DecoponSessionResponse, headers(("ETag" = String))),
+        (status = 304, description = "Not modified since the If-None-Match ETag"),
+        (status = 404, description = "Session not found", body = ErrorBody)
+    )
+)]
 #[debug_handler]
 #[tracing::instrument(skip(db, user))]
 async fn show(
-    Path(id): Path<i32>,
+    Path(id): Path<RowRef>,
     State(db): State<Arc<DatabaseConnection>>,
     Extension(user): Extension<AuthenticatedUser>,
-) -> Result<Json<DecoponSessionResponse>, ApiError> {
-    let session = decopon_sessions::get_session_by_id(&db, id, user.id).await?;
-    Ok(Json(DecoponSessionResponse::from(session)))
+) -> Result<Versioned<Json<DecoponSessionResponse>>, ApiError> {
+    let id = ids::decopon_session_id(db.as_ref(), user.id, &id).await?;
+    let session = decopon_sessions::get_session_by_id(db.as_ref(), id, user.id).await?;
+    Ok(Versioned(
+        session.version,
+        Json(DecoponSessionResponse::from(session)),
+    ))
 }
 
-#[debug_handler]
-#[tracing::instrument(skip(db, user))]
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "decopon_sessions",
+    request_body = StoreDecoponSessionRequest,
+    responses(
+        (status = 200, body = DecoponSessionResponse, headers(("ETag" = String))),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[debug_handler(state = AppState)]
+#[tracing::instrument(skip(db, events, user))]
 async fn store(
     State(db): State<Arc<DatabaseConnection>>,
+    State(events): State<Arc<EventBus>>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<StoreDecoponSessionRequest>,
-) -> Result<Json<DecoponSessionResponse>, ApiError> {
+    ValidatedJson(payload): ValidatedJson<StoreDecoponSessionRequest>,
+) -> Result<Versioned<Json<DecoponSessionResponse>>, ApiError> {
     let params = decopon_sessions::NewDecoponSession {
         status: payload.status,
         started_at: payload.started_at,
         ended_at: payload.ended_at,
         user_id: user.id,
     };
-    let session = decopon_sessions::insert_session(&db, params).await?;
-    Ok(Json(DecoponSessionResponse::from(session)))
+    let session = decopon_sessions::insert_session(db.as_ref(), params).await?;
+    webhooks::publish(
+        db.as_ref(),
+        &events,
+        user.id,
+        DomainEvent::for_session(session.id, &session.status, true),
+    )
+    .await;
+    if let Some(event) = decopon_sessions::daily_goal_event(db.as_ref(), &session).await? {
+        webhooks::publish(db.as_ref(), &events, user.id, event).await;
+    }
+    Ok(Versioned(
+        session.version,
+        Json(DecoponSessionResponse::from(session)),
+    ))
 }
 
-#[debug_handler]
-#[tracing::instrument(skip(db, user))]
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    tag = "decopon_sessions",
+    params(("id" = String, Path, description = "Session id or UUID"), ("If-Match" = Option<String>, Header, description = "Current version ETag; 412 if it no longer matches")),
+    request_body = UpdateDecoponSessionRequest,
+    responses(
+        (status = 200, body = DecoponSessionResponse, headers(("ETag" = String))),
+        (status = 404, description = "Session not found", body = ErrorBody),
+        (status = 412, description = "Session was modified by another request", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[debug_handler(state = AppState)]
+#[tracing::instrument(skip(db, events, user))]
 async fn update(
-    Path(id): Path<i32>,
+    Path(id): Path<RowRef>,
     State(db): State<Arc<DatabaseConnection>>,
+    State(events): State<Arc<EventBus>>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<UpdateDecoponSessionRequest>,
-) -> Result<Json<DecoponSessionResponse>, ApiError> {
+    IfMatch(expected_version): IfMatch,
+    ValidatedJson(payload): ValidatedJson<UpdateDecoponSessionRequest>,
+) -> Result<Versioned<Json<DecoponSessionResponse>>, ApiError> {
+    let id = ids::decopon_session_id(db.as_ref(), user.id, &id).await?;
     let params = decopon_sessions::DecoponSessionUpdate {
         id,
         status: payload.status,
         ended_at: payload.ended_at,
         user_id: user.id,
+        expected_version,
     };
-    let session = decopon_sessions::update_session(&db, params).await?;
-    Ok(Json(DecoponSessionResponse::from(session)))
+    let session = decopon_sessions::update_session(db.as_ref(), params).await?;
+    webhooks::publish(
+        db.as_ref(),
+        &events,
+        user.id,
+        DomainEvent::for_session(session.id, &session.status, false),
+    )
+    .await;
+    if let Some(event) = decopon_sessions::daily_goal_event(db.as_ref(), &session).await? {
+        webhooks::publish(db.as_ref(), &events, user.id, event).await;
+    }
+    Ok(Versioned(
+        session.version,
+        Json(DecoponSessionResponse::from(session)),
+    ))
 }
 
-#[debug_handler]
-#[tracing::instrument(skip(db, user))]
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "decopon_sessions",
+    params(("id" = String, Path, description = "Session id or UUID"), ("If-Match" = Option<String>, Header, description = "Current version ETag; 412 if it no longer matches")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 412, description = "Session was modified by another request", body = ErrorBody)
+    )
+)]
+#[debug_handler(state = AppState)]
+#[tracing::instrument(skip(db, events, user))]
 async fn destroy(
-    Path(id): Path<i32>,
+    Path(id): Path<RowRef>,
     State(db): State<Arc<DatabaseConnection>>,
+    State(events): State<Arc<EventBus>>,
     Extension(user): Extension<AuthenticatedUser>,
+    IfMatch(expected_version): IfMatch,
 ) -> Result<StatusCode, ApiError> {
-    decopon_sessions::delete_session(&db, id, user.id).await?;
+    let id = ids::decopon_session_id(db.as_ref(), user.id, &id).await?;
+    decopon_sessions::delete_session(db.as_ref(), id, user.id, expected_version).await?;
+    webhooks::publish(
+        db.as_ref(),
+        &events,
+        user.id,
+        DomainEvent::SessionDeleted { session_id: id },
+    )
+    .await;
     Ok(StatusCode::NO_CONTENT)
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct CycleQuery {
+    /// `YYYY-MM-DD`
     date: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/cycles",
+    tag = "decopon_sessions",
+    params(CycleQuery),
+    responses(
+        (status = 200, body = CycleCountResponse),
+        (status = 400, description = "Invalid date", body = ErrorBody)
+    )
+)]
 #[debug_handler]
 #[tracing::instrument(skip(db, user))]
 async fn cycles(
@@ -99,16 +221,59 @@ async fn cycles(
 ) -> Result<Json<CycleCountResponse>, ApiError> {
     let date = NaiveDate::parse_from_str(&q.date, "%Y-%m-%d")
         .map_err(|e| ApiError::BadRequest(e.to_string()))?;
-    let count = decopon_sessions::count_completed_sessions_on(&db, user.id, date).await?;
+    let count = decopon_sessions::count_completed_sessions_on(db.as_ref(), user.id, date).await?;
     Ok(Json(CycleCountResponse {
         date: q.date,
         count,
     }))
 }
 
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ExportQuery {
+    /// `YYYY-MM-DD` (UTC)。この日以降に始まったセッション
+    from: Option<NaiveDate>,
+    /// `YYYY-MM-DD` (UTC)。この日までに始まったセッション
+    to: Option<NaiveDate>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/export.csv",
+    tag = "decopon_sessions",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Sessions with their duration in minutes, streamed as CSV", content_type = "text/csv", body = String),
+        (status = 400, description = "Invalid date", body = ErrorBody),
+        (status = 422, description = "from is after to", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user))]
+async fn export_csv(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    Query(q): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let range = ExportRange {
+        from: q.from,
+        to: q.to,
+    };
+    range.validate()?;
+    csv_download("sessions", move |after| {
+        let db = db.clone();
+        async move { csv_export::sessions_page(&db, user.id, range, after).await }
+    })
+    .await
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(index, show, store, update, destroy, cycles, export_csv))]
+pub(crate) struct DecoponSessionsApi;
+
 pub fn routes() -> Router<AppState> {
     Router::<AppState>::new()
         .route("/", get(index).post(store))
         .route("/cycles", get(cycles))
+        .route("/export.csv", get(export_csv))
         .route("/{id}", get(show).put(update).delete(destroy))
 }
@@ -0,0 +1,74 @@
+use std::{convert::Infallible, sync::Arc};
+
+use axum::{
+    Extension, Router,
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+};
+use futures_util::stream::{self, Stream};
+use utoipa::OpenApi;
+
+use crate::{
+    AppState,
+    events::{EventBus, EventSubscription, PublishedEvent},
+    extractors::authenticated_user::AuthenticatedUser,
+};
+
+/// 再接続時にブラウザが送る、最後に受け取ったイベントの ID
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "events",
+    params(("Last-Event-ID" = Option<u64>, Header, description = "Resume after this event id")),
+    responses((
+        status = 200,
+        description = "Stream of change events. Each message carries the event id, its type as the event name and the JSON payload",
+        content_type = "text/event-stream",
+        body = PublishedEvent
+    ))
+)]
+#[tracing::instrument(skip(events, user, headers))]
+async fn stream_events(
+    State(events): State<Arc<EventBus>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // 解釈できない ID は無視し、新しいイベントだけを送る
+    let last_event_id = headers
+        .get(LAST_EVENT_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    let subscription = events.subscribe(user.id, last_event_id);
+
+    let stream = stream::unfold(
+        subscription,
+        |mut subscription: EventSubscription| async move {
+            let published = subscription.recv().await?;
+            Some((Ok(to_sse_event(&published)), subscription))
+        },
+    );
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+fn to_sse_event(published: &PublishedEvent) -> Event {
+    Event::default()
+        .id(published.id.to_string())
+        .event(published.event.name())
+        .json_data(published)
+        .unwrap_or_else(|err| {
+            tracing::error!(error = %err, "failed to serialize event");
+            Event::default().comment("unserializable event")
+        })
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(stream_events))]
+pub(crate) struct EventsApi;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/", get(stream_events))
+}
@@ -0,0 +1,58 @@
+use axum::{
+    Extension, Router,
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Json, Response},
+    routing::get,
+};
+use chrono::NaiveDate;
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+use crate::dto::journal::*;
+use crate::{
+    AppState,
+    errors::{ApiError, ErrorBody},
+    extractors::authenticated_user::AuthenticatedUser,
+    usecases::journal,
+};
+
+#[utoipa::path(
+    get,
+    path = "/{date}",
+    tag = "journal",
+    params(("date" = NaiveDate, Path, description = "Day to summarize (`YYYY-MM-DD`, UTC)"), JournalQuery),
+    responses(
+        (status = 200, description = "The day's logs grouped by task, completed sessions and tasks, and totals; Markdown with `format=markdown`", content(
+            (JournalResponse = "application/json"),
+            (String = "text/markdown")
+        )),
+        (status = 400, description = "Invalid date", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user))]
+async fn show(
+    Path(date): Path<NaiveDate>,
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    Query(query): Query<JournalQuery>,
+) -> Result<Response, ApiError> {
+    let journal = journal::get_journal(&db, user.id, date).await?;
+    Ok(match query.format {
+        JournalFormat::Json => Json(JournalResponse::from(journal)).into_response(),
+        JournalFormat::Markdown => (
+            [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            journal::render_markdown(&journal),
+        )
+            .into_response(),
+    })
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(show))]
+pub(crate) struct JournalApi;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/{date}", get(show))
+}
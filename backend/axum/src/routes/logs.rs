@@ -1,21 +1,36 @@
 use axum::{
     Extension, Json, Router,
     extract::{Path, State},
+    response::Response,
     routing::get,
 };
 use axum_extra::extract::Query;
 use axum_macros::debug_handler;
+use chrono::NaiveDate;
 use sea_orm::DatabaseConnection;
 use serde::Deserialize;
 use std::sync::Arc;
 use tracing::info;
+use utoipa::{IntoParams, OpenApi};
 
 use crate::{
-    AppState, dto::logs::*, errors::ApiError, extractors::authenticated_user::AuthenticatedUser,
-    usecases::logs::{self, LogFilters},
+    AppState,
+    dto::logs::*,
+    errors::{ApiError, ErrorBody},
+    events::{DomainEvent, EventBus},
+    extractors::{authenticated_user::AuthenticatedUser, validated_json::ValidatedJson},
+    middleware::conditional::Versioned,
+    routes::csv_download::csv_download,
+    usecases::{
+        csv_export::{self, ExportRange, LogExportFilters},
+        ids::{self, RowRef},
+        logs::{self, LogFilters},
+        webhooks,
+    },
 };
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct LogsQueryParams {
     #[serde(default, alias = "tag_ids[]")]
     tag_ids: Vec<i32>,
@@ -23,6 +38,13 @@ struct LogsQueryParams {
     task_name: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "logs",
+    params(LogsQueryParams),
+    responses((status = 200, body = Vec<LogResponse>))
+)]
 #[debug_handler]
 #[tracing::instrument(skip(db, user))]
 async fn index(
@@ -45,39 +67,116 @@ async fn index(
     Ok(Json(dto))
 }
 
+#[utoipa::path(
+    get,
+    path = "/task/{task_id}",
+    tag = "logs",
+    params(("task_id" = String, Path, description = "Task id or UUID")),
+    responses((status = 200, body = Vec<LogResponse>))
+)]
 #[debug_handler]
 #[tracing::instrument(skip(db, user))]
 async fn logs_by_task(
-    Path(task_id): Path<i32>,
+    Path(task_id): Path<RowRef>,
     State(db): State<Arc<DatabaseConnection>>,
     Extension(user): Extension<AuthenticatedUser>,
 ) -> Result<Json<Vec<LogResponse>>, ApiError> {
+    let task_id = ids::task_id(db.as_ref(), user.id, &task_id).await?;
     let logs_vec = logs::get_logs_by_task(&db, user.id, task_id).await?;
     let dto = logs_vec.into_iter().map(LogResponse::from).collect();
     Ok(Json(dto))
 }
 
-#[debug_handler]
-#[tracing::instrument(skip(db, user))]
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "logs",
+    request_body = StoreLogRequest,
+    responses(
+        (status = 200, body = LogResponse, headers(("ETag" = String))),
+        (status = 404, description = "Tag not found", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[debug_handler(state = AppState)]
+#[tracing::instrument(skip(db, events, user))]
 async fn store(
     State(db): State<Arc<DatabaseConnection>>,
+    State(events): State<Arc<EventBus>>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<StoreLogRequest>,
-) -> Result<Json<LogResponse>, ApiError> {
+    ValidatedJson(payload): ValidatedJson<StoreLogRequest>,
+) -> Result<Versioned<Json<LogResponse>>, ApiError> {
     let params = logs::NewLog {
         content: payload.content,
         source: payload.source,
-        task_id: payload.task_id,
+        task_id: ids::optional_task_id(db.as_ref(), user.id, payload.task_id.as_ref()).await?,
         user_id: user.id,
-        tag_ids: payload.tag_ids,
+        tag_ids: ids::tag_ids(db.as_ref(), user.id, &payload.tag_ids).await?,
         tag_names: payload.tag_names,
     };
-    let log = logs::insert_log(&db, params).await?;
-    Ok(Json(LogResponse::from(log)))
+    let log = logs::insert_log(db.as_ref(), params).await?;
+    webhooks::publish(
+        db.as_ref(),
+        &events,
+        user.id,
+        DomainEvent::LogAdded { log_id: log.id },
+    )
+    .await;
+    Ok(Versioned(log.version, Json(LogResponse::from(log))))
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LogsExportParams {
+    /// `YYYY-MM-DD` (UTC)。この日以降のログ
+    from: Option<NaiveDate>,
+    /// `YYYY-MM-DD` (UTC)。この日までのログ
+    to: Option<NaiveDate>,
+    /// すべてのタグが付いたログだけを書き出す
+    #[serde(default, alias = "tag_ids[]")]
+    tag_ids: Vec<i32>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/export.csv",
+    tag = "logs",
+    params(LogsExportParams),
+    responses(
+        (status = 200, description = "Logs with their task title and tags, streamed as CSV", content_type = "text/csv", body = String),
+        (status = 400, description = "Invalid date", body = ErrorBody),
+        (status = 422, description = "from is after to", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user))]
+async fn export_csv(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    Query(params): Query<LogsExportParams>,
+) -> Result<Response, ApiError> {
+    let filters = Arc::new(LogExportFilters {
+        range: ExportRange {
+            from: params.from,
+            to: params.to,
+        },
+        tag_ids: params.tag_ids,
+    });
+    filters.range.validate()?;
+    csv_download("logs", move |after| {
+        let db = db.clone();
+        let filters = filters.clone();
+        async move { csv_export::logs_page(&db, user.id, &filters, after).await }
+    })
+    .await
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(index, logs_by_task, store, export_csv))]
+pub(crate) struct LogsApi;
+
 pub fn routes() -> Router<AppState> {
     Router::<AppState>::new()
         .route("/", get(index).post(store))
         .route("/task/{task_id}", get(logs_by_task))
+        .route("/export.csv", get(export_csv))
 }
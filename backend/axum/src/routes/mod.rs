@@ -1,38 +1,67 @@
+pub mod admin;
+mod archive_download;
 pub mod auth;
+pub mod batch;
+pub mod calendar_feed;
+mod csv_download;
 pub mod decopon_sessions;
+pub mod events;
+pub mod journal;
 pub mod logs;
+pub mod personal_access_tokens;
 pub mod preferences;
 pub mod profiles;
+pub mod sync;
 pub mod tags;
 pub mod tasks;
+pub mod webhooks;
 
-use axum::{middleware, routing::get, Router};
+use axum::{middleware, routing::get, Json, Router};
+#[cfg(feature = "app")]
 use axum::http::StatusCode;
 use decopon_config::AppMode;
 
 use crate::{
-    middleware::auth::{auth_middleware, local_single_user_middleware},
+    middleware::{
+        auth::{auth_middleware, local_single_user_middleware},
+        conditional::conditional_get_middleware,
+        idempotency::idempotency_middleware,
+        request_id::request_id_middleware,
+    },
+    openapi::api_doc,
     AppState,
 };
 
 fn protected_routes(app_state: AppState, app_mode: AppMode) -> Router<AppState> {
     let base = Router::<AppState>::new()
+        .nest("/batch", batch::routes())
         .nest("/decopon_sessions", decopon_sessions::routes())
+        .nest("/journal", journal::routes())
         .nest("/logs", logs::routes())
         .nest("/profiles", profiles::routes())
         .nest("/preferences", preferences::routes())
         .nest("/tags", tags::routes())
         .nest("/tasks", tasks::routes());
 
+    // 再送の判定はユーザーごとなので、認証より内側に置く
+    let idempotency = middleware::from_fn_with_state(app_state.clone(), idempotency_middleware);
     match app_mode {
-        AppMode::Local => base.layer(middleware::from_fn_with_state(
+        AppMode::Local => base.layer(idempotency).layer(middleware::from_fn_with_state(
             app_state.clone(),
             local_single_user_middleware,
         )),
-        AppMode::Web => base.layer(middleware::from_fn_with_state(
-            app_state.clone(),
-            auth_middleware,
-        )),
+        AppMode::Web => base
+            .nest("/admin", admin::routes(app_state.clone()))
+            // アプリでは Tauri のイベントとして届けるので、SSE は Web のみ
+            .nest("/events", events::routes())
+            // アプリのローカル DB と同期する相手になるのは Web のアカウント
+            .nest("/sync", sync::routes())
+            .nest("/webhooks", webhooks::routes())
+            .layer(idempotency)
+            .layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                auth_middleware,
+            )),
     }
 }
 
@@ -54,8 +83,14 @@ fn auth_routes_for_mode(_app_mode: AppMode) -> Router<AppState> {
 
 pub fn create_routes(app_state: AppState, app_mode: AppMode) -> Router<AppState> {
     Router::<AppState>::new()
+        .route("/openapi.json", get(|| async { Json(api_doc()) }))
         .nest("/auth", auth_routes_for_mode(app_mode))
+        // カレンダーアプリは認証ヘッダを送れないので、URL のトークンで照合する
+        .nest("/calendar", calendar_feed::feed_routes())
         .merge(protected_routes(app_state, app_mode))
+        .layer(middleware::from_fn(conditional_get_middleware))
+        // 認証エラーを含むすべてのレスポンスに ID を付ける
+        .layer(middleware::from_fn(request_id_middleware))
 }
 
 #[cfg(all(feature = "app", feature = "web"))]
@@ -0,0 +1,152 @@
+use axum::{
+    Extension, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, patch},
+};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+use crate::dto::personal_access_tokens::*;
+use crate::{
+    AppState,
+    errors::{ApiError, ErrorBody},
+    extractors::{authenticated_user::AuthenticatedUser, validated_json::ValidatedJson},
+    middleware::auth::PersonalAccessTokenAuth,
+    usecases::personal_access_tokens,
+};
+
+/// トークン自体の管理やアカウントの変更はログインセッション (JWT) からのみ許可する
+pub(crate) fn ensure_session_auth(
+    token_auth: Option<Extension<PersonalAccessTokenAuth>>,
+) -> Result<(), ApiError> {
+    match token_auth {
+        Some(_) => Err(ApiError::Forbidden),
+        None => Ok(()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "personal_access_tokens",
+    responses(
+        (status = 200, body = Vec<PersonalAccessTokenResponse>),
+        (status = 403, description = "Tokens can only be managed from a login session", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user, token_auth))]
+async fn index(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    token_auth: Option<Extension<PersonalAccessTokenAuth>>,
+) -> Result<Json<Vec<PersonalAccessTokenResponse>>, ApiError> {
+    ensure_session_auth(token_auth)?;
+    let tokens = personal_access_tokens::get_tokens(&db, user.id).await?;
+    Ok(Json(
+        tokens
+            .into_iter()
+            .map(PersonalAccessTokenResponse::from)
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "personal_access_tokens",
+    request_body = StorePersonalAccessTokenRequest,
+    responses(
+        (status = 201, body = IssuedPersonalAccessTokenResponse),
+        (status = 403, description = "Tokens can only be managed from a login session", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user, token_auth, payload))]
+async fn store(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    token_auth: Option<Extension<PersonalAccessTokenAuth>>,
+    ValidatedJson(payload): ValidatedJson<StorePersonalAccessTokenRequest>,
+) -> Result<(StatusCode, Json<IssuedPersonalAccessTokenResponse>), ApiError> {
+    ensure_session_auth(token_auth)?;
+    let params = personal_access_tokens::NewPersonalAccessToken {
+        name: payload.name,
+        scope: payload.scope,
+        expires_at: payload.expires_at,
+        user_id: user.id,
+    };
+    let issued = personal_access_tokens::insert_token(&db, params).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(IssuedPersonalAccessTokenResponse::from(issued)),
+    ))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    tag = "personal_access_tokens",
+    params(("id" = i32, Path)),
+    request_body = UpdatePersonalAccessTokenRequest,
+    responses(
+        (status = 200, body = PersonalAccessTokenResponse),
+        (status = 403, description = "Tokens can only be managed from a login session", body = ErrorBody),
+        (status = 404, description = "Token not found", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user, token_auth))]
+async fn update(
+    Path(id): Path<i32>,
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    token_auth: Option<Extension<PersonalAccessTokenAuth>>,
+    ValidatedJson(payload): ValidatedJson<UpdatePersonalAccessTokenRequest>,
+) -> Result<Json<PersonalAccessTokenResponse>, ApiError> {
+    ensure_session_auth(token_auth)?;
+    let params = personal_access_tokens::PersonalAccessTokenUpdate {
+        id,
+        name: payload.name,
+        user_id: user.id,
+    };
+    let token = personal_access_tokens::update_token(&db, params).await?;
+    Ok(Json(PersonalAccessTokenResponse::from(token)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "personal_access_tokens",
+    params(("id" = i32, Path)),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 403, description = "Tokens can only be managed from a login session", body = ErrorBody),
+        (status = 404, description = "Token not found", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user, token_auth))]
+async fn destroy(
+    Path(id): Path<i32>,
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    token_auth: Option<Extension<PersonalAccessTokenAuth>>,
+) -> Result<StatusCode, ApiError> {
+    ensure_session_auth(token_auth)?;
+    personal_access_tokens::delete_token(&db, id, user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(index, store, update, destroy))]
+pub(crate) struct PersonalAccessTokensApi;
+
+pub fn routes() -> Router<AppState> {
+    Router::<AppState>::new()
+        .route("/", get(index).post(store))
+        .route("/{id}", patch(update).delete(destroy))
+}
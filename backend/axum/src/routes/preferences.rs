@@ -1,28 +1,47 @@
 use axum::{Extension, Router, extract::State, response::Json, routing::put};
 use sea_orm::DatabaseConnection;
 use std::sync::Arc;
+use utoipa::OpenApi;
 
 use crate::dto::preferences::*;
 use crate::{
-    AppState, errors::ApiError, extractors::authenticated_user::AuthenticatedUser,
+    AppState,
+    errors::{ApiError, ErrorBody},
+    extractors::{authenticated_user::AuthenticatedUser, validated_json::ValidatedJson},
     usecases::preferences,
 };
 
+#[utoipa::path(
+    put,
+    path = "/",
+    tag = "preferences",
+    request_body = UpdatePreferenceRequest,
+    responses(
+        (status = 200, body = PreferenceResponse),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
 #[tracing::instrument(skip(db, user))]
 async fn update(
     State(db): State<Arc<DatabaseConnection>>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<UpdatePreferenceRequest>,
+    ValidatedJson(payload): ValidatedJson<UpdatePreferenceRequest>,
 ) -> Result<Json<PreferenceResponse>, ApiError> {
     let params = preferences::UpdatePreference {
         work_time: payload.work_time,
         break_time: payload.break_time,
         locale: payload.locale,
+        daily_journal_email: payload.daily_journal_email,
+        daily_goal: payload.daily_goal,
     };
     let user = preferences::update_preference(&db, user.id, params).await?;
     Ok(Json(PreferenceResponse::from(user)))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(update))]
+pub(crate) struct PreferencesApi;
+
 pub fn routes() -> Router<AppState> {
     Router::new().route("/", put(update))
 }
@@ -1,19 +1,36 @@
 use axum::{
     Extension, Router,
-    extract::State,
+    extract::{DefaultBodyLimit, State},
     http::StatusCode,
-    response::Json,
-    routing::{get, put},
+    response::{Json, Response},
+    routing::{get, post, put},
 };
 use sea_orm::DatabaseConnection;
 use std::sync::Arc;
+use utoipa::OpenApi;
 
 use crate::dto::{auth::UserResponse, profiles::*};
 use crate::{
-    AppState, errors::ApiError, extractors::authenticated_user::AuthenticatedUser,
-    usecases::profiles,
+    AppState,
+    errors::{ApiError, ErrorBody},
+    extractors::{authenticated_user::AuthenticatedUser, validated_json::ValidatedJson},
+    middleware::auth::PersonalAccessTokenAuth,
+    routes::{
+        archive_download, calendar_feed, personal_access_tokens,
+        personal_access_tokens::ensure_session_auth,
+    },
+    usecases::{archive, profiles},
 };
 
+/// アーカイブは通常のリクエストより大きくなるので、取り込みだけ上限を広げる
+pub(crate) const IMPORT_BODY_LIMIT: usize = 32 * 1024 * 1024;
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "profiles",
+    responses((status = 200, body = UserResponse))
+)]
 #[tracing::instrument(skip(db, user))]
 async fn show(
     State(db): State<Arc<DatabaseConnection>>,
@@ -23,26 +40,55 @@ async fn show(
     Ok(Json(UserResponse::from(user)))
 }
 
-#[tracing::instrument(skip(db, user))]
+#[utoipa::path(
+    patch,
+    path = "/",
+    tag = "profiles",
+    request_body = UpdateProfileRequest,
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 403, description = "Profiles can only be changed from a login session", body = ErrorBody),
+        (status = 409, description = "Email already in use", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(app_state, user, token_auth))]
 async fn update(
-    State(db): State<Arc<DatabaseConnection>>,
+    State(app_state): State<AppState>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<UpdateProfileRequest>,
+    token_auth: Option<Extension<PersonalAccessTokenAuth>>,
+    ValidatedJson(payload): ValidatedJson<UpdateProfileRequest>,
 ) -> Result<Json<UserResponse>, ApiError> {
+    ensure_session_auth(token_auth)?;
     let params = profiles::UpdateProfile {
         name: payload.name,
         email: payload.email,
     };
-    let user = profiles::update_profile(&db, user.id, params).await?;
+    let user =
+        profiles::update_profile(app_state.db(), app_state.mailer(), user.id, params).await?;
     Ok(Json(UserResponse::from(user)))
 }
 
-#[tracing::instrument(skip(app_state, user, payload))]
+#[utoipa::path(
+    put,
+    path = "/password",
+    tag = "profiles",
+    request_body = UpdatePasswordRequest,
+    responses(
+        (status = 200, description = "Password updated"),
+        (status = 401, description = "Current password is wrong", body = ErrorBody),
+        (status = 403, description = "Passwords can only be changed from a login session", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(app_state, user, payload, token_auth))]
 async fn update_password(
     State(app_state): State<AppState>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<UpdatePasswordRequest>,
+    token_auth: Option<Extension<PersonalAccessTokenAuth>>,
+    ValidatedJson(payload): ValidatedJson<UpdatePasswordRequest>,
 ) -> Result<StatusCode, ApiError> {
+    ensure_session_auth(token_auth)?;
     let params = profiles::UpdatePassword {
         current_password: payload.current_password,
         password: payload.password,
@@ -51,12 +97,26 @@ async fn update_password(
     Ok(StatusCode::OK)
 }
 
-#[tracing::instrument(skip(app_state, user, payload))]
+#[utoipa::path(
+    delete,
+    path = "/",
+    tag = "profiles",
+    request_body = DeleteProfileRequest,
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 401, description = "Password is wrong", body = ErrorBody),
+        (status = 403, description = "Accounts can only be deleted from a login session", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(app_state, user, payload, token_auth))]
 async fn destroy(
     State(app_state): State<AppState>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<DeleteProfileRequest>,
+    token_auth: Option<Extension<PersonalAccessTokenAuth>>,
+    ValidatedJson(payload): ValidatedJson<DeleteProfileRequest>,
 ) -> Result<StatusCode, ApiError> {
+    ensure_session_auth(token_auth)?;
     let params = profiles::DeleteProfile {
         password: payload.password,
     };
@@ -64,8 +124,53 @@ async fn destroy(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    get,
+    path = "/export",
+    tag = "profiles",
+    responses((status = 200, description = "Archive of the account's data", body = archive::Archive, headers(("Content-Disposition" = String))))
+)]
+#[tracing::instrument(skip(db, user))]
+async fn export(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<Response, ApiError> {
+    archive_download::archive_download(db, user.id).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/import",
+    tag = "profiles",
+    request_body = ImportProfileRequest,
+    responses(
+        (status = 200, body = ImportProfileResponse),
+        (status = 422, description = "Unsupported or invalid archive", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user, payload))]
+async fn import(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    ValidatedJson(payload): ValidatedJson<ImportProfileRequest>,
+) -> Result<Json<ImportProfileResponse>, ApiError> {
+    let summary = archive::import_archive(&db, user.id, payload.archive).await?;
+    Ok(Json(summary.into()))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(show, update, update_password, destroy, export, import))]
+pub(crate) struct ProfilesApi;
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/", get(show).patch(update).delete(destroy))
         .route("/password", put(update_password))
+        .route("/export", get(export))
+        .route(
+            "/import",
+            post(import).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+        )
+        .nest("/calendar_feed", calendar_feed::routes())
+        .nest("/tokens", personal_access_tokens::routes())
 }
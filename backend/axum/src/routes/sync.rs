@@ -0,0 +1,75 @@
+use axum::{
+    Extension, Router,
+    extract::{Query, State},
+    response::Json,
+    routing::{get, post},
+};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+use crate::dto::sync::*;
+use crate::{
+    AppState,
+    errors::{ApiError, ErrorBody},
+    extractors::{authenticated_user::AuthenticatedUser, validated_json::ValidatedJson},
+    usecases::sync,
+};
+
+#[utoipa::path(
+    get,
+    path = "/changes",
+    tag = "sync",
+    params(SyncChangesQuery),
+    responses((
+        status = 200,
+        description = "Changes recorded after `since`, reduced to the latest change of each row",
+        body = SyncChangesResponse
+    ))
+)]
+#[tracing::instrument(skip(db, user))]
+async fn changes(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    Query(query): Query<SyncChangesQuery>,
+) -> Result<Json<SyncChangesResponse>, ApiError> {
+    let page = sync::changes_since(
+        db.as_ref(),
+        user.id,
+        query.since,
+        query.device_id.as_deref(),
+    )
+    .await?;
+    Ok(Json(page.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/push",
+    tag = "sync",
+    request_body = SyncPushRequest,
+    responses(
+        (status = 200, description = "Changes applied in one transaction; conflicts are listed as skipped", body = SyncPushResponse),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user, payload))]
+async fn push(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    ValidatedJson(payload): ValidatedJson<SyncPushRequest>,
+) -> Result<Json<SyncPushResponse>, ApiError> {
+    let result =
+        sync::push_changes(db.as_ref(), user.id, &payload.device_id, payload.changes).await?;
+    Ok(Json(result.into()))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(changes, push))]
+pub(crate) struct SyncApi;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/changes", get(changes))
+        .route("/push", post(push))
+}
@@ -7,12 +7,23 @@ use axum::{
 };
 use sea_orm::DatabaseConnection;
 use std::sync::Arc;
+use utoipa::OpenApi;
 
 use crate::dto::tags::*;
 use crate::{
-    AppState, errors::ApiError, extractors::authenticated_user::AuthenticatedUser, usecases::tags,
+    AppState,
+    errors::{ApiError, ErrorBody},
+    extractors::{authenticated_user::AuthenticatedUser, validated_json::ValidatedJson},
+    middleware::conditional::Versioned,
+    usecases::{ids, tags},
 };
 
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "tags",
+    responses((status = 200, body = Vec<TagResponse>))
+)]
 #[tracing::instrument(skip(db, user))]
 async fn index(
     State(db): State<Arc<DatabaseConnection>>,
@@ -22,50 +33,100 @@ async fn index(
     Ok(Json(tags.into_iter().map(TagResponse::from).collect()))
 }
 
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "tags",
+    request_body = StoreTagRequest,
+    responses(
+        (status = 200, body = TagResponse, headers(("ETag" = String))),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
 #[tracing::instrument(skip(db, user))]
 async fn store(
     State(db): State<Arc<DatabaseConnection>>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<StoreTagRequest>,
-) -> Result<Json<TagResponse>, ApiError> {
+    ValidatedJson(payload): ValidatedJson<StoreTagRequest>,
+) -> Result<Versioned<Json<TagResponse>>, ApiError> {
     let params = tags::NewTag {
         name: payload.name,
         user_id: user.id,
     };
     let tag = tags::insert_tag(&db, params).await?;
-    Ok(Json(TagResponse::from(tag)))
+    Ok(Versioned(tag.version, Json(TagResponse::from(tag))))
 }
 
+#[utoipa::path(
+    post,
+    path = "/relation",
+    tag = "tags",
+    request_body = TagRelationRequest,
+    responses(
+        (status = 200, body = TagResponse),
+        (status = 404, description = "Task not found", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
 #[tracing::instrument(skip(db, user))]
 async fn store_relation(
     State(db): State<Arc<DatabaseConnection>>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<TagRelationRequest>,
+    ValidatedJson(payload): ValidatedJson<TagRelationRequest>,
 ) -> Result<Json<TagResponse>, ApiError> {
-    let tag = tags::attach_tag_to_task(&db, user.id, payload.task_id, payload.name).await?;
+    let task_id = ids::task_id(db.as_ref(), user.id, &payload.task_id).await?;
+    let tag = tags::attach_tag_to_task(&db, user.id, task_id, payload.name).await?;
     Ok(Json(TagResponse::from(tag)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/relation",
+    tag = "tags",
+    request_body = TagRelationRequest,
+    responses(
+        (status = 200, body = Option<TagResponse>, description = "The detached tag, or null if it was not attached"),
+        (status = 404, description = "Task not found", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
 #[tracing::instrument(skip(db, user))]
 async fn destroy_relation(
     State(db): State<Arc<DatabaseConnection>>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<TagRelationRequest>,
+    ValidatedJson(payload): ValidatedJson<TagRelationRequest>,
 ) -> Result<Json<Option<TagResponse>>, ApiError> {
-    let tag = tags::detach_tag_from_task(&db, user.id, payload.task_id, payload.name).await?;
+    let task_id = ids::task_id(db.as_ref(), user.id, &payload.task_id).await?;
+    let tag = tags::detach_tag_from_task(&db, user.id, task_id, payload.name).await?;
     Ok(Json(tag.map(TagResponse::from)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/multiple",
+    tag = "tags",
+    request_body = DeleteTagsRequest,
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 412, description = "A tag was modified by another request", body = ErrorBody),
+        (status = 422, description = "versions does not match tag_ids", body = ErrorBody)
+    )
+)]
 #[tracing::instrument(skip(db, user))]
 async fn destroy_multiple(
     State(db): State<Arc<DatabaseConnection>>,
     Extension(user): Extension<AuthenticatedUser>,
     Json(payload): Json<DeleteTagsRequest>,
 ) -> Result<StatusCode, ApiError> {
-    tags::delete_tags(&db, user.id, payload.tag_ids).await?;
+    let tag_ids = ids::tag_ids(db.as_ref(), user.id, &payload.tag_ids).await?;
+    tags::delete_tags(&db, user.id, tag_ids, payload.versions).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(index, store, store_relation, destroy_relation, destroy_multiple))]
+pub(crate) struct TagsApi;
+
 pub fn routes() -> Router<AppState> {
     Router::<AppState>::new()
         .route("/", get(index).post(store))
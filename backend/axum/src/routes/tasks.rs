@@ -1,19 +1,37 @@
 use axum::{
     Extension, Router,
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
-    routing::get,
+    http::{StatusCode, header},
+    response::{IntoResponse, Json},
+    routing::{get, post},
 };
 
 use sea_orm::DatabaseConnection;
 use std::sync::Arc;
+use utoipa::OpenApi;
 
 use crate::dto::tasks::*;
 use crate::{
-    AppState, errors::ApiError, extractors::authenticated_user::AuthenticatedUser, usecases::tasks,
+    AppState,
+    errors::{ApiError, ErrorBody},
+    events::{DomainEvent, EventBus},
+    extractors::{
+        authenticated_user::AuthenticatedUser, if_match::IfMatch, validated_json::ValidatedJson,
+    },
+    middleware::conditional::Versioned,
+    usecases::{
+        ids::{self, RowRef},
+        task_import, task_outline, tasks, webhooks,
+    },
 };
 
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "tasks",
+    params(("tag_ids" = Option<Vec<i32>>, Query, description = "Only tasks with all of these tags")),
+    responses((status = 200, body = Vec<TaskResponse>))
+)]
 #[tracing::instrument(skip(db, user))]
 async fn index(
     State(db): State<Arc<DatabaseConnection>>,
@@ -35,69 +53,168 @@ async fn index(
     Ok(Json(tasks))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task id or UUID")),
+    responses(
+        (status = 200, body = TaskResponse, headers(("ETag" = String))),
+        (status = 304, description = "Not modified since the If-None-Match ETag"),
+        (status = 404, description = "Task not found", body = ErrorBody)
+    )
+)]
 #[tracing::instrument(skip(db, user))]
 async fn show(
-    Path(id): Path<i32>,
+    Path(id): Path<RowRef>,
     State(db): State<Arc<DatabaseConnection>>,
     Extension(user): Extension<AuthenticatedUser>,
-) -> Result<Json<TaskResponse>, ApiError> {
-    let task = tasks::get_task_by_id(&db, user.id, id).await?;
-    Ok(Json(TaskResponse::from(task)))
+) -> Result<Versioned<Json<TaskResponse>>, ApiError> {
+    let id = ids::task_id(db.as_ref(), user.id, &id).await?;
+    let task = tasks::get_task_by_id(db.as_ref(), user.id, id).await?;
+    Ok(Versioned(task.version, Json(TaskResponse::from(task))))
 }
 
-#[tracing::instrument(skip(db, user))]
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "tasks",
+    request_body = StoreTaskRequest,
+    responses(
+        (status = 200, body = TaskResponse, headers(("ETag" = String))),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, events, user))]
 async fn store(
     State(db): State<Arc<DatabaseConnection>>,
+    State(events): State<Arc<EventBus>>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<StoreTaskRequest>,
-) -> Result<Json<TaskResponse>, ApiError> {
+    ValidatedJson(payload): ValidatedJson<StoreTaskRequest>,
+) -> Result<Versioned<Json<TaskResponse>>, ApiError> {
     let params = tasks::NewTask {
         title: payload.title,
         description: payload.description,
-        parent_task_id: payload.parent_task_id,
-        tag_ids: payload.tag_ids,
+        parent_task_id: ids::optional_task_id(
+            db.as_ref(),
+            user.id,
+            payload.parent_task_id.as_ref(),
+        )
+        .await?,
+        tag_ids: ids::optional_tag_ids(db.as_ref(), user.id, payload.tag_ids.as_deref()).await?,
+        due_at: payload.due_at,
         user_id: user.id,
     };
-    let task = tasks::insert_task(&db, params).await?;
-    Ok(Json(TaskResponse::from(task)))
+    let task = tasks::insert_task(db.as_ref(), params).await?;
+    webhooks::publish(
+        db.as_ref(),
+        &events,
+        user.id,
+        DomainEvent::TaskCreated { task_id: task.id },
+    )
+    .await;
+    Ok(Versioned(task.version, Json(TaskResponse::from(task))))
 }
 
-#[tracing::instrument(skip(db, user))]
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task id or UUID"), ("If-Match" = Option<String>, Header, description = "Current version ETag; 412 if it no longer matches")),
+    request_body = UpdateTaskRequest,
+    responses(
+        (status = 200, body = TaskResponse, headers(("ETag" = String))),
+        (status = 400, description = "Unsupported change", body = ErrorBody),
+        (status = 404, description = "Task not found", body = ErrorBody),
+        (status = 412, description = "Task was modified by another request", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, events, user))]
 async fn update(
-    Path(id): Path<i32>,
+    Path(id): Path<RowRef>,
     State(db): State<Arc<DatabaseConnection>>,
+    State(events): State<Arc<EventBus>>,
     Extension(user): Extension<AuthenticatedUser>,
-    Json(payload): Json<UpdateTaskRequest>,
-) -> Result<Json<TaskResponse>, ApiError> {
+    IfMatch(expected_version): IfMatch,
+    ValidatedJson(payload): ValidatedJson<UpdateTaskRequest>,
+) -> Result<Versioned<Json<TaskResponse>>, ApiError> {
+    let id = ids::task_id(db.as_ref(), user.id, &id).await?;
+    let completed = payload.completed == Some(true);
     let params = tasks::TaskUpdate {
         id,
         title: payload.title,
         description: payload.description,
         completed: payload.completed,
-        parent_task_id: payload.parent_task_id,
-        tag_ids: payload.tag_ids,
+        parent_task_id: ids::optional_task_id(
+            db.as_ref(),
+            user.id,
+            payload.parent_task_id.as_ref(),
+        )
+        .await?,
+        tag_ids: ids::optional_tag_ids(db.as_ref(), user.id, payload.tag_ids.as_deref()).await?,
+        due_at: payload.due_at,
         user_id: user.id,
+        expected_version,
     };
-    let task = tasks::update_task(&db, params).await?;
-    Ok(Json(TaskResponse::from(task)))
+    let task = tasks::update_task(db.as_ref(), params).await?;
+    webhooks::publish(
+        db.as_ref(),
+        &events,
+        user.id,
+        DomainEvent::TaskUpdated { task_id: task.id },
+    )
+    .await;
+    if completed {
+        webhooks::publish(db.as_ref(), &events, user.id, task.completed_event()).await;
+    }
+    Ok(Versioned(task.version, Json(TaskResponse::from(task))))
 }
 
-#[tracing::instrument(skip(db, user))]
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task id or UUID"), ("If-Match" = Option<String>, Header, description = "Current version ETag; 412 if it no longer matches")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 412, description = "Task was modified by another request", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, events, user))]
 async fn destroy(
-    Path(id): Path<i32>,
+    Path(id): Path<RowRef>,
     State(db): State<Arc<DatabaseConnection>>,
+    State(events): State<Arc<EventBus>>,
     Extension(user): Extension<AuthenticatedUser>,
+    IfMatch(expected_version): IfMatch,
 ) -> Result<StatusCode, ApiError> {
-    tasks::delete_task(&db, id, user.id).await?;
+    let id = ids::task_id(db.as_ref(), user.id, &id).await?;
+    tasks::delete_task(db.as_ref(), id, user.id, expected_version).await?;
+    webhooks::publish(
+        db.as_ref(),
+        &events,
+        user.id,
+        DomainEvent::TaskDeleted { task_id: id },
+    )
+    .await;
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}/subtree",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task id or UUID")),
+    responses((status = 200, body = Vec<TaskSubtreeResponse>), (status = 404, description = "Task not found", body = ErrorBody))
+)]
 #[tracing::instrument(skip(db, user))]
 async fn subtree(
-    Path(id): Path<i32>,
+    Path(id): Path<RowRef>,
     State(db): State<Arc<DatabaseConnection>>,
     Extension(user): Extension<AuthenticatedUser>,
 ) -> Result<Json<Vec<TaskSubtreeResponse>>, ApiError> {
+    let id = ids::task_id(db.as_ref(), user.id, &id).await?;
     let subtree = tasks::get_task_subtree(&db, user.id, id).await?;
     let subtree = subtree
         .into_iter()
@@ -106,9 +223,100 @@ async fn subtree(
     Ok(Json(subtree))
 }
 
+#[utoipa::path(
+    get,
+    path = "/export",
+    tag = "tasks",
+    params(TaskExportQuery),
+    responses((status = 200, description = "All task trees as Markdown or OPML", content_type = "text/plain", body = String))
+)]
+#[tracing::instrument(skip(db, user))]
+async fn export_all(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    Query(query): Query<TaskExportQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let outline = task_outline::export_forest(&db, user.id, query.format).await?;
+    Ok((
+        [(header::CONTENT_TYPE, query.format.content_type())],
+        outline,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/export",
+    tag = "tasks",
+    params(("id" = String, Path, description = "Task id or UUID"), TaskExportQuery),
+    responses(
+        (status = 200, description = "The task and its descendants as Markdown or OPML", content_type = "text/plain", body = String),
+        (status = 404, description = "Task not found", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user))]
+async fn export(
+    Path(id): Path<RowRef>,
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    Query(query): Query<TaskExportQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let id = ids::task_id(db.as_ref(), user.id, &id).await?;
+    let outline = task_outline::export_subtree(&db, user.id, id, query.format).await?;
+    Ok((
+        [(header::CONTENT_TYPE, query.format.content_type())],
+        outline,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/import",
+    tag = "tasks",
+    request_body = ImportTasksRequest,
+    responses(
+        (status = 200, description = "Tasks that were (or with dry_run would be) created", body = ImportTasksResponse),
+        (status = 422, description = "Unreadable content or invalid tasks", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, events, user, payload))]
+async fn import(
+    State(db): State<Arc<DatabaseConnection>>,
+    State(events): State<Arc<EventBus>>,
+    Extension(user): Extension<AuthenticatedUser>,
+    ValidatedJson(payload): ValidatedJson<ImportTasksRequest>,
+) -> Result<Json<ImportTasksResponse>, ApiError> {
+    let import = task_import::import_tasks(
+        &db,
+        user.id,
+        payload.format,
+        &payload.content,
+        payload.dry_run,
+    )
+    .await?;
+    for task_id in import.tasks.iter().filter_map(|task| task.id) {
+        webhooks::publish(
+            db.as_ref(),
+            &events,
+            user.id,
+            DomainEvent::TaskCreated { task_id },
+        )
+        .await;
+    }
+    Ok(Json(ImportTasksResponse::from(import)))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    index, show, store, update, destroy, subtree, export_all, export, import
+))]
+pub(crate) struct TasksApi;
+
 pub fn routes() -> Router<AppState> {
     Router::<AppState>::new()
         .route("/", get(index).post(store))
         .route("/{id}", get(show).put(update).delete(destroy))
         .route("/{id}/subtree", get(subtree))
+        .route("/export", get(export_all))
+        .route("/import", post(import))
+        .route("/{id}/export", get(export))
 }
@@ -0,0 +1,151 @@
+use axum::{
+    Extension, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, patch},
+};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use utoipa::OpenApi;
+
+use crate::dto::webhooks::*;
+use crate::{
+    AppState,
+    errors::{ApiError, ErrorBody},
+    extractors::{authenticated_user::AuthenticatedUser, validated_json::ValidatedJson},
+    usecases::webhooks,
+};
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "webhooks",
+    responses((status = 200, body = Vec<WebhookResponse>))
+)]
+#[tracing::instrument(skip(db, user))]
+async fn index(
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<Json<Vec<WebhookResponse>>, ApiError> {
+    let webhooks = webhooks::get_webhooks(&db, user.id).await?;
+    Ok(Json(
+        webhooks.into_iter().map(WebhookResponse::from).collect(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "webhooks",
+    request_body = StoreWebhookRequest,
+    responses(
+        (status = 201, body = CreatedWebhookResponse),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user, payload))]
+async fn store(
+    State(db): State<Arc<DatabaseConnection>>,
+    State(targets): State<webhooks::TargetPolicy>,
+    Extension(user): Extension<AuthenticatedUser>,
+    ValidatedJson(payload): ValidatedJson<StoreWebhookRequest>,
+) -> Result<(StatusCode, Json<CreatedWebhookResponse>), ApiError> {
+    let params = webhooks::NewWebhook {
+        url: payload.url,
+        event_types: payload.event_types,
+        user_id: user.id,
+    };
+    let created = webhooks::insert_webhook(&db, params, targets).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedWebhookResponse::from(created)),
+    ))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    tag = "webhooks",
+    params(("id" = i32, Path)),
+    request_body = UpdateWebhookRequest,
+    responses(
+        (status = 200, body = WebhookResponse),
+        (status = 404, description = "Webhook not found", body = ErrorBody),
+        (status = 422, description = "Invalid fields", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user, payload))]
+async fn update(
+    Path(id): Path<i32>,
+    State(db): State<Arc<DatabaseConnection>>,
+    State(targets): State<webhooks::TargetPolicy>,
+    Extension(user): Extension<AuthenticatedUser>,
+    ValidatedJson(payload): ValidatedJson<UpdateWebhookRequest>,
+) -> Result<Json<WebhookResponse>, ApiError> {
+    let params = webhooks::WebhookUpdate {
+        id,
+        url: payload.url,
+        event_types: payload.event_types,
+        active: payload.active,
+        user_id: user.id,
+    };
+    let webhook = webhooks::update_webhook(&db, params, targets).await?;
+    Ok(Json(WebhookResponse::from(webhook)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "webhooks",
+    params(("id" = i32, Path)),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "Webhook not found", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user))]
+async fn destroy(
+    Path(id): Path<i32>,
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<StatusCode, ApiError> {
+    webhooks::delete_webhook(&db, id, user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/deliveries",
+    tag = "webhooks",
+    params(("id" = i32, Path)),
+    responses(
+        (status = 200, description = "Most recent deliveries first", body = Vec<WebhookDeliveryResponse>),
+        (status = 404, description = "Webhook not found", body = ErrorBody)
+    )
+)]
+#[tracing::instrument(skip(db, user))]
+async fn deliveries(
+    Path(id): Path<i32>,
+    State(db): State<Arc<DatabaseConnection>>,
+    Extension(user): Extension<AuthenticatedUser>,
+) -> Result<Json<Vec<WebhookDeliveryResponse>>, ApiError> {
+    let deliveries = webhooks::get_deliveries(&db, id, user.id).await?;
+    Ok(Json(
+        deliveries
+            .into_iter()
+            .map(WebhookDeliveryResponse::from)
+            .collect(),
+    ))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(index, store, update, destroy, deliveries))]
+pub(crate) struct WebhooksApi;
+
+pub fn routes() -> Router<AppState> {
+    Router::<AppState>::new()
+        .route("/", get(index).post(store))
+        .route("/{id}", patch(update).delete(destroy))
+        .route("/{id}/deliveries", get(deliveries))
+}
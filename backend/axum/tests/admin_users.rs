@@ -0,0 +1,169 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode, header::AUTHORIZATION},
+};
+use decopon_config::AppMode;
+use sea_orm::{ActiveModelTrait, EntityTrait, QueryOrder, Set};
+use tower::ServiceExt;
+
+use decopon_axum::{
+    entities::{admin_audit_logs, prelude::*, users},
+    routes, usecases,
+};
+
+use common::{build_app_state, create_user, new_user, setup_in_memory_db};
+
+const SECRET: &str = "test_secret";
+
+async fn create_admin(db: &sea_orm::DatabaseConnection, name: &str) -> users::Model {
+    users::ActiveModel {
+        role: Set("Admin".to_string()),
+        ..new_user(name)
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+fn app(db: &std::sync::Arc<sea_orm::DatabaseConnection>) -> Router {
+    let state = build_app_state(db, SECRET);
+    routes::create_routes(state.clone(), AppMode::Web).with_state(state)
+}
+
+async fn send(app: &Router, method: Method, uri: &str, token: &str) -> axum::response::Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+async fn json_body(response: axum::response::Response) -> serde_json::Value {
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn admin_routes_reject_regular_users() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "user").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+
+    let response = send(&app(&db), Method::GET, "/admin/users", &token).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn admin_can_search_disable_and_enable_users() {
+    let db = setup_in_memory_db(false).await;
+    let admin = create_admin(db.as_ref(), "admin").await;
+    let user = create_user(db.as_ref(), "user").await;
+    let admin_token = usecases::auth::create_jwt(admin.id, SECRET).unwrap();
+    let user_token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let app = app(&db);
+
+    let response = send(
+        &app,
+        Method::GET,
+        "/admin/users?q=use&per_page=10",
+        &admin_token,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_body(response).await;
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["items"][0]["email"], "user@example.com");
+
+    let uri = format!("/admin/users/{}/disable", user.id);
+    let response = send(&app, Method::POST, &uri, &admin_token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!json_body(response).await["disabled_at"].is_null());
+
+    // 無効化されたユーザーの発行済みトークンは使えない
+    let response = send(&app, Method::GET, "/profiles", &user_token).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let uri = format!("/admin/users/{}/enable", user.id);
+    let response = send(&app, Method::POST, &uri, &admin_token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(&app, Method::GET, "/profiles", &user_token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = send(&app, Method::GET, "/admin/audit_logs", &admin_token).await;
+    let body = json_body(response).await;
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["items"][0]["action"], "enable_user");
+    assert_eq!(body["items"][1]["action"], "disable_user");
+}
+
+#[tokio::test]
+async fn impersonation_token_is_read_only_and_cannot_use_admin_routes() {
+    let db = setup_in_memory_db(false).await;
+    let admin = create_admin(db.as_ref(), "admin").await;
+    let target = create_admin(db.as_ref(), "other_admin").await;
+    let admin_token = usecases::auth::create_jwt(admin.id, SECRET).unwrap();
+    let app = app(&db);
+
+    let uri = format!("/admin/users/{}/impersonate", target.id);
+    let response = send(&app, Method::POST, &uri, &admin_token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let token = json_body(response).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let response = send(&app, Method::GET, "/profiles", &token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(response).await["id"], target.id);
+
+    let response = send(&app, Method::DELETE, "/tasks/1", &token).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    // 代理ログイン先が管理者でも管理 API は使えない
+    let response = send(&app, Method::GET, "/admin/users", &token).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn admin_delete_is_recorded_in_audit_log() {
+    let db = setup_in_memory_db(false).await;
+    let admin = create_admin(db.as_ref(), "admin").await;
+    let user = create_user(db.as_ref(), "user").await;
+    let admin_token = usecases::auth::create_jwt(admin.id, SECRET).unwrap();
+    let app = app(&db);
+
+    let uri = format!("/admin/users/{}", user.id);
+    let response = send(&app, Method::DELETE, &uri, &admin_token).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(
+        Users::find_by_id(user.id)
+            .one(db.as_ref())
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let uri = format!("/admin/users/{}", admin.id);
+    let response = send(&app, Method::DELETE, &uri, &admin_token).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let logs = AdminAuditLogs::find()
+        .order_by_asc(admin_audit_logs::Column::Id)
+        .all(db.as_ref())
+        .await
+        .unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].action, "delete_user");
+    assert_eq!(logs[0].actor_id, Some(admin.id));
+    assert_eq!(logs[0].target_user_id, Some(user.id));
+}
@@ -9,14 +9,17 @@ use axum::{
     middleware::from_fn_with_state,
     routing::get,
 };
-use axum_password_worker::PasswordWorker;
-use lettre::SmtpTransport;
-use sea_orm::Database;
+use chrono::Utc;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ActiveModelTrait, Database, DatabaseConnection, Set};
 use tower::ServiceExt;
 
 use decopon_axum::{
     AppState, ServiceContext,
+    entities::users,
+    mail_transport::LogMailTransport,
     middleware::auth::{AuthenticatedUser, auth_middleware},
+    password::{PasswordHashConfig, PasswordWorker},
     usecases,
 };
 
@@ -26,18 +29,44 @@ async fn handler() -> StatusCode {
 
 fn build_state(db: sea_orm::DatabaseConnection, jwt_secret: String) -> AppState {
     let db = Arc::new(db);
-    let password_worker = Arc::new(PasswordWorker::new_bcrypt(1).unwrap());
-    let mailer = Some(Arc::new(
-        SmtpTransport::builder_dangerous("localhost").build(),
-    ));
+    let password_worker = Arc::new(PasswordWorker::new(1, PasswordHashConfig::default()).unwrap());
+    let mailer: usecases::mails::Mailer = Arc::new(LogMailTransport);
 
     AppState::from(
         ServiceContext::builder(db, password_worker, jwt_secret)
-            .mailer(mailer)
+            .mailer(Some(mailer))
             .build(),
     )
 }
 
+/// 認証ミドルウェアはトークンの持ち主が存在し無効化されていないことを確認する
+async fn setup_db_with_user(disabled: bool) -> (DatabaseConnection, i32) {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let user = users::ActiveModel {
+        name: Set("user".to_string()),
+        email: Set("user@example.com".to_string()),
+        email_verified_at: Set(Some(Utc::now())),
+        password: Set("hashed".to_string()),
+        work_time: Set(25),
+        break_time: Set(5),
+        locale: Set("ja".to_string()),
+        disabled_at: Set(disabled.then(Utc::now)),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    (db, user.id)
+}
+
+fn ok_app(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(handler).post(handler))
+        .with_state(state.clone())
+        .layer(from_fn_with_state(state, auth_middleware))
+}
+
 #[tokio::test]
 async fn reject_without_token() {
     let db = Database::connect("sqlite::memory:").await.unwrap();
@@ -74,7 +103,8 @@ async fn reject_invalid_token() {
 
 #[tokio::test]
 async fn accept_valid_token() {
-    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let (db, user_id) = setup_db_with_user(false).await;
+    assert_eq!(user_id, 1);
     let jwt_secret = "secret".to_string();
     let token = usecases::auth::create_jwt(1, &jwt_secret).unwrap();
     let state = build_state(db, jwt_secret);
@@ -100,3 +130,58 @@ async fn accept_valid_token() {
     let res = app.oneshot(req).await.unwrap();
     assert_eq!(res.status(), StatusCode::OK);
 }
+
+#[tokio::test]
+async fn reject_token_of_disabled_user() {
+    let (db, user_id) = setup_db_with_user(true).await;
+    let token = usecases::auth::create_jwt(user_id, "secret").unwrap();
+    let app = ok_app(build_state(db, "secret".to_string()));
+
+    let req = Request::builder()
+        .uri("/")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let res = app.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn reject_token_of_deleted_user() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let token = usecases::auth::create_jwt(1, "secret").unwrap();
+    let app = ok_app(build_state(db, "secret".to_string()));
+
+    let req = Request::builder()
+        .uri("/")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let res = app.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn impersonation_token_is_read_only() {
+    let (db, user_id) = setup_db_with_user(false).await;
+    let token = usecases::auth::create_impersonation_jwt(user_id, 99, "secret").unwrap();
+    let app = ok_app(build_state(db, "secret".to_string()));
+
+    let req = Request::builder()
+        .uri("/")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let res = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let req = Request::builder()
+        .method("POST")
+        .uri("/")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let res = app.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,156 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    Router,
+    http::{Method, StatusCode},
+};
+use chrono::Utc;
+use decopon_config::AppMode;
+use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait};
+
+use decopon_axum::{
+    entities::prelude::{DecoponSessions, Tasks},
+    routes, usecases,
+};
+
+use common::{build_app_state, create_user, send, setup_in_memory_db};
+
+const SECRET: &str = "test_secret";
+
+async fn create_task(db: &DatabaseConnection, user_id: i32, title: &str) -> i32 {
+    usecases::tasks::insert_task(
+        db,
+        usecases::tasks::NewTask {
+            title: title.to_string(),
+            description: String::new(),
+            parent_task_id: None,
+            tag_ids: None,
+            due_at: None,
+            user_id,
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+async fn post_batch(
+    app: &Router,
+    token: &str,
+    operations: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let payload = serde_json::json!({ "operations": operations });
+    let (response, body) = send(app, token, Method::POST, "/batch", &[], Some(payload)).await;
+    (response.status(), serde_json::from_slice(&body).unwrap())
+}
+
+fn app(db: &std::sync::Arc<DatabaseConnection>) -> Router {
+    let state = build_app_state(db, SECRET);
+    routes::create_routes(state.clone(), AppMode::Web).with_state(state)
+}
+
+#[tokio::test]
+async fn runs_operations_in_order_and_reports_each_result() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let first = create_task(&db, user.id, "first").await;
+    let second = create_task(&db, user.id, "second").await;
+    let session = usecases::decopon_sessions::insert_session(
+        db.as_ref(),
+        usecases::decopon_sessions::NewDecoponSession {
+            status: "Completed".to_string(),
+            started_at: Utc::now(),
+            ended_at: None,
+            user_id: user.id,
+        },
+    )
+    .await
+    .unwrap();
+
+    let (status, body) = post_batch(
+        &app(&db),
+        &token,
+        serde_json::json!([
+            { "op": "update_task", "id": first, "version": 1, "data": { "completed": true } },
+            { "op": "update_task", "id": second, "data": { "completed": true } },
+            { "op": "create_log", "data": { "content": "done", "source": "User", "tag_names": ["weekly"] } },
+            { "op": "delete_decopon_session", "id": session.id },
+        ]),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0]["type"], "task");
+    assert_eq!(results[0]["data"]["completed"], true);
+    assert_eq!(results[0]["data"]["version"], 2);
+    assert_eq!(results[2]["type"], "log");
+    assert_eq!(results[2]["data"]["tags"][0]["name"], "weekly");
+    assert_eq!(results[3]["type"], "deleted");
+    assert_eq!(results[3]["data"]["id"], session.id);
+    assert_eq!(DecoponSessions::find().count(db.as_ref()).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn a_failing_operation_rolls_back_the_whole_batch() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let existing = create_task(&db, user.id, "existing").await;
+
+    let (status, body) = post_batch(
+        &app(&db),
+        &token,
+        serde_json::json!([
+            { "op": "create_task", "data": { "title": "new", "description": "", "parent_task_id": null, "tag_ids": null } },
+            { "op": "delete_task", "id": existing },
+            { "op": "update_task", "id": existing + 100, "data": { "completed": true } },
+        ]),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["operation"], 2);
+    assert_eq!(body["resource"], "task");
+    let remaining = Tasks::find().all(db.as_ref()).await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].id, existing);
+}
+
+#[tokio::test]
+async fn field_errors_point_at_the_operation() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let existing = create_task(&db, user.id, "existing").await;
+
+    let (status, body) = post_batch(
+        &app(&db),
+        &token,
+        serde_json::json!([
+            { "op": "delete_task", "id": existing },
+            { "op": "update_task", "id": existing, "data": { "title": "" } },
+        ]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["errors"][0]["field"], "operations[1].data.title");
+
+    // ユースケース側の検査で見つかった誤りも同じ形で返す
+    let (status, body) = post_batch(
+        &app(&db),
+        &token,
+        serde_json::json!([
+            { "op": "update_task", "id": existing, "data": { "tag_ids": [999] } },
+        ]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["operation"], 0);
+    assert_eq!(body["errors"][0]["field"], "operations[0].data.tag_ids");
+    assert_eq!(Tasks::find().count(db.as_ref()).await.unwrap(), 1);
+}
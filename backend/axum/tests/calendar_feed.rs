@@ -0,0 +1,109 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode, header},
+};
+use chrono::{Duration, Utc};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use decopon_axum::{middleware::auth::AuthenticatedUser, routes, usecases};
+
+use common::{build_app_state, create_user, setup_in_memory_db};
+
+async fn regenerate(app: axum::Router, user_id: i32) -> String {
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/calendar_feed")
+                .extension(AuthenticatedUser {
+                    id: user_id,
+                    exp: 0,
+                })
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert!(body["url"].as_str().unwrap().ends_with(".ics"));
+    body["token"].as_str().unwrap().to_string()
+}
+
+async fn fetch(app: axum::Router, token: &str) -> (StatusCode, String, String) {
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/{token}.ics"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        content_type,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn publishes_sessions_and_due_tasks_behind_a_regenerable_url() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    let started_at = Utc::now() - Duration::hours(2);
+    let session = usecases::decopon_sessions::insert_session(
+        db.as_ref(),
+        usecases::decopon_sessions::NewDecoponSession {
+            status: "Completed".to_string(),
+            started_at,
+            ended_at: Some(started_at + Duration::minutes(25)),
+            user_id: user.id,
+        },
+    )
+    .await
+    .unwrap();
+    let due = usecases::tasks::insert_task(
+        db.as_ref(),
+        usecases::tasks::NewTask {
+            title: "File taxes".to_string(),
+            description: String::new(),
+            parent_task_id: None,
+            tag_ids: None,
+            due_at: Some(Utc::now() + Duration::days(3)),
+            user_id: user.id,
+        },
+    )
+    .await
+    .unwrap();
+    let state = build_app_state(&db, "test_secret");
+    let profiles = routes::profiles::routes().with_state(state.clone());
+    let feed = routes::calendar_feed::feed_routes().with_state(state);
+
+    let token = regenerate(profiles.clone(), user.id).await;
+    let (status, content_type, body) = fetch(feed.clone(), &token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("text/calendar"));
+    assert!(body.contains(&format!("UID:{}@decopon", session.uuid)));
+    assert!(body.contains(&format!("UID:{}@decopon\r\n", due.uuid)));
+    assert!(body.contains("SUMMARY:File taxes"));
+
+    // 作り直すと古い URL は使えなくなる
+    let renewed = regenerate(profiles, user.id).await;
+    assert_eq!(fetch(feed.clone(), &token).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(fetch(feed.clone(), &renewed).await.0, StatusCode::OK);
+    assert_eq!(fetch(feed, "dcal_guess").await.0, StatusCode::NOT_FOUND);
+}
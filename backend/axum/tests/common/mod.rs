@@ -2,11 +2,28 @@
 
 use std::sync::Arc;
 
-use axum_password_worker::{Bcrypt, PasswordWorker};
-use decopon_axum::{AppState, ServiceContext};
-use lettre::SmtpTransport;
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{
+        HeaderName, Method, Request,
+        header::{AUTHORIZATION, CONTENT_TYPE},
+    },
+    response::Response,
+};
+use chrono::Utc;
+use decopon_axum::{
+    AppState, ServiceContext, ServiceContextBuilder,
+    entities::users,
+    mail_transport::LogMailTransport,
+    password::{PasswordHashConfig, PasswordWorker},
+    usecases::mails::Mailer,
+};
 use migration::{Migrator, MigratorTrait};
-use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, Statement};
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, Database, DatabaseConnection, DbBackend, Set, Statement,
+};
+use tower::ServiceExt;
 
 /// Create an in-memory SQLite database, apply migrations, and optionally enable foreign keys.
 pub async fn setup_in_memory_db(enable_foreign_keys: bool) -> Arc<DatabaseConnection> {
@@ -27,19 +44,101 @@ pub async fn setup_in_memory_db(enable_foreign_keys: bool) -> Arc<DatabaseConnec
     Arc::new(db)
 }
 
-fn test_password_worker() -> Arc<PasswordWorker<Bcrypt>> {
-    Arc::new(PasswordWorker::new_bcrypt(1).expect("create password worker"))
+/// テストを高速に保つため、最小限のパラメータで Argon2id を使う。
+fn test_password_worker() -> Arc<PasswordWorker> {
+    Arc::new(
+        PasswordWorker::new(
+            1,
+            PasswordHashConfig::Argon2id {
+                memory_kib: 1024,
+                iterations: 1,
+                parallelism: 1,
+            },
+        )
+        .expect("create password worker"),
+    )
 }
 
-fn test_mailer() -> Arc<SmtpTransport> {
-    Arc::new(SmtpTransport::builder_dangerous("localhost").build())
+fn test_mailer() -> Mailer {
+    Arc::new(LogMailTransport)
 }
 
 /// Build an `AppState` using the provided database handle and JWT secret.
+#[allow(dead_code)]
 pub fn build_app_state(db: &Arc<DatabaseConnection>, jwt_secret: impl Into<String>) -> AppState {
-    AppState::from(
-        ServiceContext::builder(Arc::clone(db), test_password_worker(), jwt_secret.into())
-            .mailer(Some(test_mailer()))
-            .build(),
-    )
+    build_app_state_with_mailer(db, jwt_secret, test_mailer())
+}
+
+/// 送信内容を検証したいテスト向けに、任意のメールトランスポートで `AppState` を組み立てる。
+#[allow(dead_code)]
+pub fn build_app_state_with_mailer(
+    db: &Arc<DatabaseConnection>,
+    jwt_secret: impl Into<String>,
+    mailer: Mailer,
+) -> AppState {
+    AppState::from(service_context(db, jwt_secret).mailer(Some(mailer)).build())
+}
+
+/// ほかの設定も変えたいテスト向けに、テスト用のパスワードワーカーを入れたビルダーを返す。
+#[allow(dead_code)]
+pub fn service_context(
+    db: &Arc<DatabaseConnection>,
+    jwt_secret: impl Into<String>,
+) -> ServiceContextBuilder {
+    ServiceContext::builder(Arc::clone(db), test_password_worker(), jwt_secret.into())
+        .mailer(Some(test_mailer()))
+}
+
+/// メールアドレスを確認済みのユーザー。項目を変えたいテストは `..new_user(name)` で上書きする。
+#[allow(dead_code)]
+pub fn new_user(name: &str) -> users::ActiveModel {
+    users::ActiveModel {
+        name: Set(name.to_string()),
+        email: Set(format!("{name}@example.com")),
+        email_verified_at: Set(Some(Utc::now())),
+        password: Set("hashed".to_string()),
+        work_time: Set(25),
+        break_time: Set(5),
+        locale: Set("ja".to_string()),
+        ..Default::default()
+    }
+}
+
+#[allow(dead_code)]
+pub async fn create_user(db: &DatabaseConnection, name: &str) -> users::Model {
+    new_user(name).insert(db).await.unwrap()
+}
+
+/// Bearer トークンを付けてリクエストを送り、読み切った本文と、本文を外した応答を返す。
+#[allow(dead_code)]
+pub async fn send(
+    app: &Router,
+    token: &str,
+    method: Method,
+    uri: &str,
+    headers: &[(HeaderName, &str)],
+    payload: Option<serde_json::Value>,
+) -> (Response, Vec<u8>) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(AUTHORIZATION, format!("Bearer {token}"));
+    for (name, value) in headers {
+        request = request.header(name, *value);
+    }
+    let body = match payload {
+        Some(payload) => {
+            request = request.header(CONTENT_TYPE, "application/json");
+            Body::from(payload.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX).await.unwrap().to_vec();
+    (Response::from_parts(parts, Body::empty()), body)
 }
@@ -0,0 +1,311 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    Router,
+    http::{
+        Method, StatusCode,
+        header::{ETAG, IF_MATCH, IF_NONE_MATCH},
+    },
+    response::Response,
+};
+use decopon_config::AppMode;
+use sea_orm::DatabaseConnection;
+
+use decopon_axum::{routes, usecases};
+
+use common::{build_app_state, create_user, send, setup_in_memory_db};
+
+const SECRET: &str = "test_secret";
+
+struct Client {
+    app: Router,
+    token: String,
+}
+
+impl Client {
+    async fn new(db: &std::sync::Arc<DatabaseConnection>) -> Self {
+        let user = create_user(db, "alice").await;
+        let state = build_app_state(db, SECRET);
+        Self {
+            app: routes::create_routes(state.clone(), AppMode::Web).with_state(state),
+            token: usecases::auth::create_jwt(user.id, SECRET).unwrap(),
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        uri: &str,
+        headers: &[(axum::http::HeaderName, &str)],
+        body: Option<serde_json::Value>,
+    ) -> (Response, Vec<u8>) {
+        send(&self.app, &self.token, method, uri, headers, body).await
+    }
+
+    async fn create_task(&self) -> (i32, String) {
+        let (response, body) = self
+            .send(
+                Method::POST,
+                "/tasks",
+                &[],
+                Some(serde_json::json!({
+                    "title": "write report",
+                    "description": "",
+                    "parent_task_id": null,
+                    "tag_ids": null,
+                })),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let task: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        (task["id"].as_i64().unwrap() as i32, etag(&response))
+    }
+}
+
+fn etag(response: &Response) -> String {
+    response
+        .headers()
+        .get(ETAG)
+        .expect("etag header")
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn update_with_stale_if_match_is_rejected() {
+    let db = setup_in_memory_db(false).await;
+    let client = Client::new(&db).await;
+    let (id, original) = client.create_task().await;
+    assert_eq!(original, "\"1\"");
+    let uri = format!("/tasks/{id}");
+
+    let (response, body) = client
+        .send(
+            Method::PUT,
+            &uri,
+            &[(IF_MATCH, &original)],
+            Some(serde_json::json!({ "title": "first edit" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(etag(&response), "\"2\"");
+    let task: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(task["version"], 2);
+
+    // 読み込んだ時点の版のままでは上書きできない
+    let (response, body) = client
+        .send(
+            Method::PUT,
+            &uri,
+            &[(IF_MATCH, &original)],
+            Some(serde_json::json!({ "title": "lost update" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(problem["code"], "precondition_failed");
+    assert_eq!(problem["resource"], "task");
+
+    let (response, _) = client
+        .send(Method::DELETE, &uri, &[(IF_MATCH, &original)], None)
+        .await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+    let (response, _) = client
+        .send(Method::DELETE, &uri, &[(IF_MATCH, "\"2\"")], None)
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn replacing_log_tags_with_stale_version_is_rejected() {
+    let db = setup_in_memory_db(false).await;
+    let client = Client::new(&db).await;
+    let (response, body) = client
+        .send(
+            Method::POST,
+            "/logs",
+            &[],
+            Some(serde_json::json!({ "content": "Reviewed PR", "source": "User" })),
+        )
+        .await;
+    assert_eq!(etag(&response), "\"1\"");
+    let log: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let set_tags = |name: &str| {
+        serde_json::json!({ "operations": [{
+            "op": "set_log_tags",
+            "id": log["id"],
+            "version": 1,
+            "tag_names": [name],
+        }] })
+    };
+
+    let (response, body) = client
+        .send(Method::POST, "/batch", &[], Some(set_tags("work")))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let batch: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(batch["results"][0]["data"]["version"], 2);
+
+    // 読み込んだ時点の版のままでは付け替えられない
+    let (response, body) = client
+        .send(Method::POST, "/batch", &[], Some(set_tags("home")))
+        .await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(problem["resource"], "log");
+    assert_eq!(problem["operation"], 0);
+}
+
+#[tokio::test]
+async fn deleting_tags_with_stale_versions_is_rejected() {
+    let db = setup_in_memory_db(false).await;
+    let client = Client::new(&db).await;
+    let mut ids = Vec::new();
+    for name in ["work", "home"] {
+        let (response, body) = client
+            .send(
+                Method::POST,
+                "/tags",
+                &[],
+                Some(serde_json::json!({ "name": name })),
+            )
+            .await;
+        assert_eq!(etag(&response), "\"1\"");
+        let tag: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        ids.push(tag["id"].clone());
+    }
+
+    // 一つでも版が食い違えば、どのタグも削除しない
+    let (response, body) = client
+        .send(
+            Method::DELETE,
+            "/tags/multiple",
+            &[],
+            Some(serde_json::json!({ "tag_ids": ids, "versions": [1, 2] })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(problem["resource"], "tag");
+    let (_, body) = client.send(Method::GET, "/tags", &[], None).await;
+    let tags: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(tags.as_array().unwrap().len(), 2);
+
+    let (response, _) = client
+        .send(
+            Method::DELETE,
+            "/tags/multiple",
+            &[],
+            Some(serde_json::json!({ "tag_ids": ids, "versions": [1] })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (response, _) = client
+        .send(
+            Method::DELETE,
+            "/tags/multiple",
+            &[],
+            Some(serde_json::json!({ "tag_ids": ids, "versions": [1, 1] })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let (_, body) = client.send(Method::GET, "/tags", &[], None).await;
+    assert_eq!(body, b"[]");
+}
+
+#[tokio::test]
+async fn malformed_if_match_is_a_bad_request() {
+    let db = setup_in_memory_db(false).await;
+    let client = Client::new(&db).await;
+    let (id, _) = client.create_task().await;
+
+    let (response, _) = client
+        .send(
+            Method::PUT,
+            &format!("/tasks/{id}"),
+            &[(IF_MATCH, "W/\"1\"")],
+            Some(serde_json::json!({ "title": "edit" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn get_with_matching_if_none_match_is_not_modified() {
+    let db = setup_in_memory_db(false).await;
+    let client = Client::new(&db).await;
+    let (id, created) = client.create_task().await;
+    let uri = format!("/tasks/{id}");
+
+    let (response, body) = client
+        .send(Method::GET, &uri, &[(IF_NONE_MATCH, &created)], None)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(etag(&response), created);
+    assert!(body.is_empty());
+
+    client
+        .send(
+            Method::PUT,
+            &uri,
+            &[],
+            Some(serde_json::json!({ "completed": true })),
+        )
+        .await;
+    let (response, _) = client
+        .send(Method::GET, &uri, &[(IF_NONE_MATCH, &created)], None)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(etag(&response), "\"2\"");
+}
+
+#[tokio::test]
+async fn collections_get_content_etags() {
+    let db = setup_in_memory_db(false).await;
+    let client = Client::new(&db).await;
+    client.create_task().await;
+
+    let (response, _) = client.send(Method::GET, "/tasks", &[], None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let listed = etag(&response);
+    assert!(listed.starts_with("W/\""));
+
+    let (response, _) = client
+        .send(Method::GET, "/tasks", &[(IF_NONE_MATCH, &listed)], None)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    client.create_task().await;
+    let (response, _) = client
+        .send(Method::GET, "/tasks", &[(IF_NONE_MATCH, &listed)], None)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_ne!(etag(&response), listed);
+}
+
+#[tokio::test]
+async fn streamed_downloads_are_not_buffered_for_etags() {
+    let db = setup_in_memory_db(false).await;
+    let client = Client::new(&db).await;
+    client
+        .send(
+            Method::POST,
+            "/logs",
+            &[],
+            Some(serde_json::json!({ "content": "Reviewed PR", "source": "User" })),
+        )
+        .await;
+
+    // 本文を読み切ってハッシュを取っていれば、弱い ETag が付く
+    let (response, body) = client
+        .send(Method::GET, "/logs/export.csv", &[], None)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(ETAG).is_none());
+    assert!(String::from_utf8(body).unwrap().contains("Reviewed PR"));
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode, header},
+};
+use chrono::{Duration, Utc};
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, Set};
+use tower::ServiceExt;
+
+use decopon_axum::{
+    entities::logs as log_entity, middleware::auth::AuthenticatedUser, routes, usecases,
+};
+
+use common::{build_app_state, create_user, setup_in_memory_db};
+
+async fn get(app: axum::Router, uri: &str, user_id: i32) -> (StatusCode, String, String) {
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .extension(AuthenticatedUser {
+                    id: user_id,
+                    exp: 0,
+                })
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let disposition = response
+        .headers()
+        .get(header::CONTENT_DISPOSITION)
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        disposition,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn streams_sessions_and_logs_as_csv() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    let started_at = Utc::now() - Duration::hours(1);
+    usecases::decopon_sessions::insert_session(
+        db.as_ref(),
+        usecases::decopon_sessions::NewDecoponSession {
+            status: "Completed".to_string(),
+            started_at,
+            ended_at: Some(started_at + Duration::minutes(25)),
+            user_id: user.id,
+        },
+    )
+    .await
+    .unwrap();
+    for (content, tags) in [("Reviewed PR", vec!["work"]), ("Went for a run", vec![])] {
+        usecases::logs::insert_log(
+            db.as_ref(),
+            usecases::logs::NewLog {
+                content: content.to_string(),
+                source: usecases::logs::LogSource::User,
+                task_id: None,
+                user_id: user.id,
+                tag_ids: Vec::new(),
+                tag_names: tags.into_iter().map(String::from).collect(),
+            },
+        )
+        .await
+        .unwrap();
+    }
+    let work = usecases::tags::get_tags(&db, user.id).await.unwrap()[0].id;
+    let state = build_app_state(&db, "test_secret");
+    let sessions = routes::decopon_sessions::routes().with_state(state.clone());
+    let logs = routes::logs::routes().with_state(state);
+
+    let from = started_at.format("%Y-%m-%d");
+    let to = Utc::now().format("%Y-%m-%d");
+    let (status, disposition, body) = get(
+        sessions.clone(),
+        &format!("/export.csv?from={from}&to={to}"),
+        user.id,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(disposition.starts_with("attachment; filename=\"decopon-sessions-"));
+    let body = body.strip_prefix('\u{feff}').unwrap();
+    let lines = body.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0],
+        "id,uuid,status,started_at,ended_at,duration_minutes"
+    );
+    assert!(lines[1].ends_with(",25.0"));
+
+    let (status, _, body) = get(
+        logs.clone(),
+        &format!("/export.csv?tag_ids={work}"),
+        user.id,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let lines = body.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].contains(",User,Reviewed PR,,,work"));
+
+    // 期間はログを書いた日 (UTC) で絞り込む
+    let run = log_entity::Entity::find()
+        .all(db.as_ref())
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|log| log.content == "Went for a run")
+        .unwrap();
+    let mut run = run.into_active_model();
+    run.created_at = Set(Utc::now() - Duration::days(10));
+    run.update(db.as_ref()).await.unwrap();
+    let (_, _, body) = get(
+        logs.clone(),
+        &format!("/export.csv?from={to}&to={to}"),
+        user.id,
+    )
+    .await;
+    let lines = body.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].contains(",Reviewed PR,"));
+    let before = (Utc::now() - Duration::days(9)).format("%Y-%m-%d");
+    let (_, _, body) = get(logs, &format!("/export.csv?to={before}"), user.id).await;
+    let lines = body.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].contains(",Went for a run,"));
+
+    let (status, _, _) = get(
+        sessions,
+        "/export.csv?from=2025-10-02&to=2025-10-01",
+        user.id,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
@@ -45,6 +45,7 @@ async fn start_and_end_session() {
         status: Some("Completed".to_string()),
         ended_at: Some(ended_at),
         user_id: user.id,
+        expected_version: None,
     };
     let session = decopon_sessions::update_session(db.as_ref(), params)
         .await
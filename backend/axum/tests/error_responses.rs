@@ -0,0 +1,146 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{
+        Method, Request, StatusCode,
+        header::{AUTHORIZATION, CONTENT_TYPE},
+    },
+    response::Response,
+};
+use decopon_config::AppMode;
+use tower::ServiceExt;
+
+use decopon_axum::{routes, usecases};
+
+use common::{build_app_state, create_user, setup_in_memory_db};
+
+const SECRET: &str = "test_secret";
+
+fn app(db: &std::sync::Arc<sea_orm::DatabaseConnection>) -> Router {
+    let state = build_app_state(db, SECRET);
+    routes::create_routes(state.clone(), AppMode::Web).with_state(state)
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (Response, serde_json::Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX).await.unwrap();
+    let json = serde_json::from_slice(&body).unwrap();
+    (Response::from_parts(parts, Body::empty()), json)
+}
+
+fn request_id(response: &Response) -> &str {
+    response
+        .headers()
+        .get("x-request-id")
+        .expect("x-request-id header")
+        .to_str()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn not_found_is_problem_json_with_resource_and_request_id() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+
+    let (response, body) = send(
+        &app(&db),
+        Request::builder()
+            .uri("/tasks/9999")
+            .header(AUTHORIZATION, format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/problem+json");
+    assert_eq!(body["type"], "urn:decopon:problem:not_found");
+    assert_eq!(body["status"], 404);
+    assert_eq!(body["code"], "not_found");
+    assert_eq!(body["resource"], "task");
+    assert_eq!(body["detail"], "task not found");
+    assert_eq!(body["request_id"], request_id(&response));
+}
+
+#[tokio::test]
+async fn validation_errors_are_reported_per_field() {
+    let db = setup_in_memory_db(true).await;
+    let alice = create_user(&db, "alice").await;
+    let bob = create_user(&db, "bob").await;
+    let bobs_tag = usecases::tags::insert_tag(
+        &db,
+        usecases::tags::NewTag {
+            name: "private".to_string(),
+            user_id: bob.id,
+        },
+    )
+    .await
+    .unwrap();
+    let token = usecases::auth::create_jwt(alice.id, SECRET).unwrap();
+
+    let payload = serde_json::json!({
+        "title": "  ",
+        "description": "",
+        "parent_task_id": null,
+        "tag_ids": [bobs_tag.id],
+    });
+    let (response, body) = send(
+        &app(&db),
+        Request::builder()
+            .method(Method::POST)
+            .uri("/tasks")
+            .header(AUTHORIZATION, format!("Bearer {token}"))
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(payload.to_string()))
+            .unwrap(),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "validation_failed");
+    let errors: Vec<(String, String)> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| {
+            (
+                error["field"].as_str().unwrap().to_string(),
+                error["code"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            ("title".to_string(), "blank".to_string()),
+            ("tag_ids".to_string(), "not_owned".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn auth_failures_echo_the_client_request_id() {
+    let db = setup_in_memory_db(false).await;
+
+    let (response, body) = send(
+        &app(&db),
+        Request::builder()
+            .uri("/tasks")
+            .header("x-request-id", "client-abc.123")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(request_id(&response), "client-abc.123");
+    assert_eq!(body["code"], "unauthorized");
+    assert_eq!(body["request_id"], "client-abc.123");
+    assert!(body.get("detail").is_none());
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use std::time::Duration;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{
+        Method, Request, StatusCode,
+        header::{AUTHORIZATION, CONTENT_TYPE},
+    },
+};
+use decopon_config::AppMode;
+use futures_util::StreamExt;
+use tower::ServiceExt;
+
+use decopon_axum::{routes, usecases};
+
+use common::{build_app_state, create_user, send, setup_in_memory_db};
+
+const SECRET: &str = "test_secret";
+
+async fn post_task(app: &Router, token: &str, title: &str) {
+    let payload = serde_json::json!({
+        "title": title,
+        "description": "",
+        "parent_task_id": null,
+        "tag_ids": null,
+    });
+    let (response, _) = send(app, token, Method::POST, "/tasks", &[], Some(payload)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+async fn open_stream(app: &Router, token: &str, last_event_id: Option<&str>) -> Body {
+    let mut request = Request::builder()
+        .uri("/events")
+        .header(AUTHORIZATION, format!("Bearer {token}"));
+    if let Some(last_event_id) = last_event_id {
+        request = request.header("last-event-id", last_event_id);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], "text/event-stream");
+    response.into_body()
+}
+
+/// 次のイベント一件分のテキストを読む
+async fn next_event(stream: &mut axum::body::BodyDataStream) -> String {
+    let chunk = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("event within timeout")
+        .expect("stream open")
+        .unwrap();
+    String::from_utf8(chunk.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn streams_changes_made_after_subscribing() {
+    let db = setup_in_memory_db(false).await;
+    let alice = create_user(&db, "alice").await;
+    let bob = create_user(&db, "bob").await;
+    let alice_token = usecases::auth::create_jwt(alice.id, SECRET).unwrap();
+    let bob_token = usecases::auth::create_jwt(bob.id, SECRET).unwrap();
+    let state = build_app_state(&db, SECRET);
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+
+    let mut stream = open_stream(&app, &alice_token, None)
+        .await
+        .into_data_stream();
+    post_task(&app, &bob_token, "not for alice").await;
+    post_task(&app, &alice_token, "buy milk").await;
+
+    let event = next_event(&mut stream).await;
+    assert!(event.contains("event: task_created"), "{event}");
+    assert!(event.contains("id: 2"), "{event}");
+    assert!(event.contains("\"type\":\"task_created\""), "{event}");
+}
+
+#[tokio::test]
+async fn resumes_after_the_last_event_id() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let state = build_app_state(&db, SECRET);
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+
+    post_task(&app, &token, "first").await;
+    post_task(&app, &token, "second").await;
+
+    let mut stream = open_stream(&app, &token, Some("1"))
+        .await
+        .into_data_stream();
+    let event = next_event(&mut stream).await;
+    assert!(event.contains("id: 2"), "{event}");
+    assert!(event.contains("event: task_created"), "{event}");
+}
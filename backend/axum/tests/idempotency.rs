@@ -0,0 +1,197 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{
+        HeaderName, Method, Request, StatusCode,
+        header::{AUTHORIZATION, CONTENT_TYPE, ETAG},
+    },
+    response::Response,
+};
+use decopon_config::AppMode;
+use sea_orm::{EntityTrait, PaginatorTrait};
+use tower::ServiceExt;
+
+use decopon_axum::{entities::prelude::Tasks, routes, usecases};
+
+use common::{build_app_state, create_user, send, setup_in_memory_db};
+
+const SECRET: &str = "test_secret";
+
+async fn post_task(
+    app: &Router,
+    token: &str,
+    key: Option<&str>,
+    title: &str,
+) -> (Response, serde_json::Value) {
+    let key = key.map(|key| (HeaderName::from_static("idempotency-key"), key));
+    let payload = serde_json::json!({
+        "title": title,
+        "description": "",
+        "parent_task_id": null,
+        "tag_ids": null,
+    });
+    let (response, body) = send(
+        app,
+        token,
+        Method::POST,
+        "/tasks",
+        key.as_slice(),
+        Some(payload),
+    )
+    .await;
+    (response, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn retried_post_replays_the_first_response() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let state = build_app_state(&db, SECRET);
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+
+    let (first, created) = post_task(&app, &token, Some("retry-1"), "buy milk").await;
+    assert_eq!(first.status(), StatusCode::OK);
+    assert!(first.headers().get("idempotent-replayed").is_none());
+
+    let (retry, replayed) = post_task(&app, &token, Some("retry-1"), "buy milk").await;
+    assert_eq!(retry.status(), StatusCode::OK);
+    assert_eq!(retry.headers()["idempotent-replayed"], "true");
+    assert_eq!(replayed, created);
+    // 最初のレスポンスのヘッダも再現する
+    assert_eq!(retry.headers()[ETAG], first.headers()[ETAG]);
+    assert_eq!(retry.headers()[CONTENT_TYPE], "application/json");
+    assert_eq!(Tasks::find().count(db.as_ref()).await.unwrap(), 1);
+
+    // キーがなければ従来どおり毎回作成する
+    post_task(&app, &token, None, "buy milk").await;
+    post_task(&app, &token, None, "buy milk").await;
+    assert_eq!(Tasks::find().count(db.as_ref()).await.unwrap(), 3);
+}
+
+#[tokio::test]
+async fn reusing_a_key_with_a_different_body_is_rejected() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let state = build_app_state(&db, SECRET);
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+
+    post_task(&app, &token, Some("retry-1"), "buy milk").await;
+    let (response, body) = post_task(&app, &token, Some("retry-1"), "buy eggs").await;
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["errors"][0]["field"], "Idempotency-Key");
+    assert_eq!(body["errors"][0]["code"], "key_reused");
+    assert_eq!(Tasks::find().count(db.as_ref()).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn retry_while_the_first_request_is_running_is_a_conflict() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let state = build_app_state(&db, SECRET);
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+
+    // 最初のリクエストがキーを予約したまま、まだ処理している
+    let payload = serde_json::json!({
+        "title": "buy milk",
+        "description": "",
+        "parent_task_id": null,
+        "tag_ids": null,
+    });
+    let hash =
+        usecases::idempotency::request_hash("POST", "/tasks", payload.to_string().as_bytes());
+    usecases::idempotency::reserve(&db, user.id, "retry-1", &hash)
+        .await
+        .unwrap();
+
+    let (response, body) = post_task(&app, &token, Some("retry-1"), "buy milk").await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(body["resource"], "idempotency_key");
+    assert_eq!(Tasks::find().count(db.as_ref()).await.unwrap(), 0);
+
+    // 失敗したリクエストの予約は取り消され、やり直せる
+    let (response, _) = post_task(&app, &token, Some("retry-2"), "").await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let (response, _) = post_task(&app, &token, Some("retry-2"), "").await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.headers().get("idempotent-replayed").is_none());
+}
+
+/// 本文の末尾を空白で埋め、通常の上限 (2 MiB) を超える大きさにして送る
+async fn post_padded(app: &Router, token: &str, uri: &str, key: &str, payload: String) -> Response {
+    let body = payload + &" ".repeat(3 * 1024 * 1024);
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(AUTHORIZATION, format!("Bearer {token}"))
+        .header(CONTENT_TYPE, "application/json")
+        .header("idempotency-key", key)
+        .body(Body::from(body))
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn large_imports_follow_the_import_body_limit() {
+    let source = setup_in_memory_db(false).await;
+    let source_user = create_user(&source, "alice").await;
+    usecases::tasks::insert_task(
+        source.as_ref(),
+        usecases::tasks::NewTask {
+            title: "imported".to_string(),
+            description: String::new(),
+            parent_task_id: None,
+            tag_ids: None,
+            due_at: None,
+            user_id: source_user.id,
+        },
+    )
+    .await
+    .unwrap();
+    let archive = usecases::archive::export_archive(&source, source_user.id)
+        .await
+        .unwrap();
+    let archive = serde_json::to_string(&archive).unwrap();
+
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let state = build_app_state(&db, SECRET);
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+
+    let first = post_padded(
+        &app,
+        &token,
+        "/profiles/import",
+        "import-1",
+        archive.clone(),
+    )
+    .await;
+    assert_eq!(first.status(), StatusCode::OK);
+    let retry = post_padded(&app, &token, "/profiles/import", "import-1", archive).await;
+    assert_eq!(retry.status(), StatusCode::OK);
+    assert_eq!(retry.headers()["idempotent-replayed"], "true");
+    assert_eq!(
+        to_bytes(retry.into_body(), usize::MAX).await.unwrap(),
+        to_bytes(first.into_body(), usize::MAX).await.unwrap()
+    );
+    assert_eq!(Tasks::find().count(db.as_ref()).await.unwrap(), 1);
+
+    // ほかのルートでは、キーがあっても通常の上限で断る
+    let payload = serde_json::json!({
+        "title": "buy milk",
+        "description": "",
+        "parent_task_id": null,
+        "tag_ids": null,
+    });
+    let response = post_padded(&app, &token, "/tasks", "task-1", payload.to_string()).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(Tasks::find().count(db.as_ref()).await.unwrap(), 1);
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode, header},
+};
+use chrono::{Duration, Utc};
+use sea_orm::{ActiveModelTrait, Set};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+use decopon_axum::{entities::users, middleware::auth::AuthenticatedUser, routes, usecases};
+
+use common::{build_app_state, new_user, setup_in_memory_db};
+
+async fn send(
+    app: axum::Router,
+    method: Method,
+    uri: &str,
+    user_id: i32,
+    payload: Option<Value>,
+) -> (StatusCode, String, String) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .extension(AuthenticatedUser {
+            id: user_id,
+            exp: 0,
+        });
+    let body = match payload {
+        Some(payload) => {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+            Body::from(payload.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        content_type,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn summarizes_a_day_as_json_or_markdown() {
+    let db = setup_in_memory_db(false).await;
+    let user = users::ActiveModel {
+        locale: Set("en".to_string()),
+        ..new_user("alice")
+    }
+    .insert(db.as_ref())
+    .await
+    .unwrap();
+    let ended_at = Utc::now();
+    usecases::decopon_sessions::insert_session(
+        db.as_ref(),
+        usecases::decopon_sessions::NewDecoponSession {
+            status: "Completed".to_string(),
+            started_at: ended_at - Duration::minutes(25),
+            ended_at: Some(ended_at),
+            user_id: user.id,
+        },
+    )
+    .await
+    .unwrap();
+    usecases::logs::insert_log(
+        db.as_ref(),
+        usecases::logs::NewLog {
+            content: "Reviewed PR".to_string(),
+            source: usecases::logs::LogSource::User,
+            task_id: None,
+            user_id: user.id,
+            tag_ids: Vec::new(),
+            tag_names: vec!["work".to_string()],
+        },
+    )
+    .await
+    .unwrap();
+    let state = build_app_state(&db, "test_secret");
+    let journal = routes::journal::routes().with_state(state.clone());
+    let today = ended_at.format("%Y-%m-%d");
+
+    let (status, _, body) = send(
+        journal.clone(),
+        Method::GET,
+        &format!("/{today}"),
+        user.id,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["date"], today.to_string());
+    assert_eq!(body["totals"]["sessions"], 1);
+    assert_eq!(body["totals"]["focus_minutes"], 25);
+    assert_eq!(body["log_groups"][0]["task_id"], Value::Null);
+    assert_eq!(body["log_groups"][0]["logs"][0]["content"], "Reviewed PR");
+
+    let (status, content_type, body) = send(
+        journal.clone(),
+        Method::GET,
+        &format!("/{today}?format=markdown"),
+        user.id,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("text/markdown"));
+    assert!(body.starts_with(&format!("# Journal {today} (UTC)\n")));
+    assert!(body.contains(" Reviewed PR #work\n"));
+
+    let (status, _, _) = send(journal, Method::GET, "/yesterday", user.id, None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 日報のメールは設定から切り替え、省略すれば変わらない
+    let preferences = routes::preferences::routes().with_state(state);
+    let payload = json!({"work_time": 25, "break_time": 5, "locale": "en"});
+    let mut opt_in = payload.clone();
+    opt_in["daily_journal_email"] = json!(true);
+    let (_, _, body) = send(preferences.clone(), Method::PUT, "/", user.id, Some(opt_in)).await;
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap()["daily_journal_email"],
+        true
+    );
+    let (_, _, body) = send(preferences, Method::PUT, "/", user.id, Some(payload)).await;
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap()["daily_journal_email"],
+        true
+    );
+}
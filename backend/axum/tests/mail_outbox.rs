@@ -0,0 +1,60 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode, header::CONTENT_TYPE},
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use tower::ServiceExt;
+
+use decopon_axum::{
+    entities::{mail_outbox, prelude::*, users},
+    routes,
+};
+
+use common::{build_app_state, setup_in_memory_db};
+
+/// 登録処理はキューへの登録だけを行い、送信はワーカーに任せる
+#[tokio::test]
+async fn registration_queues_verification_mail_instead_of_sending_inline() {
+    let db = setup_in_memory_db(false).await;
+    let app = routes::auth::web_routes().with_state(build_app_state(&db, "test_secret"));
+
+    let payload = serde_json::json!({
+        "name": "Alice",
+        "email": "alice@example.com",
+        "password": "password",
+        "password_confirmation": "password",
+    });
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/users")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    assert!(
+        Users::find()
+            .filter(users::Column::Email.eq("alice@example.com"))
+            .one(db.as_ref())
+            .await
+            .unwrap()
+            .is_some()
+    );
+    let queued = MailOutbox::find()
+        .filter(mail_outbox::Column::Recipient.eq("alice@example.com"))
+        .all(db.as_ref())
+        .await
+        .unwrap();
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].status, "Pending");
+    assert!(queued[0].body.contains("/guest/verify-email/"));
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use std::{env, fs, path::Path, sync::Arc};
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Method, Request, StatusCode, header::CONTENT_TYPE},
+};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use tower::ServiceExt;
+
+use decopon_axum::{
+    entities::{prelude::*, users},
+    mail_transport::FileMailTransport,
+    routes,
+    usecases::{mail_outbox::MailOutboxWorker, mails::Mailer},
+};
+
+use common::{build_app_state_with_mailer, setup_in_memory_db};
+
+async fn send_json(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    payload: serde_json::Value,
+) -> StatusCode {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+/// ディレクトリ内の `.eml` をすべて読み込む
+fn read_mails(dir: &Path) -> Vec<String> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "eml"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| fs::read_to_string(path).unwrap())
+        .collect()
+}
+
+/// 本文中の `marker` の直後に続くトークンを取り出す
+fn extract_token(mail: &str, marker: &str) -> String {
+    let start = mail.find(marker).expect("marker in mail body") + marker.len();
+    mail[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
+
+#[tokio::test]
+async fn file_transport_supports_verification_and_password_reset_flows() {
+    let dir = env::temp_dir().join(format!("decopon-mail-flow-{}", rand::random::<u64>()));
+    let transport = FileMailTransport::new(&dir).unwrap();
+    let mailer: Mailer = Arc::new(transport);
+
+    let db = setup_in_memory_db(false).await;
+    let state = build_app_state_with_mailer(&db, "test_secret", Arc::clone(&mailer));
+    let app = routes::auth::web_routes().with_state(state);
+    let worker = MailOutboxWorker::new(Arc::clone(&db), mailer);
+
+    let status = send_json(
+        &app,
+        Method::POST,
+        "/users",
+        serde_json::json!({
+            "name": "Alice",
+            "email": "alice@example.com",
+            "password": "password",
+            "password_confirmation": "password",
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(worker.process_due().await.unwrap(), 1);
+
+    let mails = read_mails(&dir);
+    assert_eq!(mails.len(), 1);
+    assert!(mails[0].contains("To: alice@example.com\r\n"));
+    let token = extract_token(&mails[0], "/guest/verify-email/");
+    let status = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/email/verify/{token}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status();
+    assert_eq!(status, StatusCode::OK);
+
+    // 再設定メールはユーザーの言語で送られる
+    let user = Users::find()
+        .filter(users::Column::Email.eq("alice@example.com"))
+        .one(db.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    let mut user: users::ActiveModel = user.into();
+    user.locale = Set("ja".to_string());
+    user.update(db.as_ref()).await.unwrap();
+
+    let status = send_json(
+        &app,
+        Method::POST,
+        "/password/forgot",
+        serde_json::json!({ "email": "alice@example.com" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(worker.process_due().await.unwrap(), 1);
+
+    let mails = read_mails(&dir);
+    assert_eq!(mails.len(), 2);
+    let reset_mail = mails
+        .iter()
+        .find(|mail| mail.contains("/guest/reset-password/"))
+        .expect("password reset mail");
+    assert!(reset_mail.contains("Subject: パスワードの再設定\r\n"));
+    assert!(reset_mail.contains("Content-Type: multipart/alternative"));
+    assert!(reset_mail.contains("?email=alice%40example.com"));
+    let token = extract_token(reset_mail, "/guest/reset-password/");
+    let status = send_json(
+        &app,
+        Method::POST,
+        "/password/reset",
+        serde_json::json!({
+            "token": token,
+            "email": "alice@example.com",
+            "password": "new-password",
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let status = send_json(
+        &app,
+        Method::POST,
+        "/sessions",
+        serde_json::json!({ "email": "alice@example.com", "password": "new-password" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,346 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Form, Json, Router,
+    body::{Body, to_bytes},
+    extract::{Query, State},
+    http::{Method, Request, StatusCode, header::CONTENT_TYPE, header::LOCATION},
+    response::{IntoResponse, Redirect},
+    routing::{get, post},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use jsonwebtoken::{EncodingKey, Header, encode};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+use decopon_axum::{
+    AppState, ServiceContext,
+    entities::{prelude::*, user_identities, users},
+    password::{PasswordHashConfig, PasswordWorker},
+    routes,
+    usecases::oidc::{OidcClient, OidcSettings},
+};
+
+use common::{create_user, setup_in_memory_db};
+
+const CLIENT_ID: &str = "decopon";
+const CLIENT_SECRET: &str = "mock-client-secret";
+const REDIRECT_URL: &str = "http://localhost:5173/guest/oidc/callback";
+
+#[derive(Clone)]
+struct MockIdentity {
+    sub: String,
+    email: String,
+    email_verified: bool,
+}
+
+struct PendingAuthorization {
+    nonce: String,
+    code_challenge: String,
+}
+
+#[derive(Clone)]
+struct MockIssuer {
+    issuer: String,
+    identity: Arc<Mutex<MockIdentity>>,
+    pending: Arc<Mutex<HashMap<String, PendingAuthorization>>>,
+}
+
+#[derive(Deserialize)]
+struct AuthorizeParams {
+    client_id: String,
+    redirect_uri: String,
+    state: String,
+    nonce: String,
+    code_challenge: String,
+    code_challenge_method: String,
+}
+
+#[derive(Deserialize)]
+struct TokenForm {
+    grant_type: String,
+    code: String,
+    code_verifier: String,
+    client_id: String,
+    client_secret: Option<String>,
+}
+
+async fn discovery(State(mock): State<MockIssuer>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "issuer": mock.issuer,
+        "authorization_endpoint": format!("{}/authorize", mock.issuer),
+        "token_endpoint": format!("{}/token", mock.issuer),
+        "jwks_uri": format!("{}/jwks", mock.issuer),
+    }))
+}
+
+async fn authorize(
+    State(mock): State<MockIssuer>,
+    Query(params): Query<AuthorizeParams>,
+) -> impl IntoResponse {
+    assert_eq!(params.client_id, CLIENT_ID);
+    assert_eq!(params.code_challenge_method, "S256");
+    let code = format!("code-{}", params.state);
+    mock.pending.lock().unwrap().insert(
+        code.clone(),
+        PendingAuthorization {
+            nonce: params.nonce,
+            code_challenge: params.code_challenge,
+        },
+    );
+    Redirect::to(&format!(
+        "{}?code={}&state={}",
+        params.redirect_uri, code, params.state
+    ))
+}
+
+async fn token(State(mock): State<MockIssuer>, Form(form): Form<TokenForm>) -> impl IntoResponse {
+    let pending = mock.pending.lock().unwrap().remove(&form.code);
+    let Some(pending) = pending else {
+        return (StatusCode::BAD_REQUEST, "invalid_grant").into_response();
+    };
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(form.code_verifier.as_bytes()));
+    if form.grant_type != "authorization_code"
+        || form.client_id != CLIENT_ID
+        || form.client_secret.as_deref() != Some(CLIENT_SECRET)
+        || challenge != pending.code_challenge
+    {
+        return (StatusCode::BAD_REQUEST, "invalid_grant").into_response();
+    }
+
+    let identity = mock.identity.lock().unwrap().clone();
+    let now = Utc::now().timestamp();
+    let claims = serde_json::json!({
+        "iss": mock.issuer,
+        "aud": CLIENT_ID,
+        "sub": identity.sub,
+        "email": identity.email,
+        "email_verified": identity.email_verified,
+        "name": "SSO User",
+        "nonce": pending.nonce,
+        "iat": now,
+        "exp": now + 300,
+    });
+    let id_token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(CLIENT_SECRET.as_bytes()),
+    )
+    .unwrap();
+    Json(serde_json::json!({
+        "access_token": "mock-access-token",
+        "token_type": "Bearer",
+        "id_token": id_token,
+    }))
+    .into_response()
+}
+
+async fn spawn_mock_issuer(identity: MockIdentity) -> MockIssuer {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mock = MockIssuer {
+        issuer: format!("http://{}", listener.local_addr().unwrap()),
+        identity: Arc::new(Mutex::new(identity)),
+        pending: Arc::new(Mutex::new(HashMap::new())),
+    };
+    let app = Router::new()
+        .route("/.well-known/openid-configuration", get(discovery))
+        .route("/authorize", get(authorize))
+        .route("/token", post(token))
+        .with_state(mock.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    mock
+}
+
+fn build_oidc_app_state(db: &Arc<sea_orm::DatabaseConnection>, issuer: &str) -> AppState {
+    let client = OidcClient::new(OidcSettings {
+        issuer_url: issuer.to_string(),
+        client_id: CLIENT_ID.to_string(),
+        client_secret: Some(CLIENT_SECRET.to_string()),
+        redirect_url: REDIRECT_URL.to_string(),
+        scopes: vec!["openid".into(), "email".into(), "profile".into()],
+    });
+    AppState::from(
+        ServiceContext::builder(
+            Arc::clone(db),
+            Arc::new(PasswordWorker::new(1, PasswordHashConfig::default()).unwrap()),
+            "test_secret".to_string(),
+        )
+        .oidc(Some(client))
+        .build(),
+    )
+}
+
+async fn json_body(response: axum::response::Response) -> serde_json::Value {
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// `/oidc/authorize` から IdP の認可画面を経由し、コールバックに渡す (code, state) を得る。
+async fn authorize_at_issuer(app: &Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/oidc/authorize")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let authorization_url = json_body(response).await["authorization_url"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let response = client.get(&authorization_url).send().await.unwrap();
+    let location = response.headers()[LOCATION].to_str().unwrap().to_string();
+    let location = reqwest::Url::parse(&location).unwrap();
+    assert!(location.as_str().starts_with(REDIRECT_URL));
+    let params: HashMap<_, _> = location.query_pairs().into_owned().collect();
+    (params["code"].clone(), params["state"].clone())
+}
+
+async fn post_callback(app: &Router, code: &str, state: &str) -> axum::response::Response {
+    let payload = serde_json::json!({ "code": code, "state": state });
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/oidc/callback")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn oidc_login_provisions_new_user() {
+    let db = setup_in_memory_db(false).await;
+    let mock = spawn_mock_issuer(MockIdentity {
+        sub: "sso-1".into(),
+        email: "new@example.com".into(),
+        email_verified: true,
+    })
+    .await;
+    let app = routes::auth::web_routes().with_state(build_oidc_app_state(&db, &mock.issuer));
+
+    let (code, state) = authorize_at_issuer(&app).await;
+    let response = post_callback(&app, &code, &state).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_body(response).await;
+    assert!(!body["token"].as_str().unwrap().is_empty());
+    assert_eq!(body["user"]["email"], "new@example.com");
+    assert_eq!(body["user"]["name"], "SSO User");
+
+    let user = Users::find()
+        .filter(users::Column::Email.eq("new@example.com"))
+        .one(db.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(user.email_verified_at.is_some());
+    let identity = UserIdentities::find()
+        .filter(user_identities::Column::Subject.eq("sso-1"))
+        .one(db.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(identity.user_id, user.id);
+    assert_eq!(identity.issuer, mock.issuer);
+}
+
+#[tokio::test]
+async fn oidc_login_links_existing_user_by_verified_email() {
+    let db = setup_in_memory_db(false).await;
+    let existing = create_user(db.as_ref(), "existing").await;
+    let mock = spawn_mock_issuer(MockIdentity {
+        sub: "sso-2".into(),
+        email: "existing@example.com".into(),
+        email_verified: true,
+    })
+    .await;
+    let app = routes::auth::web_routes().with_state(build_oidc_app_state(&db, &mock.issuer));
+
+    let (code, state) = authorize_at_issuer(&app).await;
+    let response = post_callback(&app, &code, &state).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(response).await["user"]["id"], existing.id);
+
+    // 一度リンクした後は IdP 側のメールが変わっても sub で同じユーザーに解決される
+    mock.identity.lock().unwrap().email = "renamed@example.com".into();
+    let (code, state) = authorize_at_issuer(&app).await;
+    let response = post_callback(&app, &code, &state).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(response).await["user"]["id"], existing.id);
+
+    let stored = Users::find_by_id(existing.id)
+        .one(db.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.password, "hashed");
+}
+
+#[tokio::test]
+async fn oidc_login_rejects_unverified_email_and_reused_state() {
+    let db = setup_in_memory_db(false).await;
+    let mock = spawn_mock_issuer(MockIdentity {
+        sub: "sso-3".into(),
+        email: "unverified@example.com".into(),
+        email_verified: false,
+    })
+    .await;
+    let app = routes::auth::web_routes().with_state(build_oidc_app_state(&db, &mock.issuer));
+
+    let (code, state) = authorize_at_issuer(&app).await;
+    let response = post_callback(&app, &code, &state).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(
+        Users::find()
+            .filter(users::Column::Email.eq("unverified@example.com"))
+            .one(db.as_ref())
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let response = post_callback(&app, &code, &state).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn oidc_routes_return_not_found_when_unconfigured() {
+    let db = setup_in_memory_db(false).await;
+    let app = routes::auth::web_routes().with_state(common::build_app_state(&db, "test_secret"));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/oidc/authorize")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
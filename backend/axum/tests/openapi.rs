@@ -0,0 +1,58 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use std::{env, fs, path::Path};
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{Request, StatusCode},
+};
+use decopon_axum::{openapi::api_doc, routes};
+use decopon_config::AppMode;
+use tower::ServiceExt;
+
+use common::{build_app_state, setup_in_memory_db};
+
+/// コミット済みの仕様がルートや DTO の変更に追従しているかを確認する。
+/// 更新するときは `UPDATE_OPENAPI=1 cargo test --features web --test openapi` を実行する。
+#[test]
+fn committed_spec_is_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+    let generated = api_doc().to_pretty_json().unwrap() + "\n";
+
+    if env::var_os("UPDATE_OPENAPI").is_some() {
+        fs::write(&path, &generated).unwrap();
+        return;
+    }
+
+    let committed = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "openapi.json is out of date; rerun with UPDATE_OPENAPI=1 to regenerate it"
+    );
+}
+
+#[tokio::test]
+async fn serves_spec_without_authentication() {
+    let db = setup_in_memory_db(false).await;
+    let state = build_app_state(&db, "test_secret");
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/openapi.json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let spec: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(spec["info"]["title"], "Decopon API");
+    assert!(spec["paths"]["/tasks/{id}"]["put"].is_object());
+    assert!(spec["paths"]["/auth/sessions"]["post"]["security"].is_array());
+}
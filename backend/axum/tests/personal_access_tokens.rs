@@ -0,0 +1,305 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode, header::AUTHORIZATION, header::CONTENT_TYPE},
+    middleware::from_fn_with_state,
+    routing::get,
+};
+use chrono::{Duration, Utc};
+use decopon_config::AppMode;
+use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+use tower::ServiceExt;
+
+use decopon_axum::{
+    AppState,
+    entities::prelude::*,
+    middleware::auth::{AuthenticatedUser, auth_middleware},
+    routes, usecases,
+    usecases::personal_access_tokens::{NewPersonalAccessToken, TokenScope},
+};
+
+use common::{build_app_state, create_user, send, setup_in_memory_db};
+
+fn protected_app(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/",
+            get(
+                |axum::Extension(user): axum::Extension<AuthenticatedUser>| async move {
+                    user.id.to_string()
+                },
+            )
+            .post(|| async { StatusCode::OK }),
+        )
+        .with_state(state.clone())
+        .layer(from_fn_with_state(state, auth_middleware))
+}
+
+#[tokio::test]
+async fn personal_access_token_authenticates_and_records_last_used() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    let issued = usecases::personal_access_tokens::insert_token(
+        &db,
+        NewPersonalAccessToken {
+            name: "cron".to_string(),
+            scope: TokenScope::ReadWrite,
+            expires_at: None,
+            user_id: user.id,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(issued.token.last_used_at.is_none());
+
+    let app = protected_app(build_app_state(&db, "test_secret"));
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/")
+                .header(AUTHORIZATION, format!("Bearer {}", issued.plain_text))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body, user.id.to_string());
+
+    let stored = PersonalAccessTokens::find_by_id(issued.token.id)
+        .one(db.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(stored.last_used_at.is_some());
+    assert_ne!(stored.token_hash, issued.plain_text);
+}
+
+#[tokio::test]
+async fn read_only_token_rejects_writes() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    let issued = usecases::personal_access_tokens::insert_token(
+        &db,
+        NewPersonalAccessToken {
+            name: "prompt".to_string(),
+            scope: TokenScope::Read,
+            expires_at: None,
+            user_id: user.id,
+        },
+    )
+    .await
+    .unwrap();
+
+    let app = protected_app(build_app_state(&db, "test_secret"));
+    let read = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/")
+                .header(AUTHORIZATION, format!("Bearer {}", issued.plain_text))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(read.status(), StatusCode::OK);
+
+    let write = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(AUTHORIZATION, format!("Bearer {}", issued.plain_text))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(write.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn expired_or_unknown_tokens_are_rejected() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    let issued = usecases::personal_access_tokens::insert_token(
+        &db,
+        NewPersonalAccessToken {
+            name: "short-lived".to_string(),
+            scope: TokenScope::Read,
+            expires_at: Some(Utc::now() + Duration::hours(1)),
+            user_id: user.id,
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut expired: decopon_axum::entities::personal_access_tokens::ActiveModel =
+        PersonalAccessTokens::find_by_id(issued.token.id)
+            .one(db.as_ref())
+            .await
+            .unwrap()
+            .unwrap()
+            .into();
+    expired.expires_at = Set(Some(Utc::now() - Duration::minutes(1)));
+    expired.update(db.as_ref()).await.unwrap();
+
+    let app = protected_app(build_app_state(&db, "test_secret"));
+    for token in [issued.plain_text.as_str(), "dcp_unknown"] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header(AUTHORIZATION, format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[tokio::test]
+async fn token_routes_return_plain_text_only_on_creation() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    let app =
+        routes::personal_access_tokens::routes().with_state(build_app_state(&db, "test_secret"));
+    let auth_user = AuthenticatedUser {
+        id: user.id,
+        exp: 0,
+    };
+
+    let payload = serde_json::json!({ "name": "cron", "scope": "ReadWrite", "expires_at": null });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .extension(auth_user.clone())
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(created["token"].as_str().unwrap().starts_with("dcp_"));
+    assert_eq!(created["personal_access_token"]["scope"], "ReadWrite");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/")
+                .extension(auth_user.clone())
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let listed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let listed = listed.as_array().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["name"], "cron");
+    assert!(listed[0].get("token").is_none());
+    // 存在しないトークンの削除は 404 になる
+    let id = created["personal_access_token"]["id"].as_i64().unwrap();
+    for (id, expected) in [
+        (id, StatusCode::NO_CONTENT),
+        (id, StatusCode::NOT_FOUND),
+        (id + 1, StatusCode::NOT_FOUND),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::DELETE)
+                    .uri(format!("/{id}"))
+                    .extension(auth_user.clone())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected);
+    }
+}
+
+#[tokio::test]
+async fn tokens_cannot_change_the_account() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    let issued = usecases::personal_access_tokens::insert_token(
+        &db,
+        NewPersonalAccessToken {
+            name: "cron".to_string(),
+            scope: TokenScope::ReadWrite,
+            expires_at: None,
+            user_id: user.id,
+        },
+    )
+    .await
+    .unwrap();
+    let state = build_app_state(&db, "test_secret");
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+
+    // 漏れたトークンでメールアドレスを変えられると、パスワードの再設定まで奪われる
+    let (response, _) = send(
+        &app,
+        &issued.plain_text,
+        Method::PATCH,
+        "/profiles",
+        &[],
+        Some(serde_json::json!({ "name": "alice", "email": "mallory@example.com" })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let (response, _) = send(
+        &app,
+        &issued.plain_text,
+        Method::PUT,
+        "/profiles/password",
+        &[],
+        Some(serde_json::json!({
+            "current_password": "hashed",
+            "password": "new-password-123",
+        })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let stored = Users::find_by_id(user.id)
+        .one(db.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.email, "alice@example.com");
+    assert!(stored.pending_email.is_none());
+
+    // ログインセッションからは変えられる
+    let jwt = usecases::auth::create_jwt(user.id, "test_secret").unwrap();
+    let (response, _) = send(
+        &app,
+        &jwt,
+        Method::PATCH,
+        "/profiles",
+        &[],
+        Some(serde_json::json!({ "name": "alice", "email": "alice@example.com" })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode, header},
+};
+use chrono::{Duration, Utc};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use decopon_axum::{middleware::auth::AuthenticatedUser, routes, usecases};
+
+use common::{build_app_state, create_user, setup_in_memory_db};
+
+fn request(method: Method, uri: &str, user_id: i32, body: Body) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .extension(AuthenticatedUser {
+            id: user_id,
+            exp: 0,
+        })
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn exported_archive_imports_into_another_instance() {
+    let local = setup_in_memory_db(false).await;
+    let local_user = create_user(local.as_ref(), "alice").await;
+    let parent = usecases::tasks::insert_task(
+        local.as_ref(),
+        usecases::tasks::NewTask {
+            title: "parent".to_string(),
+            description: String::new(),
+            parent_task_id: None,
+            tag_ids: None,
+            due_at: None,
+            user_id: local_user.id,
+        },
+    )
+    .await
+    .unwrap();
+    usecases::tasks::insert_task(
+        local.as_ref(),
+        usecases::tasks::NewTask {
+            title: "child".to_string(),
+            description: String::new(),
+            parent_task_id: Some(parent.id),
+            tag_ids: None,
+            due_at: None,
+            user_id: local_user.id,
+        },
+    )
+    .await
+    .unwrap();
+
+    let app = routes::profiles::routes().with_state(build_app_state(&local, "test_secret"));
+    let response = app
+        .oneshot(request(
+            Method::GET,
+            "/export",
+            local_user.id,
+            Body::empty(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let disposition = response.headers()[header::CONTENT_DISPOSITION]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(disposition.starts_with("attachment; filename=\"decopon-"));
+    let archive = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    let web = setup_in_memory_db(false).await;
+    let web_user = create_user(web.as_ref(), "alice").await;
+    let app = routes::profiles::routes().with_state(build_app_state(&web, "test_secret"));
+    let response = app
+        .oneshot(request(
+            Method::POST,
+            "/import",
+            web_user.id,
+            Body::from(archive),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let summary: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(summary["tasks"], 2);
+    assert_eq!(summary["skipped"], 0);
+
+    let tasks = usecases::tasks::get_tasks(&web, web_user.id, None)
+        .await
+        .unwrap();
+    let child = tasks.iter().find(|task| task.title == "child").unwrap();
+    let parent = tasks.iter().find(|task| task.title == "parent").unwrap();
+    assert_eq!(child.parent_task_id, Some(parent.id));
+}
+
+#[tokio::test]
+async fn rejects_an_unknown_archive_format() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    let mut archive = serde_json::to_value(
+        usecases::archive::export_archive(&db, user.id)
+            .await
+            .unwrap(),
+    )
+    .unwrap();
+    archive["format"] = Value::from("something-else");
+
+    let app = routes::profiles::routes().with_state(build_app_state(&db, "test_secret"));
+    let response = app
+        .oneshot(request(
+            Method::POST,
+            "/import",
+            user.id,
+            Body::from(archive.to_string()),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn export_streams_sections_page_by_page() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    // 一ページに収まらない数のセッションを作る
+    let started_at = Utc::now() - Duration::days(1);
+    for minutes in 0..501 {
+        usecases::decopon_sessions::insert_session(
+            db.as_ref(),
+            usecases::decopon_sessions::NewDecoponSession {
+                status: "Completed".to_string(),
+                started_at: started_at + Duration::minutes(minutes),
+                ended_at: None,
+                user_id: user.id,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let app = routes::profiles::routes().with_state(build_app_state(&db, "test_secret"));
+    let response = app
+        .oneshot(request(Method::GET, "/export", user.id, Body::empty()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(header::CONTENT_LENGTH).is_none());
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let archive: usecases::archive::Archive = serde_json::from_slice(&body).unwrap();
+    assert_eq!(archive.profile.name, "alice");
+    assert!(archive.tags.is_empty());
+    assert_eq!(archive.decopon_sessions.len(), 501);
+}
@@ -0,0 +1,198 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{
+        Method, Request, StatusCode,
+        header::{AUTHORIZATION, CONTENT_TYPE},
+    },
+};
+use decopon_config::AppMode;
+use tower::ServiceExt;
+
+use decopon_axum::{routes, usecases};
+
+use common::{build_app_state, create_user, setup_in_memory_db};
+
+const SECRET: &str = "test_secret";
+
+async fn app_with_token() -> (Router, String) {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let state = build_app_state(&db, SECRET);
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+    (app, token)
+}
+
+async fn send(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    token: Option<&str>,
+    body: String,
+) -> (StatusCode, serde_json::Value) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(CONTENT_TYPE, "application/json");
+    if let Some(token) = token {
+        request = request.header(AUTHORIZATION, format!("Bearer {token}"));
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::from(body)).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+/// 422 の本文から `(field, code)` の組を取り出す
+fn field_errors(body: &serde_json::Value) -> Vec<(String, String)> {
+    body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| {
+            (
+                error["field"].as_str().unwrap().to_string(),
+                error["code"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+fn pair(field: &str, code: &str) -> (String, String) {
+    (field.to_string(), code.to_string())
+}
+
+#[tokio::test]
+async fn task_and_log_payloads_are_validated() {
+    let (app, token) = app_with_token().await;
+
+    let payload = serde_json::json!({
+        "title": "",
+        "description": "x".repeat(10_001),
+        "parent_task_id": null,
+    });
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        "/tasks",
+        Some(&token),
+        payload.to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        field_errors(&body),
+        vec![pair("description", "length"), pair("title", "length")]
+    );
+
+    let payload = serde_json::json!({
+        "content": "x".repeat(10_001),
+        "source": "User",
+        "tag_names": ["ok", " "],
+    });
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        "/logs",
+        Some(&token),
+        payload.to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        field_errors(&body),
+        vec![pair("content", "length"), pair("tag_names", "length")]
+    );
+}
+
+#[tokio::test]
+async fn preferences_reject_out_of_range_times_and_unknown_locales() {
+    let (app, token) = app_with_token().await;
+
+    let payload = serde_json::json!({ "work_time": -5, "break_time": 5, "locale": "xx" });
+    let (status, body) = send(
+        &app,
+        Method::PUT,
+        "/preferences",
+        Some(&token),
+        payload.to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        field_errors(&body),
+        vec![
+            pair("locale", "unsupported_locale"),
+            pair("work_time", "range"),
+        ]
+    );
+
+    let payload = serde_json::json!({ "work_time": 50, "break_time": 10, "locale": "en" });
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        "/preferences",
+        Some(&token),
+        payload.to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn registration_reports_every_invalid_field() {
+    let (app, _) = app_with_token().await;
+
+    let payload = serde_json::json!({
+        "name": "Bob",
+        "email": "not-an-email",
+        "password": "short",
+        "password_confirmation": "different",
+    });
+    let (status, body) = send(&app, Method::POST, "/auth/users", None, payload.to_string()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        field_errors(&body),
+        vec![
+            pair("email", "email"),
+            pair("password", "length"),
+            pair("password_confirmation", "must_match"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn malformed_bodies_are_rejected_before_validation() {
+    let (app, token) = app_with_token().await;
+
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        "/tasks",
+        Some(&token),
+        "{not json".to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "bad_request");
+
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        "/tasks",
+        Some(&token),
+        serde_json::json!({ "description": "" }).to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(field_errors(&body), vec![pair("body", "invalid")]);
+}
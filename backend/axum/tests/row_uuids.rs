@@ -0,0 +1,264 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode, header},
+};
+use chrono::Utc;
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+use decopon_axum::{middleware::auth::AuthenticatedUser, routes, usecases};
+
+use common::{build_app_state, create_user, setup_in_memory_db};
+
+async fn send(
+    app: axum::Router,
+    method: Method,
+    uri: &str,
+    user_id: i32,
+    payload: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .extension(AuthenticatedUser {
+            id: user_id,
+            exp: 0,
+        });
+    let body = match payload {
+        Some(payload) => {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+            Body::from(payload.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn tasks_can_be_addressed_by_uuid() {
+    let db = setup_in_memory_db(false).await;
+    let owner = create_user(db.as_ref(), "owner").await;
+    let other = create_user(db.as_ref(), "other").await;
+    let task = usecases::tasks::insert_task(
+        db.as_ref(),
+        usecases::tasks::NewTask {
+            title: "task".to_string(),
+            description: String::new(),
+            parent_task_id: None,
+            tag_ids: None,
+            due_at: None,
+            user_id: owner.id,
+        },
+    )
+    .await
+    .unwrap();
+    let app = routes::tasks::routes().with_state(build_app_state(&db, "test_secret"));
+
+    let (status, body) = send(
+        app.clone(),
+        Method::GET,
+        &format!("/{}", task.uuid),
+        owner.id,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["id"], task.id);
+    assert_eq!(body["uuid"], task.uuid.as_str());
+
+    let (status, _) = send(
+        app.clone(),
+        Method::GET,
+        &format!("/{}", task.id),
+        owner.id,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send(
+        app.clone(),
+        Method::GET,
+        &format!("/{}", task.uuid),
+        other.id,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(app.clone(), Method::GET, "/not-an-id", owner.id, None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = send(
+        app,
+        Method::DELETE,
+        &format!("/{}", task.uuid),
+        owner.id,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn sessions_can_be_addressed_by_uuid() {
+    let db = setup_in_memory_db(false).await;
+    let owner = create_user(db.as_ref(), "owner").await;
+    let session = usecases::decopon_sessions::insert_session(
+        db.as_ref(),
+        usecases::decopon_sessions::NewDecoponSession {
+            status: "In_Progress".to_string(),
+            started_at: Utc::now(),
+            ended_at: None,
+            user_id: owner.id,
+        },
+    )
+    .await
+    .unwrap();
+    let app = routes::decopon_sessions::routes().with_state(build_app_state(&db, "test_secret"));
+
+    let (status, body) = send(
+        app,
+        Method::GET,
+        &format!("/{}", session.uuid),
+        owner.id,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["id"], session.id);
+    assert_eq!(body["uuid"], session.uuid.as_str());
+}
+
+#[tokio::test]
+async fn body_fields_accept_uuids() {
+    let db = setup_in_memory_db(false).await;
+    let owner = create_user(db.as_ref(), "owner").await;
+    let state = build_app_state(&db, "test_secret");
+    let parent = usecases::tasks::insert_task(
+        db.as_ref(),
+        usecases::tasks::NewTask {
+            title: "parent".to_string(),
+            description: String::new(),
+            parent_task_id: None,
+            tag_ids: None,
+            due_at: None,
+            user_id: owner.id,
+        },
+    )
+    .await
+    .unwrap();
+    let tag = usecases::tags::insert_tag(
+        db.as_ref(),
+        usecases::tags::NewTag {
+            name: "work".to_string(),
+            user_id: owner.id,
+        },
+    )
+    .await
+    .unwrap();
+
+    let tasks = routes::tasks::routes().with_state(state.clone());
+    let (status, child) = send(
+        tasks,
+        Method::POST,
+        "/",
+        owner.id,
+        Some(json!({
+            "title": "child",
+            "description": "",
+            "parent_task_id": parent.uuid,
+            "tag_ids": [tag.uuid],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(child["parent_task_id"], parent.id);
+    assert_eq!(child["tags"][0]["id"], tag.id);
+
+    let logs = routes::logs::routes().with_state(state.clone());
+    let (status, log) = send(
+        logs.clone(),
+        Method::POST,
+        "/",
+        owner.id,
+        Some(json!({
+            "content": "Reviewed PR",
+            "source": "User",
+            "task_id": parent.uuid,
+            "tag_ids": [tag.uuid],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(log["task_id"], parent.id);
+    assert_eq!(log["tags"][0]["id"], tag.id);
+
+    // 見つからない UUID は他の行と取り違えずに 404 にする
+    let (status, body) = send(
+        logs,
+        Method::POST,
+        "/",
+        owner.id,
+        Some(json!({
+            "content": "Reviewed PR",
+            "source": "User",
+            "tag_ids": ["6f9619ff-8b86-d011-b42d-00c04fc964ff"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["resource"], "tag");
+
+    let batch = routes::batch::routes().with_state(state.clone());
+    let (status, body) = send(
+        batch,
+        Method::POST,
+        "/",
+        owner.id,
+        Some(json!({ "operations": [{
+            "op": "set_log_tags",
+            "id": log["uuid"],
+            "tag_names": ["home"],
+        }] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["results"][0]["data"]["tags"][0]["name"], "home");
+
+    let tags = routes::tags::routes().with_state(state);
+    let (status, body) = send(
+        tags.clone(),
+        Method::DELETE,
+        "/relation",
+        owner.id,
+        Some(json!({ "task_id": child["uuid"], "name": "work" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["id"], tag.id);
+
+    let (status, _) = send(
+        tags.clone(),
+        Method::DELETE,
+        "/multiple",
+        owner.id,
+        Some(json!({ "tag_ids": [tag.uuid] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, body) = send(tags, Method::GET, "/", owner.id, None).await;
+    assert!(
+        body.as_array()
+            .unwrap()
+            .iter()
+            .all(|listed| listed["id"] != tag.id)
+    );
+}
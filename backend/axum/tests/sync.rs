@@ -0,0 +1,176 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    Router,
+    http::{Method, StatusCode},
+};
+use decopon_config::AppMode;
+
+use decopon_axum::{
+    routes, usecases,
+    usecases::{sync, tasks},
+};
+
+use common::{build_app_state, create_user, send, setup_in_memory_db};
+
+const SECRET: &str = "test_secret";
+
+async fn send_json(
+    app: &Router,
+    token: &str,
+    method: Method,
+    uri: &str,
+    payload: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    let (response, body) = send(app, token, method, uri, &[], payload).await;
+    let json = if body.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_slice(&body).unwrap()
+    };
+    (response.status(), json)
+}
+
+/// アプリ側のローカル DB で作ったタスクの変更
+async fn local_task_changes(title: &str) -> Vec<sync::SyncChange> {
+    let local = setup_in_memory_db(false).await;
+    let user = create_user(&local, "alice").await;
+    tasks::insert_task(
+        local.as_ref(),
+        tasks::NewTask {
+            title: title.to_string(),
+            description: String::new(),
+            parent_task_id: None,
+            tag_ids: None,
+            due_at: None,
+            user_id: user.id,
+        },
+    )
+    .await
+    .unwrap();
+    sync::local_changes_since(local.as_ref(), user.id, 0, "laptop")
+        .await
+        .unwrap()
+        .changes
+}
+
+#[tokio::test]
+async fn pushed_changes_reach_other_devices_but_not_the_sender() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let state = build_app_state(&db, SECRET);
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+
+    let changes = local_task_changes("from laptop").await;
+    let (status, body) = send_json(
+        &app,
+        &token,
+        Method::POST,
+        "/sync/push",
+        Some(serde_json::json!({ "device_id": "laptop", "changes": changes })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["applied"], 1);
+    assert_eq!(body["skipped"], serde_json::json!([]));
+
+    let (status, tasks) = send_json(&app, &token, Method::GET, "/tasks", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(tasks[0]["title"], "from laptop");
+
+    let (_, echo) = send_json(
+        &app,
+        &token,
+        Method::GET,
+        "/sync/changes?device_id=laptop",
+        None,
+    )
+    .await;
+    assert_eq!(echo["changes"], serde_json::json!([]));
+
+    let (status, page) = send_json(
+        &app,
+        &token,
+        Method::GET,
+        "/sync/changes?since=0&device_id=phone",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["has_more"], false);
+    let change = &page["changes"][0];
+    assert_eq!(change["entity"], "task");
+    assert_eq!(change["operation"], "upsert");
+    assert_eq!(change["origin"], "laptop");
+    assert_eq!(change["data"]["title"], "from laptop");
+
+    // 受け取り済みのカーソル以降には何もない
+    let cursor = page["cursor"].as_i64().unwrap();
+    let (_, rest) = send_json(
+        &app,
+        &token,
+        Method::GET,
+        &format!("/sync/changes?since={cursor}&device_id=phone"),
+        None,
+    )
+    .await;
+    assert_eq!(rest["changes"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn web_changes_are_reported_with_the_web_origin() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let state = build_app_state(&db, SECRET);
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+
+    let (status, _) = send_json(
+        &app,
+        &token,
+        Method::POST,
+        "/tasks",
+        Some(serde_json::json!({
+            "title": "from browser",
+            "description": "",
+            "parent_task_id": null,
+            "tag_ids": null,
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, page) = send_json(
+        &app,
+        &token,
+        Method::GET,
+        "/sync/changes?device_id=laptop",
+        None,
+    )
+    .await;
+    assert_eq!(page["changes"][0]["origin"], sync::WEB_ORIGIN);
+    assert_eq!(page["changes"][0]["data"]["title"], "from browser");
+}
+
+#[tokio::test]
+async fn rejects_the_reserved_device_id() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let state = build_app_state(&db, SECRET);
+    let app = routes::create_routes(state.clone(), AppMode::Web).with_state(state);
+
+    let (status, body) = send_json(
+        &app,
+        &token,
+        Method::POST,
+        "/sync/push",
+        Some(serde_json::json!({ "device_id": "web", "changes": [] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["errors"][0]["field"], "device_id");
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode, header},
+};
+use sea_orm::DatabaseConnection;
+use tower::ServiceExt;
+
+use decopon_axum::{middleware::auth::AuthenticatedUser, routes, usecases};
+
+use common::{build_app_state, create_user, setup_in_memory_db};
+
+async fn create_task(
+    db: &DatabaseConnection,
+    user_id: i32,
+    title: &str,
+    parent_task_id: Option<i32>,
+    tag_ids: Option<Vec<i32>>,
+) -> usecases::tasks::Task {
+    usecases::tasks::insert_task(
+        db,
+        usecases::tasks::NewTask {
+            title: title.to_string(),
+            description: String::new(),
+            parent_task_id,
+            tag_ids,
+            due_at: None,
+            user_id,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn get(app: axum::Router, uri: &str, user_id: i32) -> (StatusCode, String, String) {
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .extension(AuthenticatedUser {
+                    id: user_id,
+                    exp: 0,
+                })
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        content_type,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn exports_task_trees_as_markdown_and_opml() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    let tag = usecases::tags::insert_tag(
+        &db,
+        usecases::tags::NewTag {
+            name: "work".to_string(),
+            user_id: user.id,
+        },
+    )
+    .await
+    .unwrap();
+    let plan = create_task(db.as_ref(), user.id, "Plan", None, Some(vec![tag.id])).await;
+    create_task(db.as_ref(), user.id, "Draft", Some(plan.id), None).await;
+    create_task(db.as_ref(), user.id, "Review", Some(plan.id), None).await;
+    create_task(db.as_ref(), user.id, "Other", None, None).await;
+    let app = routes::tasks::routes().with_state(build_app_state(&db, "test_secret"));
+
+    let (status, content_type, body) =
+        get(app.clone(), &format!("/{}/export", plan.id), user.id).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("text/markdown"));
+    assert_eq!(body, "- [ ] Plan #work\n  - [ ] Draft\n  - [ ] Review\n");
+
+    let (status, content_type, body) = get(app.clone(), "/export?format=opml", user.id).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("text/x-opml"));
+    assert!(body.contains("<outline text=\"Plan #work\" category=\"work\">"));
+    assert!(body.contains("<outline text=\"Other\"/>"));
+
+    let (status, _, _) = get(app, "/export?format=docx", user.id).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode, header},
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+use decopon_axum::{middleware::auth::AuthenticatedUser, routes, usecases};
+
+use common::{build_app_state, create_user, setup_in_memory_db};
+
+async fn post_import(app: axum::Router, user_id: i32, payload: Value) -> (StatusCode, Value) {
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/import")
+                .header(header::CONTENT_TYPE, "application/json")
+                .extension(AuthenticatedUser {
+                    id: user_id,
+                    exp: 0,
+                })
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn imports_taskwarrior_exports_after_a_dry_run() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(db.as_ref(), "alice").await;
+    let app = routes::tasks::routes().with_state(build_app_state(&db, "test_secret"));
+    let content = json!([
+        {"uuid": "a", "description": "Move", "status": "pending", "tags": ["home"]},
+        {"uuid": "b", "description": "Pack books", "status": "completed", "parent": "a"}
+    ])
+    .to_string();
+
+    let (status, body) = post_import(
+        app.clone(),
+        user.id,
+        json!({"format": "taskwarrior_json", "content": content, "dry_run": true}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["dry_run"], true);
+    assert_eq!(body["new_tags"], json!(["home"]));
+    assert_eq!(body["tasks"][1]["parent_index"], 0);
+    assert_eq!(body["tasks"][1]["id"], Value::Null);
+    assert!(
+        usecases::tasks::get_tasks(&db, user.id, None)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let (status, body) = post_import(
+        app.clone(),
+        user.id,
+        json!({"format": "taskwarrior_json", "content": content}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let parent_id = body["tasks"][0]["id"].as_i64().unwrap() as i32;
+    let child_id = body["tasks"][1]["id"].as_i64().unwrap() as i32;
+    let child = usecases::tasks::get_task_by_id(db.as_ref(), user.id, child_id)
+        .await
+        .unwrap();
+    assert_eq!(child.parent_task_id, Some(parent_id));
+    assert!(child.completed);
+
+    let (status, body) = post_import(
+        app,
+        user.id,
+        json!({"format": "todoist_csv", "content": "just some text"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["errors"][0]["field"], "content");
+}
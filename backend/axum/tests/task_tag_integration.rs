@@ -155,6 +155,7 @@ async fn task_update_syncs_tags() {
             description: "desc".to_string(),
             parent_task_id: None,
             tag_ids: Some(vec![tag1.id]),
+            due_at: None,
             user_id: user.id,
         },
     )
@@ -250,6 +251,7 @@ async fn task_delete_detaches_tags() {
             description: "desc".to_string(),
             parent_task_id: None,
             tag_ids: Some(vec![tag1.id, tag2.id]),
+            due_at: None,
             user_id: user.id,
         },
     )
@@ -330,6 +332,7 @@ async fn tasks_index_filters_by_tag() {
             description: "desc".to_string(),
             parent_task_id: None,
             tag_ids: Some(vec![tag1.id]),
+            due_at: None,
             user_id: user.id,
         },
     )
@@ -343,6 +346,7 @@ async fn tasks_index_filters_by_tag() {
             description: "desc".to_string(),
             parent_task_id: None,
             tag_ids: Some(vec![tag2.id]),
+            due_at: None,
             user_id: user.id,
         },
     )
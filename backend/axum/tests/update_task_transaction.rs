@@ -58,6 +58,7 @@ async fn insert_task_returns_related_tags() {
             description: "desc".to_string(),
             parent_task_id: None,
             tag_ids: Some(vec![tag1.id, tag2.id]),
+            due_at: None,
             user_id: user.id,
         },
     )
@@ -117,7 +118,9 @@ async fn update_task_rollback_on_error() {
             completed: None,
             parent_task_id: None,
             tag_ids: Some(vec![999]),
+            due_at: None,
             user_id: user.id,
+            expected_version: None,
         },
     )
     .await;
@@ -184,7 +187,9 @@ async fn update_child_task_keeps_parent_relationship() {
             completed: None,
             parent_task_id: None,
             tag_ids: None,
+            due_at: None,
             user_id: user.id,
+            expected_version: None,
         },
     )
     .await
@@ -246,6 +251,7 @@ async fn update_task_returns_related_tags() {
             description: "desc".to_string(),
             parent_task_id: None,
             tag_ids: Some(vec![tag1.id]),
+            due_at: None,
             user_id: user.id,
         },
     )
@@ -261,7 +267,9 @@ async fn update_task_returns_related_tags() {
             completed: None,
             parent_task_id: None,
             tag_ids: Some(vec![tag2.id]),
+            due_at: None,
             user_id: user.id,
+            expected_version: None,
         },
     )
     .await
@@ -0,0 +1,354 @@
+#![cfg(feature = "web")]
+
+mod common;
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU16, Ordering},
+};
+
+use axum::{
+    Router,
+    extract::State,
+    http::{HeaderMap, Method, StatusCode},
+    routing::post,
+};
+use decopon_config::AppMode;
+use sea_orm::{DatabaseConnection, EntityTrait};
+
+use decopon_axum::{
+    AppState,
+    entities::prelude::WebhookDeliveries,
+    routes, usecases,
+    usecases::webhooks::{self, TargetPolicy, WebhookConfig, WebhookWorker},
+};
+
+use common::{build_app_state, create_user, send, service_context, setup_in_memory_db};
+
+const SECRET: &str = "test_secret";
+
+/// Webhook を受け取る側の代わりに、届いたリクエストを記録するローカルの HTTP サーバー
+#[derive(Clone, Default)]
+struct Receiver {
+    received: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+    status: Arc<AtomicU16>,
+}
+
+impl Receiver {
+    async fn start(status: StatusCode) -> (Self, String) {
+        let receiver = Receiver::default();
+        receiver.status.store(status.as_u16(), Ordering::SeqCst);
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(
+                    |State(receiver): State<Receiver>, headers: HeaderMap, body: String| async move {
+                        receiver.received.lock().unwrap().push((headers, body));
+                        StatusCode::from_u16(receiver.status.load(Ordering::SeqCst)).unwrap()
+                    },
+                ),
+            )
+            .with_state(receiver.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (receiver, url)
+    }
+
+    fn received(&self) -> Vec<(HeaderMap, String)> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+async fn send_json(
+    app: &Router,
+    token: &str,
+    method: Method,
+    uri: &str,
+    payload: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    let (response, body) = send(app, token, method, uri, &[], payload).await;
+    let json = if body.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_slice(&body).unwrap()
+    };
+    (response.status(), json)
+}
+
+/// テストの受信側はループバックで待ち受けるので、内部のアドレスへの送信を許す
+fn local_state(db: &Arc<DatabaseConnection>) -> AppState {
+    AppState::from(
+        service_context(db, SECRET)
+            .webhook_targets(TargetPolicy::AllowPrivate)
+            .build(),
+    )
+}
+
+fn local_worker(db: &Arc<DatabaseConnection>) -> WebhookWorker {
+    WebhookWorker::new(Arc::clone(db)).with_config(WebhookConfig {
+        targets: TargetPolicy::AllowPrivate,
+        ..Default::default()
+    })
+}
+
+fn app(state: &AppState) -> Router {
+    routes::create_routes(state.clone(), AppMode::Web).with_state(state.clone())
+}
+
+async fn create_task(app: &Router, token: &str) {
+    let (status, _) = send_json(
+        app,
+        token,
+        Method::POST,
+        "/tasks",
+        Some(serde_json::json!({
+            "title": "ship it",
+            "description": "",
+            "parent_task_id": null,
+            "tag_ids": null,
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn delivers_signed_events_to_subscribed_webhooks() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let app = app(&local_state(&db));
+    let (receiver, url) = Receiver::start(StatusCode::NO_CONTENT).await;
+
+    let (status, created) = send_json(
+        &app,
+        &token,
+        Method::POST,
+        "/webhooks",
+        Some(serde_json::json!({ "url": url, "event_types": ["task_created"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let secret = created["secret"].as_str().unwrap().to_string();
+    let webhook_id = created["webhook"]["id"].as_i64().unwrap();
+
+    create_task(&app, &token).await;
+    let worker = local_worker(&db);
+    assert_eq!(worker.process_due().await.unwrap(), 1);
+
+    let received = receiver.received();
+    assert_eq!(received.len(), 1);
+    let (headers, body) = &received[0];
+    assert_eq!(headers[webhooks::EVENT_HEADER], "task_created");
+    let timestamp: i64 = headers[webhooks::TIMESTAMP_HEADER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(
+        headers[webhooks::SIGNATURE_HEADER],
+        webhooks::sign(&secret, timestamp, body).as_str()
+    );
+    let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(payload["type"], "task_created");
+
+    let (status, deliveries) = send_json(
+        &app,
+        &token,
+        Method::GET,
+        &format!("/webhooks/{webhook_id}/deliveries"),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(deliveries[0]["status"], "Succeeded");
+    assert_eq!(deliveries[0]["response_status"], 204);
+    assert_eq!(deliveries[0]["payload"]["type"], "task_created");
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_kept_for_retry() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let app = app(&local_state(&db));
+    let (receiver, url) = Receiver::start(StatusCode::INTERNAL_SERVER_ERROR).await;
+
+    let (_, created) = send_json(
+        &app,
+        &token,
+        Method::POST,
+        "/webhooks",
+        Some(serde_json::json!({ "url": url, "event_types": ["task_created"] })),
+    )
+    .await;
+    let webhook_id = created["webhook"]["id"].as_i64().unwrap();
+
+    create_task(&app, &token).await;
+    let worker = local_worker(&db);
+    assert_eq!(worker.process_due().await.unwrap(), 1);
+    assert_eq!(receiver.received().len(), 1);
+    // バックオフ中は再送しない
+    assert_eq!(worker.process_due().await.unwrap(), 0);
+
+    let (_, deliveries) = send_json(
+        &app,
+        &token,
+        Method::GET,
+        &format!("/webhooks/{webhook_id}/deliveries"),
+        None,
+    )
+    .await;
+    assert_eq!(deliveries[0]["status"], "Pending");
+    assert_eq!(deliveries[0]["attempts"], 1);
+    assert_eq!(deliveries[0]["response_status"], 500);
+    assert!(
+        deliveries[0]["last_error"]
+            .as_str()
+            .unwrap()
+            .contains("500")
+    );
+}
+
+#[tokio::test]
+async fn rejects_unknown_event_types() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let app = app(&build_app_state(&db, SECRET));
+
+    let (status, body) = send_json(
+        &app,
+        &token,
+        Method::POST,
+        "/webhooks",
+        Some(serde_json::json!({
+            "url": "https://chat.example.com/hooks/1",
+            "event_types": ["task_exploded"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["errors"][0]["field"], "event_types");
+    assert_eq!(body["errors"][0]["code"], "unknown_event");
+}
+
+#[tokio::test]
+async fn private_targets_are_refused_unless_allowed() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let (receiver, url) = Receiver::start(StatusCode::NO_CONTENT).await;
+    let payload = serde_json::json!({ "url": url, "event_types": ["task_created"] });
+
+    let (status, body) = send_json(
+        &app(&build_app_state(&db, SECRET)),
+        &token,
+        Method::POST,
+        "/webhooks",
+        Some(payload.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["errors"][0]["field"], "url");
+    assert_eq!(body["errors"][0]["code"], "private_target");
+
+    // 許可した設定で登録されていても、許可していないワーカーは送らない
+    let app = app(&local_state(&db));
+    let (status, _) = send_json(&app, &token, Method::POST, "/webhooks", Some(payload)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    create_task(&app, &token).await;
+    let worker = WebhookWorker::new(Arc::clone(&db));
+    assert_eq!(worker.process_due().await.unwrap(), 1);
+    assert!(receiver.received().is_empty());
+    let delivery = WebhookDeliveries::find()
+        .one(db.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(delivery.last_error.unwrap().contains("private"));
+}
+
+#[tokio::test]
+async fn task_completion_and_daily_goal_carry_their_details() {
+    let db = setup_in_memory_db(false).await;
+    let user = create_user(&db, "alice").await;
+    let token = usecases::auth::create_jwt(user.id, SECRET).unwrap();
+    let app = app(&local_state(&db));
+    let (status, _) = send_json(
+        &app,
+        &token,
+        Method::POST,
+        "/webhooks",
+        Some(serde_json::json!({
+            "url": "https://chat.example.com/hooks/1",
+            "event_types": ["task_completed", "daily_goal_reached"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, preferences) = send_json(
+        &app,
+        &token,
+        Method::PUT,
+        "/preferences",
+        Some(serde_json::json!({
+            "work_time": 25,
+            "break_time": 5,
+            "locale": "en",
+            "daily_goal": 2,
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(preferences["daily_goal"], 2);
+
+    let (_, task) = send_json(
+        &app,
+        &token,
+        Method::POST,
+        "/tasks",
+        Some(serde_json::json!({ "title": "ship it", "description": "" })),
+    )
+    .await;
+    let (status, _) = send_json(
+        &app,
+        &token,
+        Method::PUT,
+        &format!("/tasks/{}", task["id"]),
+        Some(serde_json::json!({ "completed": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // 目標の 2 回目で一度だけ知らせ、3 回目では知らせない
+    let now = chrono::Utc::now();
+    for _ in 0..3 {
+        let (status, _) = send_json(
+            &app,
+            &token,
+            Method::POST,
+            "/decopon_sessions",
+            Some(serde_json::json!({
+                "status": "Completed",
+                "started_at": now - chrono::Duration::minutes(25),
+                "ended_at": now,
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let deliveries = WebhookDeliveries::find().all(db.as_ref()).await.unwrap();
+    let types: Vec<_> = deliveries.iter().map(|d| d.event_type.as_str()).collect();
+    assert_eq!(types, ["task_completed", "daily_goal_reached"]);
+    let completed: serde_json::Value = serde_json::from_str(&deliveries[0].payload).unwrap();
+    assert_eq!(completed["task_id"], task["id"]);
+    assert_eq!(completed["uuid"], task["uuid"]);
+    assert_eq!(completed["title"], "ship it");
+    let goal: serde_json::Value = serde_json::from_str(&deliveries[1].payload).unwrap();
+    assert_eq!(goal["date"], now.date_naive().to_string());
+    assert_eq!(goal["completed_sessions"], 2);
+    assert_eq!(goal["goal"], 2);
+}
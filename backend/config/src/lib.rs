@@ -1,4 +1,4 @@
-use std::env;
+use std::{env, path::PathBuf};
 
 use thiserror::Error;
 use url::Url;
@@ -20,9 +20,46 @@ pub struct SingleUserConfig {
     pub enabled: bool,
 }
 
+/// 送信メールの出力先
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct SmtpConfig {
-    pub enabled: bool,
+pub enum MailTransportConfig {
+    Disabled,
+    Smtp,
+    /// 1 通ごとに `.eml` ファイルとしてディレクトリへ書き出す
+    File {
+        dir: PathBuf,
+    },
+    /// 送信内容をログに出力する
+    Log,
+}
+
+impl MailTransportConfig {
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, MailTransportConfig::Disabled)
+    }
+}
+
+/// OpenID Connect (authorization code + PKCE) でログインする際の接続情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcConfig {
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub scopes: Vec<String>,
+}
+
+/// 新規パスワードハッシュの生成方式。既存ハッシュはログイン時にこの設定へ移行される。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordHashingConfig {
+    Bcrypt {
+        cost: u32,
+    },
+    Argon2id {
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -31,7 +68,12 @@ pub struct EnvConfig {
     pub database_url: String,
     pub jwt_secret: String,
     pub single_user: SingleUserConfig,
-    pub smtp: SmtpConfig,
+    pub mail: MailTransportConfig,
+    pub oidc: Option<OidcConfig>,
+    pub password_hashing: PasswordHashingConfig,
+    pub admin_emails: Vec<String>,
+    /// Webhook をループバックやプライベートアドレスへも送る
+    pub allow_private_webhook_targets: bool,
 }
 
 #[derive(Debug, Error)]
@@ -40,6 +82,8 @@ pub enum ConfigError {
     MissingVar(String),
     #[error("invalid database path: {0}")]
     InvalidDatabasePath(String),
+    #[error("invalid value for environment variable '{0}'")]
+    InvalidVar(String),
 }
 
 impl EnvConfig {
@@ -48,7 +92,11 @@ impl EnvConfig {
         let database_url = required_var("AXUM_DATABASE_URL")?;
         let jwt_secret = required_var("AXUM_JWT_SECRET")?;
         let single_user_enabled = resolve_single_user_flag(app_mode);
-        let smtp_enabled = matches!(app_mode, AppMode::Web) && !flag_enabled("AXUM_DISABLE_SMTP");
+        let mail = resolve_mail_transport(app_mode)?;
+        let oidc = resolve_oidc_config(app_mode)?;
+        let password_hashing = resolve_password_hashing()?;
+        let admin_emails = resolve_admin_emails(app_mode);
+        let allow_private_webhook_targets = flag_enabled("AXUM_WEBHOOKS_ALLOW_PRIVATE_TARGETS");
 
         Ok(Self {
             app_mode,
@@ -57,9 +105,11 @@ impl EnvConfig {
             single_user: SingleUserConfig {
                 enabled: single_user_enabled,
             },
-            smtp: SmtpConfig {
-                enabled: smtp_enabled,
-            },
+            mail,
+            oidc,
+            password_hashing,
+            admin_emails,
+            allow_private_webhook_targets,
         })
     }
 }
@@ -90,7 +140,7 @@ fn looks_like_windows_path(path: &str) -> bool {
         return false;
     }
 
-    matches!(bytes[0], b'a'..=b'z' | b'A'..=b'Z')
+    bytes[0].is_ascii_alphabetic()
         && bytes[1] == b':'
         && (bytes[2] == b'\\' || bytes[2] == b'/')
 }
@@ -99,6 +149,114 @@ fn required_var(key: &str) -> Result<String, ConfigError> {
     env::var(key).map_err(|_| ConfigError::MissingVar(key.to_string()))
 }
 
+fn optional_var(key: &str) -> Option<String> {
+    env::var(key)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// `AXUM_OIDC_ISSUER_URL` が設定されている Web モードでのみ OIDC ログインを有効化する。
+fn resolve_oidc_config(app_mode: AppMode) -> Result<Option<OidcConfig>, ConfigError> {
+    if app_mode.is_local() {
+        return Ok(None);
+    }
+    let Some(issuer_url) = optional_var("AXUM_OIDC_ISSUER_URL") else {
+        return Ok(None);
+    };
+
+    let client_id = optional_var("AXUM_OIDC_CLIENT_ID")
+        .ok_or_else(|| ConfigError::MissingVar("AXUM_OIDC_CLIENT_ID".to_string()))?;
+    let redirect_url = optional_var("AXUM_OIDC_REDIRECT_URL")
+        .ok_or_else(|| ConfigError::MissingVar("AXUM_OIDC_REDIRECT_URL".to_string()))?;
+    let scopes = optional_var("AXUM_OIDC_SCOPES")
+        .unwrap_or_else(|| "openid email profile".to_string())
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|scope| !scope.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    Ok(Some(OidcConfig {
+        issuer_url: issuer_url.trim_end_matches('/').to_string(),
+        client_id,
+        client_secret: optional_var("AXUM_OIDC_CLIENT_SECRET"),
+        redirect_url,
+        scopes,
+    }))
+}
+
+/// `AXUM_MAIL_TRANSPORT` (smtp | file | log | none) で送信先を選ぶ。
+/// 未指定の場合は従来どおり Web モードかつ `AXUM_DISABLE_SMTP` が無効なときのみ SMTP を使う。
+fn resolve_mail_transport(app_mode: AppMode) -> Result<MailTransportConfig, ConfigError> {
+    let Some(transport) = optional_var("AXUM_MAIL_TRANSPORT") else {
+        let smtp_enabled = matches!(app_mode, AppMode::Web) && !flag_enabled("AXUM_DISABLE_SMTP");
+        return Ok(if smtp_enabled {
+            MailTransportConfig::Smtp
+        } else {
+            MailTransportConfig::Disabled
+        });
+    };
+
+    match transport.to_ascii_lowercase().as_str() {
+        "smtp" => Ok(MailTransportConfig::Smtp),
+        "file" => Ok(MailTransportConfig::File {
+            dir: optional_var("AXUM_MAIL_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("storage/mails")),
+        }),
+        "log" | "stdout" => Ok(MailTransportConfig::Log),
+        "none" | "disabled" => Ok(MailTransportConfig::Disabled),
+        _ => Err(ConfigError::InvalidVar("AXUM_MAIL_TRANSPORT".to_string())),
+    }
+}
+
+/// 起動時に管理者へ昇格させるメールアドレス (カンマ区切り)。ローカルモードでは使用しない。
+fn resolve_admin_emails(app_mode: AppMode) -> Vec<String> {
+    if app_mode.is_local() {
+        return Vec::new();
+    }
+    optional_var("AXUM_ADMIN_EMAILS")
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|email| !email.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_u32_var(key: &str, default: u32) -> Result<u32, ConfigError> {
+    match optional_var(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| ConfigError::InvalidVar(key.to_string())),
+        None => Ok(default),
+    }
+}
+
+/// 既定は Argon2id (OWASP 推奨値 m=19MiB, t=2, p=1)。
+fn resolve_password_hashing() -> Result<PasswordHashingConfig, ConfigError> {
+    let algorithm = optional_var("AXUM_PASSWORD_HASH_ALGORITHM")
+        .unwrap_or_else(|| "argon2id".to_string())
+        .to_ascii_lowercase();
+
+    match algorithm.as_str() {
+        "argon2id" | "argon2" => Ok(PasswordHashingConfig::Argon2id {
+            memory_kib: parse_u32_var("AXUM_ARGON2_MEMORY_KIB", 19 * 1024)?,
+            iterations: parse_u32_var("AXUM_ARGON2_ITERATIONS", 2)?,
+            parallelism: parse_u32_var("AXUM_ARGON2_PARALLELISM", 1)?,
+        }),
+        "bcrypt" => Ok(PasswordHashingConfig::Bcrypt {
+            cost: parse_u32_var("AXUM_BCRYPT_COST", 12)?,
+        }),
+        _ => Err(ConfigError::InvalidVar(
+            "AXUM_PASSWORD_HASH_ALGORITHM".to_string(),
+        )),
+    }
+}
+
 fn resolve_app_mode(default_local_app_mode: bool) -> AppMode {
     let fallback = if default_local_app_mode {
         "local".to_string()
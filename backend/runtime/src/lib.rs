@@ -1,10 +1,11 @@
 use std::{env, sync::Arc};
 
-use axum_password_worker::{Bcrypt, PasswordWorker};
-use decopon_config::EnvConfig;
+use decopon_config::{EnvConfig, MailTransportConfig, OidcConfig, PasswordHashingConfig};
 pub use decopon_services::{
     entities, usecases, ServiceContext, ServiceContextBuilder, ServiceError,
 };
+use decopon_services::mail_transport::MailTransportSettings;
+use decopon_services::password::{PasswordHashConfig, PasswordHasher, PasswordWorker};
 use migration::{Migrator, MigratorTrait};
 use sea_orm::Database;
 use thiserror::Error;
@@ -30,9 +31,13 @@ pub struct ServiceRuntimeBuilder {
     database_url: String,
     jwt_secret: String,
     ensure_single_user_session: bool,
-    enable_mailer: bool,
+    mail_transport: MailTransportConfig,
     password_worker_threads: usize,
     run_migrations: bool,
+    oidc: Option<OidcConfig>,
+    password_hashing: PasswordHashConfig,
+    admin_emails: Vec<String>,
+    allow_private_webhook_targets: bool,
 }
 
 impl ServiceRuntimeBuilder {
@@ -41,9 +46,13 @@ impl ServiceRuntimeBuilder {
             database_url: database_url.into(),
             jwt_secret: jwt_secret.into(),
             ensure_single_user_session: false,
-            enable_mailer: false,
+            mail_transport: MailTransportConfig::Disabled,
             password_worker_threads: 4,
             run_migrations: false,
+            oidc: None,
+            password_hashing: PasswordHashConfig::default(),
+            admin_emails: Vec::new(),
+            allow_private_webhook_targets: false,
         }
     }
 
@@ -52,8 +61,8 @@ impl ServiceRuntimeBuilder {
         self
     }
 
-    pub fn enable_mailer(mut self, enabled: bool) -> Self {
-        self.enable_mailer = enabled;
+    pub fn mail_transport(mut self, config: MailTransportConfig) -> Self {
+        self.mail_transport = config;
         self
     }
 
@@ -67,14 +76,38 @@ impl ServiceRuntimeBuilder {
         self
     }
 
+    pub fn oidc(mut self, config: Option<OidcConfig>) -> Self {
+        self.oidc = config;
+        self
+    }
+
+    pub fn password_hashing(mut self, config: PasswordHashingConfig) -> Self {
+        self.password_hashing = password_hash_config(config);
+        self
+    }
+
+    pub fn admin_emails(mut self, emails: Vec<String>) -> Self {
+        self.admin_emails = emails;
+        self
+    }
+
+    pub fn allow_private_webhook_targets(mut self, allow: bool) -> Self {
+        self.allow_private_webhook_targets = allow;
+        self
+    }
+
     pub fn from_config(config: RuntimeConfig) -> Self {
         Self {
             database_url: config.database_url,
             jwt_secret: config.jwt_secret,
             ensure_single_user_session: config.ensure_single_user_session,
-            enable_mailer: config.enable_mailer,
+            mail_transport: config.mail_transport,
             password_worker_threads: config.password_worker_threads,
             run_migrations: config.run_migrations,
+            oidc: config.oidc,
+            password_hashing: password_hash_config(config.password_hashing),
+            admin_emails: config.admin_emails,
+            allow_private_webhook_targets: config.allow_private_webhook_targets,
         }
     }
 
@@ -87,20 +120,16 @@ impl ServiceRuntimeBuilder {
         } else {
             info!("Skipping database migrations (run_migrations = false)");
         }
-        let password_worker = Arc::new(PasswordWorker::new_bcrypt(self.password_worker_threads)?);
-
-        let mailer = if self.enable_mailer {
-            match usecases::mails::setup_mailer()? {
-                Some(mailer) => Some(mailer),
-                None => {
-                    info!("Mailer feature enabled but SMTP transport is not fully configured");
-                    None
-                }
-            }
-        } else {
+        usecases::admin::promote_admins(db.as_ref(), &self.admin_emails).await?;
+        let password_worker = Arc::new(PasswordWorker::new(
+            self.password_worker_threads,
+            self.password_hashing,
+        )?);
+
+        let mailer = usecases::mails::setup_mailer(&mail_transport_settings(self.mail_transport))?;
+        if mailer.is_none() {
             info!("Mailer disabled in runtime configuration");
-            None
-        };
+        }
 
         let single_user_session = if self.ensure_single_user_session {
             Some(
@@ -115,10 +144,50 @@ impl ServiceRuntimeBuilder {
             None
         };
 
-        let context = ServiceContext::builder(db, password_worker, self.jwt_secret)
+        #[cfg(not(feature = "oidc"))]
+        if self.oidc.is_some() {
+            info!("OIDC is configured but the oidc feature is disabled; ignoring");
+        }
+
+        // リクエスト処理では送信キューへの登録のみ行い、送信はワーカーに任せる
+        if let Some(mailer) = mailer.as_ref() {
+            usecases::mail_outbox::MailOutboxWorker::new(Arc::clone(&db), Arc::clone(mailer))
+                .spawn();
+            // 希望したユーザーへの前日の日報も、同じ送信キューから送る
+            usecases::journal::DailyJournalWorker::new(Arc::clone(&db)).spawn();
+        }
+
+        let webhook_targets = if self.allow_private_webhook_targets {
+            usecases::webhooks::TargetPolicy::AllowPrivate
+        } else {
+            usecases::webhooks::TargetPolicy::PublicOnly
+        };
+        let builder = ServiceContext::builder(db, password_worker, self.jwt_secret)
             .mailer(mailer)
             .single_user_session(single_user_session)
-            .build();
+            .webhook_targets(webhook_targets);
+        #[cfg(feature = "oidc")]
+        let builder = builder.oidc(self.oidc.map(|config| {
+            usecases::oidc::OidcClient::new(usecases::oidc::OidcSettings {
+                issuer_url: config.issuer_url,
+                client_id: config.client_id,
+                client_secret: config.client_secret,
+                redirect_url: config.redirect_url,
+                scopes: config.scopes,
+            })
+        }));
+        let context = builder.build();
+
+        // 書き込み時に配信キューへ積んだ Webhook を送信する
+        #[cfg(feature = "webhooks")]
+        {
+            usecases::webhooks::WebhookWorker::new(context.db_arc())
+                .with_config(usecases::webhooks::WebhookConfig {
+                    targets: webhook_targets,
+                    ..Default::default()
+                })
+                .spawn();
+        }
 
         Ok(ServiceRuntime {
             context: Arc::new(context),
@@ -148,23 +217,32 @@ pub struct RuntimeConfig {
     pub database_url: String,
     pub jwt_secret: String,
     pub ensure_single_user_session: bool,
-    pub enable_mailer: bool,
+    pub mail_transport: MailTransportConfig,
     pub run_migrations: bool,
     pub password_worker_threads: usize,
+    pub oidc: Option<OidcConfig>,
+    pub password_hashing: PasswordHashingConfig,
+    pub admin_emails: Vec<String>,
+    pub allow_private_webhook_targets: bool,
 }
 
 impl RuntimeConfig {
     pub fn from_env(options: RuntimeBootstrapOptions) -> Result<Self, RuntimeError> {
         let env_config = EnvConfig::from_env(options.default_local_app_mode)?;
-        let run_migrations = options.run_migrations && !env_flag_enabled("DECO_SKIP_SERVICE_BOOTSTRAP");
+        let run_migrations =
+            options.run_migrations && !env_flag_enabled("DECO_SKIP_SERVICE_BOOTSTRAP");
 
         Ok(Self {
             database_url: env_config.database_url,
             jwt_secret: env_config.jwt_secret,
             ensure_single_user_session: env_config.single_user.enabled,
-            enable_mailer: env_config.smtp.enabled,
+            mail_transport: env_config.mail,
             run_migrations,
             password_worker_threads: options.password_worker_threads.max(1),
+            oidc: env_config.oidc,
+            password_hashing: env_config.password_hashing,
+            admin_emails: env_config.admin_emails,
+            allow_private_webhook_targets: env_config.allow_private_webhook_targets,
         })
     }
 }
@@ -176,6 +254,30 @@ pub async fn bootstrap_runtime_from_env(
     ServiceRuntimeBuilder::from_config(config).build().await
 }
 
+fn password_hash_config(config: PasswordHashingConfig) -> PasswordHashConfig {
+    match config {
+        PasswordHashingConfig::Bcrypt { cost } => PasswordHashConfig::Bcrypt { cost },
+        PasswordHashingConfig::Argon2id {
+            memory_kib,
+            iterations,
+            parallelism,
+        } => PasswordHashConfig::Argon2id {
+            memory_kib,
+            iterations,
+            parallelism,
+        },
+    }
+}
+
+fn mail_transport_settings(config: MailTransportConfig) -> MailTransportSettings {
+    match config {
+        MailTransportConfig::Disabled => MailTransportSettings::Disabled,
+        MailTransportConfig::Smtp => MailTransportSettings::Smtp,
+        MailTransportConfig::File { dir } => MailTransportSettings::File { dir },
+        MailTransportConfig::Log => MailTransportSettings::Log,
+    }
+}
+
 fn env_flag_enabled(key: &str) -> bool {
     env::var(key)
         .map(|value| {
@@ -195,7 +297,7 @@ pub enum RuntimeError {
     #[error(transparent)]
     Database(#[from] sea_orm::DbErr),
     #[error(transparent)]
-    PasswordWorker(#[from] axum_password_worker::PasswordWorkerError<Bcrypt>),
+    PasswordWorker(#[from] axum_password_worker::PasswordWorkerError<PasswordHasher>),
     #[error(transparent)]
     Service(#[from] ServiceError),
     #[error("migration failed: {0}")]
@@ -1,23 +1,31 @@
 use std::sync::Arc;
 
-use axum_password_worker::{Bcrypt, PasswordWorker};
 use sea_orm::DatabaseConnection;
 
-use crate::usecases::{mails::Mailer, single_user::SingleUserSession};
+use crate::events::EventBus;
+use crate::password::PasswordWorker;
+
+#[cfg(feature = "oidc")]
+use crate::usecases::oidc::OidcClient;
+use crate::usecases::{mails::Mailer, single_user::SingleUserSession, webhooks::TargetPolicy};
 
 #[derive(Clone)]
 pub struct ServiceContext {
     db: Arc<DatabaseConnection>,
-    password_worker: Arc<PasswordWorker<Bcrypt>>,
+    password_worker: Arc<PasswordWorker>,
     mailer: Option<Mailer>,
     jwt_secret: String,
     single_user_session: Option<SingleUserSession>,
+    #[cfg(feature = "oidc")]
+    oidc: Option<Arc<OidcClient>>,
+    events: Arc<EventBus>,
+    webhook_targets: TargetPolicy,
 }
 
 impl ServiceContext {
     pub fn builder(
         db: Arc<DatabaseConnection>,
-        password_worker: Arc<PasswordWorker<Bcrypt>>,
+        password_worker: Arc<PasswordWorker>,
         jwt_secret: String,
     ) -> ServiceContextBuilder {
         ServiceContextBuilder {
@@ -26,6 +34,9 @@ impl ServiceContext {
             jwt_secret,
             mailer: None,
             single_user_session: None,
+            #[cfg(feature = "oidc")]
+            oidc: None,
+            webhook_targets: TargetPolicy::default(),
         }
     }
 
@@ -37,11 +48,11 @@ impl ServiceContext {
         Arc::clone(&self.db)
     }
 
-    pub fn password_worker(&self) -> &PasswordWorker<Bcrypt> {
+    pub fn password_worker(&self) -> &PasswordWorker {
         self.password_worker.as_ref()
     }
 
-    pub fn password_worker_arc(&self) -> Arc<PasswordWorker<Bcrypt>> {
+    pub fn password_worker_arc(&self) -> Arc<PasswordWorker> {
         Arc::clone(&self.password_worker)
     }
 
@@ -64,14 +75,34 @@ impl ServiceContext {
     pub fn single_user_session_owned(&self) -> Option<SingleUserSession> {
         self.single_user_session.clone()
     }
+
+    #[cfg(feature = "oidc")]
+    pub fn oidc(&self) -> Option<&OidcClient> {
+        self.oidc.as_deref()
+    }
+
+    pub fn events(&self) -> &EventBus {
+        self.events.as_ref()
+    }
+
+    pub fn events_arc(&self) -> Arc<EventBus> {
+        Arc::clone(&self.events)
+    }
+
+    pub fn webhook_targets(&self) -> TargetPolicy {
+        self.webhook_targets
+    }
 }
 
 pub struct ServiceContextBuilder {
     db: Arc<DatabaseConnection>,
-    password_worker: Arc<PasswordWorker<Bcrypt>>,
+    password_worker: Arc<PasswordWorker>,
     jwt_secret: String,
     mailer: Option<Mailer>,
     single_user_session: Option<SingleUserSession>,
+    #[cfg(feature = "oidc")]
+    oidc: Option<Arc<OidcClient>>,
+    webhook_targets: TargetPolicy,
 }
 
 impl ServiceContextBuilder {
@@ -85,6 +116,17 @@ impl ServiceContextBuilder {
         self
     }
 
+    #[cfg(feature = "oidc")]
+    pub fn oidc(mut self, client: Option<OidcClient>) -> Self {
+        self.oidc = client.map(Arc::new);
+        self
+    }
+
+    pub fn webhook_targets(mut self, policy: TargetPolicy) -> Self {
+        self.webhook_targets = policy;
+        self
+    }
+
     pub fn build(self) -> ServiceContext {
         ServiceContext {
             db: self.db,
@@ -92,6 +134,10 @@ impl ServiceContextBuilder {
             mailer: self.mailer,
             jwt_secret: self.jwt_secret,
             single_user_session: self.single_user_session,
+            #[cfg(feature = "oidc")]
+            oidc: self.oidc,
+            events: Arc::new(EventBus::default()),
+            webhook_targets: self.webhook_targets,
         }
     }
 }
@@ -1,6 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.14
 
-use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveValue, entity::prelude::*};
+use uuid::Uuid;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "logs")]
@@ -14,6 +15,11 @@ pub struct Model {
     pub updated_at: DateTimeUtc,
     pub user_id: i32,
     pub task_id: Option<i32>,
+    pub event_code: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub event_params: Option<String>,
+    pub uuid: String,
+    pub version: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -66,4 +72,12 @@ impl Related<super::tags::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+impl ActiveModelBehavior for ActiveModel {
+    /// 端末をまたいでデータをまとめても衝突しないよう、作成時に UUID を振る
+    fn new() -> Self {
+        Self {
+            uuid: ActiveValue::Set(Uuid::new_v4().to_string()),
+            ..ActiveModelTrait::default()
+        }
+    }
+}
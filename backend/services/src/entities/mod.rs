@@ -2,10 +2,19 @@
 
 pub mod prelude;
 
+pub mod admin_audit_logs;
 pub mod decopon_sessions;
+pub mod idempotency_keys;
 pub mod log_tag;
 pub mod logs;
+pub mod mail_outbox;
+pub mod oidc_auth_requests;
+pub mod personal_access_tokens;
+pub mod sync_changes;
 pub mod tag_task;
 pub mod tags;
 pub mod tasks;
+pub mod user_identities;
 pub mod users;
+pub mod webhook_deliveries;
+pub mod webhooks;
@@ -1,9 +1,18 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.14
 
+pub use super::admin_audit_logs::Entity as AdminAuditLogs;
 pub use super::decopon_sessions::Entity as DecoponSessions;
+pub use super::idempotency_keys::Entity as IdempotencyKeys;
 pub use super::log_tag::Entity as LogTag;
 pub use super::logs::Entity as Logs;
+pub use super::mail_outbox::Entity as MailOutbox;
+pub use super::oidc_auth_requests::Entity as OidcAuthRequests;
+pub use super::personal_access_tokens::Entity as PersonalAccessTokens;
+pub use super::sync_changes::Entity as SyncChanges;
 pub use super::tag_task::Entity as TagTask;
 pub use super::tags::Entity as Tags;
 pub use super::tasks::Entity as Tasks;
+pub use super::user_identities::Entity as UserIdentities;
 pub use super::users::Entity as Users;
+pub use super::webhook_deliveries::Entity as WebhookDeliveries;
+pub use super::webhooks::Entity as Webhooks;
@@ -1,6 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.14
 
-use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveValue, entity::prelude::*};
+use uuid::Uuid;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "tasks")]
@@ -17,6 +18,9 @@ pub struct Model {
     pub depth: i32,
     pub position: i32,
     pub parent_task_id: Option<i32>,
+    pub version: i32,
+    pub uuid: String,
+    pub due_at: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -71,4 +75,12 @@ impl Related<super::tags::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+impl ActiveModelBehavior for ActiveModel {
+    /// 端末をまたいでデータをまとめても衝突しないよう、作成時に UUID を振る
+    fn new() -> Self {
+        Self {
+            uuid: ActiveValue::Set(Uuid::new_v4().to_string()),
+            ..ActiveModelTrait::default()
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.14
 
-use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveValue, entity::prelude::*};
+use uuid::Uuid;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "users")]
@@ -18,18 +19,48 @@ pub struct Model {
     pub locale: String,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    pub pending_email: Option<String>,
+    pub email_change_token: Option<String>,
+    pub email_change_requested_at: Option<DateTimeUtc>,
+    #[sea_orm(column_type = "custom(\"enum_text\")")]
+    pub role: String,
+    pub disabled_at: Option<DateTimeUtc>,
+    pub uuid: String,
+    #[sea_orm(unique)]
+    pub calendar_token_hash: Option<String>,
+    pub daily_journal_email: bool,
+    pub daily_journal_sent_on: Option<Date>,
+    pub daily_goal: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::admin_audit_logs::Entity")]
+    AdminAuditLogs,
     #[sea_orm(has_many = "super::decopon_sessions::Entity")]
     DecoponSessions,
+    #[sea_orm(has_many = "super::idempotency_keys::Entity")]
+    IdempotencyKeys,
     #[sea_orm(has_many = "super::logs::Entity")]
     Logs,
+    #[sea_orm(has_many = "super::personal_access_tokens::Entity")]
+    PersonalAccessTokens,
+    #[sea_orm(has_many = "super::sync_changes::Entity")]
+    SyncChanges,
     #[sea_orm(has_many = "super::tags::Entity")]
     Tags,
     #[sea_orm(has_many = "super::tasks::Entity")]
     Tasks,
+    #[sea_orm(has_many = "super::user_identities::Entity")]
+    UserIdentities,
+    #[sea_orm(has_many = "super::webhooks::Entity")]
+    Webhooks,
+}
+
+impl Related<super::admin_audit_logs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AdminAuditLogs.def()
+    }
 }
 
 impl Related<super::decopon_sessions::Entity> for Entity {
@@ -38,12 +69,30 @@ impl Related<super::decopon_sessions::Entity> for Entity {
     }
 }
 
+impl Related<super::idempotency_keys::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::IdempotencyKeys.def()
+    }
+}
+
 impl Related<super::logs::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Logs.def()
     }
 }
 
+impl Related<super::personal_access_tokens::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PersonalAccessTokens.def()
+    }
+}
+
+impl Related<super::sync_changes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SyncChanges.def()
+    }
+}
+
 impl Related<super::tags::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Tags.def()
@@ -56,4 +105,24 @@ impl Related<super::tasks::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+impl Related<super::user_identities::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserIdentities.def()
+    }
+}
+
+impl Related<super::webhooks::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Webhooks.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {
+    /// API で連番の ID の代わりに使えるよう、作成時に UUID を振る
+    fn new() -> Self {
+        Self {
+            uuid: ActiveValue::Set(Uuid::new_v4().to_string()),
+            ..ActiveModelTrait::default()
+        }
+    }
+}
@@ -1,7 +1,12 @@
-use axum_password_worker::{Bcrypt, PasswordWorkerError};
+use std::borrow::Cow;
+
+use axum_password_worker::PasswordWorkerError;
 use jsonwebtoken::errors::Error as JwtError;
+use serde::Serialize;
 use thiserror::Error;
 
+use crate::password::PasswordHasher;
+
 #[derive(Debug, Error)]
 pub enum ServiceError {
     #[error("not found: {0}")]
@@ -10,17 +15,27 @@ pub enum ServiceError {
     #[error("conflict: {0}")]
     Conflict(&'static str),
 
+    /// 呼び出し側が前提とした版と保存されている版が食い違う
+    #[error("precondition failed: {0}")]
+    PreconditionFailed(&'static str),
+
     #[error("bad request: {0}")]
     BadRequest(String),
 
+    #[error("validation failed")]
+    Validation(Vec<FieldError>),
+
     #[error("unauthorized")]
     Unauthorized,
 
+    #[error("forbidden")]
+    Forbidden,
+
     #[error("database error")]
     Db(#[from] sea_orm::DbErr),
 
     #[error("password error")]
-    Password(#[from] PasswordWorkerError<Bcrypt>),
+    Password(#[from] PasswordWorkerError<PasswordHasher>),
 
     #[cfg(feature = "mail")]
     #[error("mail error")]
@@ -30,6 +45,40 @@ pub enum ServiceError {
     Internal(#[source] Box<dyn std::error::Error + Send + Sync>),
 }
 
+/// 入力値の項目ごとのエラー。`code` はクライアントが分岐に使う安定した識別子。
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    pub field: String,
+    pub code: Cow<'static, str>,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(
+        field: impl Into<String>,
+        code: impl Into<Cow<'static, str>>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl ServiceError {
+    /// 単一項目のバリデーションエラーを作る
+    pub fn invalid_field(
+        field: impl Into<String>,
+        code: impl Into<Cow<'static, str>>,
+        message: impl Into<String>,
+    ) -> Self {
+        ServiceError::Validation(vec![FieldError::new(field, code, message)])
+    }
+}
+
 impl From<JwtError> for ServiceError {
     fn from(err: JwtError) -> Self {
         ServiceError::Internal(Box::new(err))
@@ -0,0 +1,298 @@
+//! 他の端末やタブへ変更を知らせるための、ユーザーごとのドメインイベントの配信です。
+//! 直近のイベントは一定数だけ保持し、再接続したクライアントには受け取り済みの ID 以降を再送します。
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// 再接続に備えて保持するイベントの数（全ユーザー合計）
+pub const DEFAULT_HISTORY_CAPACITY: usize = 1024;
+const CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    TaskCreated {
+        task_id: i32,
+    },
+    TaskUpdated {
+        task_id: i32,
+    },
+    TaskDeleted {
+        task_id: i32,
+    },
+    /// 外部のタスク管理などへ送れるよう、完了したタスクの内容も載せる
+    TaskCompleted {
+        task_id: i32,
+        uuid: String,
+        title: String,
+        completed_at: DateTime<Utc>,
+    },
+    SessionStarted {
+        session_id: i32,
+    },
+    SessionUpdated {
+        session_id: i32,
+    },
+    SessionCompleted {
+        session_id: i32,
+    },
+    SessionDeleted {
+        session_id: i32,
+    },
+    LogAdded {
+        log_id: i32,
+    },
+    LogUpdated {
+        log_id: i32,
+    },
+    /// その日に完了したセッション数が、ユーザーの決めた目標に達した
+    DailyGoalReached {
+        date: NaiveDate,
+        completed_sessions: u64,
+        goal: i32,
+    },
+}
+
+impl DomainEvent {
+    /// Webhook の購読などで指定できる種類名の一覧
+    pub const NAMES: [&'static str; 11] = [
+        "task_created",
+        "task_updated",
+        "task_deleted",
+        "task_completed",
+        "session_started",
+        "session_updated",
+        "session_completed",
+        "session_deleted",
+        "log_added",
+        "log_updated",
+        "daily_goal_reached",
+    ];
+
+    /// SSE の `event:` などに使う種類名
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomainEvent::TaskCreated { .. } => "task_created",
+            DomainEvent::TaskUpdated { .. } => "task_updated",
+            DomainEvent::TaskDeleted { .. } => "task_deleted",
+            DomainEvent::TaskCompleted { .. } => "task_completed",
+            DomainEvent::SessionStarted { .. } => "session_started",
+            DomainEvent::SessionUpdated { .. } => "session_updated",
+            DomainEvent::SessionCompleted { .. } => "session_completed",
+            DomainEvent::SessionDeleted { .. } => "session_deleted",
+            DomainEvent::LogAdded { .. } => "log_added",
+            DomainEvent::LogUpdated { .. } => "log_updated",
+            DomainEvent::DailyGoalReached { .. } => "daily_goal_reached",
+        }
+    }
+
+    /// 保存したセッションの状態から、開始・完了・その他の更新を区別する
+    pub fn for_session(session_id: i32, status: &str, created: bool) -> Self {
+        match (status, created) {
+            ("Completed", _) => DomainEvent::SessionCompleted { session_id },
+            (_, true) => DomainEvent::SessionStarted { session_id },
+            (_, false) => DomainEvent::SessionUpdated { session_id },
+        }
+    }
+}
+
+/// 採番済みのイベント。`id` はプロセス内で単調増加する
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PublishedEvent {
+    pub id: u64,
+    #[serde(skip)]
+    pub user_id: i32,
+    #[serde(flatten)]
+    pub event: DomainEvent,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct History {
+    last_id: u64,
+    events: VecDeque<Arc<PublishedEvent>>,
+}
+
+impl History {
+    fn since(&self, user_id: i32, last_event_id: u64) -> VecDeque<Arc<PublishedEvent>> {
+        self.events
+            .iter()
+            .filter(|event| event.user_id == user_id && event.id > last_event_id)
+            .cloned()
+            .collect()
+    }
+}
+
+pub struct EventBus {
+    sender: broadcast::Sender<Arc<PublishedEvent>>,
+    history: Arc<Mutex<History>>,
+    history_capacity: usize,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+impl EventBus {
+    pub fn new(history_capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            history: Arc::new(Mutex::new(History::default())),
+            history_capacity,
+        }
+    }
+
+    pub fn publish(&self, user_id: i32, event: DomainEvent) -> Arc<PublishedEvent> {
+        let mut history = lock(&self.history);
+        history.last_id += 1;
+        let published = Arc::new(PublishedEvent {
+            id: history.last_id,
+            user_id,
+            event,
+            occurred_at: Utc::now(),
+        });
+        history.events.push_back(Arc::clone(&published));
+        while history.events.len() > self.history_capacity {
+            history.events.pop_front();
+        }
+        // 購読者がいなくても履歴には残す。履歴と同じロックの中で送り、順序を揃える
+        let _ = self.sender.send(Arc::clone(&published));
+        published
+    }
+
+    /// `last_event_id` を渡すと、それより後に発行された保持中のイベントから受け取る
+    pub fn subscribe(&self, user_id: i32, last_event_id: Option<u64>) -> EventSubscription {
+        let history = lock(&self.history);
+        let receiver = self.sender.subscribe();
+        let backlog = match last_event_id {
+            Some(last_event_id) => history.since(user_id, last_event_id),
+            None => VecDeque::new(),
+        };
+        EventSubscription {
+            user_id,
+            last_id: last_event_id.unwrap_or(history.last_id),
+            backlog,
+            receiver,
+            history: Arc::clone(&self.history),
+        }
+    }
+}
+
+pub struct EventSubscription {
+    user_id: i32,
+    last_id: u64,
+    backlog: VecDeque<Arc<PublishedEvent>>,
+    receiver: broadcast::Receiver<Arc<PublishedEvent>>,
+    history: Arc<Mutex<History>>,
+}
+
+impl EventSubscription {
+    /// 次のイベントを待つ。バスが破棄されたら `None`
+    pub async fn recv(&mut self) -> Option<Arc<PublishedEvent>> {
+        loop {
+            if let Some(event) = self.backlog.pop_front() {
+                self.last_id = event.id;
+                return Some(event);
+            }
+            match self.receiver.recv().await {
+                Ok(event) if event.user_id == self.user_id && event.id > self.last_id => {
+                    self.last_id = event.id;
+                    return Some(event);
+                }
+                Ok(_) => continue,
+                // 取りこぼした分は履歴から補う
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "event subscriber lagged behind");
+                    self.backlog = lock(&self.history).since(self.user_id, self.last_id);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+fn lock(history: &Mutex<History>) -> MutexGuard<'_, History> {
+    history
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn delivers_only_the_subscribers_events() {
+        let bus = EventBus::default();
+        let mut alice = bus.subscribe(1, None);
+        bus.publish(2, DomainEvent::TaskCreated { task_id: 10 });
+        bus.publish(1, DomainEvent::TaskCreated { task_id: 11 });
+
+        let event = alice.recv().await.unwrap();
+        assert_eq!(event.event, DomainEvent::TaskCreated { task_id: 11 });
+        assert_eq!(event.id, 2);
+    }
+
+    #[tokio::test]
+    async fn replays_events_after_the_last_event_id() {
+        let bus = EventBus::default();
+        let first = bus.publish(1, DomainEvent::TaskCreated { task_id: 1 });
+        bus.publish(1, DomainEvent::TaskUpdated { task_id: 1 });
+        bus.publish(1, DomainEvent::TaskDeleted { task_id: 1 });
+
+        let mut resumed = bus.subscribe(1, Some(first.id));
+        assert_eq!(resumed.recv().await.unwrap().event.name(), "task_updated");
+        assert_eq!(resumed.recv().await.unwrap().event.name(), "task_deleted");
+
+        bus.publish(1, DomainEvent::LogAdded { log_id: 5 });
+        assert_eq!(resumed.recv().await.unwrap().event.name(), "log_added");
+    }
+
+    #[test]
+    fn names_cover_every_event() {
+        let events = [
+            DomainEvent::TaskCreated { task_id: 1 },
+            DomainEvent::TaskUpdated { task_id: 1 },
+            DomainEvent::TaskDeleted { task_id: 1 },
+            DomainEvent::TaskCompleted {
+                task_id: 1,
+                uuid: "task-uuid".to_string(),
+                title: "ship it".to_string(),
+                completed_at: Utc::now(),
+            },
+            DomainEvent::SessionStarted { session_id: 1 },
+            DomainEvent::SessionUpdated { session_id: 1 },
+            DomainEvent::SessionCompleted { session_id: 1 },
+            DomainEvent::SessionDeleted { session_id: 1 },
+            DomainEvent::LogAdded { log_id: 1 },
+            DomainEvent::LogUpdated { log_id: 1 },
+            DomainEvent::DailyGoalReached {
+                date: Utc::now().date_naive(),
+                completed_sessions: 4,
+                goal: 4,
+            },
+        ];
+        let names: Vec<_> = events.iter().map(DomainEvent::name).collect();
+        assert_eq!(names, DomainEvent::NAMES);
+    }
+
+    #[tokio::test]
+    async fn keeps_a_bounded_history() {
+        let bus = EventBus::new(2);
+        for task_id in 1..=3 {
+            bus.publish(1, DomainEvent::TaskCreated { task_id });
+        }
+        let mut resumed = bus.subscribe(1, Some(0));
+        assert_eq!(resumed.recv().await.unwrap().id, 2);
+        assert_eq!(resumed.recv().await.unwrap().id, 3);
+    }
+}
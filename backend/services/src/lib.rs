@@ -1,8 +1,15 @@
 pub mod entities;
 pub mod errors;
+pub mod events;
+pub mod locale;
+pub mod mail_templates;
+pub mod mail_transport;
+pub mod password;
 pub mod usecases;
 
 mod context;
+#[cfg(test)]
+mod test_support;
 
 pub use context::{ServiceContext, ServiceContextBuilder};
 pub use errors::ServiceError;
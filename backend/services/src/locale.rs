@@ -0,0 +1,53 @@
+//! ユーザーの `locale` から、メールやシステムログの文面に使う言語を決めます。
+
+/// 文面を用意している言語。未対応の locale は英語にする。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    Ja,
+}
+
+impl Locale {
+    /// `ja`、`ja-JP`、`ja_JP` のような値を受け付ける
+    pub fn from_user_locale(locale: &str) -> Self {
+        let language = locale.split(['-', '_']).next().unwrap_or_default();
+        if language.eq_ignore_ascii_case("ja") {
+            Locale::Ja
+        } else {
+            Locale::En
+        }
+    }
+
+    /// 設定値として受け付ける言語コード (`en`、`ja`) を厳密に解釈する
+    pub fn parse(locale: &str) -> Option<Self> {
+        match locale {
+            "en" => Some(Locale::En),
+            "ja" => Some(Locale::Ja),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Ja => "ja",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_locales_fall_back_to_english() {
+        assert_eq!(Locale::from_user_locale("ja"), Locale::Ja);
+        assert_eq!(Locale::from_user_locale("ja-JP"), Locale::Ja);
+        assert_eq!(Locale::from_user_locale("en"), Locale::En);
+        assert_eq!(Locale::from_user_locale("fr"), Locale::En);
+        assert_eq!(Locale::from_user_locale(""), Locale::En);
+        assert_eq!(Locale::parse("ja"), Some(Locale::Ja));
+        assert_eq!(Locale::parse("ja-JP"), None);
+    }
+}
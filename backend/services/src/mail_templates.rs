@@ -0,0 +1,163 @@
+//! メールの件名と本文 (テキスト / HTML) のテンプレートです。
+//! テンプレートはバイナリに埋め込み、ユーザーの `locale` に応じて言語を切り替えます。
+
+use std::sync::LazyLock;
+
+use minijinja::{Environment, UndefinedBehavior, Value, context};
+
+use crate::{errors::ServiceError, locale::Locale, mail_transport::OutgoingMail};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MailTemplate {
+    VerifyEmail,
+    ResetPassword,
+    EmailChangeConfirmation,
+    EmailChangeNotice,
+    DailyJournal,
+}
+
+impl MailTemplate {
+    fn name(&self) -> &'static str {
+        match self {
+            MailTemplate::VerifyEmail => "verify_email",
+            MailTemplate::ResetPassword => "reset_password",
+            MailTemplate::EmailChangeConfirmation => "email_change_confirmation",
+            MailTemplate::EmailChangeNotice => "email_change_notice",
+            MailTemplate::DailyJournal => "daily_journal",
+        }
+    }
+}
+
+macro_rules! embed_templates {
+    ($env:ident, $($name:expr),* $(,)?) => {
+        $(
+            $env.add_template($name, include_str!(concat!("../templates/mail/", $name)))
+                .expect(concat!("invalid mail template: ", $name));
+        )*
+    };
+}
+
+macro_rules! embed_localized {
+    ($env:ident, $($template:literal),* $(,)?) => {
+        $(
+            embed_templates!(
+                $env,
+                concat!("en/", $template, ".subject.txt"),
+                concat!("en/", $template, ".txt"),
+                concat!("en/", $template, ".html"),
+                concat!("ja/", $template, ".subject.txt"),
+                concat!("ja/", $template, ".txt"),
+                concat!("ja/", $template, ".html"),
+            );
+        )*
+    };
+}
+
+static TEMPLATES: LazyLock<Environment<'static>> = LazyLock::new(|| {
+    let mut env = Environment::new();
+    // 変数の渡し忘れで空のリンクを送らないよう、未定義の変数はエラーにする
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    embed_templates!(env, "layout.html", "button.html");
+    embed_localized!(
+        env,
+        "verify_email",
+        "reset_password",
+        "email_change_confirmation",
+        "email_change_notice",
+        "daily_journal",
+    );
+    env
+});
+
+/// テンプレートから宛先 `to` へのメールを組み立てる。HTML テンプレートでは値がエスケープされる。
+pub fn render(
+    template: MailTemplate,
+    locale: &str,
+    to: &str,
+    values: Value,
+) -> Result<OutgoingMail, ServiceError> {
+    let locale = Locale::from_user_locale(locale);
+    let render = |kind: &str, ctx: &Value| {
+        let name = format!("{}/{}.{}", locale.as_str(), template.name(), kind);
+        TEMPLATES
+            .get_template(&name)
+            .and_then(|tmpl| tmpl.render(ctx))
+            .map_err(|e| ServiceError::Internal(Box::new(e)))
+    };
+
+    let subject = render("subject.txt", &values)?.trim().to_string();
+    let ctx = context! { lang => locale.as_str(), subject => &subject, ..values };
+    Ok(OutgoingMail {
+        to: to.to_string(),
+        body: render("txt", &ctx)?,
+        html: Some(render("html", &ctx)?),
+        subject,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_TEMPLATES: [MailTemplate; 5] = [
+        MailTemplate::VerifyEmail,
+        MailTemplate::ResetPassword,
+        MailTemplate::EmailChangeConfirmation,
+        MailTemplate::EmailChangeNotice,
+        MailTemplate::DailyJournal,
+    ];
+
+    #[test]
+    fn every_template_renders_in_each_locale() {
+        for template in ALL_TEMPLATES {
+            for locale in ["en", "ja"] {
+                let values = context! {
+                    url => "https://app.example.com/link",
+                    new_email => "new@example.com",
+                    date => "2025-10-31",
+                    markdown => "# Journal 2025-10-31 (UTC)",
+                    totals => context! { sessions => 1, focus_minutes => 25, completed_tasks => 1, logs => 1 },
+                    completed_tasks => ["Write report"],
+                    log_groups => [context! { title => (), logs => [context! { time => "09:30", content => "Drafted intro" }] }],
+                };
+                let mail = render(template, locale, "user@example.com", values).unwrap();
+                assert!(!mail.subject.is_empty());
+                assert!(!mail.subject.contains('\n'));
+                let html = mail.html.unwrap();
+                assert!(html.contains(&format!("<html lang=\"{locale}\">")));
+                assert!(html.contains(&format!("<title>{}</title>", mail.subject)));
+            }
+        }
+    }
+
+    #[test]
+    fn selects_language_and_escapes_html_only() {
+        let values = context! { url => "https://app.example.com/?a=1&b=2" };
+        let en = render(
+            MailTemplate::VerifyEmail,
+            "en",
+            "user@example.com",
+            values.clone(),
+        )
+        .unwrap();
+        let ja = render(MailTemplate::VerifyEmail, "ja", "user@example.com", values).unwrap();
+
+        assert_eq!(en.subject, "Verify your email address");
+        assert_eq!(ja.subject, "メールアドレスの確認");
+        assert!(en.body.contains("https://app.example.com/?a=1&b=2"));
+        assert!(en.html.unwrap().contains("?a=1&amp;b=2"));
+    }
+
+    #[test]
+    fn missing_values_are_errors() {
+        assert!(
+            render(
+                MailTemplate::VerifyEmail,
+                "en",
+                "user@example.com",
+                context! {}
+            )
+            .is_err()
+        );
+    }
+}
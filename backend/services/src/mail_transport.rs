@@ -0,0 +1,269 @@
+//! メールの送信手段を抽象化するモジュールです。
+//! 本番は SMTP、開発やテストではディレクトリへの `.eml` 出力やログ出力に切り替えることで、
+//! 確認メールやパスワード再設定メールの内容を実際に読みながらフローを確認できます。
+
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use rand::{Rng, distributions::Alphanumeric};
+
+use crate::errors::ServiceError;
+
+#[cfg(feature = "mail")]
+use lettre::{
+    Message, SmtpTransport, Transport,
+    message::{Mailbox, MultiPart, SinglePart},
+};
+
+/// 送信するメール 1 通分の内容。送信元は各トランスポートが保持する。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutgoingMail {
+    pub to: String,
+    pub subject: String,
+    /// テキスト形式の本文
+    pub body: String,
+    /// HTML 形式の本文。ある場合は multipart/alternative として送る。
+    pub html: Option<String>,
+}
+
+pub trait MailTransport: fmt::Debug + Send + Sync {
+    /// メールを送信する。ブロッキング I/O を伴うため非同期タスクから直接呼ばないこと。
+    fn send(&self, mail: &OutgoingMail) -> Result<(), ServiceError>;
+}
+
+/// 利用するトランスポートの種類
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MailTransportSettings {
+    Disabled,
+    Smtp,
+    File { dir: PathBuf },
+    Log,
+}
+
+/// 送信元の表示名 (`AXUM_MAIL_FROM_NAME`)
+fn from_name() -> String {
+    env::var("AXUM_MAIL_FROM_NAME").unwrap_or_else(|_| "Default Name".to_string())
+}
+
+#[cfg(feature = "mail")]
+#[derive(Debug)]
+pub struct SmtpMailTransport {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+#[cfg(feature = "mail")]
+impl SmtpMailTransport {
+    pub fn new(transport: SmtpTransport) -> Result<Self, ServiceError> {
+        let email = env::var("AXUM_MAIL_FROM_EMAIL")?;
+        let address = email
+            .parse()
+            .map_err(|e| ServiceError::Internal(Box::new(e)))?;
+        Ok(Self {
+            transport,
+            from: Mailbox::new(Some(from_name()), address),
+        })
+    }
+
+    /// `AXUM_SMTP_*` の設定から SMTP 接続を組み立てる。設定が揃っていなければ None。
+    pub fn from_env() -> Result<Option<Self>, ServiceError> {
+        let var = |key: &str| {
+            let value = env::var(key).ok();
+            if value.is_none() {
+                tracing::warn!("{key} is not set; skipping SMTP setup");
+            }
+            value
+        };
+        let (Some(server), Some(username), Some(password)) = (
+            var("AXUM_SMTP_SERVER"),
+            var("AXUM_SMTP_USERNAME"),
+            var("AXUM_SMTP_PASSWORD"),
+        ) else {
+            return Ok(None);
+        };
+
+        let creds = lettre::transport::smtp::authentication::Credentials::new(username, password);
+        let transport = SmtpTransport::relay(&server)?.credentials(creds).build();
+        Self::new(transport).map(Some)
+    }
+}
+
+#[cfg(feature = "mail")]
+impl MailTransport for SmtpMailTransport {
+    fn send(&self, mail: &OutgoingMail) -> Result<(), ServiceError> {
+        let to = Mailbox::new(
+            None,
+            mail.to
+                .parse()
+                .map_err(|e| ServiceError::Internal(Box::new(e)))?,
+        );
+        let builder = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(mail.subject.as_str());
+        let message = match &mail.html {
+            Some(html) => builder.multipart(MultiPart::alternative_plain_html(
+                mail.body.clone(),
+                html.clone(),
+            )),
+            None => builder.singlepart(SinglePart::plain(mail.body.clone())),
+        }
+        .map_err(|e| ServiceError::Internal(Box::new(e)))?;
+        self.transport.send(&message)?;
+        Ok(())
+    }
+}
+
+/// 1 通ごとに `.eml` ファイルとしてディレクトリへ書き出すトランスポート
+#[derive(Debug)]
+pub struct FileMailTransport {
+    dir: PathBuf,
+    from: String,
+}
+
+impl FileMailTransport {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, ServiceError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| ServiceError::Internal(Box::new(e)))?;
+        let email =
+            env::var("AXUM_MAIL_FROM_EMAIL").unwrap_or_else(|_| "noreply@localhost".to_string());
+        Ok(Self {
+            dir,
+            from: format!("{} <{}>", from_name(), email),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn render(&self, mail: &OutgoingMail, message_id: &str) -> String {
+        let headers = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@decopon.local>\r\n\
+             MIME-Version: 1.0\r\n",
+            self.from,
+            mail.to,
+            mail.subject,
+            Utc::now().to_rfc2822(),
+            message_id,
+        );
+        match &mail.html {
+            Some(html) => {
+                let boundary = format!("alt-{message_id}");
+                format!(
+                    "{headers}Content-Type: multipart/alternative; boundary=\"{boundary}\"\r\n\r\n\
+                     --{boundary}\r\n{}\r\n--{boundary}\r\n{}\r\n--{boundary}--\r\n",
+                    text_part("text/plain", &mail.body),
+                    text_part("text/html", html),
+                )
+            }
+            None => format!("{headers}{}", text_part("text/plain", &mail.body)),
+        }
+    }
+}
+
+/// 8bit のテキストパート (ヘッダーと本文) を CRLF 改行で組み立てる
+fn text_part(content_type: &str, content: &str) -> String {
+    format!(
+        "Content-Type: {content_type}; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{}\r\n",
+        content.replace("\r\n", "\n").replace('\n', "\r\n"),
+    )
+}
+
+impl MailTransport for FileMailTransport {
+    fn send(&self, mail: &OutgoingMail) -> Result<(), ServiceError> {
+        let suffix: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(8)
+            .map(char::from)
+            .collect();
+        let message_id = format!("{}-{}", Utc::now().format("%Y%m%dT%H%M%S%.6f"), suffix);
+        let path = self.dir.join(format!("{message_id}.eml"));
+        // 読み手が書きかけのファイルを拾わないよう、一時ファイルに書いてから名前を変える
+        let tmp_path = path.with_extension("eml.tmp");
+        fs::write(&tmp_path, self.render(mail, &message_id))
+            .and_then(|_| fs::rename(&tmp_path, &path))
+            .map_err(|e| ServiceError::Internal(Box::new(e)))?;
+        tracing::info!(path = %path.display(), to = %mail.to, "wrote mail to file");
+        Ok(())
+    }
+}
+
+/// 送信内容をログへ出力するだけの開発用トランスポート
+#[derive(Debug, Default)]
+pub struct LogMailTransport;
+
+impl MailTransport for LogMailTransport {
+    fn send(&self, mail: &OutgoingMail) -> Result<(), ServiceError> {
+        tracing::info!(
+            to = %mail.to,
+            subject = %mail.subject,
+            body = %mail.body,
+            has_html = mail.html.is_some(),
+            "mail (log transport)"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_transport_writes_one_eml_per_message() {
+        let dir = env::temp_dir().join(format!("decopon-mails-{}", rand::random::<u64>()));
+        let transport = FileMailTransport::new(&dir).unwrap();
+        for subject in ["first", "second"] {
+            transport
+                .send(&OutgoingMail {
+                    to: "user@example.com".into(),
+                    subject: subject.into(),
+                    body: "line1\nline2".into(),
+                    html: None,
+                })
+                .unwrap();
+        }
+
+        let mut files: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|path| path.extension().unwrap() == "eml"));
+        let content = fs::read_to_string(&files[0]).unwrap();
+        assert!(content.contains("To: user@example.com\r\n"));
+        assert!(content.ends_with("\r\n\r\nline1\r\nline2\r\n"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn file_transport_writes_html_as_multipart_alternative() {
+        let dir = env::temp_dir().join(format!("decopon-mails-{}", rand::random::<u64>()));
+        let transport = FileMailTransport::new(&dir).unwrap();
+        transport
+            .send(&OutgoingMail {
+                to: "user@example.com".into(),
+                subject: "html".into(),
+                body: "plain".into(),
+                html: Some("<p>rich</p>".into()),
+            })
+            .unwrap();
+
+        let path = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let content = fs::read_to_string(path).unwrap();
+        assert!(content.contains("Content-Type: multipart/alternative; boundary="));
+        let plain = content.find("Content-Type: text/plain").unwrap();
+        let html = content.find("Content-Type: text/html").unwrap();
+        // 受信側は後ろのパートを優先するため HTML を最後に置く
+        assert!(plain < html);
+        assert!(content.contains("\r\n\r\n<p>rich</p>\r\n"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+//! パスワードハッシュの生成と検証を担うモジュールです。
+//! 新規ハッシュは設定されたアルゴリズム (既定は Argon2id) で生成し、検証時は保存済みハッシュの
+//! プレフィックスからアルゴリズムを判別するため、bcrypt で保存された既存ユーザーもそのままログインできます。
+
+use argon2::{
+    Algorithm, Argon2, Params, Version,
+    password_hash::{
+        self, PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString,
+        rand_core::OsRng,
+    },
+};
+use axum_password_worker::{Hasher, PasswordWorkerError};
+use thiserror::Error;
+
+use crate::errors::ServiceError;
+
+const ARGON2ID_PREFIX: &str = "$argon2id$";
+const BCRYPT_PREFIXES: [&str; 4] = ["$2a$", "$2b$", "$2x$", "$2y$"];
+
+/// 新規にハッシュを生成する際のアルゴリズムとパラメータ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PasswordHashConfig {
+    Bcrypt {
+        cost: u32,
+    },
+    Argon2id {
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+}
+
+impl Default for PasswordHashConfig {
+    /// OWASP Password Storage Cheat Sheet の推奨値 (m=19MiB, t=2, p=1)
+    fn default() -> Self {
+        PasswordHashConfig::Argon2id {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PasswordHashError {
+    #[error(transparent)]
+    Bcrypt(#[from] bcrypt::BcryptError),
+
+    #[error("argon2 error: {0}")]
+    Argon2(password_hash::Error),
+
+    #[error("unsupported password hash format")]
+    UnsupportedFormat,
+}
+
+impl From<password_hash::Error> for PasswordHashError {
+    fn from(err: password_hash::Error) -> Self {
+        PasswordHashError::Argon2(err)
+    }
+}
+
+impl From<argon2::Error> for PasswordHashError {
+    fn from(err: argon2::Error) -> Self {
+        PasswordHashError::Argon2(err.into())
+    }
+}
+
+/// 保存済みハッシュの形式に応じて bcrypt / Argon2id を切り替える `Hasher` 実装
+#[derive(Clone, Copy, Debug)]
+pub enum PasswordHasher {}
+
+impl Hasher for PasswordHasher {
+    type Config = PasswordHashConfig;
+    type Error = PasswordHashError;
+
+    fn hash(data: impl AsRef<[u8]>, config: &Self::Config) -> Result<String, Self::Error> {
+        match *config {
+            PasswordHashConfig::Bcrypt { cost } => Ok(bcrypt::hash(data, cost)?),
+            PasswordHashConfig::Argon2id {
+                memory_kib,
+                iterations,
+                parallelism,
+            } => {
+                let params = Params::new(memory_kib, iterations, parallelism, None)?;
+                let salt = SaltString::generate(&mut OsRng);
+                let hash = Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password(data.as_ref(), &salt)?;
+                Ok(hash.to_string())
+            }
+        }
+    }
+
+    fn verify(data: impl AsRef<[u8]>, hash: &str) -> Result<bool, Self::Error> {
+        if hash.starts_with(ARGON2ID_PREFIX) {
+            let parsed = PasswordHash::new(hash)?;
+            match Argon2::default().verify_password(data.as_ref(), &parsed) {
+                Ok(()) => Ok(true),
+                Err(password_hash::Error::Password) => Ok(false),
+                Err(err) => Err(err.into()),
+            }
+        } else if is_bcrypt_hash(hash) {
+            Ok(bcrypt::verify(data, hash)?)
+        } else {
+            Err(PasswordHashError::UnsupportedFormat)
+        }
+    }
+}
+
+fn is_bcrypt_hash(hash: &str) -> bool {
+    BCRYPT_PREFIXES
+        .iter()
+        .any(|prefix| hash.starts_with(prefix))
+}
+
+fn bcrypt_cost(hash: &str) -> Option<u32> {
+    hash.get(4..6)?.parse().ok()
+}
+
+/// 設定済みのハッシュ方式を保持するパスワードワーカー。
+/// ハッシュ計算は `axum_password_worker` のスレッドプール上で行い、非同期ランタイムをブロックしない。
+#[derive(Clone, Debug)]
+pub struct PasswordWorker {
+    inner: axum_password_worker::PasswordWorker<PasswordHasher>,
+    config: PasswordHashConfig,
+}
+
+impl PasswordWorker {
+    pub fn new(
+        max_threads: usize,
+        config: PasswordHashConfig,
+    ) -> Result<Self, PasswordWorkerError<PasswordHasher>> {
+        Ok(Self {
+            inner: axum_password_worker::PasswordWorker::new(max_threads)?,
+            config,
+        })
+    }
+
+    pub fn config(&self) -> PasswordHashConfig {
+        self.config
+    }
+
+    pub async fn hash(&self, password: &str) -> Result<String, ServiceError> {
+        Ok(self.inner.hash(password, self.config).await?)
+    }
+
+    pub async fn verify(
+        &self,
+        password: &str,
+        hashed_password: &str,
+    ) -> Result<bool, ServiceError> {
+        Ok(self.inner.verify(password, hashed_password).await?)
+    }
+
+    /// 保存済みハッシュが現在の設定と異なるアルゴリズム・パラメータで生成されていれば true を返す。
+    pub fn needs_rehash(&self, hashed_password: &str) -> bool {
+        match self.config {
+            PasswordHashConfig::Bcrypt { cost } => {
+                !is_bcrypt_hash(hashed_password) || bcrypt_cost(hashed_password) != Some(cost)
+            }
+            PasswordHashConfig::Argon2id {
+                memory_kib,
+                iterations,
+                parallelism,
+            } => {
+                if !hashed_password.starts_with(ARGON2ID_PREFIX) {
+                    return true;
+                }
+                let Ok(parsed) = PasswordHash::new(hashed_password) else {
+                    return true;
+                };
+                match Params::try_from(&parsed) {
+                    Ok(params) => {
+                        params.m_cost() != memory_kib
+                            || params.t_cost() != iterations
+                            || params.p_cost() != parallelism
+                    }
+                    Err(_) => true,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAST_ARGON2ID: PasswordHashConfig = PasswordHashConfig::Argon2id {
+        memory_kib: 1024,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[tokio::test]
+    async fn argon2id_hash_round_trip() {
+        let worker = PasswordWorker::new(1, FAST_ARGON2ID).unwrap();
+        let hashed = worker.hash("secret").await.unwrap();
+        assert!(hashed.starts_with(ARGON2ID_PREFIX));
+        assert!(worker.verify("secret", &hashed).await.unwrap());
+        assert!(!worker.verify("wrong", &hashed).await.unwrap());
+        assert!(!worker.needs_rehash(&hashed));
+    }
+
+    #[tokio::test]
+    async fn verifies_legacy_bcrypt_hash_and_requests_rehash() {
+        let legacy = bcrypt::hash("secret", 4).unwrap();
+        let worker = PasswordWorker::new(1, FAST_ARGON2ID).unwrap();
+        assert!(worker.verify("secret", &legacy).await.unwrap());
+        assert!(worker.needs_rehash(&legacy));
+    }
+
+    #[tokio::test]
+    async fn requests_rehash_when_parameters_change() {
+        let old = PasswordWorker::new(1, FAST_ARGON2ID).unwrap();
+        let hashed = old.hash("secret").await.unwrap();
+
+        let stronger = PasswordWorker::new(
+            1,
+            PasswordHashConfig::Argon2id {
+                memory_kib: 2048,
+                iterations: 1,
+                parallelism: 1,
+            },
+        )
+        .unwrap();
+        assert!(stronger.verify("secret", &hashed).await.unwrap());
+        assert!(stronger.needs_rehash(&hashed));
+
+        let bcrypt_worker = PasswordWorker::new(1, PasswordHashConfig::Bcrypt { cost: 5 }).unwrap();
+        assert!(bcrypt_worker.needs_rehash(&bcrypt::hash("secret", 4).unwrap()));
+        assert!(!bcrypt_worker.needs_rehash(&bcrypt::hash("secret", 5).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_hash_format() {
+        let worker = PasswordWorker::new(1, FAST_ARGON2ID).unwrap();
+        assert!(matches!(
+            worker.verify("secret", "plain").await,
+            Err(ServiceError::Password(_))
+        ));
+    }
+}
@@ -0,0 +1,32 @@
+//! ユニットテストで共有する DB の準備。
+
+use chrono::Utc;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ActiveModelTrait, Database, DatabaseConnection, Set};
+
+use crate::entities::users;
+
+/// マイグレーションを適用したインメモリの SQLite
+pub(crate) async fn setup_db() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    db
+}
+
+/// メールアドレスを確認済みのユーザー。項目を変えたいテストは `..new_user(name)` で上書きする
+pub(crate) fn new_user(name: &str) -> users::ActiveModel {
+    users::ActiveModel {
+        name: Set(name.to_string()),
+        email: Set(format!("{name}@example.com")),
+        email_verified_at: Set(Some(Utc::now())),
+        password: Set("hashed".to_string()),
+        work_time: Set(25),
+        break_time: Set(5),
+        locale: Set("ja".to_string()),
+        ..Default::default()
+    }
+}
+
+pub(crate) async fn create_user(db: &DatabaseConnection, name: &str) -> users::Model {
+    new_user(name).insert(db).await.unwrap()
+}
@@ -0,0 +1,576 @@
+//! Web デプロイ向けの管理者用ユースケースです。
+//! 管理者による操作はすべて `admin_audit_logs` に記録します。
+
+use crate::{
+    entities::{admin_audit_logs, prelude::*, users},
+    errors::ServiceError,
+    password::PasswordWorker,
+    usecases::{
+        auth, mails,
+        users::{UserFull, UserRole},
+    },
+};
+
+use chrono::Utc;
+use rand::{Rng, distributions::Alphanumeric};
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, TransactionTrait,
+};
+
+pub const DEFAULT_PER_PAGE: u64 = 20;
+pub const MAX_PER_PAGE: u64 = 100;
+
+/// 監査ログに記録する操作の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdminAction {
+    DisableUser,
+    EnableUser,
+    DeleteUser,
+    ImpersonateUser,
+    ResendVerification,
+    ForcePasswordReset,
+}
+
+impl AdminAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminAction::DisableUser => "disable_user",
+            AdminAction::EnableUser => "enable_user",
+            AdminAction::DeleteUser => "delete_user",
+            AdminAction::ImpersonateUser => "impersonate_user",
+            AdminAction::ResendVerification => "resend_verification",
+            AdminAction::ForcePasswordReset => "force_password_reset",
+        }
+    }
+}
+
+pub struct Pagination {
+    pub page: u64,
+    pub per_page: u64,
+}
+
+impl Pagination {
+    /// 1 始まりのページ番号と 1 ページあたりの件数を正規化する。
+    pub fn new(page: Option<u64>, per_page: Option<u64>) -> Self {
+        Self {
+            page: page.unwrap_or(1).max(1),
+            per_page: per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE),
+        }
+    }
+}
+
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u64,
+    pub per_page: u64,
+    pub total: u64,
+}
+
+pub struct AdminUser {
+    pub id: i32,
+    pub uuid: String,
+    pub name: String,
+    pub email: String,
+    pub role: UserRole,
+    pub email_verified_at: Option<DateTimeUtc>,
+    pub disabled_at: Option<DateTimeUtc>,
+    pub created_at: DateTimeUtc,
+}
+
+impl From<users::Model> for AdminUser {
+    fn from(model: users::Model) -> Self {
+        Self {
+            id: model.id,
+            uuid: model.uuid,
+            name: model.name,
+            email: model.email,
+            role: model.role.into(),
+            email_verified_at: model.email_verified_at,
+            disabled_at: model.disabled_at,
+            created_at: model.created_at,
+        }
+    }
+}
+
+pub struct AuditLog {
+    pub id: i32,
+    pub actor_id: Option<i32>,
+    pub target_user_id: Option<i32>,
+    pub action: String,
+    pub details: Option<String>,
+    pub created_at: DateTimeUtc,
+}
+
+impl From<admin_audit_logs::Model> for AuditLog {
+    fn from(model: admin_audit_logs::Model) -> Self {
+        Self {
+            id: model.id,
+            actor_id: model.actor_id,
+            target_user_id: model.target_user_id,
+            action: model.action,
+            details: model.details,
+            created_at: model.created_at,
+        }
+    }
+}
+
+pub struct UserSearch {
+    pub query: Option<String>,
+    pub pagination: Pagination,
+}
+
+/// 有効な管理者であることを確認する。
+pub async fn require_admin(db: &DatabaseConnection, user_id: i32) -> Result<(), ServiceError> {
+    let user = Users::find_by_id(user_id)
+        .one(db)
+        .await?
+        .map(UserFull::from)
+        .ok_or(ServiceError::Forbidden)?;
+    if !user.role.is_admin() || user.disabled_at.is_some() {
+        return Err(ServiceError::Forbidden);
+    }
+    Ok(())
+}
+
+/// 設定で指定されたメールアドレスのユーザーを管理者に昇格する。
+pub async fn promote_admins(
+    db: &DatabaseConnection,
+    emails: &[String],
+) -> Result<(), ServiceError> {
+    if emails.is_empty() {
+        return Ok(());
+    }
+    let result = Users::update_many()
+        .col_expr(users::Column::Role, Expr::value(UserRole::Admin.as_str()))
+        .filter(users::Column::Email.is_in(emails.iter().map(String::as_str)))
+        .filter(users::Column::Role.ne(UserRole::Admin.as_str()))
+        .exec(db)
+        .await?;
+    if result.rows_affected > 0 {
+        tracing::info!(
+            count = result.rows_affected,
+            "promoted configured users to admin"
+        );
+    }
+    Ok(())
+}
+
+pub async fn list_users(
+    db: &DatabaseConnection,
+    params: UserSearch,
+) -> Result<Page<AdminUser>, ServiceError> {
+    let mut query = Users::find().order_by_asc(users::Column::Id);
+    if let Some(q) = params
+        .query
+        .as_deref()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+    {
+        query = query.filter(
+            Condition::any()
+                .add(users::Column::Email.contains(q))
+                .add(users::Column::Name.contains(q)),
+        );
+    }
+
+    let Pagination { page, per_page } = params.pagination;
+    let paginator = query.paginate(db, per_page);
+    let total = paginator.num_items().await?;
+    let items = paginator
+        .fetch_page(page - 1)
+        .await?
+        .into_iter()
+        .map(AdminUser::from)
+        .collect();
+    Ok(Page {
+        items,
+        page,
+        per_page,
+        total,
+    })
+}
+
+pub async fn list_audit_logs(
+    db: &DatabaseConnection,
+    pagination: Pagination,
+) -> Result<Page<AuditLog>, ServiceError> {
+    let Pagination { page, per_page } = pagination;
+    let paginator = AdminAuditLogs::find()
+        .order_by_desc(admin_audit_logs::Column::Id)
+        .paginate(db, per_page);
+    let total = paginator.num_items().await?;
+    let items = paginator
+        .fetch_page(page - 1)
+        .await?
+        .into_iter()
+        .map(AuditLog::from)
+        .collect();
+    Ok(Page {
+        items,
+        page,
+        per_page,
+        total,
+    })
+}
+
+async fn record_audit(
+    db: &impl ConnectionTrait,
+    actor_id: i32,
+    target_user_id: i32,
+    action: AdminAction,
+    details: Option<String>,
+) -> Result<(), ServiceError> {
+    admin_audit_logs::ActiveModel {
+        actor_id: ActiveValue::Set(Some(actor_id)),
+        target_user_id: ActiveValue::Set(Some(target_user_id)),
+        action: ActiveValue::Set(action.as_str().to_string()),
+        details: ActiveValue::Set(details),
+        created_at: ActiveValue::Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    tracing::info!(
+        actor_id,
+        target_user_id,
+        action = action.as_str(),
+        "admin action recorded"
+    );
+    Ok(())
+}
+
+async fn find_target(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+) -> Result<users::Model, ServiceError> {
+    Users::find_by_id(user_id)
+        .one(db)
+        .await?
+        .ok_or(ServiceError::NotFound("user"))
+}
+
+/// 自分自身を無効化・削除して管理者が締め出されることを防ぐ。
+fn ensure_not_self(actor_id: i32, target_id: i32) -> Result<(), ServiceError> {
+    if actor_id == target_id {
+        return Err(ServiceError::BadRequest(
+            "cannot perform this action on your own account".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn disable_user(
+    db: &DatabaseConnection,
+    actor_id: i32,
+    target_id: i32,
+) -> Result<AdminUser, ServiceError> {
+    ensure_not_self(actor_id, target_id)?;
+    let txn = db.begin().await?;
+    let target = find_target(&txn, target_id).await?;
+    let target = if target.disabled_at.is_none() {
+        let mut active: users::ActiveModel = target.into();
+        active.disabled_at = ActiveValue::Set(Some(Utc::now()));
+        active.updated_at = ActiveValue::Set(Utc::now());
+        active.update(&txn).await?
+    } else {
+        target
+    };
+    record_audit(&txn, actor_id, target_id, AdminAction::DisableUser, None).await?;
+    txn.commit().await?;
+    Ok(target.into())
+}
+
+pub async fn enable_user(
+    db: &DatabaseConnection,
+    actor_id: i32,
+    target_id: i32,
+) -> Result<AdminUser, ServiceError> {
+    let txn = db.begin().await?;
+    let target = find_target(&txn, target_id).await?;
+    let mut active: users::ActiveModel = target.into();
+    active.disabled_at = ActiveValue::Set(None);
+    active.updated_at = ActiveValue::Set(Utc::now());
+    let target = active.update(&txn).await?;
+    record_audit(&txn, actor_id, target_id, AdminAction::EnableUser, None).await?;
+    txn.commit().await?;
+    Ok(target.into())
+}
+
+pub async fn delete_user(
+    db: &DatabaseConnection,
+    actor_id: i32,
+    target_id: i32,
+) -> Result<(), ServiceError> {
+    ensure_not_self(actor_id, target_id)?;
+    let txn = db.begin().await?;
+    let target = find_target(&txn, target_id).await?;
+    // 削除後も誰を削除したか追えるようにメールアドレスを残す
+    let details = Some(format!("email={}", target.email));
+    let target: users::ActiveModel = target.into();
+    target.delete(&txn).await?;
+    record_audit(&txn, actor_id, target_id, AdminAction::DeleteUser, details).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// 対象ユーザーとして閲覧するための読み取り専用トークンを発行する。
+pub async fn impersonate_user(
+    db: &DatabaseConnection,
+    jwt_secret: &str,
+    actor_id: i32,
+    target_id: i32,
+) -> Result<String, ServiceError> {
+    ensure_not_self(actor_id, target_id)?;
+    let txn = db.begin().await?;
+    let target = find_target(&txn, target_id).await?;
+    if target.disabled_at.is_some() {
+        return Err(ServiceError::BadRequest("user is disabled".into()));
+    }
+    let token = auth::create_impersonation_jwt(target.id, actor_id, jwt_secret)?;
+    record_audit(
+        &txn,
+        actor_id,
+        target_id,
+        AdminAction::ImpersonateUser,
+        None,
+    )
+    .await?;
+    txn.commit().await?;
+    Ok(token)
+}
+
+pub async fn resend_verification(
+    db: &DatabaseConnection,
+    mailer: Option<&mails::Mailer>,
+    actor_id: i32,
+    target_id: i32,
+) -> Result<(), ServiceError> {
+    let txn = db.begin().await?;
+    let target = find_target(&txn, target_id).await?;
+    auth::resend_verification(&txn, mailer, &target.email).await?;
+    record_audit(
+        &txn,
+        actor_id,
+        target_id,
+        AdminAction::ResendVerification,
+        None,
+    )
+    .await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// 現在のパスワードを無効化し、再設定用のメールを送る。
+pub async fn force_password_reset(
+    db: &DatabaseConnection,
+    password_worker: &PasswordWorker,
+    mailer: Option<&mails::Mailer>,
+    actor_id: i32,
+    target_id: i32,
+) -> Result<(), ServiceError> {
+    // メールを送れない環境で実行すると対象ユーザーが復旧できなくなる
+    if mailer.is_none() {
+        return Err(ServiceError::BadRequest(
+            "password reset requires mail delivery".into(),
+        ));
+    }
+    let random_password: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    let hashed = auth::hash_password(&random_password, password_worker).await?;
+
+    // パスワードの無効化、再設定メール、監査ログのどれかだけが残らないようにする
+    let txn = db.begin().await?;
+    let target = find_target(&txn, target_id).await?;
+    let email = target.email.clone();
+    let mut active: users::ActiveModel = target.into();
+    active.password = ActiveValue::Set(hashed);
+    active.updated_at = ActiveValue::Set(Utc::now());
+    active.update(&txn).await?;
+
+    auth::forgot_password(&txn, mailer, &email).await?;
+    record_audit(
+        &txn,
+        actor_id,
+        target_id,
+        AdminAction::ForcePasswordReset,
+        None,
+    )
+    .await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mail_transport::LogMailTransport;
+    use crate::password::PasswordHashConfig;
+    use crate::test_support::{create_user, setup_db};
+    use std::sync::Arc;
+
+    async fn audit_actions(db: &DatabaseConnection) -> Vec<String> {
+        AdminAuditLogs::find()
+            .order_by_asc(admin_audit_logs::Column::Id)
+            .all(db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|log| log.action)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn promote_admins_grants_role_to_configured_emails() {
+        let db = setup_db().await;
+        let admin = create_user(&db, "admin").await;
+        let user = create_user(&db, "user").await;
+        assert!(matches!(
+            require_admin(&db, admin.id).await,
+            Err(ServiceError::Forbidden)
+        ));
+
+        promote_admins(&db, &["admin@example.com".to_string()])
+            .await
+            .unwrap();
+        require_admin(&db, admin.id).await.unwrap();
+        assert!(matches!(
+            require_admin(&db, user.id).await,
+            Err(ServiceError::Forbidden)
+        ));
+    }
+
+    #[tokio::test]
+    async fn list_users_searches_and_paginates() {
+        let db = setup_db().await;
+        for name in ["alice", "bob", "alicia", "carol"] {
+            create_user(&db, name).await;
+        }
+
+        let page = list_users(
+            &db,
+            UserSearch {
+                query: Some("ali".into()),
+                pagination: Pagination::new(Some(1), Some(1)),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].name, "alice");
+
+        let page = list_users(
+            &db,
+            UserSearch {
+                query: None,
+                pagination: Pagination::new(Some(2), Some(3)),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.total, 4);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].name, "carol");
+    }
+
+    #[tokio::test]
+    async fn disable_enable_and_delete_are_audited() {
+        let db = setup_db().await;
+        let admin = create_user(&db, "admin").await;
+        let user = create_user(&db, "user").await;
+
+        let disabled = disable_user(&db, admin.id, user.id).await.unwrap();
+        assert!(disabled.disabled_at.is_some());
+        let enabled = enable_user(&db, admin.id, user.id).await.unwrap();
+        assert!(enabled.disabled_at.is_none());
+        delete_user(&db, admin.id, user.id).await.unwrap();
+        assert!(Users::find_by_id(user.id).one(&db).await.unwrap().is_none());
+
+        assert_eq!(
+            audit_actions(&db).await,
+            vec!["disable_user", "enable_user", "delete_user"]
+        );
+        assert!(matches!(
+            disable_user(&db, admin.id, admin.id).await,
+            Err(ServiceError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn impersonation_token_carries_admin_id() {
+        let db = setup_db().await;
+        let admin = create_user(&db, "admin").await;
+        let user = create_user(&db, "user").await;
+
+        let token = impersonate_user(&db, "secret", admin.id, user.id)
+            .await
+            .unwrap();
+        let claims = auth::decode_jwt(token, "secret").unwrap();
+        assert_eq!(claims.sub, user.id);
+        assert_eq!(claims.impersonated_by, Some(admin.id));
+        assert_eq!(audit_actions(&db).await, vec!["impersonate_user"]);
+    }
+
+    #[tokio::test]
+    async fn force_password_reset_replaces_password_and_issues_token() {
+        let db = setup_db().await;
+        let admin = create_user(&db, "admin").await;
+        let user = create_user(&db, "user").await;
+        let worker = PasswordWorker::new(
+            1,
+            PasswordHashConfig::Argon2id {
+                memory_kib: 1024,
+                iterations: 1,
+                parallelism: 1,
+            },
+        )
+        .unwrap();
+
+        assert!(matches!(
+            force_password_reset(&db, &worker, None, admin.id, user.id).await,
+            Err(ServiceError::BadRequest(_))
+        ));
+
+        let mailer: mails::Mailer = Arc::new(LogMailTransport);
+        force_password_reset(&db, &worker, Some(&mailer), admin.id, user.id)
+            .await
+            .unwrap();
+        let stored = Users::find_by_id(user.id).one(&db).await.unwrap().unwrap();
+        assert_ne!(stored.password, "hashed");
+        assert!(stored.verification_token.is_some());
+        assert_eq!(audit_actions(&db).await, vec!["force_password_reset"]);
+    }
+
+    #[tokio::test]
+    async fn force_password_reset_rolls_back_when_mail_cannot_be_queued() {
+        let db = setup_db().await;
+        let admin = create_user(&db, "admin").await;
+        let user = create_user(&db, "user").await;
+        let worker = PasswordWorker::new(
+            1,
+            PasswordHashConfig::Argon2id {
+                memory_kib: 1024,
+                iterations: 1,
+                parallelism: 1,
+            },
+        )
+        .unwrap();
+        db.execute_unprepared("DROP TABLE mail_outbox")
+            .await
+            .unwrap();
+
+        let mailer: mails::Mailer = Arc::new(LogMailTransport);
+        let result = force_password_reset(&db, &worker, Some(&mailer), admin.id, user.id).await;
+        assert!(result.is_err());
+        let stored = Users::find_by_id(user.id).one(&db).await.unwrap().unwrap();
+        assert_eq!(stored.password, "hashed");
+        assert!(stored.verification_token.is_none());
+        assert!(audit_actions(&db).await.is_empty());
+    }
+}
@@ -0,0 +1,892 @@
+//! アカウントのデータを書き出し、別の環境へ取り込むためのアーカイブ。
+//! ローカルの環境と Web のアカウントの間でデータを移せるよう、ID はアーカイブ内だけで通用する値として扱い、
+//! 取り込むときに振り直す。行の UUID は引き継ぐので、同じアーカイブを取り込み直しても重複しない。
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, DatabaseConnection, DatabaseTransaction,
+    EntityTrait, LoaderTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::{prelude::*, *},
+    errors::ServiceError,
+    locale::Locale,
+};
+
+use super::{
+    logs as logs_usecase,
+    sync::{self, SyncEntity},
+    tag_task as tag_task_usecase, tasks as tasks_usecase,
+};
+
+pub const ARCHIVE_FORMAT: &str = "decopon-archive";
+/// 取り込める最新の版。形式を変えたら上げる
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// 書き出しで一度に読む行の数
+const PAGE_SIZE: u64 = 500;
+
+const SESSION_STATUSES: [&str; 5] = [
+    "In_Progress",
+    "Completed",
+    "Interrupted",
+    "Abandoned",
+    "Extended",
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Archive {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub profile: ArchivedProfile,
+    pub preferences: ArchivedPreferences,
+    pub tags: Vec<ArchivedTag>,
+    /// 親が先に来るよう、階層の浅い順に並べる
+    pub tasks: Vec<ArchivedTask>,
+    pub logs: Vec<ArchivedLog>,
+    pub decopon_sessions: Vec<ArchivedSession>,
+}
+
+/// 書き出し元のアカウント。取り込み先のプロフィールは書き換えない
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ArchivedProfile {
+    pub uuid: String,
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ArchivedPreferences {
+    pub work_time: i32,
+    pub break_time: i32,
+    pub locale: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ArchivedTag {
+    pub id: i32,
+    pub uuid: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ArchivedTask {
+    pub id: i32,
+    pub uuid: String,
+    pub title: String,
+    pub description: String,
+    pub completed: bool,
+    pub parent_task_id: Option<i32>,
+    pub position: i32,
+    pub tag_ids: Vec<i32>,
+    #[serde(default)]
+    pub due_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ArchivedLog {
+    pub id: i32,
+    pub uuid: String,
+    pub content: String,
+    pub source: String,
+    pub event_code: Option<String>,
+    pub event_params: Option<String>,
+    pub task_id: Option<i32>,
+    pub tag_ids: Vec<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ArchivedSession {
+    pub id: i32,
+    pub uuid: String,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 取り込んだ行の数。同じ UUID の行が既にあって取り込まなかった行は `skipped` に数える
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub tags: usize,
+    pub tasks: usize,
+    pub logs: usize,
+    pub decopon_sessions: usize,
+    pub skipped: usize,
+}
+
+/// アーカイブの配列以外の部分。書き出しを少しずつ送るとき、最初に読む
+#[derive(Debug, Serialize)]
+pub struct ArchiveHeader {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub profile: ArchivedProfile,
+    pub preferences: ArchivedPreferences,
+}
+
+/// 配列の一ページ分の行。`next` が `Some` なら、その位置から続きを読む
+#[derive(Debug)]
+pub struct ArchivePage<T, C = i32> {
+    pub items: Vec<T>,
+    pub next: Option<C>,
+}
+
+/// タスクの続きを読む位置。親が先に来るよう、階層の深さと ID の順に読む
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaskCursor {
+    pub depth: i32,
+    pub id: i32,
+}
+
+pub async fn export_header(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> Result<ArchiveHeader, ServiceError> {
+    let user = Users::find_by_id(user_id)
+        .one(db)
+        .await?
+        .ok_or(ServiceError::NotFound("user"))?;
+    Ok(ArchiveHeader {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        exported_at: Utc::now(),
+        profile: ArchivedProfile {
+            uuid: user.uuid,
+            name: user.name,
+            email: user.email,
+        },
+        preferences: ArchivedPreferences {
+            work_time: user.work_time,
+            break_time: user.break_time,
+            locale: user.locale,
+        },
+    })
+}
+
+pub async fn tags_page(
+    db: &DatabaseConnection,
+    user_id: i32,
+    after: Option<i32>,
+) -> Result<ArchivePage<ArchivedTag>, ServiceError> {
+    let rows = Tags::find()
+        .filter(tags::Column::UserId.eq(user_id))
+        .filter(tags::Column::Id.gt(after.unwrap_or(0)))
+        .order_by_asc(tags::Column::Id)
+        .limit(PAGE_SIZE)
+        .all(db)
+        .await?;
+    let next = next_cursor(&rows, |tag| tag.id);
+    let items = rows
+        .into_iter()
+        .map(|tag| ArchivedTag {
+            id: tag.id,
+            uuid: tag.uuid,
+            name: tag.name,
+            created_at: tag.created_at,
+            updated_at: tag.updated_at,
+        })
+        .collect();
+    Ok(ArchivePage { items, next })
+}
+
+pub async fn tasks_page(
+    db: &DatabaseConnection,
+    user_id: i32,
+    after: Option<TaskCursor>,
+) -> Result<ArchivePage<ArchivedTask, TaskCursor>, ServiceError> {
+    let mut query = Tasks::find().filter(tasks::Column::UserId.eq(user_id));
+    if let Some(after) = after {
+        query = query.filter(
+            Condition::any()
+                .add(tasks::Column::Depth.gt(after.depth))
+                .add(
+                    Condition::all()
+                        .add(tasks::Column::Depth.eq(after.depth))
+                        .add(tasks::Column::Id.gt(after.id)),
+                ),
+        );
+    }
+    // 結合した行に上限がかからないよう、タスクを読んでからタグをまとめて読む
+    let rows = query
+        .order_by_asc(tasks::Column::Depth)
+        .order_by_asc(tasks::Column::Id)
+        .limit(PAGE_SIZE)
+        .all(db)
+        .await?;
+    let next = next_cursor(&rows, |task| TaskCursor {
+        depth: task.depth,
+        id: task.id,
+    });
+    let tags = rows.load_many_to_many(Tags, TagTask, db).await?;
+    let items = rows
+        .into_iter()
+        .zip(tags)
+        .map(|(task, tags)| ArchivedTask {
+            id: task.id,
+            uuid: task.uuid,
+            title: task.title,
+            description: task.description,
+            completed: task.completed,
+            parent_task_id: task.parent_task_id,
+            position: task.position,
+            tag_ids: tags.into_iter().map(|tag| tag.id).collect(),
+            due_at: task.due_at,
+            created_at: task.created_at,
+            updated_at: task.updated_at,
+        })
+        .collect();
+    Ok(ArchivePage { items, next })
+}
+
+pub async fn logs_page(
+    db: &DatabaseConnection,
+    user_id: i32,
+    after: Option<i32>,
+) -> Result<ArchivePage<ArchivedLog>, ServiceError> {
+    let rows = Logs::find()
+        .filter(logs::Column::UserId.eq(user_id))
+        .filter(logs::Column::Id.gt(after.unwrap_or(0)))
+        .order_by_asc(logs::Column::Id)
+        .limit(PAGE_SIZE)
+        .all(db)
+        .await?;
+    let next = next_cursor(&rows, |log| log.id);
+    let tags = rows.load_many_to_many(Tags, LogTag, db).await?;
+    let items = rows
+        .into_iter()
+        .zip(tags)
+        .map(|(log, tags)| ArchivedLog {
+            id: log.id,
+            uuid: log.uuid,
+            content: log.content,
+            source: log.source,
+            event_code: log.event_code,
+            event_params: log.event_params,
+            task_id: log.task_id,
+            tag_ids: tags.into_iter().map(|tag| tag.id).collect(),
+            created_at: log.created_at,
+            updated_at: log.updated_at,
+        })
+        .collect();
+    Ok(ArchivePage { items, next })
+}
+
+pub async fn sessions_page(
+    db: &DatabaseConnection,
+    user_id: i32,
+    after: Option<i32>,
+) -> Result<ArchivePage<ArchivedSession>, ServiceError> {
+    let rows = DecoponSessions::find()
+        .filter(decopon_sessions::Column::UserId.eq(user_id))
+        .filter(decopon_sessions::Column::Id.gt(after.unwrap_or(0)))
+        .order_by_asc(decopon_sessions::Column::Id)
+        .limit(PAGE_SIZE)
+        .all(db)
+        .await?;
+    let next = next_cursor(&rows, |session| session.id);
+    let items = rows
+        .into_iter()
+        .map(|session| ArchivedSession {
+            id: session.id,
+            uuid: session.uuid,
+            status: session.status,
+            started_at: session.started_at,
+            ended_at: session.ended_at,
+            created_at: session.created_at,
+            updated_at: session.updated_at,
+        })
+        .collect();
+    Ok(ArchivePage { items, next })
+}
+
+/// アーカイブ全体をまとめて読む。HTTP では `export_header` と各ページを少しずつ送る
+pub async fn export_archive(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Archive, ServiceError> {
+    let header = export_header(db, user_id).await?;
+    let mut archive = Archive {
+        format: header.format,
+        version: header.version,
+        exported_at: header.exported_at,
+        profile: header.profile,
+        preferences: header.preferences,
+        tags: Vec::new(),
+        tasks: Vec::new(),
+        logs: Vec::new(),
+        decopon_sessions: Vec::new(),
+    };
+
+    let mut after = None;
+    loop {
+        let page = tags_page(db, user_id, after).await?;
+        archive.tags.extend(page.items);
+        match page.next {
+            Some(next) => after = Some(next),
+            None => break,
+        }
+    }
+    let mut after = None;
+    loop {
+        let page = tasks_page(db, user_id, after).await?;
+        archive.tasks.extend(page.items);
+        match page.next {
+            Some(next) => after = Some(next),
+            None => break,
+        }
+    }
+    let mut after = None;
+    loop {
+        let page = logs_page(db, user_id, after).await?;
+        archive.logs.extend(page.items);
+        match page.next {
+            Some(next) => after = Some(next),
+            None => break,
+        }
+    }
+    let mut after = None;
+    loop {
+        let page = sessions_page(db, user_id, after).await?;
+        archive.decopon_sessions.extend(page.items);
+        match page.next {
+            Some(next) => after = Some(next),
+            None => break,
+        }
+    }
+    Ok(archive)
+}
+
+fn next_cursor<T, C>(rows: &[T], cursor: impl Fn(&T) -> C) -> Option<C> {
+    if rows.len() as u64 == PAGE_SIZE {
+        rows.last().map(cursor)
+    } else {
+        None
+    }
+}
+
+/// アーカイブを取り込む。全体を一つのトランザクションで行い、途中で失敗したら何も残さない
+pub async fn import_archive(
+    db: &DatabaseConnection,
+    user_id: i32,
+    archive: Archive,
+) -> Result<ImportSummary, ServiceError> {
+    validate_archive(&archive)?;
+
+    let txn = db.begin().await?;
+    let mut summary = ImportSummary::default();
+
+    let mut user: users::ActiveModel = Users::find_by_id(user_id)
+        .one(&txn)
+        .await?
+        .ok_or(ServiceError::NotFound("user"))?
+        .into();
+    user.work_time = ActiveValue::Set(archive.preferences.work_time);
+    user.break_time = ActiveValue::Set(archive.preferences.break_time);
+    user.locale = ActiveValue::Set(archive.preferences.locale);
+    user.updated_at = ActiveValue::Set(Utc::now());
+    user.update(&txn).await?;
+
+    let tag_ids = import_tags(&txn, user_id, archive.tags, &mut summary).await?;
+    let task_ids = import_tasks(&txn, user_id, archive.tasks, &tag_ids, &mut summary).await?;
+    import_logs(
+        &txn,
+        user_id,
+        archive.logs,
+        &tag_ids,
+        &task_ids,
+        &mut summary,
+    )
+    .await?;
+    import_sessions(&txn, user_id, archive.decopon_sessions, &mut summary).await?;
+
+    txn.commit().await?;
+    Ok(summary)
+}
+
+fn validate_archive(archive: &Archive) -> Result<(), ServiceError> {
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(ServiceError::invalid_field(
+            "format",
+            "unsupported_format",
+            format!("must be {}", ARCHIVE_FORMAT),
+        ));
+    }
+    if archive.version == 0 || archive.version > ARCHIVE_VERSION {
+        return Err(ServiceError::invalid_field(
+            "version",
+            "unsupported_version",
+            format!("must be between 1 and {}", ARCHIVE_VERSION),
+        ));
+    }
+    if Locale::parse(&archive.preferences.locale).is_none() {
+        return Err(ServiceError::invalid_field(
+            "preferences.locale",
+            "unsupported_locale",
+            "must be one of: en, ja",
+        ));
+    }
+    if let Some(session) = archive
+        .decopon_sessions
+        .iter()
+        .find(|session| !SESSION_STATUSES.contains(&session.status.as_str()))
+    {
+        return Err(ServiceError::invalid_field(
+            "decopon_sessions.status",
+            "unknown_status",
+            format!("unknown session status: {}", session.status),
+        ));
+    }
+    Ok(())
+}
+
+/// アーカイブの UUID を引き継ぐ。空や不正な値なら新しく振る
+fn archived_uuid(uuid: &str) -> String {
+    Uuid::parse_str(uuid)
+        .map(|uuid| uuid.hyphenated().to_string())
+        .unwrap_or_else(|_| Uuid::new_v4().to_string())
+}
+
+/// 同じ UUID、なければ同じ名前のタグを使い回す。アーカイブの ID から取り込み先の ID への対応を返す
+async fn import_tags(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    archived: Vec<ArchivedTag>,
+    summary: &mut ImportSummary,
+) -> Result<HashMap<i32, i32>, ServiceError> {
+    let existing = Tags::find()
+        .filter(tags::Column::UserId.eq(user_id))
+        .all(txn)
+        .await?;
+    let by_uuid: HashMap<String, i32> = existing
+        .iter()
+        .map(|tag| (tag.uuid.clone(), tag.id))
+        .collect();
+    let mut by_name: HashMap<String, i32> =
+        existing.into_iter().map(|tag| (tag.name, tag.id)).collect();
+
+    let mut ids = HashMap::new();
+    for tag in archived {
+        let uuid = archived_uuid(&tag.uuid);
+        if let Some(id) = by_uuid.get(&uuid).or_else(|| by_name.get(&tag.name)) {
+            ids.insert(tag.id, *id);
+            summary.skipped += 1;
+            continue;
+        }
+        let inserted = tags::ActiveModel {
+            uuid: ActiveValue::Set(uuid),
+            name: ActiveValue::Set(tag.name.clone()),
+            user_id: ActiveValue::Set(user_id),
+            created_at: ActiveValue::Set(tag.created_at),
+            updated_at: ActiveValue::Set(tag.updated_at),
+            ..Default::default()
+        }
+        .insert(txn)
+        .await?;
+        sync::record_upsert(txn, SyncEntity::Tag, inserted.id).await?;
+        by_name.insert(tag.name, inserted.id);
+        ids.insert(tag.id, inserted.id);
+        summary.tags += 1;
+    }
+    Ok(ids)
+}
+
+fn mapped_tag_ids(archived: &[i32], tag_ids: &HashMap<i32, i32>) -> Vec<i32> {
+    let mut ids: Vec<i32> = archived
+        .iter()
+        .filter_map(|id| tag_ids.get(id).copied())
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+/// 親を先に作りながらタスクを取り込む。親がアーカイブにないタスクは最上位に置く
+async fn import_tasks(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    mut archived: Vec<ArchivedTask>,
+    tag_ids: &HashMap<i32, i32>,
+    summary: &mut ImportSummary,
+) -> Result<HashMap<i32, i32>, ServiceError> {
+    let existing: HashMap<String, i32> = Tasks::find()
+        .filter(tasks::Column::UserId.eq(user_id))
+        .all(txn)
+        .await?
+        .into_iter()
+        .map(|task| (task.uuid, task.id))
+        .collect();
+    let known: HashSet<i32> = archived.iter().map(|task| task.id).collect();
+    // 兄弟の並び順を保つため、元の位置の順に作る
+    archived.sort_by_key(|task| task.position);
+
+    let mut ids: HashMap<i32, i32> = HashMap::new();
+    let mut pending = archived;
+    while !pending.is_empty() {
+        let (ready, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|task| {
+            task.parent_task_id
+                .is_none_or(|parent| !known.contains(&parent) || ids.contains_key(&parent))
+        });
+        // 親子が循環していれば、残りは最上位に置く
+        let (ready, rest) = if ready.is_empty() {
+            (rest, Vec::new())
+        } else {
+            (ready, rest)
+        };
+        for task in ready {
+            let uuid = archived_uuid(&task.uuid);
+            if let Some(id) = existing.get(&uuid) {
+                ids.insert(task.id, *id);
+                summary.skipped += 1;
+                continue;
+            }
+            let parent_task_id = task
+                .parent_task_id
+                .and_then(|parent| ids.get(&parent).copied());
+            let hierarchy =
+                tasks_usecase::build_hierarchy_context(txn, user_id, parent_task_id).await?;
+            let inserted = tasks::ActiveModel {
+                uuid: ActiveValue::Set(uuid),
+                title: ActiveValue::Set(task.title),
+                description: ActiveValue::Set(task.description),
+                completed: ActiveValue::Set(task.completed),
+                parent_task_id: ActiveValue::Set(hierarchy.parent_task_id),
+                user_id: ActiveValue::Set(user_id),
+                root_task_id: ActiveValue::Set(hierarchy.root_task_id),
+                depth: ActiveValue::Set(hierarchy.depth),
+                position: ActiveValue::Set(hierarchy.position),
+                due_at: ActiveValue::Set(task.due_at),
+                created_at: ActiveValue::Set(task.created_at),
+                updated_at: ActiveValue::Set(task.updated_at),
+                ..Default::default()
+            }
+            .insert(txn)
+            .await?;
+            let task_id = inserted.id;
+            if hierarchy.root_task_id.is_none() {
+                let mut inserted: tasks::ActiveModel = inserted.into();
+                inserted.root_task_id = ActiveValue::Set(Some(task_id));
+                inserted.update(txn).await?;
+            }
+            tag_task_usecase::attach_tags_with_conn(
+                txn,
+                task_id,
+                mapped_tag_ids(&task.tag_ids, tag_ids),
+            )
+            .await?;
+            sync::record_upsert(txn, SyncEntity::Task, task_id).await?;
+            ids.insert(task.id, task_id);
+            summary.tasks += 1;
+        }
+        pending = rest;
+    }
+    Ok(ids)
+}
+
+async fn import_logs(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    archived: Vec<ArchivedLog>,
+    tag_ids: &HashMap<i32, i32>,
+    task_ids: &HashMap<i32, i32>,
+    summary: &mut ImportSummary,
+) -> Result<(), ServiceError> {
+    let existing: HashSet<String> = Logs::find()
+        .select_only()
+        .column(logs::Column::Uuid)
+        .filter(logs::Column::UserId.eq(user_id))
+        .into_tuple()
+        .all(txn)
+        .await?
+        .into_iter()
+        .collect();
+    for log in archived {
+        let uuid = archived_uuid(&log.uuid);
+        if existing.contains(&uuid) {
+            summary.skipped += 1;
+            continue;
+        }
+        let inserted = logs::ActiveModel {
+            uuid: ActiveValue::Set(uuid),
+            content: ActiveValue::Set(log.content),
+            source: ActiveValue::Set(
+                logs_usecase::LogSource::from(log.source)
+                    .as_str()
+                    .to_string(),
+            ),
+            event_code: ActiveValue::Set(log.event_code),
+            event_params: ActiveValue::Set(log.event_params),
+            task_id: ActiveValue::Set(log.task_id.and_then(|id| task_ids.get(&id).copied())),
+            user_id: ActiveValue::Set(user_id),
+            created_at: ActiveValue::Set(log.created_at),
+            updated_at: ActiveValue::Set(log.updated_at),
+            ..Default::default()
+        }
+        .insert(txn)
+        .await?;
+        let log_tag_ids = mapped_tag_ids(&log.tag_ids, tag_ids);
+        if !log_tag_ids.is_empty() {
+            logs_usecase::attach_tags_to_log(txn, inserted.id, &log_tag_ids).await?;
+        }
+        sync::record_upsert(txn, SyncEntity::Log, inserted.id).await?;
+        summary.logs += 1;
+    }
+    Ok(())
+}
+
+async fn import_sessions(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    archived: Vec<ArchivedSession>,
+    summary: &mut ImportSummary,
+) -> Result<(), ServiceError> {
+    let existing: HashSet<String> = DecoponSessions::find()
+        .select_only()
+        .column(decopon_sessions::Column::Uuid)
+        .filter(decopon_sessions::Column::UserId.eq(user_id))
+        .into_tuple()
+        .all(txn)
+        .await?
+        .into_iter()
+        .collect();
+    for session in archived {
+        let uuid = archived_uuid(&session.uuid);
+        if existing.contains(&uuid) {
+            summary.skipped += 1;
+            continue;
+        }
+        let inserted = decopon_sessions::ActiveModel {
+            uuid: ActiveValue::Set(uuid),
+            status: ActiveValue::Set(session.status),
+            started_at: ActiveValue::Set(session.started_at),
+            ended_at: ActiveValue::Set(session.ended_at),
+            user_id: ActiveValue::Set(user_id),
+            created_at: ActiveValue::Set(session.created_at),
+            updated_at: ActiveValue::Set(session.updated_at),
+            ..Default::default()
+        }
+        .insert(txn)
+        .await?;
+        sync::record_upsert(txn, SyncEntity::DecoponSession, inserted.id).await?;
+        summary.decopon_sessions += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_user, new_user, setup_db};
+    use crate::usecases::{
+        decopon_sessions as sessions_usecase,
+        logs::{LogSource, NewLog, insert_log},
+        tags::{NewTag, insert_tag},
+        tasks::{NewTask, insert_task},
+    };
+    use sea_orm::Set;
+
+    async fn seed(db: &DatabaseConnection, user_id: i32) {
+        let tag = insert_tag(
+            db,
+            NewTag {
+                name: "work".to_string(),
+                user_id,
+            },
+        )
+        .await
+        .unwrap();
+        let parent = insert_task(
+            db,
+            NewTask {
+                title: "parent".to_string(),
+                description: "top".to_string(),
+                parent_task_id: None,
+                tag_ids: Some(vec![tag.id]),
+                due_at: None,
+                user_id,
+            },
+        )
+        .await
+        .unwrap();
+        for title in ["first child", "second child"] {
+            insert_task(
+                db,
+                NewTask {
+                    title: title.to_string(),
+                    description: String::new(),
+                    parent_task_id: Some(parent.id),
+                    tag_ids: None,
+                    due_at: None,
+                    user_id,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        insert_log(
+            db,
+            NewLog {
+                content: "started".to_string(),
+                source: LogSource::User,
+                task_id: Some(parent.id),
+                user_id,
+                tag_ids: vec![tag.id],
+                tag_names: Vec::new(),
+            },
+        )
+        .await
+        .unwrap();
+        sessions_usecase::insert_session(
+            db,
+            sessions_usecase::NewDecoponSession {
+                status: "Completed".to_string(),
+                started_at: Utc::now(),
+                ended_at: Some(Utc::now()),
+                user_id,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    /// ID に依らない中身。取り込み先では ID が振り直されるので UUID と名前で比べる
+    fn contents(archive: &Archive) -> Vec<String> {
+        let tag_names: HashMap<i32, &str> = archive
+            .tags
+            .iter()
+            .map(|tag| (tag.id, tag.name.as_str()))
+            .collect();
+        let task_uuids: HashMap<i32, &str> = archive
+            .tasks
+            .iter()
+            .map(|task| (task.id, task.uuid.as_str()))
+            .collect();
+        let names = |ids: &[i32]| ids.iter().map(|id| tag_names[id]).collect::<Vec<_>>();
+        let mut contents = Vec::new();
+        for tag in &archive.tags {
+            contents.push(format!("tag {} {}", tag.uuid, tag.name));
+        }
+        for task in &archive.tasks {
+            contents.push(format!(
+                "task {} {} {} parent={:?} position={} tags={:?}",
+                task.uuid,
+                task.title,
+                task.completed,
+                task.parent_task_id.map(|id| task_uuids[&id]),
+                task.position,
+                names(&task.tag_ids),
+            ));
+        }
+        for log in &archive.logs {
+            contents.push(format!(
+                "log {} {} task={:?} tags={:?}",
+                log.uuid,
+                log.content,
+                log.task_id.map(|id| task_uuids[&id]),
+                names(&log.tag_ids),
+            ));
+        }
+        for session in &archive.decopon_sessions {
+            contents.push(format!(
+                "session {} {} {}",
+                session.uuid, session.status, session.started_at
+            ));
+        }
+        contents
+    }
+
+    #[tokio::test]
+    async fn round_trips_an_account_between_databases() {
+        let local = setup_db().await;
+        let local_user = create_user(&local, "alice").await;
+        seed(&local, local_user.id).await;
+        let exported = export_archive(&local, local_user.id).await.unwrap();
+        let expected = contents(&exported);
+
+        // 書き出したものは JSON を経由して取り込まれる
+        let json = serde_json::to_string(&exported).unwrap();
+        let web = setup_db().await;
+        // 取り込み先の ID がずれていても対応が保たれるよう、先に別のユーザーの行を作っておく
+        let other = users::ActiveModel {
+            locale: Set("en".to_string()),
+            ..new_user("bob")
+        }
+        .insert(&web)
+        .await
+        .unwrap();
+        seed(&web, other.id).await;
+        let web_user = users::ActiveModel {
+            locale: Set("en".to_string()),
+            ..new_user("alice")
+        }
+        .insert(&web)
+        .await
+        .unwrap();
+
+        let summary = import_archive(&web, web_user.id, serde_json::from_str(&json).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                tags: 1,
+                tasks: 3,
+                logs: 1,
+                decopon_sessions: 1,
+                skipped: 0,
+            }
+        );
+
+        let imported = export_archive(&web, web_user.id).await.unwrap();
+        assert_eq!(contents(&imported), expected);
+        assert_eq!(imported.preferences.locale, "ja");
+
+        // 取り込み直しても行は増えない
+        let again = import_archive(&web, web_user.id, serde_json::from_str(&json).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(again.skipped, 6);
+        assert_eq!(again.tasks, 0);
+        let imported = export_archive(&web, web_user.id).await.unwrap();
+        assert_eq!(contents(&imported), expected);
+    }
+
+    #[tokio::test]
+    async fn rejects_archives_from_newer_versions() {
+        let db = setup_db().await;
+        let user = users::ActiveModel {
+            locale: Set("en".to_string()),
+            ..new_user("alice")
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        let mut archive = export_archive(&db, user.id).await.unwrap();
+        archive.version = ARCHIVE_VERSION + 1;
+
+        let result = import_archive(&db, user.id, archive).await;
+        assert!(
+            matches!(result, Err(ServiceError::Validation(errors)) if errors[0].field == "version")
+        );
+    }
+}
@@ -1,19 +1,25 @@
 use crate::{
     entities::users,
     errors::ServiceError,
+    password::PasswordWorker,
     usecases::{self, mails, users::User},
 };
-use axum_password_worker::{Bcrypt, BcryptConfig, PasswordWorker};
 use chrono::Utc;
 use jsonwebtoken::{EncodingKey, Header, encode};
 use rand::{Rng, distributions::Alphanumeric};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    Set, TransactionTrait,
+};
 use sha2::{Digest, Sha256};
 // JWT claims構造体
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Claims {
     pub sub: i32,   // user_id
     pub exp: usize, // 有効期限 (Unix timestamp)
+    /// 管理者による代理ログインで発行されたトークンの場合は管理者のユーザーID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonated_by: Option<i32>,
 }
 
 pub struct RegisterUserResult {
@@ -27,7 +33,7 @@ pub struct AuthResponse {
 
 pub async fn register_user(
     db: &DatabaseConnection,
-    password_worker: &PasswordWorker<Bcrypt>,
+    password_worker: &PasswordWorker,
     mailer: Option<&mails::Mailer>,
     name: &str,
     email: &str,
@@ -65,14 +71,17 @@ pub async fn register_user(
     if mailer.is_none() {
         user_active.email_verified_at = Set(Some(Utc::now()));
     }
-    let user = user_active.insert(db).await?;
-    if let (Some(mailer), Some(raw_token)) = (mailer, raw_token.as_deref()) {
-        mails::send_verification_email(mailer.clone(), email, raw_token)?;
+    // 確認メールを積めなければ、ユーザーも作らない
+    let txn = db.begin().await?;
+    let user = user_active.insert(&txn).await?;
+    if let Some(raw_token) = raw_token.as_deref() {
+        mails::send_verification_email(&txn, email, &user.locale, raw_token).await?;
     } else {
         tracing::info!(
-            "Skipping verification email because SMTP transport is disabled; marking user as verified"
+            "Skipping verification email because mail transport is disabled; marking user as verified"
         );
     }
+    txn.commit().await?;
 
     Ok(RegisterUserResult { user: user.into() })
 }
@@ -88,22 +97,19 @@ pub async fn get_auth_user_from_token(
         return Err(ServiceError::Unauthorized);
     }
     let user_id = claims.sub;
-    usecases::users::get_user_by_id(&db, user_id).await
+    usecases::users::get_user_by_id(db, user_id).await
 }
 
 pub async fn hash_password(
     password: &str,
-    password_worker: &PasswordWorker<Bcrypt>,
+    password_worker: &PasswordWorker,
 ) -> Result<String, ServiceError> {
-    let hashed_password = password_worker
-        .hash(password, BcryptConfig { cost: 12 }) // costは調整可能
-        .await?;
-    Ok(hashed_password)
+    password_worker.hash(password).await
 }
 
 pub async fn login_user(
     db: &DatabaseConnection,
-    password_worker: &PasswordWorker<Bcrypt>,
+    password_worker: &PasswordWorker,
     jwt_secret: &str,
     email: &str,
     password: &str,
@@ -124,6 +130,16 @@ pub async fn login_user(
         return Err(ServiceError::Unauthorized);
     }
 
+    // 無効化の有無が第三者に漏れないよう、パスワード照合後に判定する
+    if user_full.disabled_at.is_some() {
+        return Err(ServiceError::Forbidden);
+    }
+
+    // 旧アルゴリズム・旧パラメータのハッシュはログイン成功時に再ハッシュして移行する
+    if password_worker.needs_rehash(&user_full.password) {
+        rehash_password(db, password_worker, user_full.id, password).await;
+    }
+
     // JWTトークンを生成
     let token = create_jwt(user_full.id, jwt_secret)?;
     let user: User = user_full.into();
@@ -131,6 +147,32 @@ pub async fn login_user(
     Ok(AuthResponse { token, user })
 }
 
+/// 再ハッシュの失敗はログインを妨げないよう警告ログのみに留める。
+async fn rehash_password(
+    db: &DatabaseConnection,
+    password_worker: &PasswordWorker,
+    user_id: i32,
+    password: &str,
+) {
+    let result = async {
+        let hashed_password = hash_password(password, password_worker).await?;
+        users::ActiveModel {
+            id: Set(user_id),
+            password: Set(hashed_password),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+        Ok::<_, ServiceError>(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => tracing::info!(user_id, "rehashed password with current parameters"),
+        Err(e) => tracing::warn!(error = %e, user_id, "failed to rehash password"),
+    }
+}
+
 #[tracing::instrument(skip(db, jwt_secret, token))]
 pub async fn verify_email(
     db: &DatabaseConnection,
@@ -165,7 +207,7 @@ pub async fn verify_email(
 }
 
 pub async fn forgot_password(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     mailer: Option<&mails::Mailer>,
     email: &str,
 ) -> Result<(), ServiceError> {
@@ -183,20 +225,19 @@ pub async fn forgot_password(
     let hashed = hash_token(&raw_token);
     let mut user_active: users::ActiveModel = user.into();
     user_active.verification_token = Set(Some(hashed));
-    user_active.update(db).await?;
+    let user = user_active.update(db).await?;
 
-    let body = format!("Reset token: {}", raw_token);
-    if let Some(mailer) = mailer {
-        mails::send_plain_text(mailer.clone(), email, "Reset your password", &body)?;
+    if mailer.is_some() {
+        mails::send_password_reset_email(db, &user.email, &user.locale, &raw_token).await?;
     } else {
-        tracing::info!("Skipping password reset email because SMTP transport is disabled");
+        tracing::info!("Skipping password reset email because mail transport is disabled");
     }
     Ok(())
 }
 
 pub async fn reset_password(
     db: &DatabaseConnection,
-    password_worker: &PasswordWorker<Bcrypt>,
+    password_worker: &PasswordWorker,
     token: &str,
     email: &str,
     password: &str,
@@ -219,7 +260,7 @@ pub async fn reset_password(
 
 pub async fn confirm_password(
     db: &DatabaseConnection,
-    password_worker: &PasswordWorker<Bcrypt>,
+    password_worker: &PasswordWorker,
     jwt_secret: &str,
     token: &str,
     password: &str,
@@ -243,7 +284,7 @@ pub async fn confirm_password(
 }
 
 pub async fn resend_verification(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     mailer: Option<&mails::Mailer>,
     email: &str,
 ) -> Result<(), ServiceError> {
@@ -268,32 +309,53 @@ pub async fn resend_verification(
     user_active.verification_token = Set(Some(hashed));
     let user = user_active.update(db).await?;
 
-    if let Some(mailer) = mailer {
-        mails::send_verification_email(mailer.clone(), &user.email, &raw_token)?;
+    if mailer.is_some() {
+        mails::send_verification_email(db, &user.email, &user.locale, &raw_token).await?;
     } else {
-        tracing::info!("Skipping verification email resend because SMTP transport is disabled");
+        tracing::info!("Skipping verification email resend because mail transport is disabled");
     }
 
     Ok(())
 }
 
-fn hash_token(token: &str) -> String {
+pub(crate) fn hash_token(token: &str) -> String {
     format!("{:x}", Sha256::digest(token.as_bytes()))
 }
 
 pub async fn verify_password(
     password: &str,
     hashed_password: &str,
-    password_worker: &PasswordWorker<Bcrypt>,
+    password_worker: &PasswordWorker,
 ) -> Result<bool, ServiceError> {
-    let is_valid = password_worker.verify(password, hashed_password).await?;
-    Ok(is_valid)
+    password_worker.verify(password, hashed_password).await
 }
 
 pub fn create_jwt(user_id: i32, secret: &str) -> Result<String, ServiceError> {
     let claims = Claims {
         sub: user_id,
         exp: (Utc::now() + chrono::Duration::days(30)).timestamp() as usize, // 30日間有効
+        impersonated_by: None,
+    };
+
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )?;
+    Ok(token)
+}
+
+/// 管理者がユーザーとして閲覧するための短命なトークンを発行する。
+/// `impersonated_by` を持つトークンは認証ミドルウェアで読み取り専用として扱われる。
+pub fn create_impersonation_jwt(
+    user_id: i32,
+    admin_id: i32,
+    secret: &str,
+) -> Result<String, ServiceError> {
+    let claims = Claims {
+        sub: user_id,
+        exp: (Utc::now() + chrono::Duration::hours(1)).timestamp() as usize, // 1時間有効
+        impersonated_by: Some(admin_id),
     };
 
     let token = encode(
@@ -323,13 +385,27 @@ pub fn verify_jwt(claims: &Claims) -> Result<bool, ServiceError> {
 #[cfg(test)]
 mod auth_tests {
     use super::*;
-    use axum_password_worker::PasswordWorker;
+    use crate::password::PasswordHashConfig;
     use chrono::Utc;
     use jsonwebtoken::{EncodingKey, Header};
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::Database;
+
+    fn test_worker() -> PasswordWorker {
+        PasswordWorker::new(
+            1,
+            PasswordHashConfig::Argon2id {
+                memory_kib: 1024,
+                iterations: 1,
+                parallelism: 1,
+            },
+        )
+        .unwrap()
+    }
 
     #[tokio::test]
     async fn hash_password_and_verify_success() {
-        let worker = PasswordWorker::new_bcrypt(4).unwrap();
+        let worker = test_worker();
         let password = "secret";
         let hashed = hash_password(password, &worker).await.unwrap();
         assert_ne!(password, hashed);
@@ -338,11 +414,51 @@ mod auth_tests {
 
     #[tokio::test]
     async fn verify_password_mismatch() {
-        let worker = PasswordWorker::new_bcrypt(4).unwrap();
+        let worker = test_worker();
         let hashed = hash_password("secret", &worker).await.unwrap();
         assert!(!verify_password("wrong", &hashed, &worker).await.unwrap());
     }
 
+    #[tokio::test]
+    async fn login_rehashes_legacy_bcrypt_password() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let legacy_worker = PasswordWorker::new(1, PasswordHashConfig::Bcrypt { cost: 4 }).unwrap();
+        let legacy_hash = hash_password("secret", &legacy_worker).await.unwrap();
+        let user = users::ActiveModel {
+            name: Set("Legacy".into()),
+            email: Set("legacy@example.com".into()),
+            email_verified_at: Set(Some(Utc::now())),
+            password: Set(legacy_hash.clone()),
+            work_time: Set(25),
+            break_time: Set(5),
+            locale: Set("en".into()),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        let worker = test_worker();
+        login_user(&db, &worker, "secret", "legacy@example.com", "secret")
+            .await
+            .unwrap();
+
+        let stored = users::Entity::find_by_id(user.id)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_ne!(stored.password, legacy_hash);
+        assert!(stored.password.starts_with("$argon2id$"));
+        assert!(!worker.needs_rehash(&stored.password));
+
+        // 再ハッシュ後も同じパスワードでログインできる
+        login_user(&db, &worker, "secret", "legacy@example.com", "secret")
+            .await
+            .unwrap();
+    }
+
     #[test]
     fn create_and_decode_jwt_success() {
         let secret = "secret";
@@ -358,6 +474,7 @@ mod auth_tests {
         let expired_claims = Claims {
             sub: 1,
             exp: (Utc::now() - chrono::Duration::seconds(1)).timestamp() as usize,
+            impersonated_by: None,
         };
         let token = jsonwebtoken::encode(
             &Header::default(),
@@ -380,21 +497,16 @@ mod auth_tests {
 #[cfg(all(test, feature = "mail"))]
 mod tests {
     use super::*;
+    use crate::mail_transport::SmtpMailTransport;
     use chrono::Utc;
+    use lettre::SmtpTransport;
     use migration::{Migrator, MigratorTrait};
-    use sea_orm::{ActiveModelTrait, Database, Set};
+    use sea_orm::{ActiveModelTrait, ConnectionTrait, Database, Set};
     use std::process::{Child, Command};
     use std::sync::Arc;
     use tokio::time::{Duration, sleep};
 
-    async fn setup(
-        port: u16,
-    ) -> (
-        DatabaseConnection,
-        PasswordWorker<Bcrypt>,
-        mails::Mailer,
-        Child,
-    ) {
+    async fn setup(port: u16) -> (DatabaseConnection, PasswordWorker, mails::Mailer, Child) {
         // 環境変数設定（メール送信元）
         unsafe {
             std::env::set_var("AXUM_MAIL_FROM_EMAIL", "from@example.com");
@@ -416,13 +528,18 @@ mod tests {
         // サーバ起動に時間がかかる環境があるため待ち時間を延長
         sleep(Duration::from_millis(500)).await;
 
-        let mailer = Arc::new(
-            SmtpTransport::builder_dangerous("127.0.0.1")
-                .port(port)
-                .build(),
+        let mailer: mails::Mailer = Arc::new(
+            SmtpMailTransport::new(
+                SmtpTransport::builder_dangerous("127.0.0.1")
+                    .port(port)
+                    .build(),
+            )
+            .unwrap(),
         );
 
-        let password_worker = PasswordWorker::new_bcrypt(4).unwrap();
+        let password_worker =
+            PasswordWorker::new(1, crate::password::PasswordHashConfig::Bcrypt { cost: 4 })
+                .unwrap();
 
         // メモリDBを作成しマイグレーション
         let db = Database::connect("sqlite::memory:")
@@ -480,6 +597,33 @@ mod tests {
         child.kill().ok();
     }
 
+    #[tokio::test]
+    async fn register_user_rolls_back_when_mail_cannot_be_queued() {
+        let (db, pw, mailer, mut child) = setup(3131).await;
+        db.execute_unprepared("DROP TABLE mail_outbox")
+            .await
+            .unwrap();
+
+        let res = register_user(
+            &db,
+            &pw,
+            Some(&mailer),
+            "Erin",
+            "erin@example.com",
+            "password",
+        )
+        .await;
+        assert!(res.is_err());
+        let user = users::Entity::find()
+            .filter(users::Column::Email.eq("erin@example.com"))
+            .one(&db)
+            .await
+            .unwrap();
+        assert!(user.is_none());
+
+        child.kill().ok();
+    }
+
     #[tokio::test]
     async fn confirm_password_success() {
         let (db, pw, _mailer, mut child) = setup(2727).await;
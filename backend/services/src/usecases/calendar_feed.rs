@@ -0,0 +1,302 @@
+//! カレンダーアプリから購読する iCalendar (ICS) のフィード。
+//! 終わったセッションと進行中のセッションを VEVENT に、期限のあるタスクを VTODO にする。
+//! 購読するアプリは認証ヘッダを送れないので、URL に含めた秘密のトークンでユーザーを特定する。
+//! トークンは PAT と同じくハッシュだけを保存し、作り直すと古い URL は使えなくなる。
+
+use chrono::{DateTime, Duration, Utc};
+use rand::{Rng, distributions::Alphanumeric};
+use sea_orm::prelude::Expr;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+
+use crate::entities::{decopon_sessions, prelude::*, tags, tasks, users};
+use crate::errors::ServiceError;
+
+use super::{auth::hash_token, mails};
+
+const TOKEN_PREFIX: &str = "dcal_";
+const TOKEN_RANDOM_LENGTH: usize = 40;
+/// フィードに載せるセッションの期間
+const SESSION_WINDOW_DAYS: i64 = 180;
+const PUBLISHED_STATUSES: [&str; 2] = ["Completed", "In_Progress"];
+
+/// 作り直したトークンと、それを含む購読用の URL
+pub struct CalendarFeedToken {
+    pub token: String,
+    pub url: String,
+}
+
+pub async fn is_enabled(db: &DatabaseConnection, user_id: i32) -> Result<bool, ServiceError> {
+    let user = Users::find_by_id(user_id)
+        .one(db)
+        .await?
+        .ok_or(ServiceError::NotFound("user"))?;
+    Ok(user.calendar_token_hash.is_some())
+}
+
+/// 新しいトークンを発行する。以前の URL は使えなくなる
+pub async fn regenerate_token(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> Result<CalendarFeedToken, ServiceError> {
+    let user = Users::find_by_id(user_id)
+        .one(db)
+        .await?
+        .ok_or(ServiceError::NotFound("user"))?;
+    let random: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_RANDOM_LENGTH)
+        .map(char::from)
+        .collect();
+    let token = format!("{TOKEN_PREFIX}{random}");
+    let mut user: users::ActiveModel = user.into();
+    user.calendar_token_hash = ActiveValue::Set(Some(hash_token(&token)));
+    user.update(db).await?;
+    let url = mails::public_link(&["calendar", &format!("{token}.ics")], &[])?;
+    Ok(CalendarFeedToken { token, url })
+}
+
+/// フィードを止める
+pub async fn revoke_token(db: &DatabaseConnection, user_id: i32) -> Result<(), ServiceError> {
+    Users::update_many()
+        .col_expr(
+            users::Column::CalendarTokenHash,
+            Expr::value(Option::<String>::None),
+        )
+        .filter(users::Column::Id.eq(user_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// トークンに対応するユーザーのフィードを返す。末尾の `.ics` は省略できる
+pub async fn render_feed(db: &DatabaseConnection, token: &str) -> Result<String, ServiceError> {
+    let token = token.strip_suffix(".ics").unwrap_or(token);
+    if !token.starts_with(TOKEN_PREFIX) {
+        return Err(ServiceError::NotFound("calendar_feed"));
+    }
+    let user = Users::find()
+        .filter(users::Column::CalendarTokenHash.eq(hash_token(token)))
+        .filter(users::Column::DisabledAt.is_null())
+        .one(db)
+        .await?
+        .ok_or(ServiceError::NotFound("calendar_feed"))?;
+
+    let sessions = DecoponSessions::find()
+        .filter(decopon_sessions::Column::UserId.eq(user.id))
+        .filter(decopon_sessions::Column::Status.is_in(PUBLISHED_STATUSES))
+        .filter(
+            decopon_sessions::Column::StartedAt
+                .gte(Utc::now() - Duration::days(SESSION_WINDOW_DAYS)),
+        )
+        .order_by_asc(decopon_sessions::Column::StartedAt)
+        .all(db)
+        .await?;
+    let tasks = Tasks::find()
+        .filter(tasks::Column::UserId.eq(user.id))
+        .filter(tasks::Column::DueAt.is_not_null())
+        .order_by_asc(tasks::Column::DueAt)
+        .find_with_related(Tags)
+        .all(db)
+        .await?;
+    Ok(render(&sessions, &tasks, Utc::now()))
+}
+
+fn render(
+    sessions: &[decopon_sessions::Model],
+    tasks: &[(tasks::Model, Vec<tags::Model>)],
+    now: DateTime<Utc>,
+) -> String {
+    let mut calendar = Calendar::default();
+    calendar.line("BEGIN:VCALENDAR");
+    calendar.line("VERSION:2.0");
+    calendar.line("PRODID:-//Decopon//Decopon//EN");
+    calendar.line("CALSCALE:GREGORIAN");
+    calendar.line("METHOD:PUBLISH");
+    calendar.line("X-WR-CALNAME:Decopon");
+
+    for session in sessions {
+        let in_progress = session.status == "In_Progress";
+        // 進行中のセッションは、今の時点までを予定として見せる
+        let ended_at = session.ended_at.unwrap_or(now).max(session.started_at);
+        calendar.line("BEGIN:VEVENT");
+        calendar.line(&format!("UID:{}@decopon", session.uuid));
+        calendar.line(&format!("DTSTAMP:{}", timestamp(session.updated_at)));
+        calendar.line(&format!("DTSTART:{}", timestamp(session.started_at)));
+        calendar.line(&format!("DTEND:{}", timestamp(ended_at)));
+        calendar.line(if in_progress {
+            "SUMMARY:Decopon (in progress)"
+        } else {
+            "SUMMARY:Decopon"
+        });
+        calendar.line(if in_progress {
+            "STATUS:TENTATIVE"
+        } else {
+            "STATUS:CONFIRMED"
+        });
+        calendar.line("TRANSP:OPAQUE");
+        calendar.line("END:VEVENT");
+    }
+
+    for (task, tags) in tasks {
+        let Some(due_at) = task.due_at else {
+            continue;
+        };
+        calendar.line("BEGIN:VTODO");
+        calendar.line(&format!("UID:{}@decopon", task.uuid));
+        calendar.line(&format!("DTSTAMP:{}", timestamp(task.updated_at)));
+        calendar.line(&format!("DUE:{}", timestamp(due_at)));
+        calendar.line(&format!("SUMMARY:{}", escape_text(&task.title)));
+        if !task.description.trim().is_empty() {
+            calendar.line(&format!("DESCRIPTION:{}", escape_text(&task.description)));
+        }
+        if !tags.is_empty() {
+            let categories = tags
+                .iter()
+                .map(|tag| escape_text(&tag.name))
+                .collect::<Vec<_>>()
+                .join(",");
+            calendar.line(&format!("CATEGORIES:{categories}"));
+        }
+        calendar.line(if task.completed {
+            "STATUS:COMPLETED"
+        } else {
+            "STATUS:NEEDS-ACTION"
+        });
+        calendar.line("END:VTODO");
+    }
+
+    calendar.line("END:VCALENDAR");
+    calendar.0
+}
+
+/// 行を CRLF で区切り、75 オクテットを超える行は折り返す (RFC 5545 3.1)
+#[derive(Default)]
+struct Calendar(String);
+
+impl Calendar {
+    fn line(&mut self, line: &str) {
+        let mut width = 0;
+        for c in line.chars() {
+            if width + c.len_utf8() > 75 {
+                self.0.push_str("\r\n ");
+                width = 1;
+            }
+            self.0.push(c);
+            width += c.len_utf8();
+        }
+        self.0.push_str("\r\n");
+    }
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 10, 31, hour, minute, 0).unwrap()
+    }
+
+    fn session(
+        uuid: &str,
+        status: &str,
+        ended_at: Option<DateTime<Utc>>,
+    ) -> decopon_sessions::Model {
+        decopon_sessions::Model {
+            id: 1,
+            status: status.to_string(),
+            started_at: at(9, 0),
+            ended_at,
+            created_at: at(9, 0),
+            updated_at: at(9, 25),
+            user_id: 1,
+            version: 1,
+            uuid: uuid.to_string(),
+        }
+    }
+
+    #[test]
+    fn renders_sessions_and_due_tasks() {
+        let task = tasks::Model {
+            id: 1,
+            title: "Write report; draft, then send".to_string(),
+            description: "line one\nline two".to_string(),
+            completed: false,
+            created_at: at(8, 0),
+            updated_at: at(8, 0),
+            user_id: 1,
+            root_task_id: Some(1),
+            depth: 0,
+            position: 0,
+            parent_task_id: None,
+            version: 1,
+            uuid: "task-uuid".to_string(),
+            due_at: Some(at(17, 0)),
+        };
+        let tag = tags::Model {
+            id: 1,
+            name: "work".to_string(),
+            created_at: at(8, 0),
+            updated_at: at(8, 0),
+            user_id: 1,
+            uuid: "tag-uuid".to_string(),
+            version: 1,
+        };
+        let ics = render(
+            &[
+                session("done", "Completed", Some(at(9, 25))),
+                session("running", "In_Progress", None),
+            ],
+            &[(task, vec![tag])],
+            at(9, 10),
+        );
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains(
+            "BEGIN:VEVENT\r\nUID:done@decopon\r\nDTSTAMP:20251031T092500Z\r\n\
+             DTSTART:20251031T090000Z\r\nDTEND:20251031T092500Z\r\nSUMMARY:Decopon\r\n"
+        ));
+        assert!(ics.contains("UID:running@decopon\r\nDTSTAMP:20251031T092500Z\r\nDTSTART:20251031T090000Z\r\nDTEND:20251031T091000Z\r\n"));
+        assert!(ics.contains(
+            "BEGIN:VTODO\r\nUID:task-uuid@decopon\r\nDTSTAMP:20251031T080000Z\r\n\
+             DUE:20251031T170000Z\r\nSUMMARY:Write report\\; draft\\, then send\r\n\
+             DESCRIPTION:line one\\nline two\r\nCATEGORIES:work\r\nSTATUS:NEEDS-ACTION\r\n"
+        ));
+    }
+
+    #[test]
+    fn folds_long_lines_without_splitting_characters() {
+        let mut calendar = Calendar::default();
+        calendar.line(&format!("SUMMARY:{}", "あ".repeat(30)));
+        let lines = calendar.0.split("\r\n").collect::<Vec<_>>();
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert_eq!(lines[1], format!(" {}", "あ".repeat(8)));
+        assert_eq!(
+            lines.concat().replace(" ", ""),
+            format!("SUMMARY:{}", "あ".repeat(30))
+        );
+    }
+}
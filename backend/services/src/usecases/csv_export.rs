@@ -0,0 +1,402 @@
+//! セッションとログを表計算ソフトで扱える CSV に書き出す。
+//! 全件を読み込まないよう、ID の順に一定件数ずつ読み、ページごとに CSV の断片を返す。
+//! 呼び出し側は `next` が `None` になるまで続きを読み、断片をそのまま連結して送る。
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use sea_orm::prelude::Expr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, LoaderTrait, QueryFilter, QueryOrder,
+    QuerySelect, QueryTrait,
+};
+
+use crate::entities::{decopon_sessions, log_tag, logs, prelude::*, tasks};
+use crate::errors::ServiceError;
+
+use super::logs::{Log, user_locale};
+
+/// 一度に読み込む行の数
+pub const PAGE_SIZE: u64 = 500;
+
+/// 書き出す期間。どちらも UTC の日付で、両端を含む
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExportRange {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl ExportRange {
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        match (self.from, self.to) {
+            (Some(from), Some(to)) if from > to => Err(ServiceError::invalid_field(
+                "from",
+                "after_to",
+                "from must not be after to",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn start(&self) -> Option<DateTime<Utc>> {
+        self.from
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|at| at.and_utc())
+    }
+
+    /// `to` の翌日の始まり。これより前を含める
+    fn end(&self) -> Option<DateTime<Utc>> {
+        self.to
+            .and_then(|date| date.checked_add_days(Days::new(1)))
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|at| at.and_utc())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LogExportFilters {
+    pub range: ExportRange,
+    /// すべてのタグが付いたログだけを書き出す
+    pub tag_ids: Vec<i32>,
+}
+
+/// CSV の断片と、続きを読むときに渡す位置
+pub struct CsvPage {
+    pub csv: Vec<u8>,
+    pub next: Option<i32>,
+}
+
+const SESSION_HEADER: [&str; 6] = [
+    "id",
+    "uuid",
+    "status",
+    "started_at",
+    "ended_at",
+    "duration_minutes",
+];
+
+const LOG_HEADER: [&str; 8] = [
+    "id",
+    "uuid",
+    "created_at",
+    "source",
+    "content",
+    "task_id",
+    "task_title",
+    "tags",
+];
+
+/// セッションを書き出す。`after` が `None` なら見出しの行から始める。
+/// セッションにはタスクやタグが紐付かないので、期間だけで絞り込む
+pub async fn sessions_page(
+    db: &DatabaseConnection,
+    user_id: i32,
+    range: ExportRange,
+    after: Option<i32>,
+) -> Result<CsvPage, ServiceError> {
+    let mut query = DecoponSessions::find()
+        .filter(decopon_sessions::Column::UserId.eq(user_id))
+        .filter(decopon_sessions::Column::Id.gt(after.unwrap_or(0)));
+    if let Some(start) = range.start() {
+        query = query.filter(decopon_sessions::Column::StartedAt.gte(start));
+    }
+    if let Some(end) = range.end() {
+        query = query.filter(decopon_sessions::Column::StartedAt.lt(end));
+    }
+    let sessions = query
+        .order_by_asc(decopon_sessions::Column::Id)
+        .limit(PAGE_SIZE)
+        .all(db)
+        .await?;
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if after.is_none() {
+        writer.write_record(SESSION_HEADER).map_err(internal)?;
+    }
+    for session in &sessions {
+        let duration = session
+            .ended_at
+            .map(|ended_at| {
+                format!(
+                    "{:.1}",
+                    (ended_at - session.started_at).num_seconds() as f64 / 60.0
+                )
+            })
+            .unwrap_or_default();
+        writer
+            .write_record([
+                session.id.to_string(),
+                session.uuid.clone(),
+                session.status.clone(),
+                session.started_at.to_rfc3339(),
+                session
+                    .ended_at
+                    .map(|at| at.to_rfc3339())
+                    .unwrap_or_default(),
+                duration,
+            ])
+            .map_err(internal)?;
+    }
+    finish(writer, next_cursor(&sessions, |session| session.id))
+}
+
+/// ログを書き出す。`after` が `None` なら見出しの行から始める
+pub async fn logs_page(
+    db: &DatabaseConnection,
+    user_id: i32,
+    filters: &LogExportFilters,
+    after: Option<i32>,
+) -> Result<CsvPage, ServiceError> {
+    let mut query = Logs::find()
+        .filter(logs::Column::UserId.eq(user_id))
+        .filter(logs::Column::Id.gt(after.unwrap_or(0)));
+    if let Some(start) = filters.range.start() {
+        query = query.filter(logs::Column::CreatedAt.gte(start));
+    }
+    if let Some(end) = filters.range.end() {
+        query = query.filter(logs::Column::CreatedAt.lt(end));
+    }
+    let mut tag_ids = filters.tag_ids.clone();
+    tag_ids.sort_unstable();
+    tag_ids.dedup();
+    if !tag_ids.is_empty() {
+        let with_all_tags = LogTag::find()
+            .select_only()
+            .column(log_tag::Column::LogId)
+            .filter(log_tag::Column::TagId.is_in(tag_ids.clone()))
+            .group_by(log_tag::Column::LogId)
+            .having(
+                Expr::col(log_tag::Column::TagId)
+                    .count_distinct()
+                    .eq(tag_ids.len() as i64),
+            )
+            .into_query();
+        query = query.filter(logs::Column::Id.in_subquery(with_all_tags));
+    }
+    // 結合した行に上限がかからないよう、ログを読んでからタグをまとめて読む
+    let rows = query
+        .order_by_asc(logs::Column::Id)
+        .limit(PAGE_SIZE)
+        .all(db)
+        .await?;
+    let next = next_cursor(&rows, |log| log.id);
+    let tags = rows.load_many_to_many(Tags, LogTag, db).await?;
+
+    let task_ids = rows
+        .iter()
+        .filter_map(|log| log.task_id)
+        .collect::<Vec<_>>();
+    let titles: HashMap<i32, String> = if task_ids.is_empty() {
+        HashMap::new()
+    } else {
+        Tasks::find()
+            .select_only()
+            .column(tasks::Column::Id)
+            .column(tasks::Column::Title)
+            .filter(tasks::Column::Id.is_in(task_ids))
+            .into_tuple::<(i32, String)>()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect()
+    };
+    let locale = user_locale(db, user_id).await?;
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if after.is_none() {
+        writer.write_record(LOG_HEADER).map_err(internal)?;
+    }
+    for (model, tags) in rows.into_iter().zip(tags) {
+        let log = Log::from_model(model, tags, locale);
+        let task_title = log
+            .task_id
+            .and_then(|task_id| titles.get(&task_id))
+            .map(|title| cell(title))
+            .unwrap_or_default();
+        let tags = log
+            .tags
+            .iter()
+            .map(|tag| tag.name.as_str())
+            .collect::<Vec<_>>()
+            .join("; ");
+        writer
+            .write_record([
+                log.id.to_string(),
+                log.uuid,
+                log.created_at.to_rfc3339(),
+                log.source.as_str().to_string(),
+                cell(&log.content),
+                log.task_id.map(|id| id.to_string()).unwrap_or_default(),
+                task_title,
+                cell(&tags),
+            ])
+            .map_err(internal)?;
+    }
+    finish(writer, next)
+}
+
+fn next_cursor<T>(rows: &[T], id: impl Fn(&T) -> i32) -> Option<i32> {
+    if rows.len() as u64 == PAGE_SIZE {
+        rows.last().map(id)
+    } else {
+        None
+    }
+}
+
+fn finish(writer: csv::Writer<Vec<u8>>, next: Option<i32>) -> Result<CsvPage, ServiceError> {
+    let csv = writer
+        .into_inner()
+        .map_err(|error| ServiceError::Internal(Box::new(error.into_error())))?;
+    Ok(CsvPage { csv, next })
+}
+
+fn internal(error: csv::Error) -> ServiceError {
+    ServiceError::Internal(Box::new(error))
+}
+
+/// 表計算ソフトが数式として評価しないよう、記号で始まる文字列の先頭に `'` を付ける
+fn cell(text: &str) -> String {
+    if text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{text}")
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_user, setup_db};
+    use crate::usecases::{
+        decopon_sessions::{NewDecoponSession, insert_session},
+        logs::{LogSource, NewLog, insert_log},
+        tasks::{NewTask, insert_task},
+    };
+    use chrono::TimeZone;
+
+    async fn collect<F, Fut>(mut page: F) -> String
+    where
+        F: FnMut(Option<i32>) -> Fut,
+        Fut: Future<Output = Result<CsvPage, ServiceError>>,
+    {
+        let mut out = Vec::new();
+        let mut after = None;
+        loop {
+            let next = page(after).await.unwrap();
+            out.extend(next.csv);
+            match next.next {
+                Some(cursor) => after = Some(cursor),
+                None => break,
+            }
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[tokio::test]
+    async fn exports_sessions_within_the_range_across_pages() {
+        let db = setup_db().await;
+        let user_id = create_user(&db, "exporter").await.id;
+        let day = |d: u32| Utc.with_ymd_and_hms(2025, 10, d, 9, 0, 0).unwrap();
+        for d in [1, 2, 3] {
+            insert_session(
+                &db,
+                NewDecoponSession {
+                    status: "Completed".to_string(),
+                    started_at: day(d),
+                    ended_at: Some(day(d) + chrono::Duration::seconds(25 * 60 + 30)),
+                    user_id,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        for _ in 0..PAGE_SIZE {
+            insert_session(
+                &db,
+                NewDecoponSession {
+                    status: "In_Progress".to_string(),
+                    started_at: day(2),
+                    ended_at: None,
+                    user_id,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        let range = ExportRange {
+            from: NaiveDate::from_ymd_opt(2025, 10, 2),
+            to: NaiveDate::from_ymd_opt(2025, 10, 2),
+        };
+
+        let csv = collect(|after| sessions_page(&db, user_id, range, after)).await;
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1 + 1 + PAGE_SIZE as usize);
+        assert_eq!(
+            lines[0],
+            "id,uuid,status,started_at,ended_at,duration_minutes"
+        );
+        assert!(lines[1].starts_with("2,"));
+        assert!(
+            lines[1]
+                .ends_with(",Completed,2025-10-02T09:00:00+00:00,2025-10-02T09:25:30+00:00,25.5")
+        );
+        assert!(lines[2].ends_with(",In_Progress,2025-10-02T09:00:00+00:00,,"));
+    }
+
+    #[tokio::test]
+    async fn exports_logs_with_task_titles_and_all_requested_tags() {
+        let db = setup_db().await;
+        let user_id = create_user(&db, "exporter").await.id;
+        let task = insert_task(
+            &db,
+            NewTask {
+                title: "=Budget".to_string(),
+                description: String::new(),
+                parent_task_id: None,
+                tag_ids: None,
+                due_at: None,
+                user_id,
+            },
+        )
+        .await
+        .unwrap();
+        let mut tag_ids = Vec::new();
+        for (content, tags) in [
+            ("both, tagged", vec!["work", "focus"]),
+            ("only work", vec!["work"]),
+        ] {
+            let log = insert_log(
+                &db,
+                NewLog {
+                    content: content.to_string(),
+                    source: LogSource::User,
+                    task_id: Some(task.id),
+                    user_id,
+                    tag_ids: Vec::new(),
+                    tag_names: tags.into_iter().map(String::from).collect(),
+                },
+            )
+            .await
+            .unwrap();
+            if tag_ids.is_empty() {
+                tag_ids = log.tags.iter().map(|tag| tag.id).collect();
+            }
+        }
+        let filters = LogExportFilters {
+            range: ExportRange::default(),
+            tag_ids,
+        };
+
+        let csv = collect(|after| logs_page(&db, user_id, &filters, after)).await;
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "id,uuid,created_at,source,content,task_id,task_title,tags"
+        );
+        assert!(lines[1].ends_with(&format!(
+            ",User,\"both, tagged\",{},'=Budget,work; focus",
+            task.id
+        )));
+    }
+}
@@ -1,13 +1,16 @@
 use crate::{
     entities::{decopon_sessions, prelude::*},
     errors::ServiceError,
+    events::DomainEvent,
 };
 
+use super::sync::{self, SyncEntity};
+
 use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use sea_orm::prelude::DateTimeUtc;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, DeleteResult, EntityTrait,
-    PaginatorTrait, QueryFilter,
+    ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, DeleteResult, EntityTrait,
+    PaginatorTrait, QueryFilter, TransactionTrait,
 };
 
 pub struct NewDecoponSession {
@@ -22,28 +25,34 @@ pub struct DecoponSessionUpdate {
     pub status: Option<String>,
     pub ended_at: Option<DateTimeUtc>,
     pub user_id: i32,
+    /// 指定されていれば、保存されている版と一致する場合だけ更新する
+    pub expected_version: Option<i32>,
 }
 
 pub struct DecoponSession {
     pub id: i32,
+    pub uuid: String,
     pub status: String,
     pub started_at: DateTimeUtc,
     pub ended_at: Option<DateTimeUtc>,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
     pub user_id: i32,
+    pub version: i32,
 }
 
 impl From<decopon_sessions::Model> for DecoponSession {
     fn from(model: decopon_sessions::Model) -> Self {
         Self {
             id: model.id,
+            uuid: model.uuid,
             status: model.status,
             started_at: model.started_at,
             ended_at: model.ended_at,
             created_at: model.created_at,
             updated_at: model.updated_at,
             user_id: model.user_id,
+            version: model.version,
         }
     }
 }
@@ -60,7 +69,7 @@ pub async fn get_sessions(
 }
 
 pub async fn get_session_by_id(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     id: i32,
     user_id: i32,
 ) -> Result<DecoponSession, ServiceError> {
@@ -73,7 +82,7 @@ pub async fn get_session_by_id(
 }
 
 pub async fn insert_session(
-    db: &DatabaseConnection,
+    db: &(impl ConnectionTrait + TransactionTrait),
     params: NewDecoponSession,
 ) -> Result<DecoponSession, ServiceError> {
     let new_session = decopon_sessions::ActiveModel {
@@ -83,7 +92,10 @@ pub async fn insert_session(
         user_id: ActiveValue::Set(params.user_id),
         ..Default::default()
     };
-    let result = DecoponSessions::insert(new_session).exec(db).await?;
+    let txn = db.begin().await?;
+    let result = DecoponSessions::insert(new_session).exec(&txn).await?;
+    sync::record_upsert(&txn, SyncEntity::DecoponSession, result.last_insert_id).await?;
+    txn.commit().await?;
     let session = DecoponSessions::find_by_id(result.last_insert_id)
         .one(db)
         .await?
@@ -92,7 +104,7 @@ pub async fn insert_session(
 }
 
 pub async fn update_session(
-    db: &DatabaseConnection,
+    db: &(impl ConnectionTrait + TransactionTrait),
     params: DecoponSessionUpdate,
 ) -> Result<DecoponSession, ServiceError> {
     let DecoponSessionUpdate {
@@ -100,13 +112,18 @@ pub async fn update_session(
         status,
         ended_at,
         user_id,
+        expected_version,
     } = params;
-    let mut session: decopon_sessions::ActiveModel = DecoponSessions::find_by_id(id)
+    let current = DecoponSessions::find_by_id(id)
         .filter(decopon_sessions::Column::UserId.eq(user_id))
         .one(db)
         .await?
-        .ok_or(ServiceError::NotFound("decopon_session"))?
-        .into();
+        .ok_or(ServiceError::NotFound("decopon_session"))?;
+    if expected_version.is_some_and(|version| version != current.version) {
+        return Err(ServiceError::PreconditionFailed("decopon_session"));
+    }
+    let current_version = current.version;
+    let mut session: decopon_sessions::ActiveModel = current.into();
 
     if let Some(status) = status {
         session.status = ActiveValue::Set(status);
@@ -115,25 +132,57 @@ pub async fn update_session(
         session.ended_at = ActiveValue::Set(Some(ended_at));
     }
     session.updated_at = ActiveValue::Set(Utc::now());
-    let session = session.update(db).await?;
-    Ok(session.into())
+    session.version = ActiveValue::Set(current_version + 1);
+    let txn = db.begin().await?;
+    let updated = DecoponSessions::update_many()
+        .set(session)
+        .filter(decopon_sessions::Column::Id.eq(id))
+        .filter(decopon_sessions::Column::Version.eq(current_version))
+        .exec(&txn)
+        .await?;
+    if updated.rows_affected == 0 {
+        return Err(ServiceError::PreconditionFailed("decopon_session"));
+    }
+    sync::record_upsert(&txn, SyncEntity::DecoponSession, id).await?;
+    txn.commit().await?;
+    get_session_by_id(db, id, user_id).await
 }
 
 pub async fn delete_session(
-    db: &DatabaseConnection,
+    db: &(impl ConnectionTrait + TransactionTrait),
     id: i32,
     user_id: i32,
+    expected_version: Option<i32>,
 ) -> Result<DeleteResult, ServiceError> {
-    DecoponSessions::delete_many()
+    let txn = db.begin().await?;
+    let deleted_keys = sync::row_keys(&txn, SyncEntity::DecoponSession, vec![id]).await?;
+    let mut query = DecoponSessions::delete_many()
         .filter(decopon_sessions::Column::Id.eq(id))
-        .filter(decopon_sessions::Column::UserId.eq(user_id))
-        .exec(db)
-        .await
-        .map_err(Into::into)
+        .filter(decopon_sessions::Column::UserId.eq(user_id));
+    if let Some(version) = expected_version {
+        query = query.filter(decopon_sessions::Column::Version.eq(version));
+    }
+    let result = query.exec(&txn).await?;
+
+    if result.rows_affected == 0 && expected_version.is_some() {
+        let exists = DecoponSessions::find_by_id(id)
+            .filter(decopon_sessions::Column::UserId.eq(user_id))
+            .one(&txn)
+            .await?
+            .is_some();
+        if exists {
+            return Err(ServiceError::PreconditionFailed("decopon_session"));
+        }
+    }
+    if result.rows_affected > 0 {
+        sync::record_deletes(&txn, &deleted_keys).await?;
+    }
+    txn.commit().await?;
+    Ok(result)
 }
 
 pub async fn count_completed_sessions_on(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     user_id: i32,
     date: NaiveDate,
 ) -> Result<u64, ServiceError> {
@@ -150,3 +199,32 @@ pub async fn count_completed_sessions_on(
         .await?;
     Ok(count)
 }
+
+/// 完了したセッションで、その日の完了数がちょうど目標に達したら達成のイベントを返す。
+/// 日付は `count_completed_sessions_on` と同じく UTC で区切る。
+pub async fn daily_goal_event(
+    db: &impl ConnectionTrait,
+    session: &DecoponSession,
+) -> Result<Option<DomainEvent>, ServiceError> {
+    let Some(ended_at) = session.ended_at.filter(|_| session.status == "Completed") else {
+        return Ok(None);
+    };
+    let goal = Users::find_by_id(session.user_id)
+        .one(db)
+        .await?
+        .ok_or(ServiceError::NotFound("user"))?
+        .daily_goal;
+    if goal <= 0 {
+        return Ok(None);
+    }
+    let date = ended_at.date_naive();
+    let completed_sessions = count_completed_sessions_on(db, session.user_id, date).await?;
+    if completed_sessions != goal as u64 {
+        return Ok(None);
+    }
+    Ok(Some(DomainEvent::DailyGoalReached {
+        date,
+        completed_sessions,
+        goal,
+    }))
+}
@@ -0,0 +1,275 @@
+//! `Idempotency-Key` 付きの POST を再送されたときに、最初のレスポンスを再現するためのユースケースです。
+//! キーはユーザーごとに 24 時間保持し、同じキーで異なるリクエストが来たら拒否します。
+//! 処理を始める前にキーを予約するので、同時に届いた再送は処理中として断ります。
+
+use crate::{
+    entities::{idempotency_keys, prelude::*},
+    errors::ServiceError,
+};
+
+use chrono::{Duration, Utc};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{ActiveValue, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter};
+use sha2::{Digest, Sha256};
+
+/// キーを保持する時間
+pub const KEY_TTL_HOURS: i64 = 24;
+/// クライアントが指定できるキーの最大長
+pub const MAX_KEY_LEN: usize = 255;
+/// 処理中のまま終わらなかった予約 (処理中のパニックなど) を引き継げるようになるまでの時間
+pub const IN_FLIGHT_TIMEOUT_MINUTES: i64 = 5;
+
+/// 保存しておいたレスポンス
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    /// 再送時に付け直すヘッダの名前と値
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// キーを予約しようとした結果
+#[derive(Debug, PartialEq, Eq)]
+pub enum Reservation {
+    /// このリクエストが処理し、終わったら `complete` か `release` を呼ぶ
+    Reserved,
+    /// 同じリクエストが処理済みなので、保存済みのレスポンスを返す
+    Completed(StoredResponse),
+}
+
+/// メソッド・パス・本文から、同じリクエストかどうかを判定するためのハッシュを作る
+pub fn request_hash(method: &str, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b" ");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    format!("{:x}", hasher.finalize())
+}
+
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= MAX_KEY_LEN && key.chars().all(|c| c.is_ascii_graphic())
+}
+
+/// 処理を始める前にキーを予約する。同じキーのリクエストが処理中なら 409、
+/// 別のリクエストに使われていれば 422 にする。
+pub async fn reserve(
+    db: &DatabaseConnection,
+    user_id: i32,
+    key: &str,
+    request_hash: &str,
+) -> Result<Reservation, ServiceError> {
+    // 期限切れのキーと、処理中のまま残った予約は再利用できるように先に消す
+    IdempotencyKeys::delete_many()
+        .filter(idempotency_keys::Column::UserId.eq(user_id))
+        .filter(
+            Condition::any()
+                .add(idempotency_keys::Column::CreatedAt.lt(expiry_cutoff()))
+                .add(
+                    Condition::all()
+                        .add(idempotency_keys::Column::ResponseStatus.is_null())
+                        .add(idempotency_keys::Column::CreatedAt.lt(abandoned_cutoff())),
+                ),
+        )
+        .exec(db)
+        .await?;
+
+    let record = idempotency_keys::ActiveModel {
+        user_id: ActiveValue::Set(user_id),
+        key: ActiveValue::Set(key.to_string()),
+        request_hash: ActiveValue::Set(request_hash.to_string()),
+        created_at: ActiveValue::Set(Utc::now()),
+        ..Default::default()
+    };
+    let inserted = IdempotencyKeys::insert(record)
+        .on_conflict(
+            OnConflict::columns([
+                idempotency_keys::Column::UserId,
+                idempotency_keys::Column::Key,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    if inserted > 0 {
+        return Ok(Reservation::Reserved);
+    }
+
+    let stored = IdempotencyKeys::find()
+        .filter(idempotency_keys::Column::UserId.eq(user_id))
+        .filter(idempotency_keys::Column::Key.eq(key))
+        .one(db)
+        .await?
+        .ok_or(ServiceError::Conflict("idempotency_key"))?;
+    if stored.request_hash != request_hash {
+        return Err(ServiceError::invalid_field(
+            "Idempotency-Key",
+            "key_reused",
+            "this key was already used for a different request",
+        ));
+    }
+    let (Some(status), Some(body)) = (stored.response_status, stored.response_body) else {
+        return Err(ServiceError::Conflict("idempotency_key"));
+    };
+    let headers = match stored.response_headers {
+        Some(headers) => {
+            serde_json::from_str(&headers).map_err(|err| ServiceError::Internal(Box::new(err)))?
+        }
+        None => Vec::new(),
+    };
+    Ok(Reservation::Completed(StoredResponse {
+        status: u16::try_from(status).map_err(|err| ServiceError::Internal(Box::new(err)))?,
+        headers,
+        body,
+    }))
+}
+
+/// 予約したキーに、処理が成功したときのレスポンスを保存する
+pub async fn complete(
+    db: &DatabaseConnection,
+    user_id: i32,
+    key: &str,
+    response: StoredResponse,
+) -> Result<(), ServiceError> {
+    let headers = serde_json::to_string(&response.headers)
+        .map_err(|err| ServiceError::Internal(Box::new(err)))?;
+    IdempotencyKeys::update_many()
+        .col_expr(
+            idempotency_keys::Column::ResponseStatus,
+            Expr::value(i32::from(response.status)),
+        )
+        .col_expr(
+            idempotency_keys::Column::ResponseHeaders,
+            Expr::value(headers),
+        )
+        .col_expr(
+            idempotency_keys::Column::ResponseBody,
+            Expr::value(response.body),
+        )
+        .filter(idempotency_keys::Column::UserId.eq(user_id))
+        .filter(idempotency_keys::Column::Key.eq(key))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// 失敗したリクエストはやり直せるように、予約を取り消す
+pub async fn release(db: &DatabaseConnection, user_id: i32, key: &str) -> Result<(), ServiceError> {
+    IdempotencyKeys::delete_many()
+        .filter(idempotency_keys::Column::UserId.eq(user_id))
+        .filter(idempotency_keys::Column::Key.eq(key))
+        .filter(idempotency_keys::Column::ResponseStatus.is_null())
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+fn expiry_cutoff() -> chrono::DateTime<Utc> {
+    Utc::now() - Duration::hours(KEY_TTL_HOURS)
+}
+
+fn abandoned_cutoff() -> chrono::DateTime<Utc> {
+    Utc::now() - Duration::minutes(IN_FLIGHT_TIMEOUT_MINUTES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_user, setup_db};
+
+    fn created() -> StoredResponse {
+        StoredResponse {
+            status: 200,
+            headers: vec![("etag".to_string(), "\"1\"".to_string())],
+            body: b"{\"id\":1}".to_vec(),
+        }
+    }
+
+    async fn age_keys(db: &DatabaseConnection, age: Duration) {
+        IdempotencyKeys::update_many()
+            .col_expr(
+                idempotency_keys::Column::CreatedAt,
+                Expr::value(Utc::now() - age),
+            )
+            .exec(db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn replays_only_the_same_request_once_completed() {
+        let db = setup_db().await;
+        let user_id = create_user(&db, "alice").await.id;
+        let hash = request_hash("POST", "/tasks", b"{}");
+        let reserved = reserve(&db, user_id, "key-1", &hash).await.unwrap();
+        assert_eq!(reserved, Reservation::Reserved);
+
+        // 処理中の再送は断る
+        let err = reserve(&db, user_id, "key-1", &hash).await.unwrap_err();
+        assert!(matches!(err, ServiceError::Conflict("idempotency_key")));
+
+        complete(&db, user_id, "key-1", created()).await.unwrap();
+        let found = reserve(&db, user_id, "key-1", &hash).await.unwrap();
+        assert_eq!(found, Reservation::Completed(created()));
+
+        let other = request_hash("POST", "/tasks", b"{\"title\":\"x\"}");
+        let err = reserve(&db, user_id, "key-1", &other).await.unwrap_err();
+        assert!(matches!(err, ServiceError::Validation(_)));
+    }
+
+    #[tokio::test]
+    async fn replays_bodies_that_are_not_utf8() {
+        let db = setup_db().await;
+        let user_id = create_user(&db, "alice").await.id;
+        let hash = request_hash("POST", "/tasks", b"{}");
+        reserve(&db, user_id, "key-1", &hash).await.unwrap();
+
+        // 圧縮した本文などもバイト列のまま再送する
+        let response = StoredResponse {
+            body: vec![0x1f, 0x8b, 0x08, 0xff],
+            ..created()
+        };
+        complete(&db, user_id, "key-1", response.clone())
+            .await
+            .unwrap();
+        let found = reserve(&db, user_id, "key-1", &hash).await.unwrap();
+        assert_eq!(found, Reservation::Completed(response));
+    }
+
+    #[tokio::test]
+    async fn released_and_abandoned_reservations_can_be_retried() {
+        let db = setup_db().await;
+        let user_id = create_user(&db, "alice").await.id;
+        let hash = request_hash("POST", "/tasks", b"{}");
+        reserve(&db, user_id, "key-1", &hash).await.unwrap();
+        release(&db, user_id, "key-1").await.unwrap();
+        assert_eq!(
+            reserve(&db, user_id, "key-1", &hash).await.unwrap(),
+            Reservation::Reserved
+        );
+
+        age_keys(&db, Duration::minutes(IN_FLIGHT_TIMEOUT_MINUTES + 1)).await;
+        assert_eq!(
+            reserve(&db, user_id, "key-1", &hash).await.unwrap(),
+            Reservation::Reserved
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_keys_are_reusable() {
+        let db = setup_db().await;
+        let user_id = create_user(&db, "alice").await.id;
+        let hash = request_hash("POST", "/tasks", b"{}");
+        reserve(&db, user_id, "key-1", &hash).await.unwrap();
+        complete(&db, user_id, "key-1", created()).await.unwrap();
+        age_keys(&db, Duration::hours(KEY_TTL_HOURS + 1)).await;
+
+        let other = request_hash("POST", "/logs", b"{}");
+        assert_eq!(
+            reserve(&db, user_id, "key-1", &other).await.unwrap(),
+            Reservation::Reserved
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! API で行を指定する識別子。連番の ID と UUID のどちらでも受け付ける。
+//! 内部の結合には連番の ID を使い続けるので、UUID はここで ID に解決してから渡す。
+
+use std::fmt;
+use std::str::FromStr;
+
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect, Select};
+use serde::{Deserialize, Deserializer, Serialize, de};
+use uuid::Uuid;
+
+use crate::entities::{decopon_sessions, logs, prelude::*, tags, tasks, users};
+use crate::errors::ServiceError;
+
+/// 連番の ID (数値) か UUID (文字列)。パスでは数字だけなら ID として扱う
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum RowRef {
+    Id(i32),
+    Uuid(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidRowRef;
+
+impl fmt::Display for InvalidRowRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("expected an integer id or a UUID")
+    }
+}
+
+impl std::error::Error for InvalidRowRef {}
+
+impl FromStr for RowRef {
+    type Err = InvalidRowRef;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Ok(id) = value.parse::<i32>() {
+            return Ok(RowRef::Id(id));
+        }
+        // 保存している形式に揃えて比較する
+        Uuid::parse_str(value)
+            .map(|uuid| RowRef::Uuid(uuid.hyphenated().to_string()))
+            .map_err(|_| InvalidRowRef)
+    }
+}
+
+impl From<i32> for RowRef {
+    fn from(id: i32) -> Self {
+        RowRef::Id(id)
+    }
+}
+
+impl<'de> Deserialize<'de> for RowRef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RowRefVisitor)
+    }
+}
+
+/// 本文では数値と文字列、パスやクエリでは文字列として届く
+struct RowRefVisitor;
+
+impl de::Visitor<'_> for RowRefVisitor {
+    type Value = RowRef;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&InvalidRowRef, f)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        i32::try_from(value)
+            .map(RowRef::Id)
+            .map_err(|_| E::custom(InvalidRowRef))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        i32::try_from(value)
+            .map(RowRef::Id)
+            .map_err(|_| E::custom(InvalidRowRef))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+async fn find_id<E: EntityTrait>(
+    db: &impl ConnectionTrait,
+    query: Select<E>,
+    id_column: E::Column,
+    not_found: &'static str,
+) -> Result<i32, ServiceError> {
+    query
+        .select_only()
+        .column(id_column)
+        .into_tuple::<i32>()
+        .one(db)
+        .await?
+        .ok_or(ServiceError::NotFound(not_found))
+}
+
+pub async fn task_id(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+    row: &RowRef,
+) -> Result<i32, ServiceError> {
+    match row {
+        RowRef::Id(id) => Ok(*id),
+        RowRef::Uuid(uuid) => {
+            let query = Tasks::find()
+                .filter(tasks::Column::UserId.eq(user_id))
+                .filter(tasks::Column::Uuid.eq(uuid.as_str()));
+            find_id(db, query, tasks::Column::Id, "task").await
+        }
+    }
+}
+
+pub async fn decopon_session_id(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+    row: &RowRef,
+) -> Result<i32, ServiceError> {
+    match row {
+        RowRef::Id(id) => Ok(*id),
+        RowRef::Uuid(uuid) => {
+            let query = DecoponSessions::find()
+                .filter(decopon_sessions::Column::UserId.eq(user_id))
+                .filter(decopon_sessions::Column::Uuid.eq(uuid.as_str()));
+            find_id(db, query, decopon_sessions::Column::Id, "decopon_session").await
+        }
+    }
+}
+
+/// 省略できる項目用。`None` はそのまま返す
+pub async fn optional_task_id(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+    row: Option<&RowRef>,
+) -> Result<Option<i32>, ServiceError> {
+    match row {
+        Some(row) => task_id(db, user_id, row).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+pub async fn log_id(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+    row: &RowRef,
+) -> Result<i32, ServiceError> {
+    match row {
+        RowRef::Id(id) => Ok(*id),
+        RowRef::Uuid(uuid) => {
+            let query = Logs::find()
+                .filter(logs::Column::UserId.eq(user_id))
+                .filter(logs::Column::Uuid.eq(uuid.as_str()));
+            find_id(db, query, logs::Column::Id, "log").await
+        }
+    }
+}
+
+/// 並びはそのままに解決する。一つでも見つからなければ 404
+pub async fn tag_ids(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+    rows: &[RowRef],
+) -> Result<Vec<i32>, ServiceError> {
+    let mut ids = Vec::with_capacity(rows.len());
+    for row in rows {
+        let id = match row {
+            RowRef::Id(id) => *id,
+            RowRef::Uuid(uuid) => {
+                let query = Tags::find()
+                    .filter(tags::Column::UserId.eq(user_id))
+                    .filter(tags::Column::Uuid.eq(uuid.as_str()));
+                find_id(db, query, tags::Column::Id, "tag").await?
+            }
+        };
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// 省略できる項目用。`None` はそのまま返す
+pub async fn optional_tag_ids(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+    rows: Option<&[RowRef]>,
+) -> Result<Option<Vec<i32>>, ServiceError> {
+    match rows {
+        Some(rows) => tag_ids(db, user_id, rows).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// 管理 API 用。ユーザーは所有者で絞らずに解決する
+pub async fn user_id(db: &impl ConnectionTrait, row: &RowRef) -> Result<i32, ServiceError> {
+    match row {
+        RowRef::Id(id) => Ok(*id),
+        RowRef::Uuid(uuid) => {
+            let query = Users::find().filter(users::Column::Uuid.eq(uuid.as_str()));
+            find_id(db, query, users::Column::Id, "user").await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_user, setup_db};
+    use crate::usecases::tasks::{NewTask, insert_task};
+
+    #[test]
+    fn parses_ids_and_uuids() {
+        assert_eq!("42".parse::<RowRef>(), Ok(RowRef::Id(42)));
+        assert_eq!(
+            "6F9619FF-8B86-D011-B42D-00C04FC964FF".parse::<RowRef>(),
+            Ok(RowRef::Uuid(
+                "6f9619ff-8b86-d011-b42d-00c04fc964ff".to_string()
+            ))
+        );
+        assert_eq!("task-1".parse::<RowRef>(), Err(InvalidRowRef));
+    }
+
+    #[test]
+    fn deserializes_numbers_and_strings_from_json() {
+        let rows: Vec<RowRef> =
+            serde_json::from_str(r#"[7, "7", "6f9619ff-8b86-d011-b42d-00c04fc964ff"]"#).unwrap();
+        assert_eq!(rows[0], RowRef::Id(7));
+        assert_eq!(rows[1], RowRef::Id(7));
+        assert!(matches!(rows[2], RowRef::Uuid(_)));
+        assert!(serde_json::from_str::<RowRef>("4294967296").is_err());
+        assert_eq!(serde_json::to_string(&rows[0]).unwrap(), "7");
+    }
+
+    #[tokio::test]
+    async fn resolves_uuids_only_within_the_owner() {
+        let db = setup_db().await;
+        let mut members = Vec::new();
+        for name in ["owner", "other"] {
+            members.push(create_user(&db, name).await);
+        }
+        let task = insert_task(
+            &db,
+            NewTask {
+                title: "task".to_string(),
+                description: String::new(),
+                parent_task_id: None,
+                tag_ids: None,
+                due_at: None,
+                user_id: members[0].id,
+            },
+        )
+        .await
+        .unwrap();
+
+        let row: RowRef = task.uuid.parse().unwrap();
+        assert_eq!(task_id(&db, members[0].id, &row).await.unwrap(), task.id);
+        assert!(matches!(
+            task_id(&db, members[1].id, &row).await,
+            Err(ServiceError::NotFound("task"))
+        ));
+
+        let row: RowRef = members[1].uuid.parse().unwrap();
+        assert_eq!(user_id(&db, &row).await.unwrap(), members[1].id);
+    }
+}
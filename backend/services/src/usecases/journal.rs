@@ -0,0 +1,614 @@
+//! 1 日分のログ、終えたセッション、完了したタスクをまとめた日報。スタンドアップでの振り返りに使う。
+//! 日の区切りはほかの集計と同じく UTC。完了したタスクはシステムが残した `task.completed` のログから拾う。
+//! 希望したユーザーには、前日の日報を [`DailyJournalWorker`] がメールで送る。
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use minijinja::context;
+use sea_orm::prelude::{DateTimeUtc, Expr};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, LoaderTrait, QueryFilter, QueryOrder,
+    QuerySelect, TransactionTrait,
+};
+use tokio::task::JoinHandle;
+
+use crate::entities::{decopon_sessions, logs, prelude::*, tasks, users};
+use crate::errors::ServiceError;
+use crate::locale::Locale;
+use crate::mail_templates::{self, MailTemplate};
+
+use super::{
+    decopon_sessions::DecoponSession,
+    logs::{Log, LogEvent, LogSource, user_locale},
+    mail_outbox,
+};
+
+pub struct Journal {
+    pub date: NaiveDate,
+    /// ログの文面とタスクの見出しの言語
+    pub locale: Locale,
+    /// ユーザーが書いたログをタスクごとにまとめたもの。タスクのないログは最後
+    pub log_groups: Vec<JournalLogGroup>,
+    /// その日に終えたセッション
+    pub sessions: Vec<DecoponSession>,
+    pub completed_tasks: Vec<CompletedTask>,
+    pub totals: JournalTotals,
+}
+
+pub struct JournalLogGroup {
+    pub task_id: Option<i32>,
+    /// タスクが削除されていれば None
+    pub task_title: Option<String>,
+    pub logs: Vec<Log>,
+}
+
+pub struct CompletedTask {
+    pub task_id: i32,
+    /// 完了したときのタイトル
+    pub title: String,
+    pub completed_at: DateTimeUtc,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JournalTotals {
+    pub logs: usize,
+    pub sessions: usize,
+    pub focus_minutes: i64,
+    pub completed_tasks: usize,
+}
+
+impl Journal {
+    /// 何も記録がなかった日
+    pub fn is_empty(&self) -> bool {
+        self.log_groups.is_empty() && self.sessions.is_empty() && self.completed_tasks.is_empty()
+    }
+}
+
+/// `date` (UTC) の日報を組み立てる
+pub async fn get_journal(
+    db: &DatabaseConnection,
+    user_id: i32,
+    date: NaiveDate,
+) -> Result<Journal, ServiceError> {
+    let (start, end) = day_bounds(date)?;
+    let locale = user_locale(db, user_id).await?;
+
+    let sessions = DecoponSessions::find()
+        .filter(decopon_sessions::Column::UserId.eq(user_id))
+        .filter(decopon_sessions::Column::Status.eq("Completed"))
+        .filter(decopon_sessions::Column::EndedAt.gte(start))
+        .filter(decopon_sessions::Column::EndedAt.lt(end))
+        .order_by_asc(decopon_sessions::Column::StartedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(DecoponSession::from)
+        .collect::<Vec<_>>();
+
+    // 結合した行に上限がかからないよう、ログを読んでからタグをまとめて読む
+    let rows = Logs::find()
+        .filter(logs::Column::UserId.eq(user_id))
+        .filter(logs::Column::CreatedAt.gte(start))
+        .filter(logs::Column::CreatedAt.lt(end))
+        .order_by_asc(logs::Column::CreatedAt)
+        .order_by_asc(logs::Column::Id)
+        .all(db)
+        .await?;
+    let tags = rows.load_many_to_many(Tags, LogTag, db).await?;
+    let (user_logs, system_logs): (Vec<_>, Vec<_>) = rows
+        .into_iter()
+        .zip(tags)
+        .map(|(log, tags)| Log::from_model(log, tags, locale))
+        .partition(|log| log.source == LogSource::User);
+
+    // 完了を取り消してから完了し直したタスクは、最後に完了した時刻で 1 件にする
+    let mut completed_tasks: Vec<CompletedTask> = Vec::new();
+    for log in system_logs {
+        let Some(LogEvent::TaskCompleted { task_id, title }) = log.event else {
+            continue;
+        };
+        completed_tasks.retain(|task| task.task_id != task_id);
+        completed_tasks.push(CompletedTask {
+            task_id,
+            title,
+            completed_at: log.created_at,
+        });
+    }
+
+    let task_ids = user_logs
+        .iter()
+        .filter_map(|log| log.task_id)
+        .collect::<Vec<_>>();
+    let titles: HashMap<i32, String> = if task_ids.is_empty() {
+        HashMap::new()
+    } else {
+        Tasks::find()
+            .select_only()
+            .column(tasks::Column::Id)
+            .column(tasks::Column::Title)
+            .filter(tasks::Column::Id.is_in(task_ids))
+            .into_tuple::<(i32, String)>()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect()
+    };
+
+    let totals = JournalTotals {
+        logs: user_logs.len(),
+        sessions: sessions.len(),
+        focus_minutes: sessions
+            .iter()
+            .filter_map(|session| {
+                session
+                    .ended_at
+                    .map(|ended_at| (ended_at - session.started_at).num_minutes().max(0))
+            })
+            .sum(),
+        completed_tasks: completed_tasks.len(),
+    };
+
+    Ok(Journal {
+        date,
+        locale,
+        log_groups: group_logs(user_logs, &titles),
+        sessions,
+        completed_tasks,
+        totals,
+    })
+}
+
+/// 最初にログを書いた順にタスクを並べ、タスクのないログは最後にまとめる
+fn group_logs(logs: Vec<Log>, titles: &HashMap<i32, String>) -> Vec<JournalLogGroup> {
+    let mut groups: Vec<JournalLogGroup> = Vec::new();
+    let mut without_task = Vec::new();
+    for log in logs {
+        let Some(task_id) = log.task_id else {
+            without_task.push(log);
+            continue;
+        };
+        match groups
+            .iter_mut()
+            .find(|group| group.task_id == Some(task_id))
+        {
+            Some(group) => group.logs.push(log),
+            None => groups.push(JournalLogGroup {
+                task_id: Some(task_id),
+                task_title: titles.get(&task_id).cloned(),
+                logs: vec![log],
+            }),
+        }
+    }
+    if !without_task.is_empty() {
+        groups.push(JournalLogGroup {
+            task_id: None,
+            task_title: None,
+            logs: without_task,
+        });
+    }
+    groups
+}
+
+fn day_bounds(date: NaiveDate) -> Result<(DateTime<Utc>, DateTime<Utc>), ServiceError> {
+    let start = date.and_hms_opt(0, 0, 0).map(|at| at.and_utc());
+    let end = date
+        .checked_add_days(Days::new(1))
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|at| at.and_utc());
+    match (start, end) {
+        (Some(start), Some(end)) => Ok((start, end)),
+        _ => Err(ServiceError::invalid_field(
+            "date",
+            "out_of_range",
+            "date is out of range",
+        )),
+    }
+}
+
+/// 見出しの文言
+struct Labels {
+    title: &'static str,
+    totals: &'static str,
+    sessions: &'static str,
+    completed_tasks: &'static str,
+    logs: &'static str,
+    minutes: &'static str,
+    no_task: &'static str,
+    deleted_task: &'static str,
+    nothing: &'static str,
+}
+
+impl Labels {
+    fn for_locale(locale: Locale) -> Self {
+        match locale {
+            Locale::En => Labels {
+                title: "Journal",
+                totals: "Totals",
+                sessions: "Sessions",
+                completed_tasks: "Completed tasks",
+                logs: "Logs",
+                minutes: "min",
+                no_task: "No task",
+                deleted_task: "Deleted task",
+                nothing: "Nothing was recorded.",
+            },
+            Locale::Ja => Labels {
+                title: "日報",
+                totals: "集計",
+                sessions: "セッション",
+                completed_tasks: "完了したタスク",
+                logs: "ログ",
+                minutes: "分",
+                no_task: "タスクなし",
+                deleted_task: "削除されたタスク",
+                nothing: "記録はありません。",
+            },
+        }
+    }
+}
+
+/// Markdown にする。時刻は UTC の `HH:MM`
+pub fn render_markdown(journal: &Journal) -> String {
+    let labels = Labels::for_locale(journal.locale);
+    let totals = &journal.totals;
+    let mut out = format!("# {} {} (UTC)\n\n", labels.title, journal.date);
+
+    out.push_str(&format!("## {}\n\n", labels.totals));
+    out.push_str(&format!(
+        "- {}: {} ({} {})\n",
+        labels.sessions, totals.sessions, totals.focus_minutes, labels.minutes
+    ));
+    out.push_str(&format!(
+        "- {}: {}\n",
+        labels.completed_tasks, totals.completed_tasks
+    ));
+    out.push_str(&format!("- {}: {}\n", labels.logs, totals.logs));
+    if journal.is_empty() {
+        out.push_str(&format!("\n{}\n", labels.nothing));
+        return out;
+    }
+
+    if !journal.sessions.is_empty() {
+        out.push_str(&format!("\n## {}\n\n", labels.sessions));
+        for session in &journal.sessions {
+            let Some(ended_at) = session.ended_at else {
+                continue;
+            };
+            out.push_str(&format!(
+                "- {}–{} ({} {})\n",
+                clock(session.started_at),
+                clock(ended_at),
+                (ended_at - session.started_at).num_minutes().max(0),
+                labels.minutes
+            ));
+        }
+    }
+
+    if !journal.completed_tasks.is_empty() {
+        out.push_str(&format!("\n## {}\n\n", labels.completed_tasks));
+        for task in &journal.completed_tasks {
+            out.push_str(&format!(
+                "- [x] {} ({})\n",
+                single_line(&task.title),
+                clock(task.completed_at)
+            ));
+        }
+    }
+
+    if !journal.log_groups.is_empty() {
+        out.push_str(&format!("\n## {}\n", labels.logs));
+        for group in &journal.log_groups {
+            let heading = match (&group.task_title, group.task_id) {
+                (Some(title), _) => single_line(title),
+                (None, Some(_)) => labels.deleted_task.to_string(),
+                (None, None) => labels.no_task.to_string(),
+            };
+            out.push_str(&format!("\n### {heading}\n\n"));
+            for log in &group.logs {
+                write_log(log, &mut out);
+            }
+        }
+    }
+    out
+}
+
+/// 複数行のログは、リストの項目が崩れないよう続きの行を字下げする
+fn write_log(log: &Log, out: &mut String) {
+    let mut lines = log.content.lines().filter(|line| !line.trim().is_empty());
+    out.push_str(&format!(
+        "- {} {}",
+        clock(log.created_at),
+        lines.next().unwrap_or_default()
+    ));
+    for tag in &log.tags {
+        out.push_str(&format!(
+            " #{}",
+            tag.name.split_whitespace().collect::<Vec<_>>().join("-")
+        ));
+    }
+    out.push('\n');
+    for line in lines {
+        out.push_str(&format!("  {line}\n"));
+    }
+}
+
+fn clock(at: DateTimeUtc) -> String {
+    at.format("%H:%M").to_string()
+}
+
+/// 改行を含むタイトルは見出しやリストの項目が崩れないよう一行にまとめる
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 日報のメールを希望したユーザーのうち、`date` の分をまだ受け取っていない人へ送信キューに積む。
+/// 記録のない日は送らないが、送ったものとして扱う。積んだ件数を返す
+pub async fn enqueue_daily_journals(
+    db: &DatabaseConnection,
+    date: NaiveDate,
+) -> Result<usize, ServiceError> {
+    let recipients = Users::find()
+        .filter(users::Column::DailyJournalEmail.eq(true))
+        .filter(users::Column::DisabledAt.is_null())
+        .filter(users::Column::EmailVerifiedAt.is_not_null())
+        .filter(
+            Condition::any()
+                .add(users::Column::DailyJournalSentOn.is_null())
+                .add(users::Column::DailyJournalSentOn.lt(date)),
+        )
+        .order_by_asc(users::Column::Id)
+        .all(db)
+        .await?;
+
+    let mut queued = 0;
+    for user in recipients {
+        let journal = get_journal(db, user.id, date).await?;
+        let mail = if journal.is_empty() {
+            None
+        } else {
+            Some(render_mail(&journal, &user)?)
+        };
+
+        let txn = db.begin().await?;
+        // 複数のワーカーが動いていても 1 通だけ送るよう、送った日付を先に書き換えられたものだけ積む
+        let sent_on = users::Column::DailyJournalSentOn;
+        let claimed = Users::update_many()
+            .col_expr(sent_on, Expr::value(date))
+            .filter(users::Column::Id.eq(user.id))
+            .filter(match user.daily_journal_sent_on {
+                Some(previous) => sent_on.eq(previous),
+                None => sent_on.is_null(),
+            })
+            .exec(&txn)
+            .await?
+            .rows_affected
+            == 1;
+        if !claimed {
+            continue;
+        }
+        if let Some(mail) = mail {
+            mail_outbox::enqueue(&txn, &mail).await?;
+            queued += 1;
+        }
+        txn.commit().await?;
+    }
+    Ok(queued)
+}
+
+fn render_mail(
+    journal: &Journal,
+    user: &users::Model,
+) -> Result<crate::mail_transport::OutgoingMail, ServiceError> {
+    let completed_tasks = journal
+        .completed_tasks
+        .iter()
+        .map(|task| single_line(&task.title))
+        .collect::<Vec<_>>();
+    let log_groups = journal
+        .log_groups
+        .iter()
+        .map(|group| {
+            context! {
+                title => group.task_title.as_deref().map(single_line),
+                logs => group.logs.iter().map(|log| context! {
+                    time => clock(log.created_at),
+                    content => log.content.clone(),
+                }).collect::<Vec<_>>(),
+            }
+        })
+        .collect::<Vec<_>>();
+    mail_templates::render(
+        MailTemplate::DailyJournal,
+        &user.locale,
+        &user.email,
+        context! {
+            date => journal.date.to_string(),
+            markdown => render_markdown(journal),
+            totals => context! {
+                sessions => journal.totals.sessions,
+                focus_minutes => journal.totals.focus_minutes,
+                completed_tasks => journal.totals.completed_tasks,
+                logs => journal.totals.logs,
+            },
+            completed_tasks => completed_tasks,
+            log_groups => log_groups,
+        },
+    )
+}
+
+/// 前日の日報を定期的に送信キューへ積むワーカー
+pub struct DailyJournalWorker {
+    db: Arc<DatabaseConnection>,
+    poll_interval: Duration,
+}
+
+impl DailyJournalWorker {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self {
+            db,
+            poll_interval: Duration::from_secs(15 * 60),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// ワーカーを Tokio のタスクとして起動する。
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.poll_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let Some(yesterday) = Utc::now().date_naive().pred_opt() else {
+                    continue;
+                };
+                if let Err(err) = enqueue_daily_journals(&self.db, yesterday).await {
+                    tracing::error!(error = %err, "failed to queue daily journals");
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{new_user, setup_db};
+    use crate::usecases::{
+        decopon_sessions::{NewDecoponSession, insert_session},
+        logs::{NewLog, insert_event_log, insert_log},
+        tasks::{NewTask, Task, insert_task},
+    };
+    use sea_orm::{ActiveModelTrait, ActiveValue::Set, PaginatorTrait};
+
+    /// 今日の記録。セッション 1 件、タスクに紐付いたログと紐付かないログ、完了したタスク
+    async fn record_today(db: &DatabaseConnection, user_id: i32) -> Task {
+        let now = Utc::now();
+        let started_at = now.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
+        insert_session(
+            db,
+            NewDecoponSession {
+                status: "Completed".to_string(),
+                started_at,
+                ended_at: Some(started_at + chrono::Duration::minutes(25)),
+                user_id,
+            },
+        )
+        .await
+        .unwrap();
+        let task = insert_task(
+            db,
+            NewTask {
+                title: "Write report".to_string(),
+                description: String::new(),
+                parent_task_id: None,
+                tag_ids: None,
+                due_at: None,
+                user_id,
+            },
+        )
+        .await
+        .unwrap();
+        for (content, task_id) in [("Went for a run", None), ("Drafted intro", Some(task.id))] {
+            insert_log(
+                db,
+                NewLog {
+                    content: content.to_string(),
+                    source: LogSource::User,
+                    task_id,
+                    user_id,
+                    tag_ids: Vec::new(),
+                    tag_names: Vec::new(),
+                },
+            )
+            .await
+            .unwrap();
+        }
+        insert_event_log(
+            db,
+            user_id,
+            Some(task.id),
+            LogEvent::TaskCompleted {
+                task_id: task.id,
+                title: task.title.clone(),
+            },
+        )
+        .await
+        .unwrap();
+        task
+    }
+
+    #[tokio::test]
+    async fn groups_the_days_logs_by_task() {
+        let db = setup_db().await;
+        let user = users::ActiveModel {
+            locale: Set("en".to_string()),
+            daily_journal_email: Set(true),
+            ..new_user("alice")
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        let task = record_today(&db, user.id).await;
+        let today = Utc::now().date_naive();
+
+        let journal = get_journal(&db, user.id, today).await.unwrap();
+        assert_eq!(
+            journal.totals,
+            JournalTotals {
+                logs: 2,
+                sessions: 1,
+                focus_minutes: 25,
+                completed_tasks: 1,
+            }
+        );
+        assert_eq!(journal.log_groups[0].task_id, Some(task.id));
+        assert_eq!(journal.log_groups[1].task_id, None);
+        assert_eq!(journal.completed_tasks[0].title, "Write report");
+
+        let markdown = render_markdown(&journal);
+        assert!(markdown.starts_with(&format!("# Journal {today} (UTC)\n")));
+        assert!(markdown.contains("- Sessions: 1 (25 min)\n"));
+        assert!(markdown.contains("- 00:00–00:25 (25 min)\n"));
+        assert!(markdown.contains("- [x] Write report ("));
+        assert!(markdown.contains("### Write report\n\n- "));
+        assert!(markdown.contains("### No task\n\n- "));
+
+        let yesterday = today.pred_opt().unwrap();
+        let empty = get_journal(&db, user.id, yesterday).await.unwrap();
+        assert!(empty.is_empty());
+        assert!(render_markdown(&empty).ends_with("Nothing was recorded.\n"));
+    }
+
+    #[tokio::test]
+    async fn queues_one_mail_per_day_for_opted_in_users() {
+        let db = setup_db().await;
+        let user = users::ActiveModel {
+            locale: Set("ja".to_string()),
+            daily_journal_email: Set(true),
+            ..new_user("alice")
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        record_today(&db, user.id).await;
+        let today = Utc::now().date_naive();
+
+        assert_eq!(enqueue_daily_journals(&db, today).await.unwrap(), 1);
+        assert_eq!(enqueue_daily_journals(&db, today).await.unwrap(), 0);
+        let mail = MailOutbox::find().one(&db).await.unwrap().unwrap();
+        assert_eq!(mail.subject, format!("{today} の Decopon 日報"));
+        assert!(mail.body.contains("## 完了したタスク"));
+
+        // 記録のない日は送らない
+        let tomorrow = today.succ_opt().unwrap();
+        assert_eq!(enqueue_daily_journals(&db, tomorrow).await.unwrap(), 0);
+        assert_eq!(MailOutbox::find().count(&db).await.unwrap(), 1);
+        let user = Users::find_by_id(user.id).one(&db).await.unwrap().unwrap();
+        assert_eq!(user.daily_journal_sent_on, Some(tomorrow));
+    }
+}
@@ -1,24 +1,29 @@
 use crate::{
     entities::{log_tag, logs, prelude::*, tags, tasks},
     errors::ServiceError,
+    locale::Locale,
 };
 
-use sea_orm::prelude::DateTimeUtc;
+use super::sync::{self, SyncEntity};
+
+use chrono::Utc;
+use sea_orm::prelude::{DateTimeUtc, Expr};
 use sea_orm::{
-    ActiveValue, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, JoinType,
-    QueryFilter, QueryOrder, QuerySelect, RelationTrait, TransactionTrait,
+    ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, DatabaseTransaction,
+    EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect, RelationTrait, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum LogSource {
     System,
     User,
 }
 
 impl LogSource {
-    fn as_str(&self) -> &'static str {
+    pub(crate) fn as_str(&self) -> &'static str {
         match self {
             LogSource::System => "System",
             LogSource::User => "User",
@@ -35,9 +40,49 @@ impl From<String> for LogSource {
     }
 }
 
+/// システムが記録する出来事。文面はコードとパラメータから閲覧者の言語で組み立てる。
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "code", content = "params")]
+pub enum LogEvent {
+    #[serde(rename = "task.completed")]
+    TaskCompleted { task_id: i32, title: String },
+}
+
+impl LogEvent {
+    pub fn render(&self, locale: Locale) -> String {
+        match (self, locale) {
+            (LogEvent::TaskCompleted { title, .. }, Locale::En) => {
+                format!("Task \"{}\" completed.", title)
+            }
+            (LogEvent::TaskCompleted { title, .. }, Locale::Ja) => {
+                format!("タスク「{}」を完了しました。", title)
+            }
+        }
+    }
+
+    /// `event_code` と `event_params` カラムに保存する値
+    fn to_columns(&self) -> Result<(String, String), ServiceError> {
+        let mut value =
+            serde_json::to_value(self).map_err(|e| ServiceError::Internal(Box::new(e)))?;
+        let code = value["code"].as_str().unwrap_or_default().to_string();
+        Ok((code, value["params"].take().to_string()))
+    }
+
+    /// 保存された値から復元する。未知のコードや壊れたパラメータは None。
+    fn from_columns(code: &str, params: Option<&str>) -> Option<Self> {
+        let params = match params {
+            Some(params) => serde_json::from_str(params).ok()?,
+            None => serde_json::Value::Null,
+        };
+        serde_json::from_value(serde_json::json!({ "code": code, "params": params })).ok()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct LogTagInfo {
     pub id: i32,
+    pub uuid: String,
     pub name: String,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
@@ -48,6 +93,7 @@ impl From<tags::Model> for LogTagInfo {
     fn from(tag: tags::Model) -> Self {
         Self {
             id: tag.id,
+            uuid: tag.uuid,
             name: tag.name,
             created_at: tag.created_at,
             updated_at: tag.updated_at,
@@ -74,25 +120,46 @@ pub struct LogFilters {
 
 pub struct Log {
     pub id: i32,
+    pub uuid: String,
+    /// イベントのログは閲覧者の言語で組み立て直した文面
     pub content: String,
     pub source: LogSource,
+    pub event: Option<LogEvent>,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
     pub user_id: i32,
     pub task_id: Option<i32>,
+    pub version: i32,
     pub tags: Vec<LogTagInfo>,
 }
 
 impl Log {
-    fn from_model(model: logs::Model, tags: Vec<tags::Model>) -> Self {
+    pub(crate) fn from_model(model: logs::Model, tags: Vec<tags::Model>, locale: Locale) -> Self {
+        let event = model.event_code.as_deref().and_then(|code| {
+            let event = LogEvent::from_columns(code, model.event_params.as_deref());
+            if event.is_none() {
+                tracing::warn!(
+                    log_id = model.id,
+                    code,
+                    "unknown log event; using stored content"
+                );
+            }
+            event
+        });
         Self {
             id: model.id,
-            content: model.content,
+            uuid: model.uuid,
+            content: event
+                .as_ref()
+                .map(|event| event.render(locale))
+                .unwrap_or(model.content),
             source: LogSource::from(model.source),
+            event,
             created_at: model.created_at,
             updated_at: model.updated_at,
             user_id: model.user_id,
             task_id: model.task_id,
+            version: model.version,
             tags: tags.into_iter().map(LogTagInfo::from).collect(),
         }
     }
@@ -143,8 +210,7 @@ pub async fn get_logs(
 
     let normalized_tag_ids = normalize_tag_ids(filters.tag_ids);
     if !normalized_tag_ids.is_empty() {
-        let log_ids =
-            find_log_ids_with_all_tags(db, &normalized_tag_ids).await?;
+        let log_ids = find_log_ids_with_all_tags(db, &normalized_tag_ids).await?;
         if log_ids.is_empty() {
             return Ok(vec![]);
         }
@@ -155,9 +221,10 @@ pub async fn get_logs(
         .find_with_related(Tags)
         .all(db)
         .await?;
+    let locale = user_locale(db, user_id).await?;
     Ok(logs
         .into_iter()
-        .map(|(log, tags)| Log::from_model(log, tags))
+        .map(|(log, tags)| Log::from_model(log, tags, locale))
         .collect())
 }
 
@@ -173,28 +240,132 @@ pub async fn get_logs_by_task(
         .find_with_related(Tags)
         .all(db)
         .await?;
+    let locale = user_locale(db, user_id).await?;
     Ok(logs
         .into_iter()
-        .map(|(log, tags)| Log::from_model(log, tags))
+        .map(|(log, tags)| Log::from_model(log, tags, locale))
         .collect())
 }
 
-pub async fn insert_log(db: &DatabaseConnection, params: NewLog) -> Result<Log, ServiceError> {
+pub async fn insert_log(
+    db: &(impl ConnectionTrait + TransactionTrait),
+    params: NewLog,
+) -> Result<Log, ServiceError> {
+    let locale = user_locale(db, params.user_id).await?;
     let txn = db.begin().await?;
-    let log = insert_log_with_txn(&txn, params).await?;
+    let log = insert_log_with_txn(&txn, params, None, locale).await?;
     txn.commit().await?;
     Ok(log)
 }
 
+/// システムイベントのログを記録する。`content` には記録時点のユーザーの言語の文面を残す。
+pub async fn insert_event_log(
+    db: &(impl ConnectionTrait + TransactionTrait),
+    user_id: i32,
+    task_id: Option<i32>,
+    event: LogEvent,
+) -> Result<Log, ServiceError> {
+    let locale = user_locale(db, user_id).await?;
+    let params = NewLog {
+        content: event.render(locale),
+        source: LogSource::System,
+        task_id,
+        user_id,
+        tag_ids: Vec::new(),
+        tag_names: Vec::new(),
+    };
+    let txn = db.begin().await?;
+    let log = insert_log_with_txn(&txn, params, Some(&event), locale).await?;
+    txn.commit().await?;
+    Ok(log)
+}
+
+/// ログのタグを指定したものに置き換える。名前で指定したタグはなければ作成する。
+/// `expected_version` を指定すれば、保存されている版と一致する場合だけ置き換える。
+pub async fn replace_log_tags(
+    db: &(impl ConnectionTrait + TransactionTrait),
+    user_id: i32,
+    log_id: i32,
+    tag_ids: Vec<i32>,
+    tag_names: Vec<String>,
+    expected_version: Option<i32>,
+) -> Result<Log, ServiceError> {
+    let locale = user_locale(db, user_id).await?;
+    let txn = db.begin().await?;
+    let current = Logs::find_by_id(log_id)
+        .filter(logs::Column::UserId.eq(user_id))
+        .one(&txn)
+        .await?
+        .ok_or(ServiceError::NotFound("log"))?;
+    if expected_version.is_some_and(|version| version != current.version) {
+        return Err(ServiceError::PreconditionFailed("log"));
+    }
+    // 読み込んだ後に他の更新が入っていれば版が進んでいるので、一行も更新されない
+    let updated = Logs::update_many()
+        .col_expr(logs::Column::Version, Expr::value(current.version + 1))
+        .col_expr(logs::Column::UpdatedAt, Expr::value(Utc::now()))
+        .filter(logs::Column::Id.eq(log_id))
+        .filter(logs::Column::Version.eq(current.version))
+        .exec(&txn)
+        .await?;
+    if updated.rows_affected == 0 {
+        return Err(ServiceError::PreconditionFailed("log"));
+    }
+    let log = Logs::find_by_id(log_id)
+        .one(&txn)
+        .await?
+        .ok_or(ServiceError::NotFound("log"))?;
+
+    let tags = ensure_tags(&txn, user_id, tag_ids, tag_names).await?;
+    LogTag::delete_many()
+        .filter(log_tag::Column::LogId.eq(log_id))
+        .exec(&txn)
+        .await?;
+    let tag_ids: Vec<i32> = tags.iter().map(|tag| tag.id).collect();
+    if !tag_ids.is_empty() {
+        attach_tags_to_log(&txn, log_id, &tag_ids).await?;
+    }
+    sync::record_upsert(&txn, SyncEntity::Log, log_id).await?;
+    txn.commit().await?;
+
+    Ok(Log::from_model(log, tags, locale))
+}
+
+pub(crate) async fn user_locale(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+) -> Result<Locale, ServiceError> {
+    let user = Users::find_by_id(user_id)
+        .one(db)
+        .await?
+        .ok_or(ServiceError::NotFound("user"))?;
+    Ok(Locale::from_user_locale(&user.locale))
+}
+
 async fn insert_log_with_txn(
     txn: &DatabaseTransaction,
     params: NewLog,
+    event: Option<&LogEvent>,
+    locale: Locale,
 ) -> Result<Log, ServiceError> {
+    let (event_code, event_params) = match event {
+        Some(event) => {
+            let (code, params) = event.to_columns()?;
+            (Some(code), Some(params))
+        }
+        None => (None, None),
+    };
+    let now = Utc::now();
     let new_log = logs::ActiveModel {
         content: ActiveValue::Set(params.content),
         source: ActiveValue::Set(params.source.as_str().to_owned()),
         task_id: ActiveValue::Set(params.task_id),
         user_id: ActiveValue::Set(params.user_id),
+        event_code: ActiveValue::Set(event_code),
+        event_params: ActiveValue::Set(event_params),
+        // DB の既定値に任せると SQLite では書式が変わり、日付で絞り込めなくなる
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
         ..Default::default()
     };
     let result = Logs::insert(new_log).exec(txn).await?;
@@ -203,17 +374,17 @@ async fn insert_log_with_txn(
         .await?
         .ok_or(ServiceError::NotFound("log"))?;
 
-    let tags =
-        ensure_tags(txn, params.user_id, params.tag_ids, params.tag_names).await?;
+    let tags = ensure_tags(txn, params.user_id, params.tag_ids, params.tag_names).await?;
     let tag_ids: Vec<i32> = tags.iter().map(|tag| tag.id).collect();
     if !tag_ids.is_empty() {
         attach_tags_to_log(txn, log.id, &tag_ids).await?;
     }
+    sync::record_upsert(txn, SyncEntity::Log, log.id).await?;
 
-    Ok(Log::from_model(log, tags))
+    Ok(Log::from_model(log, tags, locale))
 }
 
-async fn ensure_tags(
+pub(crate) async fn ensure_tags(
     txn: &DatabaseTransaction,
     user_id: i32,
     tag_ids: Vec<i32>,
@@ -253,6 +424,7 @@ async fn ensure_tags(
                     ..Default::default()
                 };
                 let res = Tags::insert(new_tag).exec(txn).await?;
+                sync::record_upsert(txn, SyncEntity::Tag, res.last_insert_id).await?;
                 Tags::find_by_id(res.last_insert_id)
                     .one(txn)
                     .await?
@@ -267,7 +439,7 @@ async fn ensure_tags(
     Ok(collected)
 }
 
-async fn attach_tags_to_log(
+pub(crate) async fn attach_tags_to_log(
     txn: &DatabaseTransaction,
     log_id: i32,
     tag_ids: &[i32],
@@ -344,11 +516,7 @@ mod tests {
         .unwrap()
     }
 
-    async fn create_tag_entity(
-        db: &DatabaseConnection,
-        user_id: i32,
-        name: &str,
-    ) -> tags::Model {
+    async fn create_tag_entity(db: &DatabaseConnection, user_id: i32, name: &str) -> tags::Model {
         tags::ActiveModel {
             name: Set(name.to_string()),
             user_id: Set(user_id),
@@ -486,4 +654,56 @@ mod tests {
         assert!(ids.contains(&log1.id));
         assert!(ids.contains(&log2.id));
     }
+
+    #[tokio::test]
+    async fn event_logs_are_rendered_in_the_readers_locale() {
+        let db = setup_db().await;
+        let user = create_user(&db, "event").await;
+        let event = LogEvent::TaskCompleted {
+            task_id: 1,
+            title: "買い物".to_string(),
+        };
+
+        let log = insert_event_log(&db, user.id, None, event.clone())
+            .await
+            .unwrap();
+        assert_eq!(log.content, "タスク「買い物」を完了しました。");
+        assert_eq!(log.source, LogSource::System);
+        assert_eq!(log.event, Some(event.clone()));
+
+        let stored = Logs::find_by_id(log.id).one(&db).await.unwrap().unwrap();
+        assert_eq!(stored.event_code.as_deref(), Some("task.completed"));
+        assert_eq!(
+            stored.event_params.as_deref(),
+            Some(r#"{"task_id":1,"title":"買い物"}"#)
+        );
+
+        // 言語を切り替えると既存のログも新しい言語で表示される
+        let mut active: users::ActiveModel = user.clone().into();
+        active.locale = Set("en".to_string());
+        active.update(&db).await.unwrap();
+        let logs = get_logs(&db, user.id, LogFilters::default()).await.unwrap();
+        assert_eq!(logs[0].content, "Task \"買い物\" completed.");
+    }
+
+    #[tokio::test]
+    async fn unknown_events_fall_back_to_stored_content() {
+        let db = setup_db().await;
+        let user = create_user(&db, "legacy").await;
+        logs::ActiveModel {
+            content: Set("stored".to_string()),
+            source: Set(LogSource::System.as_str().to_string()),
+            user_id: Set(user.id),
+            event_code: Set(Some("task.unknown".to_string())),
+            event_params: Set(Some("{}".to_string())),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        let logs = get_logs(&db, user.id, LogFilters::default()).await.unwrap();
+        assert_eq!(logs[0].content, "stored");
+        assert!(logs[0].event.is_none());
+    }
 }
@@ -0,0 +1,281 @@
+//! 送信メールを `mail_outbox` テーブルに積み、バックグラウンドのワーカーが送信するキューです。
+//! リクエスト処理中は登録だけを行い、SMTP の一時的な障害はワーカーが指数バックオフで再試行します。
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect,
+};
+use tokio::task::JoinHandle;
+
+use crate::{
+    entities::{mail_outbox, prelude::*},
+    errors::ServiceError,
+    mail_transport::OutgoingMail,
+    usecases::{
+        mails::Mailer,
+        outbox_worker::{self, OutboxConfig, OutboxRow, OutboxWorker},
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MailStatus {
+    Pending,
+    Sent,
+    Failed,
+}
+
+impl MailStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MailStatus::Pending => "Pending",
+            MailStatus::Sent => "Sent",
+            MailStatus::Failed => "Failed",
+        }
+    }
+}
+
+impl From<String> for MailStatus {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "Sent" => MailStatus::Sent,
+            "Failed" => MailStatus::Failed,
+            _ => MailStatus::Pending,
+        }
+    }
+}
+
+/// 送信キューへメールを登録する。実際の送信は [`MailOutboxWorker`] が行う。
+pub async fn enqueue(db: &impl ConnectionTrait, mail: &OutgoingMail) -> Result<(), ServiceError> {
+    let now = Utc::now();
+    let message = mail_outbox::ActiveModel {
+        recipient: ActiveValue::Set(mail.to.clone()),
+        subject: ActiveValue::Set(mail.subject.clone()),
+        body: ActiveValue::Set(mail.body.clone()),
+        body_html: ActiveValue::Set(mail.html.clone()),
+        status: ActiveValue::Set(MailStatus::Pending.as_str().to_string()),
+        attempts: ActiveValue::Set(0),
+        next_attempt_at: ActiveValue::Set(now),
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    tracing::debug!(mail_id = message.id, "queued outbound mail");
+    Ok(())
+}
+
+pub struct MailOutboxWorker {
+    db: Arc<DatabaseConnection>,
+    mailer: Mailer,
+    config: OutboxConfig,
+}
+
+impl MailOutboxWorker {
+    pub fn new(db: Arc<DatabaseConnection>, mailer: Mailer) -> Self {
+        Self {
+            db,
+            mailer,
+            config: OutboxConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: OutboxConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// ワーカーを Tokio のタスクとして起動する。
+    pub fn spawn(self) -> JoinHandle<()> {
+        outbox_worker::spawn(self)
+    }
+
+    /// 送信時刻に達したメールを送信し、処理した件数を返す。
+    pub async fn process_due(&self) -> Result<usize, ServiceError> {
+        let db = self.db.as_ref();
+        let due = MailOutbox::find()
+            .filter(mail_outbox::Column::Status.eq(MailStatus::Pending.as_str()))
+            .filter(mail_outbox::Column::NextAttemptAt.lte(Utc::now()))
+            .order_by_asc(mail_outbox::Column::NextAttemptAt)
+            .limit(self.config.batch_size)
+            .all(db)
+            .await?;
+
+        let mut processed = 0;
+        for message in due {
+            if !outbox_worker::claim(db, &self.config, &message).await? {
+                continue;
+            }
+            let result = self.deliver(&message).await;
+            self.record_result(message, result).await?;
+            processed += 1;
+        }
+        Ok(processed)
+    }
+
+    async fn deliver(&self, message: &mail_outbox::Model) -> Result<(), ServiceError> {
+        let mailer = Arc::clone(&self.mailer);
+        let mail = OutgoingMail {
+            to: message.recipient.clone(),
+            subject: message.subject.clone(),
+            body: message.body.clone(),
+            html: message.body_html.clone(),
+        };
+        // トランスポートの送信はブロッキング I/O のため専用スレッドで行う
+        tokio::task::spawn_blocking(move || mailer.send(&mail))
+            .await
+            .map_err(|e| ServiceError::Internal(Box::new(e)))?
+    }
+
+    async fn record_result(
+        &self,
+        message: mail_outbox::Model,
+        result: Result<(), ServiceError>,
+    ) -> Result<(), ServiceError> {
+        let now = Utc::now();
+        let attempts = message.attempts + 1;
+        let mail_id = message.id;
+        let mut active: mail_outbox::ActiveModel = message.into();
+        active.attempts = ActiveValue::Set(attempts);
+        active.updated_at = ActiveValue::Set(now);
+
+        match result {
+            Ok(()) => {
+                active.status = ActiveValue::Set(MailStatus::Sent.as_str().to_string());
+                active.sent_at = ActiveValue::Set(Some(now));
+                active.last_error = ActiveValue::Set(None);
+                tracing::info!(mail_id, attempts, "sent outbound mail");
+            }
+            Err(err) => {
+                active.last_error = ActiveValue::Set(Some(error_chain(&err)));
+                if attempts >= self.config.max_attempts {
+                    active.status = ActiveValue::Set(MailStatus::Failed.as_str().to_string());
+                    tracing::error!(mail_id, attempts, error = %err, "giving up on outbound mail");
+                } else {
+                    active.next_attempt_at = ActiveValue::Set(self.config.retry_at(attempts)?);
+                    tracing::warn!(mail_id, attempts, error = %err, "failed to send mail; will retry");
+                }
+            }
+        }
+        active.update(self.db.as_ref()).await?;
+        Ok(())
+    }
+}
+
+impl OutboxWorker for MailOutboxWorker {
+    const QUEUE: &'static str = "mail_outbox";
+
+    fn config(&self) -> &OutboxConfig {
+        &self.config
+    }
+
+    async fn process_due(&self) -> Result<usize, ServiceError> {
+        MailOutboxWorker::process_due(self).await
+    }
+}
+
+impl OutboxRow for mail_outbox::Model {
+    type Entity = MailOutbox;
+    const ID: mail_outbox::Column = mail_outbox::Column::Id;
+    const NEXT_ATTEMPT_AT: mail_outbox::Column = mail_outbox::Column::NextAttemptAt;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn next_attempt_at(&self) -> DateTimeUtc {
+        self.next_attempt_at
+    }
+}
+
+/// `ServiceError` の表示は概要のみのため、原因となったエラーも連結して記録する
+fn error_chain(err: &ServiceError) -> String {
+    let mut message = err.to_string();
+    let mut source = std::error::Error::source(err);
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mail_transport::{LogMailTransport, MailTransport};
+    use crate::test_support::setup_db;
+    use sea_orm::sea_query::Expr;
+
+    fn hello_mail() -> OutgoingMail {
+        OutgoingMail {
+            to: "user@example.com".into(),
+            subject: "Hello".into(),
+            body: "body".into(),
+            html: Some("<p>body</p>".into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn worker_sends_due_messages_and_records_status() {
+        let db = Arc::new(setup_db().await);
+        enqueue(db.as_ref(), &hello_mail()).await.unwrap();
+
+        let worker = MailOutboxWorker::new(Arc::clone(&db), Arc::new(LogMailTransport));
+        assert_eq!(worker.process_due().await.unwrap(), 1);
+        // 送信済みのメールは再送しない
+        assert_eq!(worker.process_due().await.unwrap(), 0);
+
+        let message = MailOutbox::find().one(db.as_ref()).await.unwrap().unwrap();
+        assert_eq!(MailStatus::from(message.status), MailStatus::Sent);
+        assert_eq!(message.attempts, 1);
+        assert!(message.sent_at.is_some());
+    }
+
+    #[derive(Debug)]
+    struct FailingTransport;
+
+    impl MailTransport for FailingTransport {
+        fn send(&self, _mail: &OutgoingMail) -> Result<(), ServiceError> {
+            Err(ServiceError::BadRequest("smtp unavailable".into()))
+        }
+    }
+
+    /// バックオフ待ちを飛ばして次の試行を即座に行えるようにする
+    async fn make_due(db: &DatabaseConnection) {
+        MailOutbox::update_many()
+            .col_expr(mail_outbox::Column::NextAttemptAt, Expr::value(Utc::now()))
+            .exec(db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn failures_are_retried_with_backoff_then_marked_failed() {
+        let db = Arc::new(setup_db().await);
+        enqueue(db.as_ref(), &hello_mail()).await.unwrap();
+        let worker = MailOutboxWorker::new(Arc::clone(&db), Arc::new(FailingTransport))
+            .with_config(OutboxConfig {
+                max_attempts: 2,
+                ..Default::default()
+            });
+
+        assert_eq!(worker.process_due().await.unwrap(), 1);
+        let message = MailOutbox::find().one(db.as_ref()).await.unwrap().unwrap();
+        assert_eq!(MailStatus::from(message.status), MailStatus::Pending);
+        assert!(message.next_attempt_at > Utc::now());
+        // バックオフ中は再送しない
+        assert_eq!(worker.process_due().await.unwrap(), 0);
+
+        make_due(db.as_ref()).await;
+        assert_eq!(worker.process_due().await.unwrap(), 1);
+        let message = MailOutbox::find().one(db.as_ref()).await.unwrap().unwrap();
+        assert_eq!(MailStatus::from(message.status), MailStatus::Failed);
+        assert_eq!(message.attempts, 2);
+        assert!(message.last_error.unwrap().contains("smtp unavailable"));
+    }
+}
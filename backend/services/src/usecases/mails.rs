@@ -1,196 +1,186 @@
 use crate::errors::ServiceError;
+use crate::mail_templates::{self, MailTemplate};
+#[cfg(feature = "mail")]
+use crate::mail_transport::SmtpMailTransport;
+use crate::mail_transport::{
+    FileMailTransport, LogMailTransport, MailTransport, MailTransportSettings,
+};
+use crate::usecases::mail_outbox;
+use minijinja::context;
+use sea_orm::ConnectionTrait;
 use std::env;
 use std::sync::Arc;
 use tracing::info;
-#[cfg(feature = "mail")]
-use tracing::warn;
-
-#[cfg(feature = "mail")]
-use lettre::message::{Mailbox, header::ContentType};
-#[cfg(feature = "mail")]
-use lettre::{Message, SmtpTransport, Transport, transport::smtp::response::Response};
-
-#[cfg(feature = "mail")]
-pub type Mailer = Arc<SmtpTransport>;
+use url::Url;
 
-#[cfg(not(feature = "mail"))]
-#[derive(Debug, Default)]
-pub struct DisabledMailer;
-
-#[cfg(not(feature = "mail"))]
-pub type Mailer = Arc<DisabledMailer>;
-
-#[cfg(feature = "mail")]
-fn is_truthy(value: &str) -> bool {
-    matches!(
-        value.trim().to_ascii_lowercase().as_str(),
-        "1" | "true" | "yes" | "on"
-    )
-}
+pub type Mailer = Arc<dyn MailTransport>;
 
-#[cfg(feature = "mail")]
-fn smtp_disabled() -> bool {
-    if let Ok(value) = env::var("AXUM_DISABLE_SMTP") {
-        if is_truthy(&value) {
-            return true;
+/// 設定に応じたトランスポートを用意する。メールを送らない構成では None を返す。
+pub fn setup_mailer(settings: &MailTransportSettings) -> Result<Option<Mailer>, ServiceError> {
+    match settings {
+        MailTransportSettings::Disabled => {
+            info!("Mail transport is disabled by configuration");
+            Ok(None)
+        }
+        MailTransportSettings::Smtp => setup_smtp_mailer(),
+        MailTransportSettings::File { dir } => {
+            info!(dir = %dir.display(), "Writing outgoing mail to files");
+            Ok(Some(Arc::new(FileMailTransport::new(dir.clone())?)))
+        }
+        MailTransportSettings::Log => {
+            info!("Writing outgoing mail to the log");
+            Ok(Some(Arc::new(LogMailTransport)))
         }
     }
-
-    let app_mode = env::var("APP_MODE")
-        .unwrap_or_else(|_| "local".to_string())
-        .to_ascii_lowercase();
-    app_mode != "web"
 }
 
 #[cfg(feature = "mail")]
-pub fn setup_mailer() -> Result<Option<Mailer>, ServiceError> {
-    if smtp_disabled() {
-        info!("SMTP transport is disabled by configuration");
-        return Ok(None);
-    }
-
-    let smtp_server = match env::var("AXUM_SMTP_SERVER") {
-        Ok(value) => value,
-        Err(_) => {
-            warn!("AXUM_SMTP_SERVER is not set; skipping SMTP setup");
-            return Ok(None);
-        }
-    };
-    let smtp_username = match env::var("AXUM_SMTP_USERNAME") {
-        Ok(value) => value,
-        Err(_) => {
-            warn!("AXUM_SMTP_USERNAME is not set; skipping SMTP setup");
-            return Ok(None);
-        }
-    };
-    let smtp_password = match env::var("AXUM_SMTP_PASSWORD") {
-        Ok(value) => value,
-        Err(_) => {
-            warn!("AXUM_SMTP_PASSWORD is not set; skipping SMTP setup");
-            return Ok(None);
-        }
-    };
-
-    let creds =
-        lettre::transport::smtp::authentication::Credentials::new(smtp_username, smtp_password);
-    let mailer = SmtpTransport::relay(&smtp_server)?
-        .credentials(creds)
-        .build();
-
-    Ok(Some(Arc::new(mailer)))
+fn setup_smtp_mailer() -> Result<Option<Mailer>, ServiceError> {
+    Ok(SmtpMailTransport::from_env()?.map(|transport| Arc::new(transport) as Mailer))
 }
 
 #[cfg(not(feature = "mail"))]
-pub fn setup_mailer() -> Result<Option<Mailer>, ServiceError> {
+fn setup_smtp_mailer() -> Result<Option<Mailer>, ServiceError> {
     info!("SMTP mailer feature disabled; returning no mailer");
     Ok(None)
 }
 
-#[cfg(feature = "mail")]
-fn get_from() -> Result<Mailbox, ServiceError> {
-    let name = env::var("AXUM_MAIL_FROM_NAME").unwrap_or_else(|_| "Default Name".to_string());
-    let email = env::var("AXUM_MAIL_FROM_EMAIL")?;
-    let address = email
-        .parse()
-        .map_err(|e| ServiceError::Internal(Box::new(e)))?;
-    Ok(Mailbox::new(Some(name), address))
+/// メール内のリンクの基準にする公開 URL (`AXUM_PUBLIC_BASE_URL`、未設定なら `FRONTEND_URL`)
+fn public_base_url() -> String {
+    env::var("AXUM_PUBLIC_BASE_URL")
+        .or_else(|_| env::var("FRONTEND_URL"))
+        .unwrap_or_else(|_| "http://localhost:5173".to_string())
 }
 
-#[cfg(feature = "mail")]
-fn send_internal(
-    mailer: &Mailer,
-    email: &str,
-    subject: &str,
-    body: &str,
-) -> Result<Response, ServiceError> {
-    let to = Mailbox::new(
-        Some(subject.to_owned()),
-        email
-            .parse()
-            .map_err(|e| ServiceError::Internal(Box::new(e)))?,
-    );
-    let from = get_from()?;
-
-    let email = Message::builder()
-        .from(from)
-        .to(to)
-        .subject(subject)
-        .header(ContentType::TEXT_PLAIN)
-        .body(String::from(body))
-        .map_err(|e| ServiceError::Internal(Box::new(e)))?;
-
-    Ok(mailer.send(&email)?)
+/// 公開 URL の下にパスとクエリを付けたリンクを組み立てる。各値はエンコードされる。
+pub fn public_link(segments: &[&str], query: &[(&str, &str)]) -> Result<String, ServiceError> {
+    build_link(&public_base_url(), segments, query)
 }
 
-pub fn send_plain_text(
-    mailer: Mailer,
+fn build_link(
+    base_url: &str,
+    segments: &[&str],
+    query: &[(&str, &str)],
+) -> Result<String, ServiceError> {
+    let mut url = Url::parse(base_url).map_err(|e| ServiceError::Internal(Box::new(e)))?;
+    url.path_segments_mut()
+        .map_err(|_| ServiceError::Internal("public base URL cannot have a path".into()))?
+        .pop_if_empty()
+        .extend(segments);
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+    Ok(url.into())
+}
+
+/// 確認リンクを記載したメールを送信キューに登録する。
+pub async fn send_verification_email(
+    db: &impl ConnectionTrait,
     email: &str,
-    subject: &str,
-    body: &str,
+    locale: &str,
+    token: &str,
 ) -> Result<(), ServiceError> {
-    #[cfg(feature = "mail")]
-    {
-        send_internal(&mailer, email, subject, body)?;
-    }
-    #[cfg(not(feature = "mail"))]
-    {
-        let _ = (mailer, email, subject, body);
-        info!("Mail feature disabled; skipping SMTP send");
-    }
-    Ok(())
+    let url = public_link(&["guest", "verify-email", token], &[])?;
+    let mail = mail_templates::render(MailTemplate::VerifyEmail, locale, email, context! { url })?;
+    mail_outbox::enqueue(db, &mail).await
 }
 
-pub fn send_verification_email(
-    mailer: Mailer,
+/// パスワード再設定画面へのリンクを送る。
+pub async fn send_password_reset_email(
+    db: &impl ConnectionTrait,
     email: &str,
+    locale: &str,
+    token: &str,
+) -> Result<(), ServiceError> {
+    let url = public_link(&["guest", "reset-password", token], &[("email", email)])?;
+    let mail =
+        mail_templates::render(MailTemplate::ResetPassword, locale, email, context! { url })?;
+    mail_outbox::enqueue(db, &mail).await
+}
+
+/// 新しいメールアドレス宛てに変更確認リンクを送る。
+pub async fn send_email_change_confirmation(
+    db: &impl ConnectionTrait,
+    new_email: &str,
+    locale: &str,
     token: &str,
 ) -> Result<(), ServiceError> {
-    let frontend_url =
-        env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:5173".to_string());
-    let url = format!(
-        "{}/guest/verify-email/{}",
-        frontend_url.trim_end_matches('/'),
-        token
-    );
-    let body = format!("Verify your email by visiting: {}", url);
-    send_plain_text(mailer, email, "Verify your email", &body)
+    let url = public_link(&["guest", "confirm-email-change", token], &[])?;
+    let mail = mail_templates::render(
+        MailTemplate::EmailChangeConfirmation,
+        locale,
+        new_email,
+        context! { url, new_email },
+    )?;
+    mail_outbox::enqueue(db, &mail).await
 }
 
-#[cfg(all(test, feature = "mail"))]
+/// 旧メールアドレス宛てに変更リクエストがあったことを知らせる。
+pub async fn send_email_change_notice(
+    db: &impl ConnectionTrait,
+    old_email: &str,
+    locale: &str,
+    new_email: &str,
+) -> Result<(), ServiceError> {
+    let mail = mail_templates::render(
+        MailTemplate::EmailChangeNotice,
+        locale,
+        old_email,
+        context! { new_email },
+    )?;
+    mail_outbox::enqueue(db, &mail).await
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
-    use dotenvy::dotenv;
 
+    #[test]
+    fn links_are_built_under_base_path_with_encoded_values() {
+        let link = build_link(
+            "https://example.com/app/",
+            &["guest", "reset-password", "abc"],
+            &[("email", "a+b@example.com")],
+        )
+        .unwrap();
+        assert_eq!(
+            link,
+            "https://example.com/app/guest/reset-password/abc?email=a%2Bb%40example.com"
+        );
+        assert_eq!(
+            build_link(
+                "http://localhost:5173",
+                &["guest", "verify-email", "t"],
+                &[]
+            )
+            .unwrap(),
+            "http://localhost:5173/guest/verify-email/t"
+        );
+        assert!(build_link("not a url", &["guest"], &[]).is_err());
+    }
+
+    #[cfg(feature = "mail")]
     fn mock_address() -> String {
         let address = env::var("AXUM_MOCK_EMAIL").unwrap_or("test@example.com".to_string());
         println!("Using mock email address: {}", address);
         address
     }
 
+    #[cfg(feature = "mail")]
     #[test]
     #[ignore = "外部サービスにメール送信するため通常はスキップ"]
     fn one_shot() {
-        dotenv().ok();
-        unsafe {
-            std::env::set_var("AXUM_DISABLE_SMTP", "0");
-            std::env::set_var("APP_MODE", "web");
-        }
-
-        let from = get_from().expect("from address");
-
-        let email = Message::builder()
-            .from(from)
-            .to(Mailbox::new(None, mock_address().parse().unwrap()))
-            .subject("Happy new year")
-            .header(ContentType::TEXT_PLAIN)
-            .body(String::from("Be happy!"))
-            .unwrap();
-
-        let mailer = setup_mailer()
+        let mailer = setup_mailer(&MailTransportSettings::Smtp)
             .expect("Failed to set up mailer")
             .expect("SMTP transport should be configured for this test");
 
-        match mailer.send(&email) {
+        let mail = crate::mail_transport::OutgoingMail {
+            to: mock_address(),
+            subject: "Happy new year".to_string(),
+            body: "Be happy!".to_string(),
+            html: Some("<p>Be happy!</p>".to_string()),
+        };
+        match mailer.send(&mail) {
             Ok(_) => println!("Email sent successfully!"),
             Err(e) => panic!("Could not send email: {e:?}"),
         }
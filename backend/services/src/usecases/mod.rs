@@ -1,11 +1,27 @@
+pub mod admin;
+pub mod archive;
 pub mod auth;
+pub mod calendar_feed;
+pub mod csv_export;
 pub mod decopon_sessions;
+pub mod idempotency;
+pub mod ids;
+pub mod journal;
 pub mod logs;
+pub mod mail_outbox;
 pub mod mails;
+#[cfg(feature = "oidc")]
+pub mod oidc;
+pub mod outbox_worker;
+pub mod personal_access_tokens;
 pub mod preferences;
 pub mod profiles;
 pub mod single_user;
+pub mod sync;
 pub mod tag_task;
 pub mod tags;
+pub mod task_import;
+pub mod task_outline;
 pub mod tasks;
 pub mod users;
+pub mod webhooks;
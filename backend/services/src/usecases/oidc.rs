@@ -0,0 +1,389 @@
+//! OpenID Connect (authorization code + PKCE) による SSO ログインを扱うユースケースです。
+//! `state`・`nonce`・`code_verifier` は DB に一時保存し、コールバック時に一度だけ消費します。
+//! ID トークンの `sub` を `user_identities` に紐付け、未連携の場合は検証済みメールで既存ユーザーへ
+//! リンクするか、新規ユーザーを自動作成します。
+
+use crate::{
+    entities::{oidc_auth_requests, prelude::*, user_identities, users},
+    errors::ServiceError,
+    password::PasswordWorker,
+    usecases::auth::{self, AuthResponse},
+};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header, jwk::JwkSet};
+use rand::{Rng, distributions::Alphanumeric};
+use reqwest::Url;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// 認可リクエストの有効期間 (分)
+const AUTH_REQUEST_TTL_MINUTES: i64 = 10;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OidcSettings {
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub scopes: Vec<String>,
+}
+
+/// IdP との通信に使う HTTP クライアントと設定をまとめたもの
+#[derive(Clone, Debug)]
+pub struct OidcClient {
+    settings: OidcSettings,
+    http: reqwest::Client,
+}
+
+impl OidcClient {
+    pub fn new(settings: OidcSettings) -> Self {
+        Self {
+            settings,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn settings(&self) -> &OidcSettings {
+        &self.settings
+    }
+
+    async fn discover(&self) -> Result<ProviderMetadata, ServiceError> {
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.settings.issuer_url.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(http_error)?
+            .json()
+            .await
+            .map_err(http_error)?;
+
+        if metadata.issuer.trim_end_matches('/') != self.settings.issuer_url.trim_end_matches('/') {
+            tracing::warn!(issuer = %metadata.issuer, "OIDC discovery returned a mismatched issuer");
+            return Err(ServiceError::Internal("OIDC issuer mismatch".into()));
+        }
+        Ok(metadata)
+    }
+
+    async fn exchange_code(
+        &self,
+        metadata: &ProviderMetadata,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<TokenResponse, ServiceError> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.settings.redirect_url.as_str()),
+            ("client_id", self.settings.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = self.settings.client_secret.as_deref() {
+            form.push(("client_secret", secret));
+        }
+
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(http_error)?;
+        if !response.status().is_success() {
+            tracing::warn!(status = %response.status(), "OIDC token exchange was rejected");
+            return Err(ServiceError::Unauthorized);
+        }
+        response.json().await.map_err(http_error)
+    }
+
+    async fn verify_id_token(
+        &self,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+    ) -> Result<IdTokenClaims, ServiceError> {
+        let header = decode_header(id_token).map_err(|_| ServiceError::Unauthorized)?;
+        let key = match header.alg {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                let secret = self
+                    .settings
+                    .client_secret
+                    .as_deref()
+                    .ok_or(ServiceError::Unauthorized)?;
+                DecodingKey::from_secret(secret.as_bytes())
+            }
+            _ => {
+                let jwks_uri = metadata
+                    .jwks_uri
+                    .as_deref()
+                    .ok_or(ServiceError::Unauthorized)?;
+                let jwks: JwkSet = self
+                    .http
+                    .get(jwks_uri)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(http_error)?
+                    .json()
+                    .await
+                    .map_err(http_error)?;
+                let jwk = match header.kid.as_deref() {
+                    Some(kid) => jwks.find(kid),
+                    None => jwks.keys.first(),
+                }
+                .ok_or(ServiceError::Unauthorized)?;
+                DecodingKey::from_jwk(jwk).map_err(|_| ServiceError::Unauthorized)?
+            }
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[metadata.issuer.as_str()]);
+        validation.set_audience(&[self.settings.client_id.as_str()]);
+
+        let data = decode::<IdTokenClaims>(id_token, &key, &validation).map_err(|e| {
+            tracing::warn!(error = %e, "failed to validate OIDC id_token");
+            ServiceError::Unauthorized
+        })?;
+        Ok(data.claims)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum BoolOrString {
+    Bool(bool),
+    String(String),
+}
+
+impl BoolOrString {
+    fn is_true(&self) -> bool {
+        match self {
+            BoolOrString::Bool(value) => *value,
+            BoolOrString::String(value) => value.eq_ignore_ascii_case("true"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    email: Option<String>,
+    email_verified: Option<BoolOrString>,
+    name: Option<String>,
+    preferred_username: Option<String>,
+    nonce: Option<String>,
+}
+
+impl IdTokenClaims {
+    fn verified_email(&self) -> Option<&str> {
+        let verified = self
+            .email_verified
+            .as_ref()
+            .map(BoolOrString::is_true)
+            .unwrap_or(false);
+        self.email.as_deref().filter(|_| verified)
+    }
+}
+
+/// IdP へリダイレクトするための認可 URL
+pub struct AuthorizationRequest {
+    pub authorization_url: String,
+    pub state: String,
+}
+
+fn http_error(err: reqwest::Error) -> ServiceError {
+    tracing::error!(error = %err, "OIDC provider request failed");
+    ServiceError::Internal(Box::new(err))
+}
+
+fn random_string(length: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+async fn purge_expired_requests(db: &DatabaseConnection) -> Result<(), ServiceError> {
+    OidcAuthRequests::delete_many()
+        .filter(
+            oidc_auth_requests::Column::CreatedAt
+                .lt(Utc::now() - Duration::minutes(AUTH_REQUEST_TTL_MINUTES)),
+        )
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// PKCE パラメータを生成して保存し、IdP の認可エンドポイント URL を組み立てる。
+pub async fn begin_login(
+    db: &DatabaseConnection,
+    client: &OidcClient,
+) -> Result<AuthorizationRequest, ServiceError> {
+    let metadata = client.discover().await?;
+    purge_expired_requests(db).await?;
+
+    let state = random_string(32);
+    let nonce = random_string(32);
+    let code_verifier = random_string(64);
+
+    oidc_auth_requests::ActiveModel {
+        state: Set(state.clone()),
+        nonce: Set(nonce.clone()),
+        code_verifier: Set(code_verifier.clone()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    let settings = client.settings();
+    let mut url = Url::parse(&metadata.authorization_endpoint)
+        .map_err(|e| ServiceError::Internal(Box::new(e)))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &settings.client_id)
+        .append_pair("redirect_uri", &settings.redirect_url)
+        .append_pair("scope", &settings.scopes.join(" "))
+        .append_pair("state", &state)
+        .append_pair("nonce", &nonce)
+        .append_pair("code_challenge", &code_challenge(&code_verifier))
+        .append_pair("code_challenge_method", "S256");
+
+    Ok(AuthorizationRequest {
+        authorization_url: url.into(),
+        state,
+    })
+}
+
+/// 認可コードをトークンへ交換し、ID トークンに対応するユーザーで JWT を発行する。
+pub async fn complete_login(
+    db: &DatabaseConnection,
+    client: &OidcClient,
+    password_worker: &PasswordWorker,
+    jwt_secret: &str,
+    code: &str,
+    state: &str,
+) -> Result<AuthResponse, ServiceError> {
+    let request = OidcAuthRequests::find()
+        .filter(oidc_auth_requests::Column::State.eq(state))
+        .one(db)
+        .await?
+        .ok_or_else(|| ServiceError::BadRequest("Invalid state".into()))?;
+    // state は一度きりの利用とする
+    OidcAuthRequests::delete_by_id(request.id).exec(db).await?;
+    if request.created_at < Utc::now() - Duration::minutes(AUTH_REQUEST_TTL_MINUTES) {
+        return Err(ServiceError::BadRequest("Invalid state".into()));
+    }
+
+    let metadata = client.discover().await?;
+    let tokens = client
+        .exchange_code(&metadata, code, &request.code_verifier)
+        .await?;
+    let claims = client.verify_id_token(&metadata, &tokens.id_token).await?;
+    if claims.nonce.as_deref() != Some(request.nonce.as_str()) {
+        tracing::warn!("OIDC id_token nonce mismatch");
+        return Err(ServiceError::Unauthorized);
+    }
+
+    let user = resolve_user(db, password_worker, &metadata.issuer, &claims).await?;
+    if user.disabled_at.is_some() {
+        return Err(ServiceError::Forbidden);
+    }
+    let token = auth::create_jwt(user.id, jwt_secret)?;
+    Ok(AuthResponse {
+        token,
+        user: user.into(),
+    })
+}
+
+async fn resolve_user(
+    db: &DatabaseConnection,
+    password_worker: &PasswordWorker,
+    issuer: &str,
+    claims: &IdTokenClaims,
+) -> Result<users::Model, ServiceError> {
+    if let Some(identity) = UserIdentities::find()
+        .filter(user_identities::Column::Issuer.eq(issuer))
+        .filter(user_identities::Column::Subject.eq(claims.sub.as_str()))
+        .one(db)
+        .await?
+    {
+        return Users::find_by_id(identity.user_id)
+            .one(db)
+            .await?
+            .ok_or(ServiceError::Unauthorized);
+    }
+
+    // 未連携の IdP アカウントは検証済みメールがなければ紐付けられない
+    let email = claims.verified_email().ok_or(ServiceError::Unauthorized)?;
+    let existing = Users::find()
+        .filter(users::Column::Email.eq(email))
+        .one(db)
+        .await?;
+
+    let user = match existing {
+        Some(user) if user.email_verified_at.is_some() => user,
+        Some(user) => {
+            // メール未確認のアカウントは第三者が登録した可能性があるため、パスワードを無効化して引き継ぐ
+            let mut user_active: users::ActiveModel = user.into();
+            user_active.email_verified_at = Set(Some(Utc::now()));
+            user_active.verification_token = Set(None);
+            user_active.password =
+                Set(auth::hash_password(&random_string(32), password_worker).await?);
+            user_active.update(db).await?
+        }
+        None => {
+            let name = claims
+                .name
+                .clone()
+                .or_else(|| claims.preferred_username.clone())
+                .unwrap_or_else(|| email.split('@').next().unwrap_or(email).to_string());
+            users::ActiveModel {
+                name: Set(name),
+                email: Set(email.to_string()),
+                email_verified_at: Set(Some(Utc::now())),
+                password: Set(auth::hash_password(&random_string(32), password_worker).await?),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?
+        }
+    };
+
+    user_identities::ActiveModel {
+        user_id: Set(user.id),
+        issuer: Set(issuer.to_string()),
+        subject: Set(claims.sub.clone()),
+        email: Set(Some(email.to_string())),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    tracing::info!(user_id = user.id, "linked OIDC identity");
+    Ok(user)
+}
@@ -0,0 +1,124 @@
+//! メールや Webhook の送信キューを定期的に確認して送る、ワーカーに共通の処理です。
+//! 取り出した行は次回送信時刻を先送りして他のワーカーと重複させず、失敗した送信は指数バックオフで再試行します。
+
+use std::{future::Future, time::Duration};
+
+use chrono::Utc;
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use tokio::task::JoinHandle;
+
+use crate::errors::ServiceError;
+
+#[derive(Clone, Debug)]
+pub struct OutboxConfig {
+    /// 送信待ちの行を確認する間隔
+    pub poll_interval: Duration,
+    /// 1 回の確認で送信する最大件数
+    pub batch_size: u64,
+    /// この回数失敗したら `Failed` として再試行をやめる
+    pub max_attempts: i32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// 送信中の行を他のワーカーが重複して取り出さないよう先送りする時間
+    pub lease: Duration,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(5),
+            batch_size: 20,
+            max_attempts: 8,
+            initial_backoff: Duration::from_secs(30),
+            max_backoff: Duration::from_secs(60 * 60),
+            lease: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl OutboxConfig {
+    /// `attempts` 回目の失敗後に待つ時間 (initial_backoff * 2^(attempts-1)、上限 max_backoff)
+    pub fn backoff(&self, attempts: i32) -> Duration {
+        let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(self.max_backoff)
+    }
+
+    /// `attempts` 回目の失敗後に次に送信する時刻
+    pub(crate) fn retry_at(&self, attempts: i32) -> Result<DateTimeUtc, ServiceError> {
+        after(self.backoff(attempts))
+    }
+}
+
+/// 送信キューの行。取り出すときに次回送信時刻を書き換える
+pub(crate) trait OutboxRow {
+    type Entity: EntityTrait;
+    const ID: <Self::Entity as EntityTrait>::Column;
+    const NEXT_ATTEMPT_AT: <Self::Entity as EntityTrait>::Column;
+
+    fn id(&self) -> i32;
+    fn next_attempt_at(&self) -> DateTimeUtc;
+}
+
+/// 次回送信時刻を先送りして取り出す。他のワーカーが先に取り出していれば false。
+pub(crate) async fn claim<R: OutboxRow>(
+    db: &DatabaseConnection,
+    config: &OutboxConfig,
+    row: &R,
+) -> Result<bool, ServiceError> {
+    let result = R::Entity::update_many()
+        .col_expr(R::NEXT_ATTEMPT_AT, Expr::value(after(config.lease)?))
+        .filter(R::ID.eq(row.id()))
+        .filter(R::NEXT_ATTEMPT_AT.eq(row.next_attempt_at()))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
+
+/// 送信時刻に達した行を送るワーカー
+pub(crate) trait OutboxWorker: Send + Sync + 'static {
+    /// ログに出す送信キューの名前
+    const QUEUE: &'static str;
+
+    fn config(&self) -> &OutboxConfig;
+
+    /// 送信時刻に達した行を送り、処理した件数を返す。
+    fn process_due(&self) -> impl Future<Output = Result<usize, ServiceError>> + Send;
+}
+
+/// ワーカーを Tokio のタスクとして起動する。
+pub(crate) fn spawn<W: OutboxWorker>(worker: W) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(worker.config().poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(err) = worker.process_due().await {
+                tracing::error!(queue = W::QUEUE, error = %err, "failed to process outbox");
+            }
+        }
+    })
+}
+
+fn after(duration: Duration) -> Result<DateTimeUtc, ServiceError> {
+    let duration =
+        chrono::Duration::from_std(duration).map_err(|e| ServiceError::Internal(Box::new(e)))?;
+    Ok(Utc::now() + duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_limit() {
+        let config = OutboxConfig::default();
+        assert_eq!(config.backoff(1), Duration::from_secs(30));
+        assert_eq!(config.backoff(2), Duration::from_secs(60));
+        assert_eq!(config.backoff(4), Duration::from_secs(240));
+        assert_eq!(config.backoff(20), Duration::from_secs(60 * 60));
+    }
+}
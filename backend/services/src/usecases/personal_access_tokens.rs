@@ -0,0 +1,238 @@
+//! スクリプトや外部連携向けのパーソナルアクセストークン (PAT) を扱うユースケースです。
+//! 平文トークンは発行時に一度だけ返し、DB には SHA-256 ハッシュのみを保存します。
+
+use crate::{
+    entities::{personal_access_tokens, prelude::*},
+    errors::ServiceError,
+    usecases::auth::hash_token,
+};
+
+use chrono::Utc;
+use rand::{Rng, distributions::Alphanumeric};
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+
+/// JWT と区別するために平文トークンの先頭へ付与するプレフィックス
+pub const TOKEN_PREFIX: &str = "dcp_";
+const TOKEN_RANDOM_LENGTH: usize = 40;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum TokenScope {
+    Read,
+    ReadWrite,
+}
+
+impl TokenScope {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::Read => "Read",
+            TokenScope::ReadWrite => "ReadWrite",
+        }
+    }
+
+    pub fn allows_write(&self) -> bool {
+        matches!(self, TokenScope::ReadWrite)
+    }
+}
+
+impl From<String> for TokenScope {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "ReadWrite" => TokenScope::ReadWrite,
+            _ => TokenScope::Read,
+        }
+    }
+}
+
+pub struct NewPersonalAccessToken {
+    pub name: String,
+    pub scope: TokenScope,
+    pub expires_at: Option<DateTimeUtc>,
+    pub user_id: i32,
+}
+
+pub struct PersonalAccessTokenUpdate {
+    pub id: i32,
+    pub name: Option<String>,
+    pub user_id: i32,
+}
+
+pub struct PersonalAccessToken {
+    pub id: i32,
+    pub name: String,
+    pub scope: TokenScope,
+    pub expires_at: Option<DateTimeUtc>,
+    pub last_used_at: Option<DateTimeUtc>,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+    pub user_id: i32,
+}
+
+impl From<personal_access_tokens::Model> for PersonalAccessToken {
+    fn from(model: personal_access_tokens::Model) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            scope: TokenScope::from(model.scope),
+            expires_at: model.expires_at,
+            last_used_at: model.last_used_at,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            user_id: model.user_id,
+        }
+    }
+}
+
+impl PersonalAccessToken {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map(|expires_at| expires_at <= Utc::now())
+            .unwrap_or(false)
+    }
+}
+
+/// 発行直後のトークン。`plain_text` はこのタイミングでしか取得できない。
+pub struct IssuedPersonalAccessToken {
+    pub token: PersonalAccessToken,
+    pub plain_text: String,
+}
+
+pub fn is_personal_access_token(token: &str) -> bool {
+    token.starts_with(TOKEN_PREFIX)
+}
+
+fn normalize_name(name: &str) -> Result<String, ServiceError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(ServiceError::invalid_field(
+            "name",
+            "blank",
+            "token name must not be empty",
+        ));
+    }
+    Ok(trimmed.to_string())
+}
+
+fn generate_plain_text_token() -> String {
+    let random: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_RANDOM_LENGTH)
+        .map(char::from)
+        .collect();
+    format!("{}{}", TOKEN_PREFIX, random)
+}
+
+pub async fn get_tokens(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Vec<PersonalAccessToken>, ServiceError> {
+    let tokens = PersonalAccessTokens::find()
+        .filter(personal_access_tokens::Column::UserId.eq(user_id))
+        .order_by_desc(personal_access_tokens::Column::CreatedAt)
+        .all(db)
+        .await?;
+    Ok(tokens.into_iter().map(Into::into).collect())
+}
+
+pub async fn insert_token(
+    db: &DatabaseConnection,
+    params: NewPersonalAccessToken,
+) -> Result<IssuedPersonalAccessToken, ServiceError> {
+    let name = normalize_name(&params.name)?;
+    if let Some(expires_at) = params.expires_at
+        && expires_at <= Utc::now()
+    {
+        return Err(ServiceError::invalid_field(
+            "expires_at",
+            "not_in_future",
+            "expires_at must be in the future",
+        ));
+    }
+
+    let plain_text = generate_plain_text_token();
+    let new_token = personal_access_tokens::ActiveModel {
+        name: ActiveValue::Set(name),
+        token_hash: ActiveValue::Set(hash_token(&plain_text)),
+        scope: ActiveValue::Set(params.scope.as_str().to_owned()),
+        expires_at: ActiveValue::Set(params.expires_at),
+        user_id: ActiveValue::Set(params.user_id),
+        ..Default::default()
+    };
+    let result = PersonalAccessTokens::insert(new_token).exec(db).await?;
+    let token = PersonalAccessTokens::find_by_id(result.last_insert_id)
+        .one(db)
+        .await?
+        .ok_or(ServiceError::NotFound("personal_access_token"))?;
+
+    Ok(IssuedPersonalAccessToken {
+        token: token.into(),
+        plain_text,
+    })
+}
+
+pub async fn update_token(
+    db: &DatabaseConnection,
+    params: PersonalAccessTokenUpdate,
+) -> Result<PersonalAccessToken, ServiceError> {
+    let mut token: personal_access_tokens::ActiveModel =
+        PersonalAccessTokens::find_by_id(params.id)
+            .filter(personal_access_tokens::Column::UserId.eq(params.user_id))
+            .one(db)
+            .await?
+            .ok_or(ServiceError::NotFound("personal_access_token"))?
+            .into();
+
+    if let Some(name) = params.name {
+        token.name = ActiveValue::Set(normalize_name(&name)?);
+    }
+    token.updated_at = ActiveValue::Set(Utc::now());
+
+    let token = token.update(db).await?;
+    Ok(token.into())
+}
+
+pub async fn delete_token(
+    db: &DatabaseConnection,
+    id: i32,
+    user_id: i32,
+) -> Result<(), ServiceError> {
+    let result = PersonalAccessTokens::delete_many()
+        .filter(personal_access_tokens::Column::Id.eq(id))
+        .filter(personal_access_tokens::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(ServiceError::NotFound("personal_access_token"));
+    }
+    Ok(())
+}
+
+/// 平文トークンを検証し、有効であれば最終利用日時を記録してトークン情報を返す。
+pub async fn authenticate_token(
+    db: &DatabaseConnection,
+    plain_text: &str,
+) -> Result<PersonalAccessToken, ServiceError> {
+    if !is_personal_access_token(plain_text) {
+        return Err(ServiceError::Unauthorized);
+    }
+
+    let model = PersonalAccessTokens::find()
+        .filter(personal_access_tokens::Column::TokenHash.eq(hash_token(plain_text)))
+        .one(db)
+        .await?
+        .ok_or(ServiceError::Unauthorized)?;
+
+    if PersonalAccessToken::from(model.clone()).is_expired() {
+        return Err(ServiceError::Unauthorized);
+    }
+
+    let mut token: personal_access_tokens::ActiveModel = model.into();
+    token.last_used_at = ActiveValue::Set(Some(Utc::now()));
+    let token = token.update(db).await?;
+    Ok(token.into())
+}
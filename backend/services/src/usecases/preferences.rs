@@ -6,6 +6,10 @@ pub struct UpdatePreference {
     pub work_time: i32,
     pub break_time: i32,
     pub locale: String,
+    /// None のときは変更しない
+    pub daily_journal_email: Option<bool>,
+    /// None のときは変更しない
+    pub daily_goal: Option<i32>,
 }
 
 pub async fn update_preference(
@@ -22,6 +26,12 @@ pub async fn update_preference(
     user.work_time = ActiveValue::Set(params.work_time);
     user.break_time = ActiveValue::Set(params.break_time);
     user.locale = ActiveValue::Set(params.locale);
+    if let Some(daily_journal_email) = params.daily_journal_email {
+        user.daily_journal_email = ActiveValue::Set(daily_journal_email);
+    }
+    if let Some(daily_goal) = params.daily_goal {
+        user.daily_goal = ActiveValue::Set(daily_goal);
+    }
     user.updated_at = ActiveValue::Set(Utc::now());
 
     let user = user.update(db).await?;
@@ -1,11 +1,18 @@
 use crate::{
     entities::users,
     errors::ServiceError,
-    usecases::{auth, users as user_usecase},
+    password::PasswordWorker,
+    usecases::{auth, mails, users as user_usecase},
 };
-use axum_password_worker::{Bcrypt, PasswordWorker};
-use chrono::Utc;
-use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection, EntityTrait};
+use chrono::{Duration, Utc};
+use rand::{Rng, distributions::Alphanumeric};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    TransactionTrait,
+};
+
+/// メールアドレス変更の確認リンクの有効期間 (時間)
+const EMAIL_CHANGE_TTL_HOURS: i64 = 24;
 
 pub struct UpdateProfile {
     pub name: Option<String>,
@@ -30,35 +37,133 @@ pub async fn get_profile(
     Ok(user.into())
 }
 
+async fn ensure_email_available(
+    db: &DatabaseConnection,
+    email: &str,
+    user_id: i32,
+) -> Result<(), ServiceError> {
+    let taken = users::Entity::find()
+        .filter(users::Column::Email.eq(email))
+        .filter(users::Column::Id.ne(user_id))
+        .one(db)
+        .await?
+        .is_some();
+    if taken {
+        return Err(ServiceError::Conflict("email"));
+    }
+    Ok(())
+}
+
+/// プロフィールを更新する。
+/// メールアドレスの変更は確認リンクが消費されるまで `pending_email` に保留し、旧アドレスでのログインを維持する。
+/// メール送信が無効な環境では確認手段がないため即時に反映する。
 pub async fn update_profile(
     db: &DatabaseConnection,
+    mailer: Option<&mails::Mailer>,
     user_id: i32,
     params: UpdateProfile,
 ) -> Result<user_usecase::User, ServiceError> {
-    let mut user: users::ActiveModel = users::Entity::find_by_id(user_id)
+    let current = users::Entity::find_by_id(user_id)
         .one(db)
         .await?
-        .ok_or(ServiceError::NotFound("user"))?
-        .into();
+        .ok_or(ServiceError::NotFound("user"))?;
+    let old_email = current.email.clone();
+    let mut user: users::ActiveModel = current.into();
 
     if let Some(name) = params.name {
         user.name = ActiveValue::Set(name);
     }
 
-    if let Some(email) = params.email {
-        user.email = ActiveValue::Set(email);
-        user.email_verified_at = ActiveValue::Set(None);
+    let mut confirmation = None;
+    match params.email.map(|email| email.trim().to_string()) {
+        // 現在のアドレスを指定した場合は保留中の変更を取り消す
+        Some(email) if email == old_email => {
+            user.pending_email = ActiveValue::Set(None);
+            user.email_change_token = ActiveValue::Set(None);
+            user.email_change_requested_at = ActiveValue::Set(None);
+        }
+        Some(email) => {
+            if email.is_empty() {
+                return Err(ServiceError::invalid_field(
+                    "email",
+                    "blank",
+                    "email must not be empty",
+                ));
+            }
+            ensure_email_available(db, &email, user_id).await?;
+
+            if mailer.is_some() {
+                let raw_token: String = rand::thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(32)
+                    .map(char::from)
+                    .collect();
+                user.pending_email = ActiveValue::Set(Some(email.clone()));
+                user.email_change_token = ActiveValue::Set(Some(auth::hash_token(&raw_token)));
+                user.email_change_requested_at = ActiveValue::Set(Some(Utc::now()));
+                confirmation = Some((email, raw_token));
+            } else {
+                tracing::info!(
+                    "Applying email change immediately because mail transport is disabled"
+                );
+                user.email = ActiveValue::Set(email);
+                user.pending_email = ActiveValue::Set(None);
+                user.email_change_token = ActiveValue::Set(None);
+                user.email_change_requested_at = ActiveValue::Set(None);
+            }
+        }
+        None => {}
     }
 
     user.updated_at = ActiveValue::Set(Utc::now());
 
+    // 確認メールを積めなければ、保留中の変更も残さない
+    let txn = db.begin().await?;
+    let user = user.update(&txn).await?;
+    if let Some((new_email, raw_token)) = confirmation {
+        mails::send_email_change_confirmation(&txn, &new_email, &user.locale, &raw_token).await?;
+        mails::send_email_change_notice(&txn, &old_email, &user.locale, &new_email).await?;
+    }
+    txn.commit().await?;
+    Ok(user.into())
+}
+
+/// 確認リンクのトークンを消費し、保留中のメールアドレスを確定する。
+pub async fn confirm_email_change(
+    db: &DatabaseConnection,
+    token: &str,
+) -> Result<user_usecase::User, ServiceError> {
+    let user = users::Entity::find()
+        .filter(users::Column::EmailChangeToken.eq(auth::hash_token(token)))
+        .one(db)
+        .await?
+        .ok_or_else(|| ServiceError::BadRequest("Invalid token".into()))?;
+
+    let expired = user
+        .email_change_requested_at
+        .map(|requested_at| requested_at + Duration::hours(EMAIL_CHANGE_TTL_HOURS) < Utc::now())
+        .unwrap_or(true);
+    let Some(new_email) = user.pending_email.clone().filter(|_| !expired) else {
+        return Err(ServiceError::BadRequest("Invalid token".into()));
+    };
+    // 確認までの間に他のユーザーが同じアドレスを取得している可能性がある
+    ensure_email_available(db, &new_email, user.id).await?;
+
+    let mut user: users::ActiveModel = user.into();
+    user.email = ActiveValue::Set(new_email);
+    user.email_verified_at = ActiveValue::Set(Some(Utc::now()));
+    user.pending_email = ActiveValue::Set(None);
+    user.email_change_token = ActiveValue::Set(None);
+    user.email_change_requested_at = ActiveValue::Set(None);
+    user.updated_at = ActiveValue::Set(Utc::now());
+
     let user = user.update(db).await?;
     Ok(user.into())
 }
 
 pub async fn update_password(
     db: &DatabaseConnection,
-    password_worker: &PasswordWorker<Bcrypt>,
+    password_worker: &PasswordWorker,
     user_id: i32,
     params: UpdatePassword,
 ) -> Result<(), ServiceError> {
@@ -85,7 +190,7 @@ pub async fn update_password(
 
 pub async fn delete_profile(
     db: &DatabaseConnection,
-    password_worker: &PasswordWorker<Bcrypt>,
+    password_worker: &PasswordWorker,
     user_id: i32,
     params: DeleteProfile,
 ) -> Result<(), ServiceError> {
@@ -104,3 +209,140 @@ pub async fn delete_profile(
     user.delete(db).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mail_transport::LogMailTransport;
+    use crate::test_support::{create_user, setup_db};
+    use sea_orm::{ConnectionTrait, Set};
+    use std::sync::Arc;
+
+    /// 確認メールに載る平文トークンはテストから取得できないため、既知の値に差し替える。
+    async fn set_known_token(db: &DatabaseConnection, user_id: i32, token: &str) {
+        users::ActiveModel {
+            id: Set(user_id),
+            email_change_token: Set(Some(auth::hash_token(token))),
+            ..Default::default()
+        }
+        .update(db)
+        .await
+        .unwrap();
+    }
+
+    fn change_email(email: &str) -> UpdateProfile {
+        UpdateProfile {
+            name: None,
+            email: Some(email.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn email_change_stays_pending_until_confirmed() {
+        let db = setup_db().await;
+        let user = create_user(&db, "alice").await;
+        let mailer: mails::Mailer = Arc::new(LogMailTransport);
+
+        let updated = update_profile(&db, Some(&mailer), user.id, change_email("new@example.com"))
+            .await
+            .unwrap();
+        assert_eq!(updated.email, "alice@example.com");
+        assert_eq!(updated.pending_email.as_deref(), Some("new@example.com"));
+
+        let stored = users::Entity::find_by_id(user.id)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stored.email_verified_at.is_some());
+        assert!(stored.email_change_token.is_some());
+
+        set_known_token(&db, user.id, "known-token").await;
+        let confirmed = confirm_email_change(&db, "known-token").await.unwrap();
+        assert_eq!(confirmed.email, "new@example.com");
+        assert!(confirmed.pending_email.is_none());
+
+        // トークンは一度しか使えない
+        assert!(matches!(
+            confirm_email_change(&db, "known-token").await,
+            Err(ServiceError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn email_change_rejects_taken_address() {
+        let db = setup_db().await;
+        let user = create_user(&db, "alice").await;
+        create_user(&db, "bob").await;
+        let mailer: mails::Mailer = Arc::new(LogMailTransport);
+
+        let result =
+            update_profile(&db, Some(&mailer), user.id, change_email("bob@example.com")).await;
+        assert!(matches!(result, Err(ServiceError::Conflict("email"))));
+    }
+
+    #[tokio::test]
+    async fn email_change_rolls_back_when_mail_cannot_be_queued() {
+        let db = setup_db().await;
+        let user = create_user(&db, "alice").await;
+        let mailer: mails::Mailer = Arc::new(LogMailTransport);
+        db.execute_unprepared("DROP TABLE mail_outbox")
+            .await
+            .unwrap();
+
+        let result =
+            update_profile(&db, Some(&mailer), user.id, change_email("new@example.com")).await;
+        assert!(result.is_err());
+        let stored = users::Entity::find_by_id(user.id)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stored.pending_email.is_none());
+        assert!(stored.email_change_token.is_none());
+    }
+
+    #[tokio::test]
+    async fn email_change_without_mailer_applies_immediately() {
+        let db = setup_db().await;
+        let user = create_user(&db, "alice").await;
+
+        let updated = update_profile(&db, None, user.id, change_email("new@example.com"))
+            .await
+            .unwrap();
+        assert_eq!(updated.email, "new@example.com");
+        assert!(updated.pending_email.is_none());
+    }
+
+    #[tokio::test]
+    async fn expired_email_change_token_is_rejected() {
+        let db = setup_db().await;
+        let user = create_user(&db, "alice").await;
+        let mailer: mails::Mailer = Arc::new(LogMailTransport);
+        update_profile(&db, Some(&mailer), user.id, change_email("new@example.com"))
+            .await
+            .unwrap();
+        set_known_token(&db, user.id, "stale-token").await;
+        users::ActiveModel {
+            id: Set(user.id),
+            email_change_requested_at: Set(Some(
+                Utc::now() - Duration::hours(EMAIL_CHANGE_TTL_HOURS + 1),
+            )),
+            ..Default::default()
+        }
+        .update(&db)
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            confirm_email_change(&db, "stale-token").await,
+            Err(ServiceError::BadRequest(_))
+        ));
+        let stored = users::Entity::find_by_id(user.id)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.email, "alice@example.com");
+    }
+}
@@ -4,9 +4,9 @@
 use crate::{
     entities::users as users_entity,
     errors::ServiceError,
+    password::PasswordWorker,
     usecases::{auth, users::User},
 };
-use axum_password_worker::{Bcrypt, PasswordWorker};
 use chrono::Utc;
 use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
 use std::env;
@@ -70,7 +70,7 @@ fn parse_i32_env(key: &str, default: i32) -> i32 {
 
 pub async fn ensure_user(
     db: &DatabaseConnection,
-    password_worker: &PasswordWorker<Bcrypt>,
+    password_worker: &PasswordWorker,
     jwt_secret: &str,
 ) -> Result<SingleUserSession, ServiceError> {
     let config = SingleUserConfig::from_env();
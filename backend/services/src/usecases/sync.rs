@@ -0,0 +1,1291 @@
+//! ローカルのアプリの DB と Web のアカウントの間でデータを同期するための変更履歴です。
+//! タグ・タスク・ログ・セッションへの変更を UUID と論理時計つきで `sync_changes` に記録し、
+//! 相手から受け取った変更は種類ごとの規則で衝突を解決してから取り込みます。
+//!
+//! - 削除は常に優先し、一度削除した UUID の行は作り直さない
+//! - タグとタスクは後勝ち。時計が並んだときは発生元の名前の大きい方を採る
+//! - 既存のタスクの親の付け替えは取り込まない
+//! - 完了したセッションは未完了へ戻さない。完了は時計に関わらず取り込む
+//! - ログは追記のみ。本文は作成後に変えず、タグの付け替えだけを後勝ちで取り込む
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DatabaseTransaction,
+    EntityTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    entities::{prelude::*, *},
+    errors::ServiceError,
+};
+
+use super::{logs as logs_usecase, tag_task as tag_task_usecase, tasks as tasks_usecase};
+
+/// Web 側で直接行われた変更の発生元
+pub const WEB_ORIGIN: &str = "web";
+/// 一度に読み出す変更履歴の最大件数
+pub const CHANGES_PAGE_LIMIT: u64 = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SyncEntity {
+    Tag,
+    Task,
+    Log,
+    DecoponSession,
+}
+
+impl SyncEntity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncEntity::Tag => "tag",
+            SyncEntity::Task => "task",
+            SyncEntity::Log => "log",
+            SyncEntity::DecoponSession => "decopon_session",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "tag" => Some(SyncEntity::Tag),
+            "task" => Some(SyncEntity::Task),
+            "log" => Some(SyncEntity::Log),
+            "decopon_session" => Some(SyncEntity::DecoponSession),
+            _ => None,
+        }
+    }
+
+    /// 参照される側から順に取り込むための並び
+    fn apply_order(&self) -> u8 {
+        match self {
+            SyncEntity::Tag => 0,
+            SyncEntity::Task => 1,
+            SyncEntity::Log => 2,
+            SyncEntity::DecoponSession => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SyncOperation {
+    Upsert,
+    Delete,
+}
+
+impl SyncOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncOperation::Upsert => "Upsert",
+            SyncOperation::Delete => "Delete",
+        }
+    }
+}
+
+impl From<String> for SyncOperation {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "Delete" => SyncOperation::Delete,
+            _ => SyncOperation::Upsert,
+        }
+    }
+}
+
+/// 同期でやり取りする一行分の変更
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SyncChange {
+    pub entity: SyncEntity,
+    pub uuid: String,
+    pub operation: SyncOperation,
+    /// 変更時の論理時計。ミリ秒単位の時刻を下回らず、同じユーザーの中で単調に増える
+    pub clock: i64,
+    /// 変更を行った端末の ID。Web 側での変更は `web`
+    pub origin: String,
+    /// upsert のときの行の内容
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub data: Option<serde_json::Value>,
+}
+
+pub struct ChangePage {
+    pub changes: Vec<SyncChange>,
+    /// 次回の `since` に渡す値
+    pub cursor: i32,
+    pub has_more: bool,
+}
+
+/// 衝突の解決などで取り込まなかった変更と、その理由
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SkippedChange {
+    pub entity: SyncEntity,
+    pub uuid: String,
+    /// `deleted` / `stale` / `completed` / `parent_missing` / `invalid_data`
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ApplyResult {
+    pub applied: usize,
+    pub skipped: Vec<SkippedChange>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TagData {
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TaskData {
+    title: String,
+    description: String,
+    completed: bool,
+    parent_uuid: Option<String>,
+    #[serde(default)]
+    tag_uuids: Vec<String>,
+    #[serde(default)]
+    due_at: Option<DateTimeUtc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LogData {
+    content: String,
+    source: String,
+    event_code: Option<String>,
+    event_params: Option<String>,
+    task_uuid: Option<String>,
+    #[serde(default)]
+    tag_uuids: Vec<String>,
+    created_at: DateTimeUtc,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionData {
+    status: String,
+    started_at: DateTimeUtc,
+    ended_at: Option<DateTimeUtc>,
+}
+
+/// 削除を記録するために、削除する前に控えておく行の識別子
+pub(crate) struct RowKey {
+    entity: SyncEntity,
+    user_id: i32,
+    uuid: String,
+}
+
+/// 作成・更新した行を変更履歴に記録する
+pub(crate) async fn record_upsert(
+    conn: &impl ConnectionTrait,
+    entity: SyncEntity,
+    id: i32,
+) -> Result<(), ServiceError> {
+    for key in row_keys(conn, entity, vec![id]).await? {
+        record(conn, &key, SyncOperation::Upsert, None).await?;
+    }
+    Ok(())
+}
+
+/// `row_keys` などで控えておいた行の削除を変更履歴に記録する
+pub(crate) async fn record_deletes(
+    conn: &impl ConnectionTrait,
+    keys: &[RowKey],
+) -> Result<(), ServiceError> {
+    for key in keys {
+        record(conn, key, SyncOperation::Delete, None).await?;
+    }
+    Ok(())
+}
+
+pub(crate) async fn row_keys(
+    conn: &impl ConnectionTrait,
+    entity: SyncEntity,
+    ids: Vec<i32>,
+) -> Result<Vec<RowKey>, ServiceError> {
+    let rows: Vec<(i32, String)> = match entity {
+        SyncEntity::Tag => {
+            Tags::find()
+                .select_only()
+                .columns([tags::Column::UserId, tags::Column::Uuid])
+                .filter(tags::Column::Id.is_in(ids))
+                .into_tuple()
+                .all(conn)
+                .await?
+        }
+        SyncEntity::Task => {
+            Tasks::find()
+                .select_only()
+                .columns([tasks::Column::UserId, tasks::Column::Uuid])
+                .filter(tasks::Column::Id.is_in(ids))
+                .into_tuple()
+                .all(conn)
+                .await?
+        }
+        SyncEntity::Log => {
+            Logs::find()
+                .select_only()
+                .columns([logs::Column::UserId, logs::Column::Uuid])
+                .filter(logs::Column::Id.is_in(ids))
+                .into_tuple()
+                .all(conn)
+                .await?
+        }
+        SyncEntity::DecoponSession => {
+            DecoponSessions::find()
+                .select_only()
+                .columns([
+                    decopon_sessions::Column::UserId,
+                    decopon_sessions::Column::Uuid,
+                ])
+                .filter(decopon_sessions::Column::Id.is_in(ids))
+                .into_tuple()
+                .all(conn)
+                .await?
+        }
+    };
+    Ok(rows
+        .into_iter()
+        .map(|(user_id, uuid)| RowKey {
+            entity,
+            user_id,
+            uuid,
+        })
+        .collect())
+}
+
+/// タスクを削除すると連鎖して消える、子孫のタスクと紐づくログの識別子
+pub(crate) async fn task_tree_keys(
+    conn: &impl ConnectionTrait,
+    user_id: i32,
+    task_id: i32,
+) -> Result<Vec<RowKey>, ServiceError> {
+    let task_ids: Vec<i32> = tasks_usecase::query_task_subtree_rows(conn, user_id, task_id)
+        .await?
+        .into_iter()
+        .map(|row| row.id)
+        .collect();
+    let log_ids: Vec<i32> = Logs::find()
+        .select_only()
+        .column(logs::Column::Id)
+        .filter(logs::Column::TaskId.is_in(task_ids.clone()))
+        .into_tuple()
+        .all(conn)
+        .await?;
+    let mut keys = row_keys(conn, SyncEntity::Task, task_ids).await?;
+    keys.extend(row_keys(conn, SyncEntity::Log, log_ids).await?);
+    Ok(keys)
+}
+
+/// 変更を記録する。相手から取り込んだ変更は、その時計と発生元をそのまま残す
+async fn record(
+    conn: &impl ConnectionTrait,
+    key: &RowKey,
+    operation: SyncOperation,
+    remote: Option<&SyncChange>,
+) -> Result<(), ServiceError> {
+    let (clock, origin) = match remote {
+        Some(change) => (change.clock, Some(change.origin.clone())),
+        None => (next_clock(conn, key.user_id).await?, None),
+    };
+    let change = sync_changes::ActiveModel {
+        user_id: ActiveValue::Set(key.user_id),
+        entity: ActiveValue::Set(key.entity.as_str().to_string()),
+        entity_uuid: ActiveValue::Set(key.uuid.clone()),
+        operation: ActiveValue::Set(operation.as_str().to_string()),
+        clock: ActiveValue::Set(clock),
+        origin: ActiveValue::Set(origin),
+        created_at: ActiveValue::Set(Utc::now()),
+        ..Default::default()
+    };
+    SyncChanges::insert(change).exec(conn).await?;
+    Ok(())
+}
+
+/// 現在時刻 (ミリ秒) と、これまでの最大の時計の次の値の大きい方
+async fn next_clock(conn: &impl ConnectionTrait, user_id: i32) -> Result<i64, ServiceError> {
+    let latest: Option<Option<i64>> = SyncChanges::find()
+        .select_only()
+        .column_as(sync_changes::Column::Clock.max(), "clock")
+        .filter(sync_changes::Column::UserId.eq(user_id))
+        .into_tuple()
+        .one(conn)
+        .await?;
+    let latest = latest.flatten().unwrap_or(0);
+    Ok(Utc::now().timestamp_millis().max(latest + 1))
+}
+
+/// `since` より後に記録された変更を行ごとに最新の一件へまとめて返す。
+/// `exclude_origin` に指定した端末から取り込んだ変更は、その端末へ送り返さないよう除く
+pub async fn changes_since(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+    since: i32,
+    exclude_origin: Option<&str>,
+) -> Result<ChangePage, ServiceError> {
+    let mut condition = Condition::all();
+    if let Some(origin) = exclude_origin {
+        condition = condition.add(
+            Condition::any()
+                .add(sync_changes::Column::Origin.is_null())
+                .add(sync_changes::Column::Origin.ne(origin)),
+        );
+    }
+    collect_changes(db, user_id, since, condition, WEB_ORIGIN).await
+}
+
+/// この DB で直接行われた変更だけを返す。端末から Web へ送る変更の取得に使う
+pub async fn local_changes_since(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+    since: i32,
+    device_id: &str,
+) -> Result<ChangePage, ServiceError> {
+    let condition = Condition::all().add(sync_changes::Column::Origin.is_null());
+    collect_changes(db, user_id, since, condition, device_id).await
+}
+
+async fn collect_changes(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+    since: i32,
+    condition: Condition,
+    native_origin: &str,
+) -> Result<ChangePage, ServiceError> {
+    let rows = SyncChanges::find()
+        .filter(sync_changes::Column::UserId.eq(user_id))
+        .filter(sync_changes::Column::Id.gt(since))
+        .filter(condition)
+        .order_by_asc(sync_changes::Column::Id)
+        .limit(CHANGES_PAGE_LIMIT)
+        .all(db)
+        .await?;
+    let has_more = rows.len() as u64 == CHANGES_PAGE_LIMIT;
+    let cursor = rows.last().map(|row| row.id).unwrap_or(since);
+
+    // 同じ行への変更は最後の一件だけを送る
+    let last_index: HashMap<(String, String), usize> = rows
+        .iter()
+        .enumerate()
+        .map(|(index, row)| ((row.entity.clone(), row.entity_uuid.clone()), index))
+        .collect();
+    let mut changes = Vec::new();
+    for (index, row) in rows.into_iter().enumerate() {
+        if last_index[&(row.entity.clone(), row.entity_uuid.clone())] != index {
+            continue;
+        }
+        let Some(entity) = SyncEntity::parse(&row.entity) else {
+            continue;
+        };
+        let operation = SyncOperation::from(row.operation);
+        let data = match operation {
+            // 後から削除された行は、続く削除の記録で伝わる
+            SyncOperation::Upsert => match snapshot(db, user_id, entity, &row.entity_uuid).await? {
+                Some(data) => Some(data),
+                None => continue,
+            },
+            SyncOperation::Delete => None,
+        };
+        changes.push(SyncChange {
+            entity,
+            uuid: row.entity_uuid,
+            operation,
+            clock: row.clock,
+            origin: row.origin.unwrap_or_else(|| native_origin.to_string()),
+            data,
+        });
+    }
+
+    Ok(ChangePage {
+        changes,
+        cursor,
+        has_more,
+    })
+}
+
+async fn snapshot(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+    entity: SyncEntity,
+    uuid: &str,
+) -> Result<Option<serde_json::Value>, ServiceError> {
+    let data = match entity {
+        SyncEntity::Tag => find_tag(db, user_id, uuid)
+            .await?
+            .map(|tag| to_value(&TagData { name: tag.name })),
+        SyncEntity::Task => {
+            let Some((task, tags)) = Tasks::find()
+                .filter(tasks::Column::UserId.eq(user_id))
+                .filter(tasks::Column::Uuid.eq(uuid))
+                .find_with_related(Tags)
+                .all(db)
+                .await?
+                .into_iter()
+                .next()
+            else {
+                return Ok(None);
+            };
+            let parent_uuid = match task.parent_task_id {
+                Some(parent_id) => uuid_of_task(db, parent_id).await?,
+                None => None,
+            };
+            Some(to_value(&TaskData {
+                title: task.title,
+                description: task.description,
+                completed: task.completed,
+                parent_uuid,
+                tag_uuids: tags.into_iter().map(|tag| tag.uuid).collect(),
+                due_at: task.due_at,
+            }))
+        }
+        SyncEntity::Log => {
+            let Some((log, tags)) = Logs::find()
+                .filter(logs::Column::UserId.eq(user_id))
+                .filter(logs::Column::Uuid.eq(uuid))
+                .find_with_related(Tags)
+                .all(db)
+                .await?
+                .into_iter()
+                .next()
+            else {
+                return Ok(None);
+            };
+            let task_uuid = match log.task_id {
+                Some(task_id) => uuid_of_task(db, task_id).await?,
+                None => None,
+            };
+            Some(to_value(&LogData {
+                content: log.content,
+                source: log.source,
+                event_code: log.event_code,
+                event_params: log.event_params,
+                task_uuid,
+                tag_uuids: tags.into_iter().map(|tag| tag.uuid).collect(),
+                created_at: log.created_at,
+            }))
+        }
+        SyncEntity::DecoponSession => find_session(db, user_id, uuid).await?.map(|session| {
+            to_value(&SessionData {
+                status: session.status,
+                started_at: session.started_at,
+                ended_at: session.ended_at,
+            })
+        }),
+    };
+    Ok(data)
+}
+
+fn to_value(data: &impl Serialize) -> serde_json::Value {
+    serde_json::to_value(data).unwrap_or_default()
+}
+
+/// 相手から受け取った変更を一つのトランザクションで取り込む。
+/// `native_origin` はこの DB で直接行われた変更の発生元の名前で、時計が並んだときの比較に使う
+pub async fn apply_changes(
+    db: &(impl ConnectionTrait + TransactionTrait),
+    user_id: i32,
+    native_origin: &str,
+    changes: Vec<SyncChange>,
+) -> Result<ApplyResult, ServiceError> {
+    let (deletes, mut upserts): (Vec<_>, Vec<_>) = changes
+        .into_iter()
+        .partition(|change| change.operation == SyncOperation::Delete);
+    upserts.sort_by_key(|change| change.entity.apply_order());
+    let upserts = parents_first(upserts);
+
+    let txn = db.begin().await?;
+    let mut result = ApplyResult::default();
+    for change in upserts.iter().chain(deletes.iter()) {
+        let outcome = if has_tombstone(&txn, user_id, change).await? {
+            Err("deleted")
+        } else if change.operation == SyncOperation::Delete {
+            apply_delete(&txn, user_id, change).await.map(Ok)?
+        } else {
+            apply_upsert(&txn, user_id, native_origin, change).await?
+        };
+        match outcome {
+            Ok(()) => result.applied += 1,
+            Err(reason) => result.skipped.push(SkippedChange {
+                entity: change.entity,
+                uuid: change.uuid.clone(),
+                reason: reason.to_string(),
+            }),
+        }
+    }
+    txn.commit().await?;
+    Ok(result)
+}
+
+/// 端末から送られた変更を Web 側の DB へ取り込む。発生元は送ってきた端末として記録する
+pub async fn push_changes(
+    db: &(impl ConnectionTrait + TransactionTrait),
+    user_id: i32,
+    device_id: &str,
+    changes: Vec<SyncChange>,
+) -> Result<ApplyResult, ServiceError> {
+    let device_id = device_id.trim();
+    if device_id.is_empty() || device_id == WEB_ORIGIN {
+        return Err(ServiceError::invalid_field(
+            "device_id",
+            "reserved",
+            format!("device_id must be a non-empty name other than `{WEB_ORIGIN}`"),
+        ));
+    }
+    let changes = changes
+        .into_iter()
+        .map(|change| SyncChange {
+            origin: device_id.to_string(),
+            ..change
+        })
+        .collect();
+    apply_changes(db, user_id, WEB_ORIGIN, changes).await
+}
+
+/// 同じ同期で届いた親タスクを子より先に作れるよう並べ替える
+fn parents_first(changes: Vec<SyncChange>) -> Vec<SyncChange> {
+    let parent_of = |change: &SyncChange| -> Option<String> {
+        if change.entity != SyncEntity::Task {
+            return None;
+        }
+        change.data.as_ref()?["parent_uuid"]
+            .as_str()
+            .map(str::to_string)
+    };
+    let mut ordered = Vec::with_capacity(changes.len());
+    let mut pending = changes;
+    while !pending.is_empty() {
+        let waiting: HashSet<String> = pending
+            .iter()
+            .filter(|change| change.entity == SyncEntity::Task)
+            .map(|change| change.uuid.clone())
+            .collect();
+        let (ready, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|change| {
+            parent_of(change)
+                .is_none_or(|parent| parent == change.uuid || !waiting.contains(&parent))
+        });
+        if ready.is_empty() {
+            // 親子が循環していれば、そのまま取り込んで親の欠落として扱う
+            ordered.extend(rest);
+            break;
+        }
+        ordered.extend(ready);
+        pending = rest;
+    }
+    ordered
+}
+
+type Outcome = Result<(), &'static str>;
+
+async fn apply_upsert(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    native_origin: &str,
+    change: &SyncChange,
+) -> Result<Outcome, ServiceError> {
+    let outcome = match change.entity {
+        SyncEntity::Tag => {
+            let Some(data) = parse_data::<TagData>(change) else {
+                return Ok(Err("invalid_data"));
+            };
+            match find_tag(txn, user_id, &change.uuid).await? {
+                Some(tag) => {
+                    if !is_newer(txn, user_id, native_origin, change).await? {
+                        return Ok(Err("stale"));
+                    }
+                    let version = tag.version;
+                    let mut tag: tags::ActiveModel = tag.into();
+                    tag.name = ActiveValue::Set(data.name);
+                    tag.updated_at = ActiveValue::Set(Utc::now());
+                    tag.version = ActiveValue::Set(version + 1);
+                    tag.update(txn).await?;
+                }
+                None => {
+                    tags::ActiveModel {
+                        uuid: ActiveValue::Set(change.uuid.clone()),
+                        name: ActiveValue::Set(data.name),
+                        user_id: ActiveValue::Set(user_id),
+                        ..Default::default()
+                    }
+                    .insert(txn)
+                    .await?;
+                }
+            }
+            Ok(())
+        }
+        SyncEntity::Task => apply_task(txn, user_id, native_origin, change).await?,
+        SyncEntity::Log => apply_log(txn, user_id, native_origin, change).await?,
+        SyncEntity::DecoponSession => apply_session(txn, user_id, native_origin, change).await?,
+    };
+    if outcome.is_ok() {
+        let key = RowKey {
+            entity: change.entity,
+            user_id,
+            uuid: change.uuid.clone(),
+        };
+        record(txn, &key, SyncOperation::Upsert, Some(change)).await?;
+    }
+    Ok(outcome)
+}
+
+async fn apply_task(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    native_origin: &str,
+    change: &SyncChange,
+) -> Result<Outcome, ServiceError> {
+    let Some(data) = parse_data::<TaskData>(change) else {
+        return Ok(Err("invalid_data"));
+    };
+    let tag_ids = tag_ids_for(txn, user_id, &data.tag_uuids).await?;
+    let existing = Tasks::find()
+        .filter(tasks::Column::UserId.eq(user_id))
+        .filter(tasks::Column::Uuid.eq(change.uuid.as_str()))
+        .one(txn)
+        .await?;
+
+    match existing {
+        Some(task) => {
+            if !is_newer(txn, user_id, native_origin, change).await? {
+                return Ok(Err("stale"));
+            }
+            let task_id = task.id;
+            let version = task.version;
+            let mut task: tasks::ActiveModel = task.into();
+            task.title = ActiveValue::Set(data.title);
+            task.description = ActiveValue::Set(data.description);
+            task.completed = ActiveValue::Set(data.completed);
+            task.due_at = ActiveValue::Set(data.due_at);
+            task.updated_at = ActiveValue::Set(Utc::now());
+            task.version = ActiveValue::Set(version + 1);
+            task.update(txn).await?;
+            tag_task_usecase::sync_tags(txn, task_id, tag_ids).await?;
+        }
+        None => {
+            let parent_task_id = match &data.parent_uuid {
+                Some(parent_uuid) => {
+                    let parent = Tasks::find()
+                        .filter(tasks::Column::UserId.eq(user_id))
+                        .filter(tasks::Column::Uuid.eq(parent_uuid.as_str()))
+                        .one(txn)
+                        .await?;
+                    match parent {
+                        Some(parent) => Some(parent.id),
+                        None => return Ok(Err("parent_missing")),
+                    }
+                }
+                None => None,
+            };
+            let hierarchy =
+                tasks_usecase::build_hierarchy_context(txn, user_id, parent_task_id).await?;
+            let inserted = tasks::ActiveModel {
+                uuid: ActiveValue::Set(change.uuid.clone()),
+                title: ActiveValue::Set(data.title),
+                description: ActiveValue::Set(data.description),
+                completed: ActiveValue::Set(data.completed),
+                parent_task_id: ActiveValue::Set(hierarchy.parent_task_id),
+                user_id: ActiveValue::Set(user_id),
+                root_task_id: ActiveValue::Set(hierarchy.root_task_id),
+                depth: ActiveValue::Set(hierarchy.depth),
+                position: ActiveValue::Set(hierarchy.position),
+                due_at: ActiveValue::Set(data.due_at),
+                ..Default::default()
+            }
+            .insert(txn)
+            .await?;
+            let task_id = inserted.id;
+            if hierarchy.root_task_id.is_none() {
+                let mut inserted: tasks::ActiveModel = inserted.into();
+                inserted.root_task_id = ActiveValue::Set(Some(task_id));
+                inserted.update(txn).await?;
+            }
+            tag_task_usecase::attach_tags_with_conn(txn, task_id, tag_ids).await?;
+        }
+    }
+    Ok(Ok(()))
+}
+
+async fn apply_log(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    native_origin: &str,
+    change: &SyncChange,
+) -> Result<Outcome, ServiceError> {
+    let Some(data) = parse_data::<LogData>(change) else {
+        return Ok(Err("invalid_data"));
+    };
+    let tag_ids = tag_ids_for(txn, user_id, &data.tag_uuids).await?;
+    let existing = Logs::find()
+        .filter(logs::Column::UserId.eq(user_id))
+        .filter(logs::Column::Uuid.eq(change.uuid.as_str()))
+        .one(txn)
+        .await?;
+
+    let log_id = match existing {
+        // 本文は作成後に変わらないので、タグの付け替えだけを取り込む
+        Some(log) => {
+            if !is_newer(txn, user_id, native_origin, change).await? {
+                return Ok(Err("stale"));
+            }
+            LogTag::delete_many()
+                .filter(log_tag::Column::LogId.eq(log.id))
+                .exec(txn)
+                .await?;
+            let log_id = log.id;
+            let version = log.version;
+            let mut log: logs::ActiveModel = log.into();
+            log.updated_at = ActiveValue::Set(Utc::now());
+            log.version = ActiveValue::Set(version + 1);
+            log.update(txn).await?;
+            log_id
+        }
+        None => {
+            let task_id = match &data.task_uuid {
+                Some(task_uuid) => Tasks::find()
+                    .filter(tasks::Column::UserId.eq(user_id))
+                    .filter(tasks::Column::Uuid.eq(task_uuid.as_str()))
+                    .one(txn)
+                    .await?
+                    .map(|task| task.id),
+                None => None,
+            };
+            logs::ActiveModel {
+                uuid: ActiveValue::Set(change.uuid.clone()),
+                content: ActiveValue::Set(data.content),
+                source: ActiveValue::Set(data.source),
+                task_id: ActiveValue::Set(task_id),
+                user_id: ActiveValue::Set(user_id),
+                event_code: ActiveValue::Set(data.event_code),
+                event_params: ActiveValue::Set(data.event_params),
+                created_at: ActiveValue::Set(data.created_at),
+                ..Default::default()
+            }
+            .insert(txn)
+            .await?
+            .id
+        }
+    };
+    if !tag_ids.is_empty() {
+        logs_usecase::attach_tags_to_log(txn, log_id, &tag_ids).await?;
+    }
+    Ok(Ok(()))
+}
+
+async fn apply_session(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    native_origin: &str,
+    change: &SyncChange,
+) -> Result<Outcome, ServiceError> {
+    let Some(data) = parse_data::<SessionData>(change) else {
+        return Ok(Err("invalid_data"));
+    };
+    match find_session(txn, user_id, &change.uuid).await? {
+        Some(session) => {
+            let completes = data.status == COMPLETED && session.status != COMPLETED;
+            if session.status == COMPLETED && data.status != COMPLETED {
+                return Ok(Err("completed"));
+            }
+            if !completes && !is_newer(txn, user_id, native_origin, change).await? {
+                return Ok(Err("stale"));
+            }
+            let version = session.version;
+            let mut session: decopon_sessions::ActiveModel = session.into();
+            session.status = ActiveValue::Set(data.status);
+            session.ended_at = ActiveValue::Set(data.ended_at);
+            session.updated_at = ActiveValue::Set(Utc::now());
+            session.version = ActiveValue::Set(version + 1);
+            session.update(txn).await?;
+        }
+        None => {
+            decopon_sessions::ActiveModel {
+                uuid: ActiveValue::Set(change.uuid.clone()),
+                status: ActiveValue::Set(data.status),
+                started_at: ActiveValue::Set(data.started_at),
+                ended_at: ActiveValue::Set(data.ended_at),
+                user_id: ActiveValue::Set(user_id),
+                ..Default::default()
+            }
+            .insert(txn)
+            .await?;
+        }
+    }
+    Ok(Ok(()))
+}
+
+const COMPLETED: &str = "Completed";
+
+/// 行が残っていれば削除し、なくても以後の作成を防ぐため削除を記録する
+async fn apply_delete(
+    txn: &DatabaseTransaction,
+    user_id: i32,
+    change: &SyncChange,
+) -> Result<(), ServiceError> {
+    let mut keys = Vec::new();
+    match change.entity {
+        SyncEntity::Tag => {
+            if let Some(tag) = find_tag(txn, user_id, &change.uuid).await? {
+                Tags::delete_by_id(tag.id).exec(txn).await?;
+            }
+        }
+        SyncEntity::Task => {
+            let task = Tasks::find()
+                .filter(tasks::Column::UserId.eq(user_id))
+                .filter(tasks::Column::Uuid.eq(change.uuid.as_str()))
+                .one(txn)
+                .await?;
+            if let Some(task) = task {
+                // 連鎖して消える子孫やログにも削除を記録し、この端末から作り直さないようにする
+                keys = task_tree_keys(txn, user_id, task.id).await?;
+                keys.retain(|key| key.entity != SyncEntity::Task || key.uuid != change.uuid);
+                Tasks::delete_by_id(task.id).exec(txn).await?;
+            }
+        }
+        SyncEntity::Log => {
+            Logs::delete_many()
+                .filter(logs::Column::UserId.eq(user_id))
+                .filter(logs::Column::Uuid.eq(change.uuid.as_str()))
+                .exec(txn)
+                .await?;
+        }
+        SyncEntity::DecoponSession => {
+            if let Some(session) = find_session(txn, user_id, &change.uuid).await? {
+                DecoponSessions::delete_by_id(session.id).exec(txn).await?;
+            }
+        }
+    }
+    keys.push(RowKey {
+        entity: change.entity,
+        user_id,
+        uuid: change.uuid.clone(),
+    });
+    for key in &keys {
+        record(txn, key, SyncOperation::Delete, Some(change)).await?;
+    }
+    Ok(())
+}
+
+async fn has_tombstone(
+    conn: &impl ConnectionTrait,
+    user_id: i32,
+    change: &SyncChange,
+) -> Result<bool, ServiceError> {
+    Ok(history(user_id, change)
+        .filter(sync_changes::Column::Operation.eq(SyncOperation::Delete.as_str()))
+        .one(conn)
+        .await?
+        .is_some())
+}
+
+/// 受け取った変更が、この DB で最後に記録されたその行への変更より新しいか
+async fn is_newer(
+    conn: &impl ConnectionTrait,
+    user_id: i32,
+    native_origin: &str,
+    change: &SyncChange,
+) -> Result<bool, ServiceError> {
+    let latest = history(user_id, change)
+        .order_by_desc(sync_changes::Column::Clock)
+        .order_by_desc(sync_changes::Column::Id)
+        .one(conn)
+        .await?;
+    Ok(latest.is_none_or(|latest| {
+        let latest_origin = latest.origin.as_deref().unwrap_or(native_origin);
+        (change.clock, change.origin.as_str()) > (latest.clock, latest_origin)
+    }))
+}
+
+fn history(user_id: i32, change: &SyncChange) -> sea_orm::Select<SyncChanges> {
+    SyncChanges::find()
+        .filter(sync_changes::Column::UserId.eq(user_id))
+        .filter(sync_changes::Column::Entity.eq(change.entity.as_str()))
+        .filter(sync_changes::Column::EntityUuid.eq(change.uuid.as_str()))
+}
+
+fn parse_data<T: DeserializeOwned>(change: &SyncChange) -> Option<T> {
+    serde_json::from_value(change.data.clone()?).ok()
+}
+
+/// UUID で指定されたタグの ID。まだ届いていないタグは無視する
+async fn tag_ids_for(
+    conn: &impl ConnectionTrait,
+    user_id: i32,
+    tag_uuids: &[String],
+) -> Result<Vec<i32>, ServiceError> {
+    if tag_uuids.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(Tags::find()
+        .select_only()
+        .column(tags::Column::Id)
+        .filter(tags::Column::UserId.eq(user_id))
+        .filter(tags::Column::Uuid.is_in(tag_uuids.iter().cloned()))
+        .into_tuple()
+        .all(conn)
+        .await?)
+}
+
+async fn find_tag(
+    conn: &impl ConnectionTrait,
+    user_id: i32,
+    uuid: &str,
+) -> Result<Option<tags::Model>, ServiceError> {
+    Ok(Tags::find()
+        .filter(tags::Column::UserId.eq(user_id))
+        .filter(tags::Column::Uuid.eq(uuid))
+        .one(conn)
+        .await?)
+}
+
+async fn find_session(
+    conn: &impl ConnectionTrait,
+    user_id: i32,
+    uuid: &str,
+) -> Result<Option<decopon_sessions::Model>, ServiceError> {
+    Ok(DecoponSessions::find()
+        .filter(decopon_sessions::Column::UserId.eq(user_id))
+        .filter(decopon_sessions::Column::Uuid.eq(uuid))
+        .one(conn)
+        .await?)
+}
+
+async fn uuid_of_task(
+    conn: &impl ConnectionTrait,
+    task_id: i32,
+) -> Result<Option<String>, ServiceError> {
+    Ok(Tasks::find_by_id(task_id)
+        .select_only()
+        .column(tasks::Column::Uuid)
+        .into_tuple()
+        .one(conn)
+        .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_user, setup_db};
+    use crate::usecases::{
+        decopon_sessions as sessions_usecase, tags as tags_usecase, tasks as tasks_usecase,
+    };
+    use sea_orm::{DatabaseConnection, PaginatorTrait};
+
+    async fn insert_task(
+        db: &DatabaseConnection,
+        user_id: i32,
+        title: &str,
+        parent_task_id: Option<i32>,
+    ) -> tasks_usecase::Task {
+        tasks_usecase::insert_task(
+            db,
+            tasks_usecase::NewTask {
+                title: title.to_string(),
+                description: String::new(),
+                parent_task_id,
+                tag_ids: None,
+                due_at: None,
+                user_id,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn complete_task(db: &DatabaseConnection, user_id: i32, id: i32) {
+        tasks_usecase::update_task(
+            db,
+            tasks_usecase::TaskUpdate {
+                id,
+                title: None,
+                description: None,
+                completed: Some(true),
+                parent_task_id: None,
+                tag_ids: None,
+                due_at: None,
+                user_id,
+                expected_version: None,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    async fn task_titles(db: &DatabaseConnection, user_id: i32) -> Vec<(String, bool)> {
+        tasks_usecase::get_tasks(db, user_id, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|task| (task.title, task.completed))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn sends_the_latest_state_of_each_changed_row() {
+        let db = setup_db().await;
+        let user = create_user(&db, "alice").await;
+        let task = insert_task(&db, user.id, "write report", None).await;
+        complete_task(&db, user.id, task.id).await;
+
+        let page = local_changes_since(&db, user.id, 0, "laptop")
+            .await
+            .unwrap();
+        let tasks: Vec<_> = page
+            .changes
+            .iter()
+            .filter(|change| change.entity == SyncEntity::Task)
+            .collect();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].origin, "laptop");
+        assert_eq!(tasks[0].data.as_ref().unwrap()["completed"], true);
+        // 完了時のログも送る
+        assert!(
+            page.changes
+                .iter()
+                .any(|change| change.entity == SyncEntity::Log)
+        );
+        assert!(!page.has_more);
+
+        let next = local_changes_since(&db, user.id, page.cursor, "laptop")
+            .await
+            .unwrap();
+        assert!(next.changes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn copies_a_task_tree_to_another_database() {
+        let local = setup_db().await;
+        let web = setup_db().await;
+        let local_user = create_user(&local, "alice").await;
+        let web_user = create_user(&web, "alice").await;
+        let parent = insert_task(&local, local_user.id, "parent", None).await;
+        insert_task(&local, local_user.id, "child", Some(parent.id)).await;
+        // 親を後から更新し、変更履歴の上では子の方が先に並ぶようにする
+        complete_task(&local, local_user.id, parent.id).await;
+
+        let page = local_changes_since(&local, local_user.id, 0, "laptop")
+            .await
+            .unwrap();
+        let result = apply_changes(&web, web_user.id, WEB_ORIGIN, page.changes)
+            .await
+            .unwrap();
+        assert!(result.skipped.is_empty(), "{:?}", result.skipped);
+
+        let tasks = tasks_usecase::get_tasks(&web, web_user.id, None)
+            .await
+            .unwrap();
+        let parent = tasks.iter().find(|task| task.title == "parent").unwrap();
+        let child = tasks.iter().find(|task| task.title == "child").unwrap();
+        assert!(parent.completed);
+        assert_eq!(child.parent_task_id, Some(parent.id));
+
+        // 取り込んだ変更は発生元の端末へ送り返さない
+        let echo = changes_since(&web, web_user.id, 0, Some("laptop"))
+            .await
+            .unwrap();
+        assert!(echo.changes.is_empty());
+        let others = changes_since(&web, web_user.id, 0, Some("phone"))
+            .await
+            .unwrap();
+        assert_eq!(others.changes.len(), result.applied);
+    }
+
+    async fn uuid_of(db: &DatabaseConnection, entity: SyncEntity, id: i32) -> String {
+        row_keys(db, entity, vec![id]).await.unwrap().remove(0).uuid
+    }
+
+    #[tokio::test]
+    async fn resolves_conflicts_per_entity() {
+        let local = setup_db().await;
+        let web = setup_db().await;
+        let local_user = create_user(&local, "alice").await;
+        let web_user = create_user(&web, "alice").await;
+        let kept = insert_task(&local, local_user.id, "kept", None).await;
+        let removed = insert_task(&local, local_user.id, "removed", None).await;
+        let session = sessions_usecase::insert_session(
+            &local,
+            sessions_usecase::NewDecoponSession {
+                status: "InProgress".to_string(),
+                started_at: Utc::now(),
+                ended_at: None,
+                user_id: local_user.id,
+            },
+        )
+        .await
+        .unwrap();
+        let initial = local_changes_since(&local, local_user.id, 0, "laptop")
+            .await
+            .unwrap();
+        apply_changes(&web, web_user.id, WEB_ORIGIN, initial.changes.clone())
+            .await
+            .unwrap();
+
+        // Web 側でセッションを完了し、タスクを改名する
+        let session_uuid = uuid_of(&local, SyncEntity::DecoponSession, session.id).await;
+        let web_session = find_session(&web, web_user.id, &session_uuid)
+            .await
+            .unwrap()
+            .unwrap();
+        sessions_usecase::update_session(
+            &web,
+            sessions_usecase::DecoponSessionUpdate {
+                id: web_session.id,
+                status: Some("Completed".to_string()),
+                ended_at: Some(Utc::now()),
+                user_id: web_user.id,
+                expected_version: None,
+            },
+        )
+        .await
+        .unwrap();
+        let kept_uuid = uuid_of(&local, SyncEntity::Task, kept.id).await;
+        let web_kept = Tasks::find()
+            .filter(tasks::Column::Uuid.eq(kept_uuid.as_str()))
+            .one(&web)
+            .await
+            .unwrap()
+            .unwrap();
+        tasks_usecase::update_task(
+            &web,
+            tasks_usecase::TaskUpdate {
+                id: web_kept.id,
+                title: Some("renamed on web".to_string()),
+                description: None,
+                completed: None,
+                parent_task_id: None,
+                tag_ids: None,
+                due_at: None,
+                user_id: web_user.id,
+                expected_version: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        // 古いタスクや進行中のセッションを再送しても上書きしない
+        let result = apply_changes(&web, web_user.id, WEB_ORIGIN, initial.changes.clone())
+            .await
+            .unwrap();
+        let reasons: HashSet<_> = result
+            .skipped
+            .iter()
+            .map(|skipped| (skipped.uuid.as_str(), skipped.reason.as_str()))
+            .collect();
+        assert!(reasons.contains(&(kept_uuid.as_str(), "stale")));
+        assert!(reasons.contains(&(session_uuid.as_str(), "completed")));
+
+        // 削除は優先し、削除後に届いた古い作成では作り直さない
+        let removed_uuid = uuid_of(&local, SyncEntity::Task, removed.id).await;
+        tasks_usecase::delete_task(&local, removed.id, local_user.id, None)
+            .await
+            .unwrap();
+        let page = local_changes_since(&local, local_user.id, initial.cursor, "laptop")
+            .await
+            .unwrap();
+        apply_changes(&web, web_user.id, WEB_ORIGIN, page.changes)
+            .await
+            .unwrap();
+        let result = apply_changes(&web, web_user.id, WEB_ORIGIN, initial.changes)
+            .await
+            .unwrap();
+        assert!(result.skipped.contains(&SkippedChange {
+            entity: SyncEntity::Task,
+            uuid: removed_uuid,
+            reason: "deleted".to_string(),
+        }));
+        assert_eq!(
+            task_titles(&web, web_user.id).await,
+            vec![("renamed on web".to_string(), false)]
+        );
+
+        // Web 側の変更を端末へ取り込む
+        let pulled = changes_since(&web, web_user.id, 0, Some("laptop"))
+            .await
+            .unwrap();
+        apply_changes(&local, local_user.id, "laptop", pulled.changes)
+            .await
+            .unwrap();
+        assert_eq!(
+            task_titles(&local, local_user.id).await,
+            vec![("renamed on web".to_string(), false)]
+        );
+        let local_session = sessions_usecase::get_session_by_id(&local, session.id, local_user.id)
+            .await
+            .unwrap();
+        assert_eq!(local_session.status, "Completed");
+    }
+
+    #[tokio::test]
+    async fn keeps_deleted_rows_when_the_tombstone_cannot_be_written() {
+        let db = setup_db().await;
+        let user = create_user(&db, "alice").await;
+        let task = insert_task(&db, user.id, "write report", None).await;
+        let session = sessions_usecase::insert_session(
+            &db,
+            sessions_usecase::NewDecoponSession {
+                status: "InProgress".to_string(),
+                started_at: Utc::now(),
+                ended_at: None,
+                user_id: user.id,
+            },
+        )
+        .await
+        .unwrap();
+        db.execute_unprepared("DROP TABLE sync_changes")
+            .await
+            .unwrap();
+
+        // 削除の記録に失敗したら、行も消さずに残す
+        let res = tasks_usecase::delete_task(&db, task.id, user.id, None).await;
+        assert!(res.is_err());
+        assert!(Tasks::find_by_id(task.id).one(&db).await.unwrap().is_some());
+        let res = sessions_usecase::delete_session(&db, session.id, user.id, None).await;
+        assert!(res.is_err());
+        assert!(
+            DecoponSessions::find_by_id(session.id)
+                .one(&db)
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_nothing_when_the_change_cannot_be_recorded() {
+        let db = setup_db().await;
+        let user = create_user(&db, "alice").await;
+        let task = insert_task(&db, user.id, "write report", None).await;
+        db.execute_unprepared("DROP TABLE sync_changes")
+            .await
+            .unwrap();
+
+        // 変更の記録に失敗したら、行の書き込みも残さない
+        let res = tags_usecase::insert_tag(
+            &db,
+            tags_usecase::NewTag {
+                name: "work".to_string(),
+                user_id: user.id,
+            },
+        )
+        .await;
+        assert!(res.is_err());
+        let res = tags_usecase::attach_tag_to_task(&db, user.id, task.id, "home".to_string()).await;
+        assert!(res.is_err());
+        assert_eq!(Tags::find().count(&db).await.unwrap(), 0);
+        assert_eq!(TagTask::find().count(&db).await.unwrap(), 0);
+        let res = sessions_usecase::insert_session(
+            &db,
+            sessions_usecase::NewDecoponSession {
+                status: "InProgress".to_string(),
+                started_at: Utc::now(),
+                ended_at: None,
+                user_id: user.id,
+            },
+        )
+        .await;
+        assert!(res.is_err());
+        assert_eq!(DecoponSessions::find().count(&db).await.unwrap(), 0);
+    }
+}
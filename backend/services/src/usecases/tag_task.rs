@@ -92,3 +92,11 @@ pub async fn detach_tags(
     detach_tags_inner(&txn, task_id, tag_ids).await?;
     txn.commit().await.map_err(Into::into)
 }
+
+pub async fn detach_tags_with_conn(
+    db: &impl ConnectionTrait,
+    task_id: i32,
+    tag_ids: Vec<i32>,
+) -> Result<(), ServiceError> {
+    detach_tags_inner(db, task_id, tag_ids).await
+}
@@ -3,11 +3,15 @@ use crate::{
     errors::ServiceError,
 };
 
-use super::tag_task as tag_task_usecase;
+use super::{
+    sync::{self, SyncEntity},
+    tag_task as tag_task_usecase, tasks as tasks_usecase,
+};
 
 use sea_orm::prelude::DateTimeUtc;
 use sea_orm::{
-    ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    ActiveValue, ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait,
+    QueryFilter, TransactionTrait,
 };
 
 pub struct NewTag {
@@ -17,10 +21,12 @@ pub struct NewTag {
 
 pub struct Tag {
     pub id: i32,
+    pub uuid: String,
     pub name: String,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
     pub user_id: i32,
+    pub version: i32,
     pub task_count: u64,
 }
 
@@ -28,10 +34,12 @@ impl From<tags::Model> for Tag {
     fn from(tag: tags::Model) -> Self {
         Self {
             id: tag.id,
+            uuid: tag.uuid,
             name: tag.name,
             created_at: tag.created_at,
             updated_at: tag.updated_at,
             user_id: tag.user_id,
+            version: tag.version,
             task_count: 0,
         }
     }
@@ -64,7 +72,10 @@ pub async fn insert_tag(db: &DatabaseConnection, params: NewTag) -> Result<Tag,
         user_id: ActiveValue::Set(params.user_id),
         ..Default::default()
     };
-    let res = Tags::insert(new_tag).exec(db).await?;
+    let txn = db.begin().await?;
+    let res = Tags::insert(new_tag).exec(&txn).await?;
+    sync::record_upsert(&txn, SyncEntity::Tag, res.last_insert_id).await?;
+    txn.commit().await?;
     let tag = Tags::find_by_id(res.last_insert_id)
         .one(db)
         .await?
@@ -78,10 +89,11 @@ pub async fn attach_tag_to_task(
     task_id: i32,
     name: String,
 ) -> Result<Tag, ServiceError> {
+    let txn = db.begin().await?;
     let tag = Tags::find()
         .filter(tags::Column::Name.eq(name.clone()))
         .filter(tags::Column::UserId.eq(user_id))
-        .one(db)
+        .one(&txn)
         .await?;
 
     let tag = match tag {
@@ -92,15 +104,18 @@ pub async fn attach_tag_to_task(
                 user_id: ActiveValue::Set(user_id),
                 ..Default::default()
             };
-            let res = Tags::insert(new_tag).exec(db).await?;
+            let res = Tags::insert(new_tag).exec(&txn).await?;
+            sync::record_upsert(&txn, SyncEntity::Tag, res.last_insert_id).await?;
             Tags::find_by_id(res.last_insert_id)
-                .one(db)
+                .one(&txn)
                 .await?
                 .ok_or(ServiceError::NotFound("tag"))?
         }
     };
 
-    tag_task_usecase::attach_tags(db, task_id, vec![tag.id]).await?;
+    tag_task_usecase::attach_tags_with_conn(&txn, task_id, vec![tag.id]).await?;
+    tasks_usecase::bump_version(&txn, task_id).await?;
+    txn.commit().await?;
     tag_with_count(db, tag).await
 }
 
@@ -117,7 +132,10 @@ pub async fn detach_tag_from_task(
         .await?;
 
     if let Some(tag) = &tag {
-        tag_task_usecase::detach_tags(db, task_id, vec![tag.id]).await?;
+        let txn = db.begin().await?;
+        tag_task_usecase::detach_tags_with_conn(&txn, task_id, vec![tag.id]).await?;
+        tasks_usecase::bump_version(&txn, task_id).await?;
+        txn.commit().await?;
     }
     if let Some(tag) = tag {
         Ok(Some(tag_with_count(db, tag).await?))
@@ -126,15 +144,51 @@ pub async fn detach_tag_from_task(
     }
 }
 
+/// タグをまとめて削除する。`expected_versions` は `tag_ids` と同じ順序の版で、
+/// 一つでも食い違えば何も削除しない。
 pub async fn delete_tags(
     db: &DatabaseConnection,
     user_id: i32,
     tag_ids: Vec<i32>,
+    expected_versions: Option<Vec<i32>>,
 ) -> Result<(), ServiceError> {
-    Tags::delete_many()
-        .filter(tags::Column::Id.is_in(tag_ids))
+    if let Some(versions) = &expected_versions
+        && versions.len() != tag_ids.len()
+    {
+        return Err(ServiceError::invalid_field(
+            "versions",
+            "length",
+            "versions must list one version per tag id",
+        ));
+    }
+    let txn = db.begin().await?;
+    let owned = Tags::find()
+        .filter(tags::Column::Id.is_in(tag_ids.clone()))
         .filter(tags::Column::UserId.eq(user_id))
-        .exec(db)
+        .all(&txn)
         .await?;
+    let mut condition = Condition::any();
+    for tag in &owned {
+        let mut matches = Condition::all().add(tags::Column::Id.eq(tag.id));
+        let expected = expected_versions.as_ref().and_then(|versions| {
+            let index = tag_ids.iter().position(|id| *id == tag.id)?;
+            versions.get(index).copied()
+        });
+        if let Some(version) = expected {
+            matches = matches.add(tags::Column::Version.eq(version));
+        }
+        condition = condition.add(matches);
+    }
+    let owned_ids: Vec<i32> = owned.iter().map(|tag| tag.id).collect();
+    let deleted_keys = sync::row_keys(&txn, SyncEntity::Tag, owned_ids).await?;
+    if !owned.is_empty() {
+        // 読み込んだ後に更新されたタグがあれば、その行は条件に合わず削除されない
+        let result = Tags::delete_many().filter(condition).exec(&txn).await?;
+        if result.rows_affected != owned.len() as u64 {
+            return Err(ServiceError::PreconditionFailed("tag"));
+        }
+    }
+    sync::record_deletes(&txn, &deleted_keys).await?;
+    txn.commit().await?;
     Ok(())
 }
@@ -0,0 +1,600 @@
+//! 他のツールから書き出したタスクを取り込む。
+//! Markdown のチェックボックスのリスト、Todoist の CSV、Taskwarrior の JSON を読み、
+//! 入れ子や親の指定は親タスクに、ラベルはタグに、完了の状態はそのまま写す。
+
+use std::collections::{HashMap, HashSet};
+
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QuerySelect, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::entities::{prelude::*, tags, tasks};
+use crate::errors::ServiceError;
+
+use super::{
+    logs,
+    sync::{self, SyncEntity},
+    tag_task as tag_task_usecase, tasks as tasks_usecase,
+};
+
+/// 一度に取り込めるタスクの数
+pub const MAX_IMPORTED_TASKS: usize = 1000;
+const MAX_TITLE_LEN: usize = 255;
+const MAX_TAG_LEN: usize = 255;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TaskImportFormat {
+    Markdown,
+    TodoistCsv,
+    TaskwarriorJson,
+}
+
+/// 読み取ったタスク。`parent` は同じ一覧の中の親の位置で、常に自分より前を指す
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportedTask {
+    /// 作成したタスクの ID。試行のときは `None`
+    pub id: Option<i32>,
+    pub title: String,
+    pub description: String,
+    pub completed: bool,
+    pub parent: Option<usize>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskImport {
+    pub dry_run: bool,
+    pub tasks: Vec<ImportedTask>,
+    /// 新しく作られる (試行なら作られるはずの) タグ
+    pub new_tags: Vec<String>,
+}
+
+/// 取り込む。`dry_run` なら何も書き込まず、作られる内容だけを返す
+pub async fn import_tasks(
+    db: &DatabaseConnection,
+    user_id: i32,
+    format: TaskImportFormat,
+    content: &str,
+    dry_run: bool,
+) -> Result<TaskImport, ServiceError> {
+    let mut parsed = parse(format, content)?;
+    let names = tag_names(&parsed);
+    let existing: HashSet<String> = if names.is_empty() {
+        HashSet::new()
+    } else {
+        Tags::find()
+            .select_only()
+            .column(tags::Column::Name)
+            .filter(tags::Column::UserId.eq(user_id))
+            .filter(tags::Column::Name.is_in(names.clone()))
+            .into_tuple::<String>()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect()
+    };
+    let new_tags = names
+        .iter()
+        .filter(|name| !existing.contains(*name))
+        .cloned()
+        .collect();
+    if dry_run {
+        return Ok(TaskImport {
+            dry_run,
+            tasks: parsed,
+            new_tags,
+        });
+    }
+
+    let txn = db.begin().await?;
+    let tag_ids: HashMap<String, i32> = logs::ensure_tags(&txn, user_id, Vec::new(), names)
+        .await?
+        .into_iter()
+        .map(|tag| (tag.name, tag.id))
+        .collect();
+    let mut ids: Vec<i32> = Vec::with_capacity(parsed.len());
+    for task in &mut parsed {
+        let parent_task_id = task.parent.map(|parent| ids[parent]);
+        let hierarchy =
+            tasks_usecase::build_hierarchy_context(&txn, user_id, parent_task_id).await?;
+        let inserted = tasks::ActiveModel {
+            title: ActiveValue::Set(task.title.clone()),
+            description: ActiveValue::Set(task.description.clone()),
+            completed: ActiveValue::Set(task.completed),
+            parent_task_id: ActiveValue::Set(hierarchy.parent_task_id),
+            user_id: ActiveValue::Set(user_id),
+            root_task_id: ActiveValue::Set(hierarchy.root_task_id),
+            depth: ActiveValue::Set(hierarchy.depth),
+            position: ActiveValue::Set(hierarchy.position),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        let task_id = inserted.id;
+        if hierarchy.root_task_id.is_none() {
+            let mut inserted: tasks::ActiveModel = inserted.into();
+            inserted.root_task_id = ActiveValue::Set(Some(task_id));
+            inserted.update(&txn).await?;
+        }
+        let task_tag_ids = task
+            .tags
+            .iter()
+            .filter_map(|name| tag_ids.get(name).copied())
+            .collect::<Vec<_>>();
+        if !task_tag_ids.is_empty() {
+            tag_task_usecase::attach_tags_with_conn(&txn, task_id, task_tag_ids).await?;
+        }
+        sync::record_upsert(&txn, SyncEntity::Task, task_id).await?;
+        ids.push(task_id);
+        task.id = Some(task_id);
+    }
+    txn.commit().await?;
+
+    Ok(TaskImport {
+        dry_run,
+        tasks: parsed,
+        new_tags,
+    })
+}
+
+/// 書式ごとに読み取り、タイトルやタグの長さと件数を検証する
+pub fn parse(format: TaskImportFormat, content: &str) -> Result<Vec<ImportedTask>, ServiceError> {
+    let tasks = match format {
+        TaskImportFormat::Markdown => parse_markdown(content),
+        TaskImportFormat::TodoistCsv => parse_todoist_csv(content)?,
+        TaskImportFormat::TaskwarriorJson => parse_taskwarrior_json(content)?,
+    };
+    if tasks.is_empty() {
+        return Err(ServiceError::invalid_field(
+            "content",
+            "empty",
+            "no tasks were found in the content",
+        ));
+    }
+    if tasks.len() > MAX_IMPORTED_TASKS {
+        return Err(ServiceError::invalid_field(
+            "content",
+            "too_many",
+            format!("at most {MAX_IMPORTED_TASKS} tasks can be imported at once"),
+        ));
+    }
+    for (index, task) in tasks.iter().enumerate() {
+        if task.title.chars().count() > MAX_TITLE_LEN {
+            return Err(ServiceError::invalid_field(
+                "content",
+                "too_long",
+                format!(
+                    "task {}: title must be at most {MAX_TITLE_LEN} characters",
+                    index + 1
+                ),
+            ));
+        }
+        if task
+            .tags
+            .iter()
+            .any(|tag| tag.chars().count() > MAX_TAG_LEN)
+        {
+            return Err(ServiceError::invalid_field(
+                "content",
+                "too_long",
+                format!(
+                    "task {}: tags must be at most {MAX_TAG_LEN} characters",
+                    index + 1
+                ),
+            ));
+        }
+    }
+    Ok(tasks)
+}
+
+fn tag_names(tasks: &[ImportedTask]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tasks
+        .iter()
+        .flat_map(|task| task.tags.iter())
+        .filter(|name| seen.insert(name.as_str()))
+        .cloned()
+        .collect()
+}
+
+fn new_task(
+    title: String,
+    completed: bool,
+    parent: Option<usize>,
+    tags: Vec<String>,
+) -> ImportedTask {
+    ImportedTask {
+        id: None,
+        title,
+        description: String::new(),
+        completed,
+        parent,
+        tags,
+    }
+}
+
+/// 入れ子の深さから親を決める。自分より浅い直前の項目が親になる
+#[derive(Default)]
+struct Nesting(Vec<(usize, usize)>);
+
+impl Nesting {
+    fn parent(&mut self, level: usize, index: usize) -> Option<usize> {
+        while self.0.last().is_some_and(|(top, _)| *top >= level) {
+            self.0.pop();
+        }
+        let parent = self.0.last().map(|(_, parent)| *parent);
+        self.0.push((level, index));
+        parent
+    }
+}
+
+/// タイトルから印の付いた語 (`#tag` や `@label`) をタグとして抜き出す
+fn split_labels(text: &str, marker: char) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut labels: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match word.strip_prefix(marker) {
+            Some(label) if !label.is_empty() && !label.starts_with(marker) => {
+                if !labels.iter().any(|existing| existing == label) {
+                    labels.push(label.to_string());
+                }
+            }
+            _ => words.push(word),
+        }
+    }
+    (words.join(" "), labels)
+}
+
+/// `- [ ] title` や `* [x] title` の形の行。チェックボックスのない項目は読まない
+fn checkbox_item(line: &str) -> Option<(bool, &str)> {
+    let rest = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))?;
+    let completed = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let title = &rest[3..];
+    if !title.is_empty() && !title.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some((completed, title.trim()))
+}
+
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// 入れ子のチェックボックスのリストを読む。項目より深く字下げされた行はその項目の説明にする
+fn parse_markdown(content: &str) -> Vec<ImportedTask> {
+    let mut tasks: Vec<ImportedTask> = Vec::new();
+    let mut nesting = Nesting::default();
+    // 説明を付けられる直前の項目と、その字下げ
+    let mut current: Option<(usize, usize)> = None;
+    let mut blank_lines = 0;
+    for line in content.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        let indent = indent_width(line);
+        let trimmed = line.trim_start();
+        if let Some((completed, text)) = checkbox_item(trimmed) {
+            let (title, tags) = split_labels(text, '#');
+            if title.is_empty() {
+                current = None;
+                continue;
+            }
+            let index = tasks.len();
+            let parent = nesting.parent(indent, index);
+            tasks.push(new_task(title, completed, parent, tags));
+            current = Some((index, indent));
+        } else {
+            match current {
+                Some((index, item_indent)) if indent > item_indent => {
+                    let description = &mut tasks[index].description;
+                    if !description.is_empty() {
+                        description.push_str(&"\n".repeat(blank_lines + 1));
+                    }
+                    description.push_str(trimmed);
+                }
+                _ => current = None,
+            }
+        }
+        blank_lines = 0;
+    }
+    tasks
+}
+
+/// Todoist のプロジェクトの CSV を読む。`TYPE` が `task` の行だけを使い、`INDENT` で入れ子にする
+fn parse_todoist_csv(content: &str) -> Result<Vec<ImportedTask>, ServiceError> {
+    let unreadable = |error: csv::Error| {
+        ServiceError::invalid_field("content", "unparsable", format!("invalid CSV: {error}"))
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+    let headers = reader.headers().map_err(unreadable)?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(name))
+    };
+    let (Some(type_column), Some(content_column)) = (column("TYPE"), column("CONTENT")) else {
+        return Err(ServiceError::invalid_field(
+            "content",
+            "unparsable",
+            "a Todoist CSV needs TYPE and CONTENT columns",
+        ));
+    };
+    let description_column = column("DESCRIPTION");
+    let indent_column = column("INDENT");
+
+    let mut tasks = Vec::new();
+    let mut nesting = Nesting::default();
+    for record in reader.records() {
+        let record = record.map_err(unreadable)?;
+        let field = |column: Option<usize>| column.and_then(|c| record.get(c)).unwrap_or("").trim();
+        match field(Some(type_column)) {
+            "task" => {}
+            // セクションをまたいで入れ子にはならない
+            "section" => {
+                nesting = Nesting::default();
+                continue;
+            }
+            _ => continue,
+        }
+        let (title, tags) = split_labels(field(Some(content_column)), '@');
+        if title.is_empty() {
+            continue;
+        }
+        let level = field(indent_column).parse::<usize>().unwrap_or(1);
+        let index = tasks.len();
+        let parent = nesting.parent(level, index);
+        let mut task = new_task(title, false, parent, tags);
+        task.description = field(description_column).to_string();
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+#[derive(Deserialize)]
+struct TaskwarriorTask {
+    #[serde(default)]
+    uuid: Option<String>,
+    description: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default)]
+    annotations: Vec<TaskwarriorAnnotation>,
+    #[serde(default)]
+    parent: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TaskwarriorAnnotation {
+    description: String,
+}
+
+/// `task export` の JSON を読む。削除済みと繰り返しのひな形は除き、
+/// `parent` が同じ書き出しの中のタスクを指していればその子にする
+fn parse_taskwarrior_json(content: &str) -> Result<Vec<ImportedTask>, ServiceError> {
+    let exported: Vec<TaskwarriorTask> = serde_json::from_str(content).map_err(|error| {
+        ServiceError::invalid_field("content", "unparsable", format!("invalid JSON: {error}"))
+    })?;
+    let exported = exported
+        .into_iter()
+        .filter(|task| !matches!(task.status.as_str(), "deleted" | "recurring"))
+        .filter(|task| !task.description.trim().is_empty())
+        .collect::<Vec<_>>();
+    let known: HashSet<&str> = exported
+        .iter()
+        .filter_map(|task| task.uuid.as_deref())
+        .collect();
+
+    // 親が先に並ぶよう、親が書き出し済みのものから順に出す
+    let mut order: Vec<usize> = Vec::with_capacity(exported.len());
+    let mut placed: HashMap<&str, usize> = HashMap::new();
+    let mut pending: Vec<usize> = (0..exported.len()).collect();
+    while !pending.is_empty() {
+        let (ready, rest): (Vec<usize>, Vec<usize>) = pending.iter().partition(|&&i| {
+            exported[i]
+                .parent
+                .as_deref()
+                .is_none_or(|parent| !known.contains(parent) || placed.contains_key(parent))
+        });
+        // 親子が循環していれば、残りは最上位に置く
+        let (ready, rest) = if ready.is_empty() {
+            (rest, Vec::new())
+        } else {
+            (ready, rest)
+        };
+        for i in ready {
+            if let Some(uuid) = exported[i].uuid.as_deref() {
+                placed.insert(uuid, order.len());
+            }
+            order.push(i);
+        }
+        pending = rest;
+    }
+
+    let mut tasks = Vec::with_capacity(order.len());
+    for (index, &i) in order.iter().enumerate() {
+        let task = &exported[i];
+        let parent = task
+            .parent
+            .as_deref()
+            .and_then(|parent| placed.get(parent).copied())
+            .filter(|parent| *parent < index);
+        let mut tags = task.tags.clone();
+        if let Some(project) = task.project.as_deref().filter(|p| !p.trim().is_empty()) {
+            tags.insert(0, project.to_string());
+        }
+        let mut seen = HashSet::new();
+        tags.retain(|tag| !tag.trim().is_empty() && seen.insert(tag.clone()));
+        let mut imported = new_task(
+            task.description.trim().to_string(),
+            task.status == "completed",
+            parent,
+            tags,
+        );
+        imported.description = task
+            .annotations
+            .iter()
+            .map(|annotation| annotation.description.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        tasks.push(imported);
+    }
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_user, setup_db};
+    use crate::usecases::tasks::get_tasks;
+
+    fn summary(tasks: &[ImportedTask]) -> Vec<(&str, bool, Option<usize>, Vec<&str>)> {
+        tasks
+            .iter()
+            .map(|task| {
+                (
+                    task.title.as_str(),
+                    task.completed,
+                    task.parent,
+                    task.tags.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_nested_markdown_checklists() {
+        let tasks = parse(
+            TaskImportFormat::Markdown,
+            "# Plan\n\
+             - [ ] Release #work #q3-goals\n\
+             \x20 Ship it\n\
+             \n\
+             \x20 before Friday\n\
+             \x20 - [x] Write notes\n\
+             \x20 - [ ] Tag build\n\
+             \t\t- [ ] Announce\n\
+             - plain bullet\n\
+             * [X] Tidy up\n",
+        )
+        .unwrap();
+        assert_eq!(
+            summary(&tasks),
+            vec![
+                ("Release", false, None, vec!["work", "q3-goals"]),
+                ("Write notes", true, Some(0), vec![]),
+                ("Tag build", false, Some(0), vec![]),
+                ("Announce", false, Some(2), vec![]),
+                ("Tidy up", true, None, vec![]),
+            ]
+        );
+        assert_eq!(tasks[0].description, "Ship it\n\nbefore Friday");
+    }
+
+    #[test]
+    fn parses_todoist_csv_exports() {
+        let tasks = parse(
+            TaskImportFormat::TodoistCsv,
+            "\u{feff}TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,AUTHOR\n\
+             task,Plan trip @travel,\"Flights, hotel\",1,1,me\n\
+             task,Book flights,,1,2,me\n\
+             task,Pick seats @travel @later,,1,3,me\n\
+             section,Later,,,,\n\
+             task,Pack,,1,2,me\n\
+             note,ignored,,,,\n",
+        )
+        .unwrap();
+        assert_eq!(
+            summary(&tasks),
+            vec![
+                ("Plan trip", false, None, vec!["travel"]),
+                ("Book flights", false, Some(0), vec![]),
+                ("Pick seats", false, Some(1), vec!["travel", "later"]),
+                ("Pack", false, None, vec![]),
+            ]
+        );
+        assert_eq!(tasks[0].description, "Flights, hotel");
+    }
+
+    #[test]
+    fn parses_taskwarrior_exports_with_parents_first() {
+        let tasks = parse(
+            TaskImportFormat::TaskwarriorJson,
+            r#"[
+                {"uuid":"b","description":"Child","status":"completed","parent":"a","tags":["x"]},
+                {"uuid":"a","description":"Parent","status":"pending","project":"home",
+                 "annotations":[{"entry":"20250101T000000Z","description":"note"}]},
+                {"uuid":"c","description":"Gone","status":"deleted"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            summary(&tasks),
+            vec![
+                ("Parent", false, None, vec!["home"]),
+                ("Child", true, Some(0), vec!["x"]),
+            ]
+        );
+        assert_eq!(tasks[0].description, "note");
+        assert!(matches!(
+            parse(TaskImportFormat::TaskwarriorJson, "{}"),
+            Err(ServiceError::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn dry_run_writes_nothing_and_import_creates_tags() {
+        let db = setup_db().await;
+        let user = create_user(&db, "importer").await;
+        let content = "- [ ] Parent #home\n  - [x] Child #home #new\n";
+
+        let preview = import_tasks(&db, user.id, TaskImportFormat::Markdown, content, true)
+            .await
+            .unwrap();
+        assert_eq!(preview.new_tags, vec!["home", "new"]);
+        assert!(preview.tasks.iter().all(|task| task.id.is_none()));
+        assert!(get_tasks(&db, user.id, None).await.unwrap().is_empty());
+
+        let imported = import_tasks(&db, user.id, TaskImportFormat::Markdown, content, false)
+            .await
+            .unwrap();
+        let stored = get_tasks(&db, user.id, None).await.unwrap();
+        assert_eq!(stored.len(), 2);
+        let child = stored
+            .iter()
+            .find(|task| Some(task.id) == imported.tasks[1].id)
+            .unwrap();
+        assert_eq!(child.parent_task_id, imported.tasks[0].id);
+        assert_eq!(child.root_task_id, imported.tasks[0].id);
+        assert!(child.completed);
+        assert_eq!(child.tags.len(), 2);
+
+        // 二度目は既存のタグを使う
+        let again = import_tasks(&db, user.id, TaskImportFormat::Markdown, content, true)
+            .await
+            .unwrap();
+        assert!(again.new_tags.is_empty());
+    }
+}
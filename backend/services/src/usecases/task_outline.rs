@@ -0,0 +1,294 @@
+//! タスクの木をドキュメントやアウトライナーへ貼り付けられる形で書き出す。
+//! Markdown ではチェックボックス付きの入れ子のリスト、OPML では入れ子の `outline` にする。
+//! どちらも兄弟は `position` の順に並べ、タグはハッシュタグ、説明は項目の下の注記にする。
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+
+use crate::errors::ServiceError;
+
+use super::tasks::{self, Task};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum OutlineFormat {
+    #[default]
+    Markdown,
+    Opml,
+}
+
+impl OutlineFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutlineFormat::Markdown => "text/markdown; charset=utf-8",
+            OutlineFormat::Opml => "text/x-opml; charset=utf-8",
+        }
+    }
+}
+
+/// タスクとその子孫を書き出す
+pub async fn export_subtree(
+    db: &DatabaseConnection,
+    user_id: i32,
+    task_id: i32,
+    format: OutlineFormat,
+) -> Result<String, ServiceError> {
+    let tasks = tasks::get_task_subtree(db, user_id, task_id)
+        .await?
+        .into_iter()
+        .map(|node| node.task)
+        .collect::<Vec<_>>();
+    let title = tasks
+        .iter()
+        .find(|task| task.id == task_id)
+        .map(|task| task.title.clone())
+        .unwrap_or_default();
+    Ok(render(&title, &tasks, format))
+}
+
+/// すべてのタスクを、最上位のタスクごとの木として書き出す
+pub async fn export_forest(
+    db: &DatabaseConnection,
+    user_id: i32,
+    format: OutlineFormat,
+) -> Result<String, ServiceError> {
+    let tasks = tasks::get_tasks(db, user_id, None).await?;
+    Ok(render("Decopon tasks", &tasks, format))
+}
+
+pub fn render(title: &str, tasks: &[Task], format: OutlineFormat) -> String {
+    let outline = Outline::new(tasks);
+    match format {
+        OutlineFormat::Markdown => outline.markdown(),
+        OutlineFormat::Opml => outline.opml(title),
+    }
+}
+
+/// 親から子をたどれるようにしたタスクの集まり。親が含まれないタスクを最上位として扱う
+struct Outline<'a> {
+    roots: Vec<&'a Task>,
+    children: HashMap<i32, Vec<&'a Task>>,
+}
+
+impl<'a> Outline<'a> {
+    fn new(tasks: &'a [Task]) -> Self {
+        let ids: HashSet<i32> = tasks.iter().map(|task| task.id).collect();
+        let mut roots = Vec::new();
+        let mut children: HashMap<i32, Vec<&Task>> = HashMap::new();
+        for task in tasks {
+            match task.parent_task_id.filter(|parent| ids.contains(parent)) {
+                Some(parent) => children.entry(parent).or_default().push(task),
+                None => roots.push(task),
+            }
+        }
+        roots.sort_by_key(|task| (task.position, task.id));
+        for siblings in children.values_mut() {
+            siblings.sort_by_key(|task| (task.position, task.id));
+        }
+        Self { roots, children }
+    }
+
+    fn children_of(&self, task: &Task) -> &[&'a Task] {
+        self.children.get(&task.id).map_or(&[], Vec::as_slice)
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = String::new();
+        for task in &self.roots {
+            self.write_markdown(task, 0, &mut out);
+        }
+        out
+    }
+
+    fn write_markdown(&self, task: &Task, level: usize, out: &mut String) {
+        let indent = "  ".repeat(level);
+        let check = if task.completed { "x" } else { " " };
+        out.push_str(&format!("{indent}- [{check}] {}", single_line(&task.title)));
+        for tag in hashtags(task) {
+            out.push(' ');
+            out.push_str(&tag);
+        }
+        out.push('\n');
+        for line in task.description.lines() {
+            if line.trim().is_empty() {
+                out.push('\n');
+            } else {
+                out.push_str(&format!("{indent}  {line}\n"));
+            }
+        }
+        for child in self.children_of(task) {
+            self.write_markdown(child, level + 1, out);
+        }
+    }
+
+    fn opml(&self, title: &str) -> String {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<opml version=\"2.0\">\n  <head>\n");
+        out.push_str(&format!("    <title>{}</title>\n", escape_xml(title)));
+        out.push_str(&format!(
+            "    <dateCreated>{}</dateCreated>\n",
+            Utc::now().to_rfc2822()
+        ));
+        out.push_str("  </head>\n  <body>\n");
+        for task in &self.roots {
+            self.write_opml(task, 2, &mut out);
+        }
+        out.push_str("  </body>\n</opml>\n");
+        out
+    }
+
+    fn write_opml(&self, task: &Task, level: usize, out: &mut String) {
+        let indent = "  ".repeat(level);
+        let mut text = single_line(&task.title);
+        for tag in hashtags(task) {
+            text.push(' ');
+            text.push_str(&tag);
+        }
+        out.push_str(&format!("{indent}<outline text=\"{}\"", escape_xml(&text)));
+        if !task.tags.is_empty() {
+            let categories = task
+                .tags
+                .iter()
+                .map(|tag| tag.name.replace(',', " "))
+                .collect::<Vec<_>>()
+                .join(",");
+            out.push_str(&format!(" category=\"{}\"", escape_xml(&categories)));
+        }
+        if !task.description.trim().is_empty() {
+            out.push_str(&format!(" _note=\"{}\"", escape_xml(&task.description)));
+        }
+        if task.completed {
+            out.push_str(" _complete=\"true\"");
+        }
+        let children = self.children_of(task);
+        if children.is_empty() {
+            out.push_str("/>\n");
+            return;
+        }
+        out.push_str(">\n");
+        for child in children {
+            self.write_opml(child, level + 1, out);
+        }
+        out.push_str(&format!("{indent}</outline>\n"));
+    }
+}
+
+/// 改行を含むタイトルはリストの項目が崩れないよう一行にまとめる
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 空白を含むタグ名は、ハッシュタグとして切れないよう `-` でつなぐ
+fn hashtags(task: &Task) -> Vec<String> {
+    task.tags
+        .iter()
+        .map(|tag| {
+            format!(
+                "#{}",
+                tag.name.split_whitespace().collect::<Vec<_>>().join("-")
+            )
+        })
+        .collect()
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecases::tasks::TaskTag;
+
+    fn task(id: i32, title: &str, parent: Option<i32>, position: i32) -> Task {
+        Task {
+            id,
+            uuid: format!("uuid-{id}"),
+            title: title.to_string(),
+            description: String::new(),
+            completed: false,
+            parent_task_id: parent,
+            root_task_id: None,
+            depth: 0,
+            position,
+            due_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            version: 1,
+            tags: Vec::new(),
+        }
+    }
+
+    fn tag(name: &str) -> TaskTag {
+        TaskTag {
+            id: 1,
+            uuid: "tag".to_string(),
+            name: name.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn plan() -> Vec<Task> {
+        let mut root = task(1, "Release", None, 0);
+        root.tags = vec![tag("work"), tag("q3 goals")];
+        root.description = "Ship it\n\nbefore Friday".to_string();
+        let mut done = task(3, "Write notes", Some(1), 0);
+        done.completed = true;
+        // 兄弟は取得順ではなく position の順に並ぶ
+        vec![
+            root,
+            task(2, "Tag build", Some(1), 1),
+            done,
+            task(4, "Announce <beta> & \"more\"", Some(2), 0),
+        ]
+    }
+
+    #[test]
+    fn renders_nested_checkbox_lists() {
+        assert_eq!(
+            render("Release", &plan(), OutlineFormat::Markdown),
+            "- [ ] Release #work #q3-goals\n\
+             \x20 Ship it\n\
+             \n\
+             \x20 before Friday\n\
+             \x20 - [x] Write notes\n\
+             \x20 - [ ] Tag build\n\
+             \x20   - [ ] Announce <beta> & \"more\"\n"
+        );
+    }
+
+    #[test]
+    fn renders_opml_outlines() {
+        let opml = render("Release", &plan(), OutlineFormat::Opml);
+        let body = opml.split("<body>\n").nth(1).unwrap();
+        assert_eq!(
+            body,
+            "    <outline text=\"Release #work #q3-goals\" category=\"work,q3 goals\" _note=\"Ship it&#10;&#10;before Friday\">\n\
+             \x20     <outline text=\"Write notes\" _complete=\"true\"/>\n\
+             \x20     <outline text=\"Tag build\">\n\
+             \x20       <outline text=\"Announce &lt;beta&gt; &amp; &quot;more&quot;\"/>\n\
+             \x20     </outline>\n\
+             \x20   </outline>\n\
+             \x20 </body>\n\
+             </opml>\n"
+        );
+        assert!(opml.contains("<title>Release</title>"));
+    }
+}
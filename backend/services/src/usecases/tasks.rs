@@ -1,11 +1,16 @@
 use crate::{
     entities::{prelude::*, tag_task, *},
-    errors::ServiceError,
+    errors::{FieldError, ServiceError},
+    events::DomainEvent,
 };
 
-use super::{logs, tag_task as tag_task_usecase};
+use super::{
+    logs,
+    sync::{self, SyncEntity},
+    tag_task as tag_task_usecase,
+};
 
-use sea_orm::prelude::DateTimeUtc;
+use sea_orm::prelude::{DateTimeUtc, Expr};
 use sea_orm::{
     ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseBackend,
     DatabaseConnection, DeleteResult, EntityName, EntityTrait, FromQueryResult, QueryFilter,
@@ -18,6 +23,7 @@ pub struct NewTask {
     pub description: String,
     pub parent_task_id: Option<i32>,
     pub tag_ids: Option<Vec<i32>>,
+    pub due_at: Option<DateTimeUtc>,
     pub user_id: i32,
 }
 
@@ -28,11 +34,16 @@ pub struct TaskUpdate {
     pub completed: Option<bool>,
     pub parent_task_id: Option<i32>,
     pub tag_ids: Option<Vec<i32>>,
+    /// `Some(None)` なら期限を外す
+    pub due_at: Option<Option<DateTimeUtc>>,
     pub user_id: i32,
+    /// 指定されていれば、保存されている版と一致する場合だけ更新する
+    pub expected_version: Option<i32>,
 }
 
 pub struct Task {
     pub id: i32,
+    pub uuid: String,
     pub title: String,
     pub description: String,
     pub completed: bool,
@@ -40,8 +51,10 @@ pub struct Task {
     pub root_task_id: Option<i32>,
     pub depth: i32,
     pub position: i32,
+    pub due_at: Option<DateTimeUtc>,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    pub version: i32,
     pub tags: Vec<TaskTag>,
 }
 
@@ -52,6 +65,7 @@ pub struct TaskSubtreeNode {
 
 pub struct TaskTag {
     pub id: i32,
+    pub uuid: String,
     pub name: String,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
@@ -63,6 +77,7 @@ impl Task {
             .into_iter()
             .map(|tag| TaskTag {
                 id: tag.id,
+                uuid: tag.uuid,
                 name: tag.name,
                 created_at: tag.created_at,
                 updated_at: tag.updated_at,
@@ -71,6 +86,7 @@ impl Task {
 
         Self {
             id: task.id,
+            uuid: task.uuid,
             title: task.title,
             description: task.description,
             completed: task.completed,
@@ -78,11 +94,23 @@ impl Task {
             root_task_id: task.root_task_id,
             depth: task.depth,
             position: task.position,
+            due_at: task.due_at,
             created_at: task.created_at,
             updated_at: task.updated_at,
+            version: task.version,
             tags,
         }
     }
+
+    /// 完了にしたタスクを外部へ知らせるイベント
+    pub fn completed_event(&self) -> DomainEvent {
+        DomainEvent::TaskCompleted {
+            task_id: self.id,
+            uuid: self.uuid.clone(),
+            title: self.title.clone(),
+            completed_at: self.updated_at,
+        }
+    }
 }
 
 pub async fn get_tasks(
@@ -114,15 +142,21 @@ pub async fn get_tasks(
     Ok(tasks)
 }
 
-pub async fn insert_task(db: &DatabaseConnection, params: NewTask) -> Result<Task, ServiceError> {
+pub async fn insert_task(
+    db: &(impl ConnectionTrait + TransactionTrait),
+    params: NewTask,
+) -> Result<Task, ServiceError> {
     let NewTask {
         title,
         description,
         parent_task_id,
         tag_ids,
+        due_at,
         user_id,
     } = params;
 
+    validate_task_input(db, user_id, Some(&title), tag_ids.as_deref()).await?;
+
     let txn = db.begin().await?;
     let hierarchy = build_hierarchy_context(&txn, user_id, parent_task_id).await?;
     let new_task = tasks::ActiveModel {
@@ -133,6 +167,7 @@ pub async fn insert_task(db: &DatabaseConnection, params: NewTask) -> Result<Tas
         root_task_id: ActiveValue::Set(hierarchy.root_task_id),
         depth: ActiveValue::Set(hierarchy.depth),
         position: ActiveValue::Set(hierarchy.position),
+        due_at: ActiveValue::Set(due_at),
         ..Default::default()
     };
 
@@ -153,6 +188,7 @@ pub async fn insert_task(db: &DatabaseConnection, params: NewTask) -> Result<Tas
         tag_task_usecase::attach_tags_with_conn(&txn, inserted_result.last_insert_id, tag_ids)
             .await?;
     }
+    sync::record_upsert(&txn, SyncEntity::Task, inserted_result.last_insert_id).await?;
 
     txn.commit().await?;
 
@@ -162,7 +198,7 @@ pub async fn insert_task(db: &DatabaseConnection, params: NewTask) -> Result<Tas
 }
 
 pub async fn get_task_by_id(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     user_id: i32,
     id: i32,
 ) -> Result<Task, ServiceError> {
@@ -171,7 +207,7 @@ pub async fn get_task_by_id(
 }
 
 pub async fn update_task(
-    db: &DatabaseConnection,
+    db: &(impl ConnectionTrait + TransactionTrait),
     params: TaskUpdate,
 ) -> Result<Task, ServiceError> {
     let id = params.id;
@@ -181,6 +217,14 @@ pub async fn update_task(
         .one(db)
         .await?
         .ok_or(ServiceError::NotFound("task"))?;
+    check_version(current_task.version, params.expected_version)?;
+    validate_task_input(
+        db,
+        params.user_id,
+        params.title.as_deref(),
+        params.tag_ids.as_deref(),
+    )
+    .await?;
     let mut task: tasks::ActiveModel = current_task.clone().into();
 
     if let Some(title) = params.title {
@@ -195,12 +239,16 @@ pub async fn update_task(
         task.completed = ActiveValue::Set(completed);
     }
 
-    if let Some(new_parent_id) = params.parent_task_id {
-        if current_task.parent_task_id != Some(new_parent_id) {
-            return Err(ServiceError::BadRequest(
-                "changing parent task hierarchy is not supported yet".to_string(),
-            ));
-        }
+    if let Some(due_at) = params.due_at {
+        task.due_at = ActiveValue::Set(due_at);
+    }
+
+    if let Some(new_parent_id) = params.parent_task_id
+        && current_task.parent_task_id != Some(new_parent_id)
+    {
+        return Err(ServiceError::BadRequest(
+            "changing parent task hierarchy is not supported yet".to_string(),
+        ));
     }
 
     task.parent_task_id = match params.parent_task_id {
@@ -208,45 +256,93 @@ pub async fn update_task(
         None => ActiveValue::NotSet,
     };
     task.updated_at = ActiveValue::Set(chrono::Utc::now());
+    task.version = ActiveValue::Set(current_task.version + 1);
 
     let txn = db.begin().await?;
-    let task = task.update(&txn).await?;
+    // 読み込んだ後に他の更新が入っていれば版が進んでいるので、一行も更新されない
+    let updated = Tasks::update_many()
+        .set(task)
+        .filter(tasks::Column::Id.eq(id))
+        .filter(tasks::Column::Version.eq(current_task.version))
+        .exec(&txn)
+        .await?;
+    if updated.rows_affected == 0 {
+        return Err(ServiceError::PreconditionFailed("task"));
+    }
     if let Some(tag_ids) = params.tag_ids {
         tag_task_usecase::sync_tags(&txn, id, tag_ids).await?;
     }
+    sync::record_upsert(&txn, SyncEntity::Task, id).await?;
     txn.commit().await?;
 
+    let (task, tags) = find_task_with_tags(db, params.user_id, id).await?;
+
     if params.completed == Some(true) {
-        logs::insert_log(
+        logs::insert_event_log(
             db,
-            logs::NewLog {
-                content: format!("Task \"{}\" completed.", task.title),
-                source: logs::LogSource::System,
-                task_id: Some(id),
-                user_id: params.user_id,
-                tag_ids: Vec::new(),
-                tag_names: Vec::new(),
+            params.user_id,
+            Some(id),
+            logs::LogEvent::TaskCompleted {
+                task_id: id,
+                title: task.title.clone(),
             },
         )
         .await?;
     }
 
-    let (task, tags) = find_task_with_tags(db, params.user_id, id).await?;
-
     Ok(Task::from_model(task, tags))
 }
 
 pub async fn delete_task(
-    db: &DatabaseConnection,
+    db: &(impl ConnectionTrait + TransactionTrait),
     id: i32,
     user_id: i32,
+    expected_version: Option<i32>,
 ) -> Result<DeleteResult, ServiceError> {
-    Tasks::delete_many()
+    let txn = db.begin().await?;
+    // 子孫のタスクとログも連鎖して消えるので、まとめて削除を記録する
+    let deleted_keys = sync::task_tree_keys(&txn, user_id, id).await?;
+    let mut query = Tasks::delete_many()
         .filter(tasks::Column::Id.eq(id))
-        .filter(tasks::Column::UserId.eq(user_id))
-        .exec(db)
-        .await
-        .map_err(Into::into)
+        .filter(tasks::Column::UserId.eq(user_id));
+    if let Some(version) = expected_version {
+        query = query.filter(tasks::Column::Version.eq(version));
+    }
+    let result = query.exec(&txn).await?;
+
+    if result.rows_affected == 0 && expected_version.is_some() {
+        let exists = Tasks::find()
+            .filter(tasks::Column::Id.eq(id))
+            .filter(tasks::Column::UserId.eq(user_id))
+            .one(&txn)
+            .await?
+            .is_some();
+        if exists {
+            return Err(ServiceError::PreconditionFailed("task"));
+        }
+    }
+    if result.rows_affected > 0 {
+        sync::record_deletes(&txn, &deleted_keys).await?;
+    }
+    txn.commit().await?;
+    Ok(result)
+}
+
+/// タグの付け外しなど、タスク本体以外の変更でも表現が変わるときに版を進める
+pub(crate) async fn bump_version(
+    conn: &impl ConnectionTrait,
+    task_id: i32,
+) -> Result<(), ServiceError> {
+    Tasks::update_many()
+        .col_expr(
+            tasks::Column::Version,
+            Expr::col(tasks::Column::Version).add(1),
+        )
+        .col_expr(tasks::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+        .filter(tasks::Column::Id.eq(task_id))
+        .exec(conn)
+        .await?;
+    sync::record_upsert(conn, SyncEntity::Task, task_id).await
 }
 
 pub async fn get_task_subtree(
@@ -288,24 +384,68 @@ pub async fn get_task_subtree(
     Ok(nodes)
 }
 
-struct HierarchyContext {
-    parent_task_id: Option<i32>,
-    root_task_id: Option<i32>,
-    depth: i32,
-    position: i32,
+pub(crate) struct HierarchyContext {
+    pub(crate) parent_task_id: Option<i32>,
+    pub(crate) root_task_id: Option<i32>,
+    pub(crate) depth: i32,
+    pub(crate) position: i32,
 }
 
 #[allow(dead_code)]
 #[derive(Debug, FromQueryResult)]
-struct TaskSubtreeRow {
-    id: i32,
+pub(crate) struct TaskSubtreeRow {
+    pub(crate) id: i32,
     relative_depth: i32,
     depth: i32,
     position: i32,
 }
 
+fn check_version(current: i32, expected: Option<i32>) -> Result<(), ServiceError> {
+    match expected {
+        Some(expected) if expected != current => Err(ServiceError::PreconditionFailed("task")),
+        _ => Ok(()),
+    }
+}
+
+/// タイトルの空欄と他ユーザーのタグの付与を検出し、問題のある項目をまとめて返す。
+async fn validate_task_input(
+    db: &impl ConnectionTrait,
+    user_id: i32,
+    title: Option<&str>,
+    tag_ids: Option<&[i32]>,
+) -> Result<(), ServiceError> {
+    let mut errors = Vec::new();
+    if title.is_some_and(|title| title.trim().is_empty()) {
+        errors.push(FieldError::new("title", "blank", "title must not be empty"));
+    }
+
+    if let Some(tag_ids) = tag_ids.filter(|ids| !ids.is_empty()) {
+        let owned: Vec<i32> = Tags::find()
+            .select_only()
+            .column(tags::Column::Id)
+            .filter(tags::Column::Id.is_in(tag_ids.iter().copied()))
+            .filter(tags::Column::UserId.eq(user_id))
+            .into_tuple()
+            .all(db)
+            .await?;
+        for tag_id in tag_ids.iter().filter(|id| !owned.contains(id)) {
+            errors.push(FieldError::new(
+                "tag_ids",
+                "not_owned",
+                format!("tag {tag_id} does not exist or belongs to another user"),
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ServiceError::Validation(errors))
+    }
+}
+
 async fn find_task_with_tags(
-    db: &DatabaseConnection,
+    db: &impl ConnectionTrait,
     user_id: i32,
     id: i32,
 ) -> Result<(tasks::Model, Vec<tags::Model>), ServiceError> {
@@ -320,7 +460,7 @@ async fn find_task_with_tags(
         .ok_or(ServiceError::NotFound("task"))
 }
 
-async fn build_hierarchy_context(
+pub(crate) async fn build_hierarchy_context(
     conn: &impl ConnectionTrait,
     user_id: i32,
     parent_task_id: Option<i32>,
@@ -376,7 +516,7 @@ async fn next_position(
     Ok(next)
 }
 
-async fn query_task_subtree_rows(
+pub(crate) async fn query_task_subtree_rows(
     conn: &impl ConnectionTrait,
     user_id: i32,
     task_id: i32,
@@ -3,19 +3,58 @@ use crate::errors::ServiceError;
 use chrono::{DateTime, Utc};
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 
+/// ユーザーの権限。管理 API は `Admin` のみが利用できる。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum UserRole {
+    #[default]
+    User,
+    Admin,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::User => "User",
+            UserRole::Admin => "Admin",
+        }
+    }
+
+    pub fn is_admin(&self) -> bool {
+        matches!(self, UserRole::Admin)
+    }
+}
+
+impl From<String> for UserRole {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "Admin" => UserRole::Admin,
+            _ => UserRole::User,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct User {
     pub id: i32,
+    pub uuid: String,
     pub email: String,
     pub name: String,
     pub work_time: i32,
     pub break_time: i32,
     pub locale: String,
+    pub pending_email: Option<String>,
+    pub role: UserRole,
+    /// 前日の日報をメールで受け取る
+    pub daily_journal_email: bool,
+    /// 1 日に完了したいセッション数。0 なら目標なし
+    pub daily_goal: i32,
 }
 
 #[derive(Clone, Debug)]
 pub struct UserFull {
     pub id: i32,
+    pub uuid: String,
     pub email: String,
     pub name: String,
     pub password: String,
@@ -24,17 +63,27 @@ pub struct UserFull {
     pub break_time: i32,
     pub locale: String,
     pub verification_token: Option<String>,
+    pub pending_email: Option<String>,
+    pub role: UserRole,
+    pub daily_journal_email: bool,
+    pub daily_goal: i32,
+    pub disabled_at: Option<DateTime<Utc>>,
 }
 
 impl From<users::Model> for User {
     fn from(model: users::Model) -> Self {
         User {
             id: model.id,
+            uuid: model.uuid,
             email: model.email,
             name: model.name,
             work_time: model.work_time,
             break_time: model.break_time,
             locale: model.locale,
+            pending_email: model.pending_email,
+            role: model.role.into(),
+            daily_journal_email: model.daily_journal_email,
+            daily_goal: model.daily_goal,
         }
     }
 }
@@ -43,6 +92,7 @@ impl From<users::Model> for UserFull {
     fn from(model: users::Model) -> Self {
         UserFull {
             id: model.id,
+            uuid: model.uuid,
             email: model.email,
             name: model.name,
             password: model.password,
@@ -51,6 +101,11 @@ impl From<users::Model> for UserFull {
             break_time: model.break_time,
             locale: model.locale,
             verification_token: model.verification_token,
+            pending_email: model.pending_email,
+            role: model.role.into(),
+            daily_journal_email: model.daily_journal_email,
+            daily_goal: model.daily_goal,
+            disabled_at: model.disabled_at,
         }
     }
 }
@@ -59,11 +114,16 @@ impl From<UserFull> for User {
     fn from(user: UserFull) -> Self {
         User {
             id: user.id,
+            uuid: user.uuid,
             email: user.email,
             name: user.name,
             work_time: user.work_time,
             break_time: user.break_time,
             locale: user.locale,
+            pending_email: user.pending_email,
+            role: user.role,
+            daily_journal_email: user.daily_journal_email,
+            daily_goal: user.daily_goal,
         }
     }
 }
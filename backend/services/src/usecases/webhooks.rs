@@ -0,0 +1,900 @@
+//! ドメインイベントを利用者が登録した URL へ POST する Webhook のユースケースです。
+//! イベントは `webhook_deliveries` テーブルに積み、ワーカーが HMAC 署名を付けて送信します。
+//! 送信に失敗した配信は指数バックオフで再試行し、結果は配信履歴として参照できます。
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::{Rng, distributions::Alphanumeric};
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::Serialize;
+use sha2::Sha256;
+use url::{Host, Url};
+
+use crate::{
+    entities::{prelude::*, webhook_deliveries, webhooks},
+    errors::ServiceError,
+    events::{DomainEvent, EventBus, PublishedEvent},
+};
+
+/// `sha256=<hex>` 形式の署名。`<timestamp>.<body>` に対する HMAC-SHA256
+pub const SIGNATURE_HEADER: &str = "x-decopon-signature";
+/// 署名に含めた UNIX 秒。受信側で古すぎるリクエストを拒否できる
+pub const TIMESTAMP_HEADER: &str = "x-decopon-timestamp";
+pub const EVENT_HEADER: &str = "x-decopon-event";
+pub const DELIVERY_HEADER: &str = "x-decopon-delivery";
+
+const SECRET_PREFIX: &str = "whsec_";
+const SECRET_RANDOM_LENGTH: usize = 32;
+/// 配信履歴として返す最大件数
+pub const DELIVERY_LOG_LIMIT: u64 = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "Pending",
+            DeliveryStatus::Succeeded => "Succeeded",
+            DeliveryStatus::Failed => "Failed",
+        }
+    }
+}
+
+impl From<String> for DeliveryStatus {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "Succeeded" => DeliveryStatus::Succeeded,
+            "Failed" => DeliveryStatus::Failed,
+            _ => DeliveryStatus::Pending,
+        }
+    }
+}
+
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub active: bool,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+    pub user_id: i32,
+}
+
+impl From<webhooks::Model> for Webhook {
+    fn from(model: webhooks::Model) -> Self {
+        Self {
+            id: model.id,
+            url: model.url,
+            event_types: decode_event_types(&model.event_types),
+            active: model.active,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            user_id: model.user_id,
+        }
+    }
+}
+
+/// 登録直後の Webhook。署名用の `secret` はこのタイミングでしか取得できない。
+pub struct CreatedWebhook {
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+pub struct NewWebhook {
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub user_id: i32,
+}
+
+pub struct WebhookUpdate {
+    pub id: i32,
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub active: Option<bool>,
+    pub user_id: i32,
+}
+
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub webhook_id: i32,
+    pub event_type: String,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTimeUtc,
+    pub delivered_at: Option<DateTimeUtc>,
+    pub created_at: DateTimeUtc,
+}
+
+impl From<webhook_deliveries::Model> for WebhookDelivery {
+    fn from(model: webhook_deliveries::Model) -> Self {
+        Self {
+            id: model.id,
+            webhook_id: model.webhook_id,
+            event_type: model.event_type,
+            payload: model.payload,
+            status: DeliveryStatus::from(model.status),
+            attempts: model.attempts,
+            response_status: model.response_status,
+            last_error: model.last_error,
+            next_attempt_at: model.next_attempt_at,
+            delivered_at: model.delivered_at,
+            created_at: model.created_at,
+        }
+    }
+}
+
+/// Webhook の送り先として認めるアドレスの範囲
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TargetPolicy {
+    /// 公開されたアドレスだけに送り、内部のネットワークへのリクエストの踏み台にさせない
+    #[default]
+    PublicOnly,
+    /// ループバックやプライベートアドレスにも送る。手元で受け取るテストや閉じたネットワーク向け
+    AllowPrivate,
+}
+
+async fn normalize_url(url: &str, policy: TargetPolicy) -> Result<String, ServiceError> {
+    let parsed = Url::parse(url.trim()).map_err(|_| {
+        ServiceError::invalid_field("url", "invalid_url", "url must be an absolute URL")
+    })?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(ServiceError::invalid_field(
+            "url",
+            "invalid_url",
+            "url must use http or https",
+        ));
+    }
+    check_target(&parsed, policy).await?;
+    Ok(parsed.to_string())
+}
+
+/// 送り先のホストを名前解決し、内部のネットワークを指していれば断る
+async fn check_target(url: &Url, policy: TargetPolicy) -> Result<(), ServiceError> {
+    if policy == TargetPolicy::AllowPrivate {
+        return Ok(());
+    }
+    let ip = match url.host() {
+        Some(Host::Domain(domain)) => {
+            let port = url.port_or_known_default().unwrap_or_default();
+            return public_addrs(domain, port).await.map(|_| ());
+        }
+        Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+        None => {
+            return Err(ServiceError::invalid_field(
+                "url",
+                "invalid_url",
+                "url must have a host",
+            ));
+        }
+    };
+    if is_public(ip) {
+        Ok(())
+    } else {
+        Err(private_target())
+    }
+}
+
+/// ホストのアドレスを引く。一つでも内部のネットワークを指していれば、どのアドレスにも送らない
+async fn public_addrs(host: &str, port: u16) -> Result<Vec<SocketAddr>, ServiceError> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|_| {
+            ServiceError::invalid_field("url", "unresolvable_host", "url host cannot be resolved")
+        })?
+        .collect();
+    if addrs.is_empty() {
+        return Err(ServiceError::invalid_field(
+            "url",
+            "unresolvable_host",
+            "url host cannot be resolved",
+        ));
+    }
+    if !addrs.iter().all(|addr| is_public(addr.ip())) {
+        return Err(private_target());
+    }
+    Ok(addrs)
+}
+
+fn private_target() -> ServiceError {
+    ServiceError::invalid_field(
+        "url",
+        "private_target",
+        "url must not point to a loopback, private or link-local address",
+    )
+}
+
+/// インターネット上で到達できるユニキャストのアドレスか
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // 0.0.0.0/8, 100.64.0.0/10 (CGNAT), 192.0.0.0/24, 198.18.0.0/15, 240.0.0.0/4
+                || a == 0
+                || (a == 100 && (b & 0xc0) == 64)
+                || (a == 192 && b == 0 && ip.octets()[2] == 0)
+                || (a == 198 && (b & 0xfe) == 18)
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(mapped));
+            }
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()
+                // 2001:db8::/32 (文書用)
+                || (ip.segments()[0] == 0x2001 && ip.segments()[1] == 0x0db8))
+        }
+    }
+}
+
+fn normalize_event_types(event_types: Vec<String>) -> Result<Vec<String>, ServiceError> {
+    let mut normalized: Vec<String> = Vec::with_capacity(event_types.len());
+    for event_type in event_types {
+        let event_type = event_type.trim().to_string();
+        if !DomainEvent::NAMES.contains(&event_type.as_str()) {
+            return Err(ServiceError::invalid_field(
+                "event_types",
+                "unknown_event",
+                format!("unknown event type: {event_type}"),
+            ));
+        }
+        if !normalized.contains(&event_type) {
+            normalized.push(event_type);
+        }
+    }
+    if normalized.is_empty() {
+        return Err(ServiceError::invalid_field(
+            "event_types",
+            "blank",
+            "subscribe to at least one event type",
+        ));
+    }
+    Ok(normalized)
+}
+
+fn encode_event_types(event_types: &[String]) -> Result<String, ServiceError> {
+    serde_json::to_string(event_types).map_err(|e| ServiceError::Internal(Box::new(e)))
+}
+
+fn decode_event_types(value: &str) -> Vec<String> {
+    serde_json::from_str(value).unwrap_or_default()
+}
+
+fn generate_secret() -> String {
+    let random: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(SECRET_RANDOM_LENGTH)
+        .map(char::from)
+        .collect();
+    format!("{SECRET_PREFIX}{random}")
+}
+
+/// 受信側は同じ計算をして `x-decopon-signature` と比較する
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+async fn find_webhook(
+    db: &impl ConnectionTrait,
+    id: i32,
+    user_id: i32,
+) -> Result<webhooks::Model, ServiceError> {
+    Webhooks::find_by_id(id)
+        .filter(webhooks::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or(ServiceError::NotFound("webhook"))
+}
+
+pub async fn get_webhooks(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Vec<Webhook>, ServiceError> {
+    let webhooks = Webhooks::find()
+        .filter(webhooks::Column::UserId.eq(user_id))
+        .order_by_asc(webhooks::Column::Id)
+        .all(db)
+        .await?;
+    Ok(webhooks.into_iter().map(Into::into).collect())
+}
+
+pub async fn insert_webhook(
+    db: &DatabaseConnection,
+    params: NewWebhook,
+    policy: TargetPolicy,
+) -> Result<CreatedWebhook, ServiceError> {
+    // 名前解決の前に、手元で確かめられる項目から確かめる
+    let event_types = normalize_event_types(params.event_types)?;
+    let url = normalize_url(&params.url, policy).await?;
+    let secret = generate_secret();
+    let now = Utc::now();
+    let webhook = webhooks::ActiveModel {
+        url: ActiveValue::Set(url),
+        secret: ActiveValue::Set(secret.clone()),
+        event_types: ActiveValue::Set(encode_event_types(&event_types)?),
+        active: ActiveValue::Set(true),
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
+        user_id: ActiveValue::Set(params.user_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(CreatedWebhook {
+        webhook: webhook.into(),
+        secret,
+    })
+}
+
+pub async fn update_webhook(
+    db: &DatabaseConnection,
+    params: WebhookUpdate,
+    policy: TargetPolicy,
+) -> Result<Webhook, ServiceError> {
+    let mut webhook: webhooks::ActiveModel =
+        find_webhook(db, params.id, params.user_id).await?.into();
+
+    if let Some(url) = params.url {
+        webhook.url = ActiveValue::Set(normalize_url(&url, policy).await?);
+    }
+    if let Some(event_types) = params.event_types {
+        let event_types = normalize_event_types(event_types)?;
+        webhook.event_types = ActiveValue::Set(encode_event_types(&event_types)?);
+    }
+    if let Some(active) = params.active {
+        webhook.active = ActiveValue::Set(active);
+    }
+    webhook.updated_at = ActiveValue::Set(Utc::now());
+
+    let webhook = webhook.update(db).await?;
+    Ok(webhook.into())
+}
+
+pub async fn delete_webhook(
+    db: &DatabaseConnection,
+    id: i32,
+    user_id: i32,
+) -> Result<(), ServiceError> {
+    let result = Webhooks::delete_many()
+        .filter(webhooks::Column::Id.eq(id))
+        .filter(webhooks::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(ServiceError::NotFound("webhook"));
+    }
+    Ok(())
+}
+
+/// 新しい順に直近の配信を返す
+pub async fn get_deliveries(
+    db: &DatabaseConnection,
+    id: i32,
+    user_id: i32,
+) -> Result<Vec<WebhookDelivery>, ServiceError> {
+    let webhook = find_webhook(db, id, user_id).await?;
+    let deliveries = WebhookDeliveries::find()
+        .filter(webhook_deliveries::Column::WebhookId.eq(webhook.id))
+        .order_by_desc(webhook_deliveries::Column::Id)
+        .limit(DELIVERY_LOG_LIMIT)
+        .all(db)
+        .await?;
+    Ok(deliveries.into_iter().map(Into::into).collect())
+}
+
+/// イベントを購読している有効な Webhook ごとに配信を登録し、登録した件数を返す。
+pub async fn enqueue_deliveries(
+    db: &impl ConnectionTrait,
+    event: &PublishedEvent,
+) -> Result<usize, ServiceError> {
+    let event_type = event.event.name();
+    let subscribed: Vec<_> = Webhooks::find()
+        .filter(webhooks::Column::UserId.eq(event.user_id))
+        .filter(webhooks::Column::Active.eq(true))
+        .all(db)
+        .await?
+        .into_iter()
+        .filter(|webhook| {
+            decode_event_types(&webhook.event_types)
+                .iter()
+                .any(|name| name == event_type)
+        })
+        .collect();
+    if subscribed.is_empty() {
+        return Ok(0);
+    }
+
+    let payload = serde_json::to_string(event).map_err(|e| ServiceError::Internal(Box::new(e)))?;
+    let now = Utc::now();
+    for webhook in &subscribed {
+        webhook_deliveries::ActiveModel {
+            webhook_id: ActiveValue::Set(webhook.id),
+            event_type: ActiveValue::Set(event_type.to_string()),
+            payload: ActiveValue::Set(payload.clone()),
+            status: ActiveValue::Set(DeliveryStatus::Pending.as_str().to_string()),
+            attempts: ActiveValue::Set(0),
+            next_attempt_at: ActiveValue::Set(now),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+    tracing::debug!(
+        event_id = event.id,
+        count = subscribed.len(),
+        "queued webhook deliveries"
+    );
+    Ok(subscribed.len())
+}
+
+/// イベントを発行し、購読している Webhook への配信をその場で登録する。
+/// 書き込みは済んでいるため、登録に失敗しても呼び出し元には返さずログに残す。
+pub async fn publish(
+    db: &impl ConnectionTrait,
+    events: &EventBus,
+    user_id: i32,
+    event: DomainEvent,
+) -> Arc<PublishedEvent> {
+    let published = events.publish(user_id, event);
+    #[cfg(feature = "webhooks")]
+    if let Err(err) = enqueue_deliveries(db, &published).await {
+        tracing::error!(event_id = published.id, error = %err, "failed to queue webhook deliveries");
+    }
+    #[cfg(not(feature = "webhooks"))]
+    let _ = db;
+    published
+}
+
+#[cfg(feature = "webhooks")]
+pub use worker::{WebhookConfig, WebhookWorker};
+
+#[cfg(feature = "webhooks")]
+mod worker {
+    use std::time::Duration;
+
+    use tokio::task::JoinHandle;
+
+    use super::*;
+    use crate::usecases::outbox_worker::{self, OutboxConfig, OutboxRow, OutboxWorker};
+
+    #[derive(Clone, Debug)]
+    pub struct WebhookConfig {
+        pub outbox: OutboxConfig,
+        /// 受信側の応答を待つ時間
+        pub timeout: Duration,
+        pub targets: TargetPolicy,
+    }
+
+    impl Default for WebhookConfig {
+        fn default() -> Self {
+            Self {
+                outbox: OutboxConfig::default(),
+                timeout: Duration::from_secs(10),
+                targets: TargetPolicy::default(),
+            }
+        }
+    }
+
+    /// 一回の送信の結果。2xx 以外の応答と通信エラーは再試行の対象
+    enum Attempt {
+        Delivered(u16),
+        Rejected(u16),
+        Failed(String),
+    }
+
+    pub struct WebhookWorker {
+        db: Arc<DatabaseConnection>,
+        http: reqwest::Client,
+        config: WebhookConfig,
+    }
+
+    impl WebhookWorker {
+        pub fn new(db: Arc<DatabaseConnection>) -> Self {
+            Self::build(db, WebhookConfig::default())
+        }
+
+        pub fn with_config(self, config: WebhookConfig) -> Self {
+            Self::build(self.db, config)
+        }
+
+        fn build(db: Arc<DatabaseConnection>, config: WebhookConfig) -> Self {
+            // 転送先や名前解決の結果で内部のネットワークへ誘導されないよう、送信時にも確かめる
+            let mut http = reqwest::Client::builder()
+                .timeout(config.timeout)
+                .redirect(reqwest::redirect::Policy::none());
+            if config.targets == TargetPolicy::PublicOnly {
+                http = http.dns_resolver(Arc::new(PublicResolver));
+            }
+            let http = http
+                .build()
+                .expect("webhook HTTP client settings are valid");
+            Self { db, http, config }
+        }
+
+        /// ワーカーを Tokio のタスクとして起動する。
+        pub fn spawn(self) -> JoinHandle<()> {
+            outbox_worker::spawn(self)
+        }
+
+        /// 送信時刻に達した配信を送り、処理した件数を返す。
+        pub async fn process_due(&self) -> Result<usize, ServiceError> {
+            let due = WebhookDeliveries::find()
+                .find_also_related(Webhooks)
+                .filter(webhook_deliveries::Column::Status.eq(DeliveryStatus::Pending.as_str()))
+                .filter(webhook_deliveries::Column::NextAttemptAt.lte(Utc::now()))
+                .order_by_asc(webhook_deliveries::Column::NextAttemptAt)
+                .limit(self.config.outbox.batch_size)
+                .all(self.db.as_ref())
+                .await?;
+
+            let mut processed = 0;
+            for (delivery, webhook) in due {
+                if !outbox_worker::claim(&self.db, &self.config.outbox, &delivery).await? {
+                    continue;
+                }
+                let attempt = match webhook {
+                    Some(webhook) if webhook.active => self.deliver(&delivery, &webhook).await,
+                    // 無効にされた Webhook へは送らずに打ち切る
+                    _ => {
+                        self.abandon(delivery).await?;
+                        processed += 1;
+                        continue;
+                    }
+                };
+                self.record_result(delivery, attempt).await?;
+                processed += 1;
+            }
+            Ok(processed)
+        }
+
+        async fn deliver(
+            &self,
+            delivery: &webhook_deliveries::Model,
+            webhook: &webhooks::Model,
+        ) -> Attempt {
+            let target = match Url::parse(&webhook.url) {
+                Ok(url) => check_target(&url, self.config.targets).await,
+                Err(err) => Err(ServiceError::BadRequest(err.to_string())),
+            };
+            match target {
+                Ok(()) => {}
+                Err(ServiceError::Validation(errors)) => {
+                    let messages: Vec<_> = errors.into_iter().map(|error| error.message).collect();
+                    return Attempt::Failed(messages.join(", "));
+                }
+                Err(err) => return Attempt::Failed(err.to_string()),
+            }
+            let timestamp = Utc::now().timestamp();
+            let result = self
+                .http
+                .post(&webhook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, &delivery.event_type)
+                .header(DELIVERY_HEADER, delivery.id.to_string())
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(
+                    SIGNATURE_HEADER,
+                    sign(&webhook.secret, timestamp, &delivery.payload),
+                )
+                .body(delivery.payload.clone())
+                .send()
+                .await;
+            match result {
+                Ok(response) if response.status().is_success() => {
+                    Attempt::Delivered(response.status().as_u16())
+                }
+                Ok(response) => Attempt::Rejected(response.status().as_u16()),
+                Err(err) => Attempt::Failed(err.to_string()),
+            }
+        }
+
+        async fn abandon(&self, delivery: webhook_deliveries::Model) -> Result<(), ServiceError> {
+            let mut active: webhook_deliveries::ActiveModel = delivery.into();
+            active.status = ActiveValue::Set(DeliveryStatus::Failed.as_str().to_string());
+            active.last_error = ActiveValue::Set(Some("webhook is disabled".to_string()));
+            active.updated_at = ActiveValue::Set(Utc::now());
+            active.update(self.db.as_ref()).await?;
+            Ok(())
+        }
+
+        async fn record_result(
+            &self,
+            delivery: webhook_deliveries::Model,
+            attempt: Attempt,
+        ) -> Result<(), ServiceError> {
+            let now = Utc::now();
+            let attempts = delivery.attempts + 1;
+            let delivery_id = delivery.id;
+            let mut active: webhook_deliveries::ActiveModel = delivery.into();
+            active.attempts = ActiveValue::Set(attempts);
+            active.updated_at = ActiveValue::Set(now);
+
+            let error = match attempt {
+                Attempt::Delivered(status) => {
+                    active.status =
+                        ActiveValue::Set(DeliveryStatus::Succeeded.as_str().to_string());
+                    active.response_status = ActiveValue::Set(Some(i32::from(status)));
+                    active.last_error = ActiveValue::Set(None);
+                    active.delivered_at = ActiveValue::Set(Some(now));
+                    tracing::info!(delivery_id, attempts, status, "delivered webhook");
+                    None
+                }
+                Attempt::Rejected(status) => {
+                    active.response_status = ActiveValue::Set(Some(i32::from(status)));
+                    Some(format!("receiver responded with HTTP {status}"))
+                }
+                Attempt::Failed(error) => {
+                    active.response_status = ActiveValue::Set(None);
+                    Some(error)
+                }
+            };
+
+            if let Some(error) = error {
+                if attempts >= self.config.outbox.max_attempts {
+                    active.status = ActiveValue::Set(DeliveryStatus::Failed.as_str().to_string());
+                    tracing::error!(delivery_id, attempts, error = %error, "giving up on webhook delivery");
+                } else {
+                    active.next_attempt_at =
+                        ActiveValue::Set(self.config.outbox.retry_at(attempts)?);
+                    tracing::warn!(delivery_id, attempts, error = %error, "webhook delivery failed; will retry");
+                }
+                active.last_error = ActiveValue::Set(Some(error));
+            }
+            active.update(self.db.as_ref()).await?;
+            Ok(())
+        }
+    }
+
+    /// 名前解決の結果が内部のネットワークを指していれば接続しない
+    struct PublicResolver;
+
+    impl reqwest::dns::Resolve for PublicResolver {
+        fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+            Box::pin(async move {
+                let addrs = public_addrs(name.as_str(), 0).await?;
+                Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+            })
+        }
+    }
+
+    impl OutboxWorker for WebhookWorker {
+        const QUEUE: &'static str = "webhook_deliveries";
+
+        fn config(&self) -> &OutboxConfig {
+            &self.config.outbox
+        }
+
+        async fn process_due(&self) -> Result<usize, ServiceError> {
+            WebhookWorker::process_due(self).await
+        }
+    }
+
+    impl OutboxRow for webhook_deliveries::Model {
+        type Entity = WebhookDeliveries;
+        const ID: webhook_deliveries::Column = webhook_deliveries::Column::Id;
+        const NEXT_ATTEMPT_AT: webhook_deliveries::Column =
+            webhook_deliveries::Column::NextAttemptAt;
+
+        fn id(&self) -> i32 {
+            self.id
+        }
+
+        fn next_attempt_at(&self) -> DateTimeUtc {
+            self.next_attempt_at
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_user, setup_db};
+    use sea_orm::PaginatorTrait;
+
+    fn new_webhook(user_id: i32, event_types: &[&str]) -> NewWebhook {
+        NewWebhook {
+            url: "https://chat.example.com/hooks/1".to_string(),
+            event_types: event_types.iter().map(|name| name.to_string()).collect(),
+            user_id,
+        }
+    }
+
+    #[test]
+    fn signs_the_timestamp_and_body() {
+        let signature = sign("whsec_test", 1_700_000_000, "{\"type\":\"task_created\"}");
+        assert_eq!(
+            signature,
+            "sha256=09fe322fa464d45cdc93411e73074c973e8526c1bc84006c123bda61ff9f1ec2"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_event_types_and_non_http_urls() {
+        let db = setup_db().await;
+        let user_id = create_user(&db, "alice").await.id;
+
+        let err = insert_webhook(
+            &db,
+            new_webhook(user_id, &["task_exploded"]),
+            TargetPolicy::AllowPrivate,
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(
+            matches!(err, ServiceError::Validation(ref errors) if errors[0].field == "event_types")
+        );
+
+        let mut params = new_webhook(user_id, &["task_created"]);
+        params.url = "ftp://example.com".to_string();
+        let err = insert_webhook(&db, params, TargetPolicy::AllowPrivate)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ServiceError::Validation(ref errors) if errors[0].field == "url"));
+    }
+
+    #[tokio::test]
+    async fn rejects_urls_that_point_into_the_private_network() {
+        let db = setup_db().await;
+        let user_id = create_user(&db, "alice").await.id;
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://localhost/hook",
+            "http://10.0.0.5/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:192.168.0.1]/hook",
+        ] {
+            let mut params = new_webhook(user_id, &["task_created"]);
+            params.url = url.to_string();
+            let err = insert_webhook(&db, params, TargetPolicy::PublicOnly)
+                .await
+                .err()
+                .unwrap();
+            assert!(
+                matches!(err, ServiceError::Validation(ref errors) if errors[0].code == "private_target"),
+                "{url}"
+            );
+        }
+
+        // 明示的に許可すれば手元の受信側にも送れる
+        let mut params = new_webhook(user_id, &["task_created"]);
+        params.url = "http://127.0.0.1:8080/hook".to_string();
+        insert_webhook(&db, params, TargetPolicy::AllowPrivate)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn public_addresses_are_allowed() {
+        assert!(is_public("93.184.216.34".parse().unwrap()));
+        assert!(is_public("2606:2800:220:1::".parse().unwrap()));
+        assert!(!is_public("100.64.0.1".parse().unwrap()));
+        assert!(!is_public("fe80::1".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn queues_deliveries_only_for_subscribed_active_webhooks() {
+        let db = setup_db().await;
+        let user_id = create_user(&db, "alice").await.id;
+        let tasks = insert_webhook(
+            &db,
+            new_webhook(user_id, &["task_created"]),
+            TargetPolicy::AllowPrivate,
+        )
+        .await
+        .unwrap();
+        assert!(tasks.secret.starts_with(SECRET_PREFIX));
+        let sessions = insert_webhook(
+            &db,
+            new_webhook(user_id, &["session_completed"]),
+            TargetPolicy::AllowPrivate,
+        )
+        .await
+        .unwrap();
+        let disabled = insert_webhook(
+            &db,
+            new_webhook(user_id, &["task_created"]),
+            TargetPolicy::AllowPrivate,
+        )
+        .await
+        .unwrap();
+        update_webhook(
+            &db,
+            WebhookUpdate {
+                id: disabled.webhook.id,
+                url: None,
+                event_types: None,
+                active: Some(false),
+                user_id,
+            },
+            TargetPolicy::AllowPrivate,
+        )
+        .await
+        .unwrap();
+
+        let bus = EventBus::default();
+        let event = bus.publish(user_id, DomainEvent::TaskCreated { task_id: 7 });
+        assert_eq!(enqueue_deliveries(&db, &event).await.unwrap(), 1);
+        // 他のユーザーのイベントは配信しない
+        let other = bus.publish(user_id + 1, DomainEvent::TaskCreated { task_id: 8 });
+        assert_eq!(enqueue_deliveries(&db, &other).await.unwrap(), 0);
+
+        let deliveries = get_deliveries(&db, tasks.webhook.id, user_id)
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Pending);
+        assert!(deliveries[0].payload.contains("\"task_id\":7"));
+        assert_eq!(
+            WebhookDeliveries::find()
+                .filter(webhook_deliveries::Column::WebhookId.eq(sessions.webhook.id))
+                .count(&db)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[cfg(feature = "webhooks")]
+    #[tokio::test]
+    async fn publishing_queues_deliveries_without_waiting_for_subscribers() {
+        let db = setup_db().await;
+        let user_id = create_user(&db, "alice").await.id;
+        let created = insert_webhook(
+            &db,
+            new_webhook(user_id, &["task_created"]),
+            TargetPolicy::AllowPrivate,
+        )
+        .await
+        .unwrap();
+
+        // 履歴を持たず購読者もいないバスでも、配信はその場で積まれる
+        let bus = EventBus::new(0);
+        for task_id in 1..=3 {
+            publish(&db, &bus, user_id, DomainEvent::TaskCreated { task_id }).await;
+        }
+
+        let deliveries = get_deliveries(&db, created.webhook.id, user_id)
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len(), 3);
+    }
+}
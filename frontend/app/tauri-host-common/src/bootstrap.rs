@@ -3,6 +3,8 @@ use std::path::PathBuf;
 use decopon_axum::AppState;
 use decopon_config::{sqlite_url_from_path, EnvConfig};
 use decopon_runtime::ServiceContext;
+use crate::events::spawn_event_forwarder;
+use crate::sync::{spawn_periodic_sync, SyncEngine};
 use crate::ipc::AppIpcState;
 use crate::services::AppServices;
 use tauri::{AppHandle, Manager, WebviewWindow};
@@ -57,6 +59,11 @@ pub fn spawn_backend_initializer(
             Ok(service_context) => {
                 let app_state = AppState::from(service_context);
                 let router = decopon_axum::routes::create_routes(app_state.clone(), env_config.app_mode);
+                if let Some(session) = app_state.single_user_session() {
+                    spawn_event_forwarder(app_handle_clone.clone(), app_state.events(), session.user.id);
+                    app_handle_clone.manage(SyncEngine::new(app_state.clone(), session.user.id, data_dir.clone()));
+                    spawn_periodic_sync(app_handle_clone.clone());
+                }
                 let handler = AppIpcState::new(router, app_state);
                 app_handle_clone.manage(handler);
                 info!("Service layer initialized");
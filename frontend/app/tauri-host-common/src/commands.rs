@@ -1,11 +1,12 @@
 use serde_json::json;
 use std::io::{Error, ErrorKind};
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 
 use crate::{
     ensure_app_data_dir,
     init_state::AppInitializationState,
     init_marker::{is_first_launch, load_state, reset_state},
+    sync::{notify_completed, SyncEngine, SyncState},
 };
 
 fn remove_file_if_exists(path: &std::path::Path) -> Result<(), Error> {
@@ -75,3 +76,33 @@ pub fn get_bootstrap_state(
         "reason": snapshot.failed_reason
     }))
 }
+
+fn sync_status(state: &SyncState) -> serde_json::Value {
+    json!({
+        "configured": state.remote_url.is_some() && state.token.is_some(),
+        "remoteUrl": state.remote_url,
+        "deviceId": state.device_id,
+    })
+}
+
+#[tauri::command]
+pub fn configure_sync(
+    engine: tauri::State<'_, SyncEngine>,
+    remote_url: Option<String>,
+    token: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let state = engine
+        .configure(remote_url, token)
+        .map_err(|e| e.to_string())?;
+    Ok(sync_status(&state))
+}
+
+#[tauri::command]
+pub async fn run_sync(app: AppHandle) -> Result<serde_json::Value, String> {
+    let engine = app
+        .try_state::<SyncEngine>()
+        .ok_or_else(|| "backend is not ready".to_string())?;
+    let report = engine.sync_now().await.map_err(|e| e.to_string())?;
+    notify_completed(&app, &report);
+    serde_json::to_value(&report).map_err(|e| e.to_string())
+}
@@ -0,0 +1,21 @@
+use std::sync::Arc;
+
+use decopon_axum::events::EventBus;
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+use crate::DOMAIN_EVENT;
+
+/// シングルユーザーの変更イベントを購読し、Tauri のイベントとしてフロントへ転送する。
+/// Web の `GET /events` と同じペイロードを送る。
+pub fn spawn_event_forwarder(app_handle: AppHandle, events: Arc<EventBus>, user_id: i32) {
+    let mut subscription = events.subscribe(user_id, None);
+    tauri::async_runtime::spawn(async move {
+        info!(user_id, "Forwarding domain events to the frontend");
+        while let Some(event) = subscription.recv().await {
+            if let Err(error) = app_handle.emit(DOMAIN_EVENT, &*event) {
+                warn!(error = ?error, event = event.event.name(), "failed to emit domain event");
+            }
+        }
+    });
+}
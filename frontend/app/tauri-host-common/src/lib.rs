@@ -8,6 +8,8 @@ use tauri::{AppHandle, Manager};
 
 pub const BACKEND_READY_EVENT: &str = "decopon://backend-ready";
 pub const FRONTEND_READY_EVENT: &str = "decopon://frontend-ready";
+pub const DOMAIN_EVENT: &str = "decopon://domain-event";
+pub const SYNC_COMPLETED_EVENT: &str = "decopon://sync-completed";
 
 pub mod init_state;
 pub mod services;
@@ -15,6 +17,8 @@ pub mod init_marker;
 pub mod commands;
 pub mod splashscreen;
 pub mod bootstrap;
+pub mod events;
+pub mod sync;
 pub mod ipc;
 pub use ipc::{dispatch_http_request, AppIpcState, IpcHttpResponse};
 
@@ -32,9 +32,13 @@ impl AppServices {
             database_url: env_config.database_url,
             jwt_secret: env_config.jwt_secret,
             ensure_single_user_session: env_config.single_user.enabled && !skip_bootstrap,
-            enable_mailer: env_config.smtp.enabled,
+            mail_transport: env_config.mail,
             run_migrations: !skip_bootstrap,
             password_worker_threads: 4,
+            oidc: env_config.oidc,
+            password_hashing: env_config.password_hashing,
+            admin_emails: env_config.admin_emails,
+            allow_private_webhook_targets: env_config.allow_private_webhook_targets,
         };
 
         let runtime = ServiceRuntimeBuilder::from_config(runtime_config)
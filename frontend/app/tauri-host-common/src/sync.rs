@@ -0,0 +1,261 @@
+//! ローカルの DB を Web のアカウントと同期するエンジン。
+//! ローカルで記録された変更を `POST /sync/push` で送り、Web 側の変更を `GET /sync/changes` で取り込む。
+//! 接続先とトークン、どこまで送受信したかは `sync_state.json` に保存する。
+
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use decopon_axum::usecases::sync::{self, SkippedChange, SyncChange};
+use decopon_axum::{AppState, ServiceError};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+use tracing::{info, warn};
+use url::Url;
+
+use crate::SYNC_COMPLETED_EVENT;
+
+/// 自動で同期する間隔
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(300);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SyncState {
+    /// 同期先の API のベース URL。未設定なら同期しない
+    pub remote_url: Option<String>,
+    /// 同期先のアカウントのパーソナルアクセストークン
+    pub token: Option<String>,
+    /// この端末の ID。Web 側で変更の発生元として記録される
+    pub device_id: String,
+    /// 送信済みのローカルの変更履歴の位置
+    pub last_pushed: i32,
+    /// 受信済みの Web 側の変更履歴の位置
+    pub last_pulled: i32,
+}
+
+fn state_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("sync_state.json")
+}
+
+/// 保存された状態を読む。初回は端末の ID を振って返す
+pub fn load_sync_state(data_dir: &Path) -> Result<SyncState, Error> {
+    let mut state = match fs::read_to_string(state_path(data_dir)) {
+        Ok(text) => serde_json::from_str::<SyncState>(&text)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?,
+        Err(e) if e.kind() == ErrorKind::NotFound => SyncState::default(),
+        Err(e) => return Err(e),
+    };
+    if state.device_id.is_empty() {
+        let suffix: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+        state.device_id = format!("device-{}", suffix.to_ascii_lowercase());
+        save_sync_state(data_dir, &state)?;
+    }
+    Ok(state)
+}
+
+pub fn save_sync_state(data_dir: &Path, state: &SyncState) -> Result<(), Error> {
+    let text = serde_json::to_string_pretty(state)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+    fs::write(state_path(data_dir), text)
+}
+
+#[derive(Debug, Error)]
+pub enum SyncError {
+    #[error("sync is not configured")]
+    NotConfigured,
+    #[error("sync is already running")]
+    AlreadyRunning,
+    #[error("invalid remote url: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+    #[error(transparent)]
+    Io(#[from] Error),
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub pushed: usize,
+    pub pulled: usize,
+    pub skipped: Vec<SkippedChange>,
+}
+
+#[derive(Deserialize)]
+struct RemoteChanges {
+    changes: Vec<SyncChange>,
+    cursor: i32,
+    has_more: bool,
+}
+
+#[derive(Deserialize)]
+struct RemotePushResult {
+    applied: usize,
+    skipped: Vec<SkippedChange>,
+}
+
+/// シングルユーザーのローカル DB と、その同期状態
+pub struct SyncEngine {
+    app_state: AppState,
+    user_id: i32,
+    data_dir: PathBuf,
+    running: AtomicBool,
+}
+
+impl SyncEngine {
+    pub fn new(app_state: AppState, user_id: i32, data_dir: PathBuf) -> Self {
+        Self {
+            app_state,
+            user_id,
+            data_dir,
+            running: AtomicBool::new(false),
+        }
+    }
+
+    /// 同期先を設定する。接続先が変わったら受信位置を最初に戻す
+    pub fn configure(
+        &self,
+        remote_url: Option<String>,
+        token: Option<String>,
+    ) -> Result<SyncState, SyncError> {
+        let mut state = load_sync_state(&self.data_dir)?;
+        let remote_url = remote_url
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty());
+        if let Some(url) = &remote_url {
+            Url::parse(url)?;
+        }
+        if remote_url != state.remote_url {
+            state.last_pushed = 0;
+            state.last_pulled = 0;
+        }
+        state.remote_url = remote_url;
+        state.token = token.filter(|token| !token.trim().is_empty());
+        save_sync_state(&self.data_dir, &state)?;
+        Ok(state)
+    }
+
+    /// ローカルの変更を送ってから、Web 側の変更を取り込む
+    pub async fn sync_now(&self) -> Result<SyncReport, SyncError> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Err(SyncError::AlreadyRunning);
+        }
+        let result = self.run().await;
+        self.running.store(false, Ordering::SeqCst);
+        result
+    }
+
+    async fn run(&self) -> Result<SyncReport, SyncError> {
+        let mut state = load_sync_state(&self.data_dir)?;
+        let (Some(remote_url), Some(token)) = (state.remote_url.clone(), state.token.clone())
+        else {
+            return Err(SyncError::NotConfigured);
+        };
+        let base = base_url(&remote_url)?;
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        let db = self.app_state.db();
+        let mut report = SyncReport::default();
+
+        loop {
+            let page =
+                sync::local_changes_since(db, self.user_id, state.last_pushed, &state.device_id)
+                    .await?;
+            if !page.changes.is_empty() {
+                let result: RemotePushResult = client
+                    .post(base.join("sync/push")?)
+                    .bearer_auth(&token)
+                    .json(&json!({ "device_id": state.device_id, "changes": page.changes }))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                report.pushed += result.applied;
+                report.skipped.extend(result.skipped);
+            }
+            state.last_pushed = page.cursor;
+            save_sync_state(&self.data_dir, &state)?;
+            if !page.has_more {
+                break;
+            }
+        }
+
+        loop {
+            let page: RemoteChanges = client
+                .get(base.join("sync/changes")?)
+                .bearer_auth(&token)
+                .query(&[
+                    ("since", state.last_pulled.to_string()),
+                    ("device_id", state.device_id.clone()),
+                ])
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            let result =
+                sync::apply_changes(db, self.user_id, &state.device_id, page.changes).await?;
+            report.pulled += result.applied;
+            report.skipped.extend(result.skipped);
+            state.last_pulled = page.cursor;
+            save_sync_state(&self.data_dir, &state)?;
+            if !page.has_more {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// `join` で末尾のパスが置き換わらないよう、ベース URL を `/` で終わらせる
+fn base_url(remote_url: &str) -> Result<Url, url::ParseError> {
+    let mut url = Url::parse(remote_url)?;
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    Ok(url)
+}
+
+/// 同期先が設定されていれば一定間隔で同期し、取り込んだ変更があればフロントへ知らせる
+pub fn spawn_periodic_sync(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(SYNC_INTERVAL).await;
+            let Some(engine) = app_handle.try_state::<SyncEngine>() else {
+                continue;
+            };
+            match engine.sync_now().await {
+                Ok(report) => notify_completed(&app_handle, &report),
+                Err(SyncError::NotConfigured | SyncError::AlreadyRunning) => {}
+                Err(error) => warn!(error = %error, "periodic sync failed"),
+            }
+        }
+    });
+}
+
+pub fn notify_completed(app_handle: &AppHandle, report: &SyncReport) {
+    info!(
+        pushed = report.pushed,
+        pulled = report.pulled,
+        skipped = report.skipped.len(),
+        "sync completed"
+    );
+    if let Err(error) = app_handle.emit(SYNC_COMPLETED_EVENT, report) {
+        warn!(error = ?error, "failed to emit sync event");
+    }
+}
@@ -1,2 +1,4 @@
 export const BACKEND_READY_EVENT = "decopon://backend-ready";
 export const FRONTEND_READY_EVENT = "decopon://frontend-ready";
+export const DOMAIN_EVENT = "decopon://domain-event";
+export const SYNC_COMPLETED_EVENT = "decopon://sync-completed";
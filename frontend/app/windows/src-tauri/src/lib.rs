@@ -4,6 +4,7 @@ use decopon_tauri_host_common::bootstrap::{prepare_environment, spawn_backend_in
 use decopon_tauri_host_common::init_marker::is_first_launch;
 use decopon_tauri_host_common::init_state::{AppInitializationState, ReadyListenerState};
 use decopon_tauri_host_common::splashscreen::{create_splashscreen, DEFAULT_SPLASH_LABEL};
+use decopon_tauri_host_common::sync::SyncEngine;
 use decopon_tauri_host_common::{
     commands, dispatch_http_request as dispatch_ipc_http_request, ensure_app_data_dir,
     should_skip_service_bootstrap, AppIpcState, IpcHttpResponse, FRONTEND_READY_EVENT,
@@ -66,6 +67,20 @@ fn get_bootstrap_state(
     commands::get_bootstrap_state(init_state)
 }
 
+#[tauri::command]
+fn configure_sync(
+    engine: State<'_, SyncEngine>,
+    remote_url: Option<String>,
+    token: Option<String>,
+) -> Result<serde_json::Value, String> {
+    commands::configure_sync(engine, remote_url, token)
+}
+
+#[tauri::command]
+async fn run_sync(app: tauri::AppHandle) -> Result<serde_json::Value, String> {
+    commands::run_sync(app).await
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     init_tracing();
@@ -73,7 +88,7 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_notification::init())
-        .invoke_handler(tauri::generate_handler![dispatch_http_request, get_init_status, reset_application_data, get_bootstrap_state])
+        .invoke_handler(tauri::generate_handler![dispatch_http_request, get_init_status, reset_application_data, get_bootstrap_state, configure_sync, run_sync])
         .setup(|app| {
             let app_handle = app.handle();
             let splash_label = create_splashscreen(&app_handle, DEFAULT_SPLASH_LABEL);
@@ -6,7 +6,13 @@ import { authStorage } from "@/scripts/lib/authStorage";
 import { tokenStorage } from "@/scripts/lib/tokenStorage";
 import { ToastMessageManager } from "@/scripts/lib/toastMessageManager";
 
-import { ApiError, type ApiClientHooks, type CallApiOptions, type TransportResponse } from "./index";
+import {
+  ApiError,
+  errorMessageOf,
+  type ApiClientHooks,
+  type CallApiOptions,
+  type TransportResponse,
+} from "./index";
 
 export function createWebApiHooks(): ApiClientHooks {
   return {
@@ -23,9 +29,9 @@ export function createWebApiHooks(): ApiClientHooks {
           ? error.response?.status
           : undefined;
       const fallbackMessage = axios.isAxiosError(error)
-        ? error.response?.data?.message
+        ? errorMessageOf(error.response?.data)
         : error instanceof ApiError
-          ? error.response?.data?.message ?? error.message
+          ? errorMessageOf(error.response?.data) ?? error.message
           : undefined;
 
       ToastMessageManager.notifyWithFallback("error", {
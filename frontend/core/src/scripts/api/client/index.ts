@@ -6,11 +6,13 @@ import type { ApiRequestData } from "@/scripts/types";
 
 import {
   ApiError,
+  type ApiErrorResponseData,
   type ApiMethod,
   type ApiRequest,
   type ApiTransport,
   type CallApiOptions,
   type TransportResponse,
+  errorMessageOf,
 } from "./types";
 import { createHttpTransport, baseURL, httpClient } from "./transports/http";
 import { createIpcTransport } from "./transports/ipc";
@@ -130,5 +132,5 @@ export async function callApi<T = unknown>(
   return currentClient.callApi<T>(method, url, requestData, options);
 }
 
-export { baseURL, httpClient, ApiError };
-export type { CallApiOptions, TransportResponse };
+export { baseURL, httpClient, ApiError, errorMessageOf };
+export type { ApiErrorResponseData, CallApiOptions, TransportResponse };
@@ -1,7 +1,14 @@
 import { authStorage } from "@/scripts/lib/authStorage";
 import { tokenStorage } from "@/scripts/lib/tokenStorage";
 
-import { ApiError, type ApiRequest, type ApiTransport, type TransportResponse } from "../types";
+import {
+  ApiError,
+  type ApiErrorResponseData,
+  type ApiRequest,
+  type ApiTransport,
+  type TransportResponse,
+  errorMessageOf,
+} from "../types";
 import { getTauriInvoke, type InvokeFn, toApiError } from "./ipc/shared";
 
 const forcedTransport = (
@@ -117,9 +124,8 @@ class IpcTransport implements ApiTransport {
     if (body && typeof body === "object") {
       const record = body as Record<string, unknown>;
       const message =
-        typeof record.message === "string"
-          ? record.message
-          : "IPC 呼び出しに失敗しました。";
+        errorMessageOf(record as ApiErrorResponseData) ??
+        "IPC 呼び出しに失敗しました。";
       return { ...record, message };
     }
 
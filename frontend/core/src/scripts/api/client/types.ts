@@ -22,12 +22,32 @@ export type CallApiOptions = {
   toast?: ToastOptions;
 };
 
+/** problem+json の項目ごとのエラー。`code` は安定した識別子 */
+export type ApiFieldError = {
+  field: string;
+  code: string;
+  message: string;
+};
+
+/** API のエラーレスポンス (RFC 7807 problem+json) */
 export type ApiErrorResponseData = {
   message?: string;
   code?: string;
+  type?: string;
+  title?: string;
+  status?: number;
+  detail?: string;
+  resource?: string;
+  errors?: ApiFieldError[];
+  request_id?: string;
   [key: string]: unknown;
 };
 
+/** エラーレスポンスから表示用のメッセージを取り出す */
+export function errorMessageOf(data?: ApiErrorResponseData): string | undefined {
+  return data?.detail ?? data?.message ?? data?.title;
+}
+
 export class ApiError extends Error {
   readonly response?: {
     status?: number;
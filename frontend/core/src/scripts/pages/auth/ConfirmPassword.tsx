@@ -1,3 +1,7 @@
+import {
+  type ApiErrorResponseData,
+  errorMessageOf,
+} from "@/scripts/api/client";
 import { AuthService } from "@/scripts/api/services/AuthService";
 import InputLabel from "@components/InputLabel";
 import PrimaryButton from "@components/PrimaryButton";
@@ -7,10 +11,6 @@ import { useTranslation } from "react-i18next";
 import type { AxiosError } from "axios";
 import { useState } from "react";
 
-interface ErrorResponse {
-  message?: string;
-}
-
 export default function ConfirmPassword() {
   const { t } = useTranslation();
   const [status, setStatus] = useState<string>("");
@@ -28,9 +28,9 @@ export default function ConfirmPassword() {
         setStatus(res.status ?? "");
         formApi.reset();
       } catch (err) {
-        const axiosError = err as AxiosError<ErrorResponse>;
+        const axiosError = err as AxiosError<ApiErrorResponseData>;
         const message =
-          axiosError.response?.data?.message ?? t("api.unknown.default");
+          errorMessageOf(axiosError.response?.data) ?? t("api.unknown.default");
         setError(message);
         formApi.reset();
       }
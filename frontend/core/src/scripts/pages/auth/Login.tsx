@@ -1,3 +1,7 @@
+import {
+  type ApiErrorResponseData,
+  errorMessageOf,
+} from "@/scripts/api/client";
 import { AuthService } from "@/scripts/api/services/AuthService";
 import { authStorage } from "@/scripts/lib/authStorage";
 import type { AuthResponse } from "@/scripts/types";
@@ -18,12 +22,6 @@ type LoginData = {
   password: string;
 };
 
-interface ErrorResponse {
-  data: {
-    message?: string;
-  };
-}
-
 const loginMutationFn = async (
   loginData: LoginData,
 ): Promise<AuthResponse> => {
@@ -47,9 +45,9 @@ export default function Login() {
       queryClient.setQueryData(["auth"], { user: data.user });
       authStorage.set({ user: data.user });
     },
-    onError: (error: AxiosError) => {
+    onError: (error: AxiosError<ApiErrorResponseData>) => {
       console.error("Login Error:", error);
-      const message = (error.response as ErrorResponse).data.message;
+      const message = errorMessageOf(error.response?.data);
       if (message) {
         setStatus(message);
       }
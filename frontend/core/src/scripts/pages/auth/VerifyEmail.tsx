@@ -1,3 +1,7 @@
+import {
+  type ApiErrorResponseData,
+  errorMessageOf,
+} from "@/scripts/api/client";
 import { AuthService } from "@/scripts/api/services/AuthService";
 import InputLabel from "@components/InputLabel";
 import PrimaryButton from "@components/PrimaryButton";
@@ -8,10 +12,6 @@ import type { AxiosError } from "axios";
 import { useState } from "react";
 import { useTranslation } from "react-i18next";
 
-interface ErrorResponse {
-  message?: string;
-}
-
 export default function VerifyEmail() {
   const { t } = useTranslation();
   const [status, setStatus] = useState<string>("");
@@ -30,9 +30,9 @@ export default function VerifyEmail() {
         const res = await AuthService.resendVerification(value);
         setStatus(res.status ?? "");
       } catch (err) {
-        const axiosError = err as AxiosError<ErrorResponse>;
+        const axiosError = err as AxiosError<ApiErrorResponseData>;
         const message =
-          axiosError.response?.data?.message ?? t("api.unknown.default");
+          errorMessageOf(axiosError.response?.data) ?? t("api.unknown.default");
         setError(message);
       }
     },
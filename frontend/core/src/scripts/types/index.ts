@@ -3,12 +3,16 @@ import type { RegisteredRouter } from "@tanstack/react-router";
 
 export interface User {
   id: number;
+  uuid?: string;
   name: string;
   email: string;
   email_verified_at?: string;
   work_time: number;
   break_time: number;
   locale: Locale;
+  pending_email?: string | null;
+  daily_journal_email?: boolean;
+  daily_goal?: number;
 }
 
 export enum Locale {
@@ -18,6 +22,7 @@ export enum Locale {
 
 export interface Task {
   id: number;
+  uuid?: string;
   title: string;
   description: string;
   completed: boolean;
@@ -27,6 +32,8 @@ export interface Task {
   root_task_id?: number;
   depth?: number;
   position?: number;
+  due_at?: string | null;
+  version?: number;
   tags?: Tag[];
 }
 
@@ -35,6 +42,7 @@ export type TaskStoreRequest = {
   description: string;
   parent_task_id?: number;
   tag_ids?: number[];
+  due_at?: string | null;
 };
 
 export enum LogSource {
@@ -42,14 +50,22 @@ export enum LogSource {
   System = "System",
 }
 
+export interface LogEvent {
+  code: string;
+  params: Record<string, unknown>;
+}
+
 export interface Log {
   id: number;
+  uuid?: string;
   content: string;
   source: LogSource;
+  event?: LogEvent;
   created_at: string;
   updated_at: string;
   user_id: number;
   task_id?: number;
+  version?: number;
   tags: Tag[];
 }
 
@@ -63,12 +79,14 @@ export enum DecoponSessionStatus {
 
 export interface DecoponSession {
   id: number;
+  uuid?: string;
   status: DecoponSessionStatus;
   started_at: string;
   ended_at: string | null;
   created_at: string;
   updated_at: string;
   user_id: number;
+  version?: number;
 }
 
 export interface CycleCount {
@@ -78,9 +96,11 @@ export interface CycleCount {
 
 export interface TagResponse {
   id: number;
+  uuid?: string;
   name: string;
   created_at: string;
   updated_at: string;
+  version?: number;
 }
 
 export type Tag = TagResponse;
@@ -127,6 +147,8 @@ export interface PreferenceResponse {
   work_time: number;
   break_time: number;
   locale: Locale;
+  daily_journal_email?: boolean;
+  daily_goal?: number;
 }
 
 export type DecoponLinkProps = Pick<LinkProps<RegisteredRouter>, "to"> & {